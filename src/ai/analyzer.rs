@@ -1,6 +1,8 @@
 use crate::ai::client::{AIMessage, AIRequest, UnifiedAIClient};
 use crate::ai::config::AIConfig;
+use crate::config::app::AppConfig;
 use crate::core::tracker::TimeTracker;
+use crate::utils::time::{self, DayBoundary};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -61,6 +63,15 @@ pub struct FocusPeriod {
 pub struct AIAnalyzer {
     config: AIConfig,
     client: UnifiedAIClient,
+    /// 划分“今日”使用的时区和日分割点
+    day_boundary: DayBoundary,
+}
+
+/// 配置文件中的时区和日分割点
+fn configured_day_boundary() -> DayBoundary {
+    AppConfig::load()
+        .map(|c| c.ui.day_boundary())
+        .unwrap_or_default()
 }
 
 impl AIAnalyzer {
@@ -68,7 +79,16 @@ impl AIAnalyzer {
         let config = AIConfig::load().unwrap_or_default();
         let client = UnifiedAIClient::new(config.clone())?;
 
-        Ok(Self { config, client })
+        Ok(Self {
+            config,
+            client,
+            day_boundary: configured_day_boundary(),
+        })
+    }
+
+    /// 设置划分日期使用的时区和日分割点
+    pub fn set_day_boundary(&mut self, day_boundary: DayBoundary) {
+        self.day_boundary = day_boundary;
     }
 
     pub fn is_configured(&self) -> bool {
@@ -100,12 +120,14 @@ impl AIAnalyzer {
         let activities = tracker.get_activities();
         let sessions = tracker.get_activity_sessions();
 
-        // 计算时间范围
-        let time_range = if let (Some(first), Some(last)) = (activities.first(), activities.last())
-        {
+        // 计算时间范围（对齐到首末记录所在逻辑日的边界）
+        let first = activities.iter().map(|a| a.start_time).min();
+        let last = activities.iter().map(|a| a.effective_end()).max();
+        let time_range = if let (Some(first), Some(last)) = (first, last) {
+            let boundary = self.day_boundary;
             TimeRange {
-                start: first.start_time,
-                end: last.end_time.unwrap_or(Utc::now()),
+                start: boundary.day_start(boundary.logical_date(first)),
+                end: boundary.day_start(boundary.logical_date(last) + chrono::Duration::days(1)),
             }
         } else {
             TimeRange {
@@ -401,11 +423,18 @@ JSON格式示例：
     }
 
     // 本地分析功能（不需要 AI API）
+    //
+    // 只统计今日（按配置的时区和日分割点划分）的数据，跨越日分割点的记录只计入今日部分。
     pub fn local_analysis(&self, tracker: &TimeTracker) -> Result<AIAnalysisResult> {
-        let _activities = tracker.get_activities();
-        let sessions = tracker.get_activity_sessions();
-        let total_time = tracker.get_total_time();
-        let app_stats = tracker.get_statistics();
+        let (today_start, today_end) =
+            time::TimeRange::Today.bounds_at(Utc::now(), self.day_boundary);
+        let sessions: Vec<_> = tracker
+            .get_activity_sessions()
+            .into_iter()
+            .filter(|s| s.start_time < today_end && s.end_time >= today_start)
+            .collect();
+        let total_time = tracker.get_total_time_in(today_start, today_end);
+        let app_stats = tracker.get_statistics_in(today_start, today_end);
 
         // 计算基本统计
         let total_hours = total_time as f64 / 3600.0;
@@ -537,6 +566,10 @@ impl Default for AIAnalyzer {
             UnifiedAIClient::new(AIConfig::default()).unwrap()
         });
 
-        Self { config, client }
+        Self {
+            config,
+            client,
+            day_boundary: configured_day_boundary(),
+        }
     }
 }
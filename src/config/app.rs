@@ -6,6 +6,14 @@ use dirs::config_dir;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::core::away::DEFAULT_AWAY_REVIEW_MINUTES;
+use crate::core::heartbeat::{
+    DEFAULT_DEBOUNCE_SECS, DEFAULT_MIN_RECORD_SECS, DEFAULT_PULSE_WINDOW_SECS,
+};
+use crate::core::title::{validate_rule, TitleNormalizer, TitleRule};
+use crate::storage::compact::DEFAULT_COMPACT_GAP_SECS;
+use crate::utils::time::{DayBoundary, DayTimeZone};
+
 use toml;
 
 /// 应用程序配置
@@ -29,6 +37,21 @@ pub struct AppConfig {
     /// 活跃度检测配置
     #[serde(default)]
     pub activity: ActivityDetectionConfig,
+    /// 存储配置
+    #[serde(default)]
+    pub storage: StorageConfig,
+    /// 数据保留配置
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    /// 每日汇总配置
+    #[serde(default)]
+    pub rollup: RollupConfig,
+    /// 记录压缩配置
+    #[serde(default)]
+    pub compaction: CompactionConfig,
+    /// 活动记录配置
+    #[serde(default)]
+    pub tracking: TrackingConfig,
 }
 
 /// 默认配置版本
@@ -54,6 +77,9 @@ pub struct UiConfig {
     /// 日分割点（小时，0-23）
     #[serde(default = "default_day_split_hour")]
     pub day_split_hour: u8,
+    /// 划分日期使用的时区（IANA 名称，如 Asia/Shanghai；local 表示系统时区）
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
 }
 
 /// 默认日分割点（0点）
@@ -61,6 +87,11 @@ fn default_day_split_hour() -> u8 {
     0
 }
 
+/// 默认时区（系统时区）
+fn default_timezone() -> String {
+    "local".to_string()
+}
+
 /// 导出配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportConfig {
@@ -96,6 +127,83 @@ pub struct ActivityDetectionConfig {
     pub video_apps: Vec<String>,
     /// 视频网站列表
     pub video_sites: Vec<String>,
+    /// 离开（闲置、锁屏、休眠）超过多少分钟后需要标注离开的时间，0 表示不提示
+    #[serde(default = "default_away_review_minutes")]
+    pub away_review_minutes: u64,
+}
+
+/// 默认离开 15 分钟后提示标注
+fn default_away_review_minutes() -> u64 {
+    DEFAULT_AWAY_REVIEW_MINUTES
+}
+
+/// 存储配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// 存储后端（json, jsonl, sqlite）
+    pub backend: String,
+    /// 是否加密存储活动记录（目前仅支持 json 后端）
+    #[serde(default)]
+    pub encrypted: bool,
+    /// 加密口令所在的密钥文件（未设置时从 TIMETRACKER_PASSPHRASE 环境变量读取或提示输入）
+    #[serde(default)]
+    pub key_file: Option<String>,
+    /// 按日期分区存储（none, day, month，目前仅支持 json 后端）
+    #[serde(default = "default_partition")]
+    pub partition: String,
+}
+
+/// 默认不分区
+fn default_partition() -> String {
+    "none".to_string()
+}
+
+/// 数据保留配置（需要按日期分区存储）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// 是否启用数据保留策略
+    pub enabled: bool,
+    /// 保留最近多少天的分区
+    pub keep_days: u32,
+    /// 过期分区的处理方式（archive: 压缩归档, delete: 删除）
+    pub action: String,
+}
+
+/// 每日汇总配置：超过指定天数的记录合并为按天、按应用窗口的汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RollupConfig {
+    /// 是否在监控期间自动汇总旧记录
+    pub enabled: bool,
+    /// 超过多少天的记录进行汇总
+    pub after_days: u32,
+}
+
+/// 记录压缩配置：把相邻的同一应用窗口记录永久合并为一条
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompactionConfig {
+    /// 是否在监控期间每天自动压缩记录
+    pub enabled: bool,
+    /// 合并间隔（秒）：相邻记录间隔不超过该值时合并
+    pub gap_secs: u64,
+}
+
+/// 活动记录配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackingConfig {
+    /// 脉冲窗口（秒）：相同窗口的相邻心跳间隔不超过该值时合并为同一条记录
+    pub pulse_window_secs: u64,
+    /// 防抖时间（秒）：切换后新窗口持续获得焦点达到该值才开始新记录，0 表示不防抖
+    pub debounce_secs: u64,
+    /// 最短记录时长（秒）：短于该值的记录并入相邻记录，0 表示不合并
+    pub min_record_secs: u64,
+    /// 是否启用内置的窗口标题规范化规则
+    pub builtin_title_rules: bool,
+    /// 自定义窗口标题规范化规则，在内置规则之后按顺序应用
+    pub title_rules: Vec<TitleRule>,
 }
 
 impl Default for AppConfig {
@@ -109,6 +217,11 @@ impl Default for AppConfig {
             export: ExportConfig::default(),
             logging: LoggingConfig::default(),
             activity: ActivityDetectionConfig::default(),
+            storage: StorageConfig::default(),
+            retention: RetentionConfig::default(),
+            rollup: RollupConfig::default(),
+            compaction: CompactionConfig::default(),
+            tracking: TrackingConfig::default(),
         }
     }
 }
@@ -123,6 +236,7 @@ impl Default for UiConfig {
             default_view_mode: "unified".to_string(),
             refresh_interval: 1000,
             day_split_hour: default_day_split_hour(),
+            timezone: default_timezone(),
         }
     }
 }
@@ -185,9 +299,28 @@ impl UiConfig {
             ));
         }
 
+        // 验证日分割点
+        if self.day_split_hour > 23 {
+            return Err(anyhow::anyhow!(
+                "日分割点必须在0-23点之间，当前值: {}",
+                self.day_split_hour
+            ));
+        }
+
+        // 验证时区
+        DayTimeZone::from_name(&self.timezone)?;
+
         Ok(())
     }
 
+    /// 划分日期使用的时区和日分割点，时区无效时使用系统时区
+    pub fn day_boundary(&self) -> DayBoundary {
+        DayBoundary::new(
+            DayTimeZone::from_name(&self.timezone).unwrap_or_default(),
+            self.day_split_hour,
+        )
+    }
+
     /// 修复UI配置
     pub fn fix(&mut self) -> Vec<String> {
         let mut fixes = Vec::new();
@@ -236,6 +369,18 @@ impl UiConfig {
             fixes.push("刷新间隔已修正为10000毫秒".to_string());
         }
 
+        // 修复日分割点
+        if self.day_split_hour > 23 {
+            self.day_split_hour = default_day_split_hour();
+            fixes.push("日分割点已重置为0点".to_string());
+        }
+
+        // 修复时区
+        if DayTimeZone::from_name(&self.timezone).is_err() {
+            self.timezone = default_timezone();
+            fixes.push("时区已重置为系统时区".to_string());
+        }
+
         fixes
     }
 }
@@ -361,6 +506,7 @@ impl Default for ActivityDetectionConfig {
                 "twitch.tv".to_string(),
                 "vimeo.com".to_string(),
             ],
+            away_review_minutes: DEFAULT_AWAY_REVIEW_MINUTES,
         }
     }
 }
@@ -377,6 +523,11 @@ impl ActivityDetectionConfig {
         }
     }
 
+    /// 需要标注的最短离开时长（分钟），未启用时返回 None
+    pub fn away_review_minutes(&self) -> Option<u64> {
+        (self.away_review_minutes > 0).then_some(self.away_review_minutes)
+    }
+
     /// 验证活跃度检测配置
     pub fn validate(&self) -> Result<()> {
         if self.idle_timeout == 0 {
@@ -395,6 +546,10 @@ impl ActivityDetectionConfig {
             return Err(anyhow::anyhow!("检测间隔不能超过60秒"));
         }
 
+        if self.away_review_minutes > 1440 {
+            return Err(anyhow::anyhow!("离开标注阈值不能超过24小时"));
+        }
+
         Ok(())
     }
 
@@ -418,6 +573,311 @@ impl ActivityDetectionConfig {
             fixes.push("检测间隔已修正为60000毫秒".to_string());
         }
 
+        if self.away_review_minutes > 1440 {
+            self.away_review_minutes = DEFAULT_AWAY_REVIEW_MINUTES;
+            fixes.push(format!(
+                "离开标注阈值已重置为{}分钟",
+                DEFAULT_AWAY_REVIEW_MINUTES
+            ));
+        }
+
+        fixes
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: "json".to_string(),
+            encrypted: false,
+            key_file: None,
+            partition: default_partition(),
+        }
+    }
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keep_days: 365,
+            action: "archive".to_string(),
+        }
+    }
+}
+
+impl Default for RollupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_days: 90,
+        }
+    }
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gap_secs: DEFAULT_COMPACT_GAP_SECS,
+        }
+    }
+}
+
+impl Default for TrackingConfig {
+    fn default() -> Self {
+        Self {
+            pulse_window_secs: DEFAULT_PULSE_WINDOW_SECS,
+            debounce_secs: DEFAULT_DEBOUNCE_SECS,
+            min_record_secs: DEFAULT_MIN_RECORD_SECS,
+            builtin_title_rules: true,
+            title_rules: Vec::new(),
+        }
+    }
+}
+
+impl StorageConfig {
+    /// 验证存储配置
+    pub fn validate(&self) -> Result<()> {
+        if crate::storage::StorageBackend::from_name(&self.backend).is_none() {
+            return Err(anyhow::anyhow!(
+                "不支持的存储后端: {}，支持的后端: json, jsonl, sqlite",
+                self.backend
+            ));
+        }
+
+        if self.encrypted && self.backend != "json" {
+            return Err(anyhow::anyhow!(
+                "加密存储目前仅支持 json 后端，当前后端: {}",
+                self.backend
+            ));
+        }
+
+        let partitioning =
+            crate::storage::Partitioning::from_name(&self.partition).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "不支持的分区方式: {}，支持的方式: none, day, month",
+                    self.partition
+                )
+            })?;
+        if partitioning != crate::storage::Partitioning::Off && self.backend != "json" {
+            return Err(anyhow::anyhow!(
+                "按日期分区目前仅支持 json 后端，当前后端: {}",
+                self.backend
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 是否启用了按日期分区
+    pub fn is_partitioned(&self) -> bool {
+        crate::storage::Partitioning::from_name(&self.partition)
+            .is_some_and(|p| p != crate::storage::Partitioning::Off)
+    }
+
+    /// 修复存储配置
+    pub fn fix(&mut self) -> Vec<String> {
+        let mut fixes = Vec::new();
+
+        if crate::storage::StorageBackend::from_name(&self.backend).is_none() {
+            self.backend = "json".to_string();
+            fixes.push("存储后端已重置为json".to_string());
+        }
+
+        // 保留加密设置，改用支持加密的后端
+        if self.encrypted && self.backend != "json" {
+            self.backend = "json".to_string();
+            fixes.push("加密存储仅支持json后端，存储后端已改为json".to_string());
+        }
+
+        if crate::storage::Partitioning::from_name(&self.partition).is_none() {
+            self.partition = default_partition();
+            fixes.push("分区方式已重置为none".to_string());
+        }
+
+        if self.is_partitioned() && self.backend != "json" {
+            self.partition = default_partition();
+            fixes.push("按日期分区仅支持json后端，已关闭分区".to_string());
+        }
+
+        fixes
+    }
+}
+
+impl RetentionConfig {
+    /// 验证数据保留配置
+    pub fn validate(&self) -> Result<()> {
+        if self.keep_days == 0 {
+            return Err(anyhow::anyhow!("数据保留天数必须大于0"));
+        }
+
+        if !["archive", "delete"].contains(&self.action.as_str()) {
+            return Err(anyhow::anyhow!(
+                "不支持的过期数据处理方式: {}，支持的方式: archive, delete",
+                self.action
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 修复数据保留配置
+    pub fn fix(&mut self) -> Vec<String> {
+        let mut fixes = Vec::new();
+
+        if self.keep_days == 0 {
+            self.keep_days = 365;
+            fixes.push("数据保留天数已重置为365天".to_string());
+        }
+
+        if !["archive", "delete"].contains(&self.action.as_str()) {
+            self.action = "archive".to_string();
+            fixes.push("过期数据处理方式已重置为archive".to_string());
+        }
+
+        fixes
+    }
+
+    /// 启用时返回对应的保留策略
+    pub fn policy(&self) -> Option<crate::storage::RetentionPolicy> {
+        if !self.enabled {
+            return None;
+        }
+
+        let action = match self.action.as_str() {
+            "delete" => crate::storage::RetentionAction::Delete,
+            _ => crate::storage::RetentionAction::Archive,
+        };
+        Some(crate::storage::RetentionPolicy {
+            keep_days: self.keep_days,
+            action,
+        })
+    }
+}
+
+impl RollupConfig {
+    /// 验证每日汇总配置
+    pub fn validate(&self) -> Result<()> {
+        if self.after_days == 0 {
+            return Err(anyhow::anyhow!("汇总天数必须大于0"));
+        }
+
+        Ok(())
+    }
+
+    /// 修复每日汇总配置
+    pub fn fix(&mut self) -> Vec<String> {
+        let mut fixes = Vec::new();
+
+        if self.after_days == 0 {
+            self.after_days = 90;
+            fixes.push("汇总天数已重置为90天".to_string());
+        }
+
+        fixes
+    }
+
+    /// 启用时返回汇总天数
+    pub fn after_days(&self) -> Option<u32> {
+        self.enabled.then_some(self.after_days)
+    }
+}
+
+impl CompactionConfig {
+    /// 验证记录压缩配置
+    pub fn validate(&self) -> Result<()> {
+        if self.gap_secs == 0 || self.gap_secs > 3600 {
+            return Err(anyhow::anyhow!("压缩间隔必须在1-3600秒之间"));
+        }
+
+        Ok(())
+    }
+
+    /// 修复记录压缩配置
+    pub fn fix(&mut self) -> Vec<String> {
+        let mut fixes = Vec::new();
+
+        if self.gap_secs == 0 || self.gap_secs > 3600 {
+            self.gap_secs = DEFAULT_COMPACT_GAP_SECS;
+            fixes.push(format!("压缩间隔已重置为{}秒", DEFAULT_COMPACT_GAP_SECS));
+        }
+
+        fixes
+    }
+
+    /// 启用时返回合并间隔
+    pub fn gap_secs(&self) -> Option<u64> {
+        self.enabled.then_some(self.gap_secs)
+    }
+}
+
+impl TrackingConfig {
+    /// 按配置编译窗口标题规范化规则，自定义规则无效时只使用内置规则
+    pub fn title_normalizer(&self) -> TitleNormalizer {
+        TitleNormalizer::new(self.builtin_title_rules, &self.title_rules).unwrap_or_else(|e| {
+            log::warn!("标题规则无效，只使用内置规则: {}", e);
+            TitleNormalizer::new(self.builtin_title_rules, &[]).unwrap_or_default()
+        })
+    }
+
+    /// 验证活动记录配置
+    pub fn validate(&self) -> Result<()> {
+        if self.pulse_window_secs == 0 || self.pulse_window_secs > 3600 {
+            return Err(anyhow::anyhow!(
+                "脉冲窗口必须在1-3600秒之间，当前值: {}",
+                self.pulse_window_secs
+            ));
+        }
+
+        if self.debounce_secs > 300 {
+            return Err(anyhow::anyhow!(
+                "防抖时间不能超过300秒，当前值: {}",
+                self.debounce_secs
+            ));
+        }
+
+        if self.min_record_secs > 300 {
+            return Err(anyhow::anyhow!(
+                "最短记录时长不能超过300秒，当前值: {}",
+                self.min_record_secs
+            ));
+        }
+
+        for rule in &self.title_rules {
+            validate_rule(rule)?;
+        }
+
+        Ok(())
+    }
+
+    /// 修复活动记录配置
+    pub fn fix(&mut self) -> Vec<String> {
+        let mut fixes = Vec::new();
+
+        if self.pulse_window_secs == 0 || self.pulse_window_secs > 3600 {
+            self.pulse_window_secs = DEFAULT_PULSE_WINDOW_SECS;
+            fixes.push(format!("脉冲窗口已重置为{}秒", DEFAULT_PULSE_WINDOW_SECS));
+        }
+
+        if self.debounce_secs > 300 {
+            self.debounce_secs = DEFAULT_DEBOUNCE_SECS;
+            fixes.push(format!("防抖时间已重置为{}秒", DEFAULT_DEBOUNCE_SECS));
+        }
+
+        if self.min_record_secs > 300 {
+            self.min_record_secs = DEFAULT_MIN_RECORD_SECS;
+            fixes.push(format!("最短记录时长已重置为{}秒", DEFAULT_MIN_RECORD_SECS));
+        }
+
+        self.title_rules.retain(|rule| {
+            let valid = validate_rule(rule).is_ok();
+            if !valid {
+                fixes.push(format!("已移除无效的标题规则: {}", rule.pattern));
+            }
+            valid
+        });
+
         fixes
     }
 }
@@ -589,6 +1049,26 @@ impl AppConfig {
         // 验证活跃度检测配置
         self.activity.validate()?;
 
+        // 验证存储配置
+        self.storage.validate()?;
+
+        // 验证数据保留配置
+        self.retention.validate()?;
+        if self.retention.enabled && !self.storage.is_partitioned() {
+            return Err(anyhow::anyhow!(
+                "数据保留策略需要按日期分区存储，请设置 [storage] partition = \"day\" 或 \"month\""
+            ));
+        }
+
+        // 验证每日汇总配置
+        self.rollup.validate()?;
+
+        // 验证记录压缩配置
+        self.compaction.validate()?;
+
+        // 验证活动记录配置
+        self.tracking.validate()?;
+
         Ok(())
     }
 
@@ -617,6 +1097,30 @@ impl AppConfig {
         // 修复活跃度检测配置
         fixes.extend(self.activity.fix());
 
+        // 修复存储配置
+        fixes.extend(self.storage.fix());
+
+        // 修复数据保留配置
+        fixes.extend(self.retention.fix());
+        if self.retention.enabled && !self.storage.is_partitioned() {
+            if self.storage.backend == "json" {
+                self.storage.partition = "month".to_string();
+                fixes.push("数据保留策略需要按日期分区，已启用按月分区".to_string());
+            } else {
+                self.retention.enabled = false;
+                fixes.push("数据保留策略仅支持json后端，已关闭数据保留".to_string());
+            }
+        }
+
+        // 修复每日汇总配置
+        fixes.extend(self.rollup.fix());
+
+        // 修复记录压缩配置
+        fixes.extend(self.compaction.fix());
+
+        // 修复活动记录配置
+        fixes.extend(self.tracking.fix());
+
         fixes
     }
 
@@ -738,7 +1242,18 @@ impl AppConfig {
             - 主题: {}\n\
             - 鼠标支持: {}\n\
             - 导出格式: {}\n\
-            - 日志级别: {}",
+            - 日志级别: {}\n\
+            - 存储后端: {}\n\
+            - 加密存储: {}\n\
+            - 分区存储: {}\n\
+            - 数据保留: {}\n\
+            - 每日汇总: {}\n\
+            - 记录压缩: {}\n\
+            - 离开标注: {}\n\
+            - 脉冲窗口: {}秒\n\
+            - 切换防抖: {}秒\n\
+            - 最短记录: {}秒\n\
+            - 标题规则: {}{} 条自定义",
             self.version,
             self.data_file,
             self.monitor_interval,
@@ -750,7 +1265,45 @@ impl AppConfig {
                 "禁用"
             },
             self.export.default_format,
-            self.logging.level
+            self.logging.level,
+            self.storage.backend,
+            if self.storage.encrypted {
+                "启用"
+            } else {
+                "禁用"
+            },
+            self.storage.partition,
+            if self.retention.enabled {
+                format!(
+                    "{}天（{}）",
+                    self.retention.keep_days, self.retention.action
+                )
+            } else {
+                "禁用".to_string()
+            },
+            if self.rollup.enabled {
+                format!("{}天前的记录", self.rollup.after_days)
+            } else {
+                "禁用".to_string()
+            },
+            if self.compaction.enabled {
+                format!("间隔{}秒内", self.compaction.gap_secs)
+            } else {
+                "禁用".to_string()
+            },
+            match self.activity.away_review_minutes() {
+                Some(minutes) => format!("离开{}分钟以上", minutes),
+                None => "禁用".to_string(),
+            },
+            self.tracking.pulse_window_secs,
+            self.tracking.debounce_secs,
+            self.tracking.min_record_secs,
+            if self.tracking.builtin_title_rules {
+                "内置 + "
+            } else {
+                ""
+            },
+            self.tracking.title_rules.len()
         )
     }
 }
@@ -12,7 +12,25 @@ use std::process::Command;
 use winapi::um::winuser::{GetLastInputInfo, LASTINPUTINFO};
 
 #[cfg(target_os = "linux")]
-use std::process::Command;
+use crate::core::idle::IdleProbe;
+use crate::core::idle::IdleSource;
+use crate::core::media::{MediaInfo, MprisClient};
+
+/// 闲置时重新读取媒体播放状态的间隔
+const MEDIA_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 窗口标题中表示视频内容的关键词，英文关键词按整词匹配
+const VIDEO_KEYWORDS: [&str; 9] = [
+    "播放",
+    "视频",
+    "电影",
+    "电视剧",
+    "直播",
+    "play",
+    "video",
+    "movie",
+    "stream",
+];
 
 /// 用户活跃状态
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -110,8 +128,21 @@ impl Default for ActivityConfig {
 pub struct ActivityDetector {
     config: ActivityConfig,
     last_activity_time: SystemTime,
+    /// 按系统闲置时间推算的最后一次键盘鼠标输入时间
+    last_input_time: SystemTime,
     last_check_time: SystemTime,
     current_status: ActivityStatus,
+    /// 上次检测使用的闲置时间来源
+    idle_source: Option<IdleSource>,
+    #[cfg(target_os = "linux")]
+    idle_probe: IdleProbe,
+    /// 读取媒体播放状态的 MPRIS 客户端，为 None 时只按窗口标题判断
+    mpris: Option<MprisClient>,
+    /// 闲置期间正在播放的媒体
+    media: Option<MediaInfo>,
+    media_checked_at: Option<SystemTime>,
+    /// 前台窗口是否全屏
+    fullscreen: bool,
 }
 
 impl ActivityDetector {
@@ -121,8 +152,16 @@ impl ActivityDetector {
         Self {
             config,
             last_activity_time: now,
+            last_input_time: now,
             last_check_time: now,
             current_status: ActivityStatus::Unknown,
+            idle_source: None,
+            #[cfg(target_os = "linux")]
+            idle_probe: IdleProbe::new(),
+            mpris: cfg!(target_os = "linux").then(MprisClient::session),
+            media: None,
+            media_checked_at: None,
+            fullscreen: false,
         }
     }
 
@@ -157,18 +196,10 @@ impl ActivityDetector {
 
         // 获取系统闲置时间
         let idle_time = self.get_system_idle_time()?;
-
-        // 检查是否正在观看视频
-        let is_watching_video = self.is_watching_video(current_app, current_window);
+        self.last_input_time = now.checked_sub(idle_time).unwrap_or(now);
 
         // 确定活跃状态
-        let status = if is_watching_video {
-            ActivityStatus::WatchingVideo
-        } else if idle_time.as_secs() > self.config.idle_timeout {
-            ActivityStatus::Idle
-        } else {
-            ActivityStatus::Active
-        };
+        let status = self.classify(idle_time, current_app, current_window);
 
         // 更新状态
         if status != ActivityStatus::Idle {
@@ -179,6 +210,88 @@ impl ActivityDetector {
         Ok(status)
     }
 
+    /// 按系统闲置时间和前台窗口判断活跃状态
+    ///
+    /// 有输入时，前台是视频应用或视频网站则为观看视频；闲置超时后只有确实在播放媒体时才算
+    /// 观看视频，否则为闲置。
+    pub fn classify(
+        &mut self,
+        idle_time: Duration,
+        current_app: Option<&str>,
+        current_window: Option<&str>,
+    ) -> ActivityStatus {
+        if idle_time.as_secs() <= self.config.idle_timeout {
+            self.media = None;
+            self.media_checked_at = None;
+            return if self.is_watching_video(current_app, current_window) {
+                ActivityStatus::WatchingVideo
+            } else {
+                ActivityStatus::Active
+            };
+        }
+
+        self.media = self.detect_media(current_app, current_window);
+        if self.media.is_some() {
+            ActivityStatus::WatchingVideo
+        } else {
+            ActivityStatus::Idle
+        }
+    }
+
+    /// 设置读取媒体播放状态的 MPRIS 客户端，为 None 时只按窗口标题判断
+    pub fn set_mpris(&mut self, mpris: Option<MprisClient>) {
+        self.mpris = mpris;
+        self.media_checked_at = None;
+    }
+
+    /// 设置前台窗口是否全屏；全屏播放视频或放映幻灯片时没有输入也不算闲置
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        if fullscreen != self.fullscreen {
+            self.fullscreen = fullscreen;
+            self.media_checked_at = None;
+        }
+    }
+
+    /// 闲置期间正在播放的媒体
+    pub fn current_media(&self) -> Option<&MediaInfo> {
+        self.media.as_ref()
+    }
+
+    /// 读取正在播放的媒体；没有播放器在播放时，全屏的前台窗口也算在观看；
+    /// 无法访问 MPRIS 时按前台窗口推测
+    fn detect_media(
+        &mut self,
+        current_app: Option<&str>,
+        current_window: Option<&str>,
+    ) -> Option<MediaInfo> {
+        let now = SystemTime::now();
+        let due = self.media_checked_at.is_none_or(|checked| {
+            now.duration_since(checked)
+                .map_or(true, |elapsed| elapsed >= MEDIA_CHECK_INTERVAL)
+        });
+        if !due {
+            return self.media.clone();
+        }
+
+        if let Some(mpris) = &self.mpris {
+            self.media_checked_at = Some(now);
+            match mpris.playing() {
+                Ok(Some(media)) => return Some(media),
+                // 播放器暂停或没有播放器时不再按窗口标题推测
+                Ok(None) if !self.fullscreen => return None,
+                Ok(None) => {}
+                Err(e) => log::debug!("无法读取 MPRIS 播放状态，按窗口标题判断: {}", e),
+            }
+        }
+
+        (self.fullscreen || self.is_watching_video(current_app, current_window)).then(|| {
+            MediaInfo {
+                player: current_app.unwrap_or_default().to_string(),
+                track: current_window.map(str::to_string),
+            }
+        })
+    }
+
     /// 获取当前活跃状态
     pub fn current_status(&self) -> &ActivityStatus {
         &self.current_status
@@ -189,6 +302,16 @@ impl ActivityDetector {
         self.last_activity_time
     }
 
+    /// 获取最后一次键盘鼠标输入的时间（上次检测时推算）
+    pub fn last_input_time(&self) -> SystemTime {
+        self.last_input_time
+    }
+
+    /// 上次检测使用的闲置时间来源，尚未检测时返回 None
+    pub fn idle_source(&self) -> Option<IdleSource> {
+        self.idle_source
+    }
+
     /// 获取闲置时长
     pub fn idle_duration(&self) -> Duration {
         SystemTime::now()
@@ -219,21 +342,12 @@ impl ActivityDetector {
             }
 
             // 检查常见的视频相关关键词
-            let video_keywords = [
-                "播放",
-                "视频",
-                "电影",
-                "电视剧",
-                "直播",
-                "play",
-                "video",
-                "movie",
-                "stream",
-            ];
-            for keyword in &video_keywords {
-                if window_title.to_lowercase().contains(keyword) {
-                    return true;
-                }
+            let window_title = window_title.to_lowercase();
+            if VIDEO_KEYWORDS
+                .iter()
+                .any(|keyword| contains_keyword(&window_title, keyword))
+            {
+                return true;
             }
         }
 
@@ -242,7 +356,8 @@ impl ActivityDetector {
 
     /// 获取系统闲置时间
     #[cfg(target_os = "macos")]
-    fn get_system_idle_time(&self) -> Result<Duration> {
+    fn get_system_idle_time(&mut self) -> Result<Duration> {
+        self.idle_source = Some(IdleSource::IoKit);
         let output = Command::new("ioreg")
             .args(&["-c", "IOHIDSystem"])
             .output()?;
@@ -266,7 +381,8 @@ impl ActivityDetector {
 
     /// 获取系统闲置时间 (Windows)
     #[cfg(target_os = "windows")]
-    fn get_system_idle_time(&self) -> Result<Duration> {
+    fn get_system_idle_time(&mut self) -> Result<Duration> {
+        self.idle_source = Some(IdleSource::Win32);
         unsafe {
             let mut last_input_info = LASTINPUTINFO {
                 cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
@@ -285,29 +401,10 @@ impl ActivityDetector {
 
     /// 获取系统闲置时间 (Linux)
     #[cfg(target_os = "linux")]
-    fn get_system_idle_time(&self) -> Result<Duration> {
-        // 尝试使用 xprintidle
-        if let Ok(output) = Command::new("xprintidle").output() {
-            if output.status.success() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                if let Ok(milliseconds) = output_str.trim().parse::<u64>() {
-                    return Ok(Duration::from_millis(milliseconds));
-                }
-            }
-        }
-
-        // 备用方法：使用 xssstate
-        if let Ok(output) = Command::new("xssstate").args(&["-i"]).output() {
-            if output.status.success() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                if let Ok(seconds) = output_str.trim().parse::<u64>() {
-                    return Ok(Duration::from_secs(seconds));
-                }
-            }
-        }
-
-        // 如果都失败了，返回0
-        Ok(Duration::ZERO)
+    fn get_system_idle_time(&mut self) -> Result<Duration> {
+        let (idle, source) = self.idle_probe.query();
+        self.idle_source = Some(source);
+        Ok(idle)
     }
 
     /// 更新配置
@@ -340,10 +437,25 @@ impl ActivityDetector {
             idle_duration: total_idle_time,
             idle_timeout: Duration::from_secs(self.config.idle_timeout),
             detection_enabled: self.config.enabled,
+            idle_source: self.idle_source,
+            media: self.media.clone(),
+            fullscreen: self.fullscreen,
         }
     }
 }
 
+/// 标题中是否包含关键词；英文关键词需要是完整的词，避免 `Playwright` 匹配 `play`
+fn contains_keyword(text: &str, keyword: &str) -> bool {
+    if !keyword.is_ascii() {
+        return text.contains(keyword);
+    }
+    text.match_indices(keyword).any(|(index, _)| {
+        let before = text[..index].chars().next_back();
+        let after = text[index + keyword.len()..].chars().next();
+        !before.is_some_and(|c| c.is_alphanumeric()) && !after.is_some_and(|c| c.is_alphanumeric())
+    })
+}
+
 /// 活跃度统计信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivityStats {
@@ -352,6 +464,15 @@ pub struct ActivityStats {
     pub idle_duration: Duration,
     pub idle_timeout: Duration,
     pub detection_enabled: bool,
+    /// 闲置时间来源，尚未检测时为 None
+    #[serde(default)]
+    pub idle_source: Option<IdleSource>,
+    /// 闲置期间正在播放的媒体
+    #[serde(default)]
+    pub media: Option<MediaInfo>,
+    /// 前台窗口是否全屏
+    #[serde(default)]
+    pub fullscreen: bool,
 }
 
 impl ActivityStats {
@@ -0,0 +1,693 @@
+// 应用规则模块
+// 用户规则文件把进程名、应用路径或 bundle id 映射为统一的应用名，并定义应用的生产力分类
+
+use crate::core::tracker::ActivityRecord;
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 规则文件名（位于配置目录下）
+pub const APP_RULES_FILE: &str = "app_rules.toml";
+
+/// 应用的生产力分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppCategory {
+    /// 生产力应用
+    Productive,
+    /// 中性应用
+    Neutral,
+    /// 娱乐/非生产力应用
+    Unproductive,
+}
+
+/// 应用别名规则：满足任一匹配条件（且满足标题条件）的窗口统一记为 `name`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppAlias {
+    /// 统一后的应用名
+    pub name: String,
+    /// 进程名或应用名，不区分大小写完全匹配
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub process: Vec<String>,
+    /// 应用路径通配符（`*` 匹配任意字符，`?` 匹配单个字符），不区分大小写
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub app_path: Vec<String>,
+    /// macOS bundle id
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bundle_id: Vec<String>,
+    /// 额外要求窗口标题包含其中之一，用于区分 electron、java 等通用进程
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub title_contains: Vec<String>,
+    /// 该应用的生产力分类
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<AppCategory>,
+}
+
+impl AppAlias {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            process: Vec::new(),
+            app_path: Vec::new(),
+            bundle_id: Vec::new(),
+            title_contains: Vec::new(),
+            category: None,
+        }
+    }
+
+    pub fn processes(mut self, names: &[&str]) -> Self {
+        self.process = names.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn paths(mut self, globs: &[&str]) -> Self {
+        self.app_path = globs.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn bundles(mut self, ids: &[&str]) -> Self {
+        self.bundle_id = ids.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn titles(mut self, patterns: &[&str]) -> Self {
+        self.title_contains = patterns.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// 窗口是否匹配该规则
+    pub fn matches(&self, window: &WindowIdentity) -> bool {
+        let identified = self
+            .process
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(window.app_name))
+            || window
+                .app_path
+                .is_some_and(|path| self.app_path.iter().any(|g| glob_match(g, path)))
+            || window
+                .bundle_id
+                .is_some_and(|id| self.bundle_id.iter().any(|b| b == id));
+        if !identified {
+            return false;
+        }
+
+        self.title_contains.is_empty()
+            || self
+                .title_contains
+                .iter()
+                .any(|t| window.window_title.contains(t.as_str()))
+    }
+}
+
+/// 按分类列出的应用名关键字，应用名与关键字互相包含即视为匹配
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategoryLists {
+    pub productive: Vec<String>,
+    pub unproductive: Vec<String>,
+    pub neutral: Vec<String>,
+}
+
+impl CategoryLists {
+    fn category(&self, app_lower: &str) -> Option<AppCategory> {
+        let matches = |list: &[String]| {
+            list.iter().any(|app| {
+                let app = app.to_lowercase();
+                app_lower.contains(&app) || app.contains(app_lower)
+            })
+        };
+
+        if matches(&self.productive) {
+            Some(AppCategory::Productive)
+        } else if matches(&self.unproductive) {
+            Some(AppCategory::Unproductive)
+        } else if matches(&self.neutral) {
+            Some(AppCategory::Neutral)
+        } else {
+            None
+        }
+    }
+}
+
+/// 用于匹配规则的窗口信息
+#[derive(Debug, Clone, Copy)]
+pub struct WindowIdentity<'a> {
+    pub app_name: &'a str,
+    pub window_title: &'a str,
+    pub app_path: Option<&'a str>,
+    pub bundle_id: Option<&'a str>,
+}
+
+impl<'a> WindowIdentity<'a> {
+    pub fn of(record: &'a ActivityRecord) -> Self {
+        Self {
+            app_name: &record.app_name,
+            window_title: &record.window_title,
+            app_path: record.app_path.as_deref(),
+            bundle_id: record.bundle_id.as_deref(),
+        }
+    }
+}
+
+/// 应用规则：用户规则优先，`builtin` 为 true 时再使用内置规则
+///
+/// 规则文件示例（`~/.config/timetracker/app_rules.toml`）：
+///
+/// ```toml
+/// [[alias]]
+/// name = "VSCode"
+/// process = ["code", "Code - OSS", "codium"]
+/// app_path = ["*/Visual Studio Code.app/*"]
+/// bundle_id = ["com.microsoft.VSCode"]
+/// category = "productive"
+///
+/// [categories]
+/// unproductive = ["hacker news"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppRules {
+    /// 是否在用户规则之后使用内置规则
+    #[serde(default = "default_builtin")]
+    pub builtin: bool,
+    /// 别名规则，按顺序匹配
+    #[serde(default, rename = "alias")]
+    pub aliases: Vec<AppAlias>,
+    /// 分类关键字
+    #[serde(default)]
+    pub categories: CategoryLists,
+}
+
+fn default_builtin() -> bool {
+    true
+}
+
+impl Default for AppRules {
+    /// 只使用内置规则
+    fn default() -> Self {
+        Self {
+            builtin: true,
+            aliases: Vec::new(),
+            categories: CategoryLists::default(),
+        }
+    }
+}
+
+impl AppRules {
+    /// 默认规则文件路径
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("timetracker").join(APP_RULES_FILE))
+    }
+
+    /// 读取规则文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("无法读取应用规则文件: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("应用规则文件格式错误: {}", path.display()))
+    }
+
+    /// 读取默认规则文件，文件不存在或无效时只使用内置规则
+    pub fn load_default() -> Self {
+        let Some(path) = Self::default_path().filter(|p| p.exists()) else {
+            return Self::default();
+        };
+        Self::load(&path).unwrap_or_else(|e| {
+            log::warn!("{}，只使用内置应用规则", e);
+            Self::default()
+        })
+    }
+
+    /// 匹配的别名规则（用户规则优先）
+    fn find_alias(&self, window: &WindowIdentity) -> Option<&AppAlias> {
+        let builtin: &[AppAlias] = if self.builtin { &BUILTIN_ALIASES } else { &[] };
+        self.aliases
+            .iter()
+            .chain(builtin)
+            .find(|alias| alias.matches(window))
+    }
+
+    /// 窗口对应的统一应用名，没有匹配的规则时返回 None
+    pub fn canonical_name(&self, window: &WindowIdentity) -> Option<&str> {
+        self.find_alias(window).map(|alias| alias.name.as_str())
+    }
+
+    /// 应用的生产力分类：别名规则上的分类优先，其次按分类关键字匹配，都不匹配时为中性
+    pub fn category(&self, app_name: &str) -> AppCategory {
+        if let Some(category) = self
+            .aliases
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(app_name))
+            .and_then(|a| a.category)
+        {
+            return category;
+        }
+
+        let app_lower = app_name.to_lowercase();
+        if let Some(category) = self.categories.category(&app_lower) {
+            return category;
+        }
+        if self.builtin {
+            if let Some(category) = BUILTIN_CATEGORIES.category(&app_lower) {
+                return category;
+            }
+        }
+        AppCategory::Neutral
+    }
+
+    /// 对已有记录重新应用规则，返回每种改名（原名, 新名）涉及的记录数
+    pub fn apply_to_records(
+        &self,
+        records: &mut [ActivityRecord],
+    ) -> BTreeMap<(String, String), usize> {
+        let mut renamed = BTreeMap::new();
+        for record in records {
+            let Some(name) = self.canonical_name(&WindowIdentity::of(record)) else {
+                continue;
+            };
+            if name != record.app_name {
+                let name = name.to_string();
+                let old = std::mem::replace(&mut record.app_name, name.clone());
+                *renamed.entry((old, name)).or_insert(0) += 1;
+            }
+        }
+        renamed
+    }
+}
+
+static BUILTIN_ALIASES: Lazy<Vec<AppAlias>> = Lazy::new(builtin_aliases);
+static BUILTIN_CATEGORIES: Lazy<CategoryLists> = Lazy::new(builtin_categories);
+
+/// 内置别名规则，覆盖 Electron、Java 等通用进程下的常见应用
+pub fn builtin_aliases() -> Vec<AppAlias> {
+    let mut aliases = vec![
+        AppAlias::new("VSCode")
+            .processes(&[
+                "code",
+                "code - oss",
+                "code-oss",
+                "vscode",
+                "visual studio code",
+                "codium",
+            ])
+            .paths(&[
+                "*/Visual Studio Code.app/*",
+                "*/code/code",
+                "*\\Microsoft VS Code\\Code.exe",
+            ])
+            .bundles(&["com.microsoft.VSCode"]),
+        AppAlias::new("VSCode")
+            .processes(&["electron"])
+            .titles(&["Visual Studio Code", "VSCode"]),
+        AppAlias::new("Warp")
+            .processes(&["stable", "warp"])
+            .titles(&["Warp"]),
+        AppAlias::new("Chrome")
+            .processes(&["chrome", "google-chrome"])
+            .titles(&["Google Chrome"]),
+        AppAlias::new("Chromium")
+            .processes(&["chrome", "chromium"])
+            .titles(&["Chromium"]),
+        AppAlias::new("Firefox")
+            .processes(&["firefox", "firefox-esr"])
+            .titles(&["Mozilla Firefox", "Firefox"]),
+    ];
+
+    for name in [
+        "Discord", "Slack", "WhatsApp", "Figma", "Notion", "Obsidian", "Spotify",
+    ] {
+        aliases.push(AppAlias::new(name).processes(&["electron"]).titles(&[name]));
+    }
+
+    for name in [
+        "IntelliJ IDEA",
+        "PyCharm",
+        "WebStorm",
+        "Android Studio",
+        "Eclipse",
+    ] {
+        aliases.push(AppAlias::new(name).processes(&["java"]).titles(&[name]));
+    }
+
+    aliases
+}
+
+/// 内置分类关键字
+pub fn builtin_categories() -> CategoryLists {
+    let list = |apps: &[&str]| apps.iter().map(|s| s.to_string()).collect();
+    CategoryLists {
+        productive: list(BUILTIN_PRODUCTIVE),
+        unproductive: list(BUILTIN_UNPRODUCTIVE),
+        neutral: list(BUILTIN_NEUTRAL),
+    }
+}
+
+/// 通配符匹配（不区分大小写），`*` 匹配任意长度字符，`?` 匹配单个字符
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // 让上一个 `*` 多匹配一个字符后重试
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+const BUILTIN_PRODUCTIVE: &[&str] = &[
+    // 开发工具
+    "vscode",
+    "visual studio code",
+    "code",
+    "xcode",
+    "intellij",
+    "idea",
+    "pycharm",
+    "webstorm",
+    "android studio",
+    "eclipse",
+    "vim",
+    "emacs",
+    "neovim",
+    "sublime text",
+    "atom",
+    "brackets",
+    "phpstorm",
+    "clion",
+    "rider",
+    // 终端和命令行工具
+    "terminal",
+    "iterm",
+    "iterm2",
+    "warp",
+    "hyper",
+    "alacritty",
+    "kitty",
+    "powershell",
+    "cmd",
+    "bash",
+    "zsh",
+    "fish",
+    // 开发相关工具
+    "git",
+    "github desktop",
+    "sourcetree",
+    "tower",
+    "fork",
+    "gitkraken",
+    "docker",
+    "docker desktop",
+    "kubernetes",
+    "postman",
+    "insomnia",
+    "paw",
+    "tableplus",
+    "sequel pro",
+    "dbeaver",
+    "navicat",
+    "mongodb compass",
+    // 设计工具
+    "figma",
+    "sketch",
+    "adobe xd",
+    "photoshop",
+    "illustrator",
+    "indesign",
+    "after effects",
+    "premiere pro",
+    "final cut pro",
+    "davinci resolve",
+    "blender",
+    "maya",
+    "3ds max",
+    "cinema 4d",
+    "zbrush",
+    // 办公软件
+    "microsoft word",
+    "word",
+    "microsoft excel",
+    "excel",
+    "microsoft powerpoint",
+    "powerpoint",
+    "microsoft outlook",
+    "outlook",
+    "microsoft teams",
+    "teams",
+    "onenote",
+    "google docs",
+    "google sheets",
+    "google slides",
+    "google drive",
+    "pages",
+    "numbers",
+    "keynote",
+    "libreoffice",
+    "openoffice",
+    // 笔记和文档
+    "notion",
+    "obsidian",
+    "typora",
+    "bear",
+    "ulysses",
+    "scrivener",
+    "evernote",
+    "onenote",
+    "joplin",
+    "logseq",
+    "roam research",
+    "markdown editor",
+    "marktext",
+    "zettlr",
+    // 通讯和协作
+    "slack",
+    "microsoft teams",
+    "zoom",
+    "skype",
+    "discord",
+    "telegram",
+    "whatsapp",
+    "wechat",
+    "dingtalk",
+    "feishu",
+    "lark",
+    // 项目管理
+    "jira",
+    "trello",
+    "asana",
+    "monday",
+    "clickup",
+    "linear",
+    "height",
+    "todoist",
+    "things",
+    "omnifocus",
+    "taskwarrior",
+    "org-mode",
+    // 浏览器（工作相关）
+    "chrome",
+    "firefox",
+    "safari",
+    "edge",
+    "brave",
+    "opera",
+    "vivaldi",
+    // 其他生产力工具
+    "alfred",
+    "raycast",
+    "spotlight",
+    "launcher",
+    "quicksilver",
+    "1password",
+    "bitwarden",
+    "lastpass",
+    "keychain access",
+    "calculator",
+    "calendar",
+    "contacts",
+    "mail",
+    "notes",
+];
+
+const BUILTIN_UNPRODUCTIVE: &[&str] = &[
+    // 视频娱乐
+    "youtube",
+    "netflix",
+    "hulu",
+    "disney+",
+    "amazon prime",
+    "hbo max",
+    "twitch",
+    "bilibili",
+    "iqiyi",
+    "youku",
+    "tencent video",
+    "douyin",
+    "tiktok",
+    "vlc",
+    "quicktime",
+    "mpv",
+    "plex",
+    "kodi",
+    "infuse",
+    // 音乐娱乐
+    "spotify",
+    "apple music",
+    "youtube music",
+    "soundcloud",
+    "pandora",
+    "tidal",
+    "deezer",
+    "qq music",
+    "netease music",
+    "xiami music",
+    // 游戏
+    "steam",
+    "epic games",
+    "origin",
+    "uplay",
+    "battle.net",
+    "gog galaxy",
+    "minecraft",
+    "world of warcraft",
+    "league of legends",
+    "dota 2",
+    "counter-strike",
+    "valorant",
+    "overwatch",
+    "fortnite",
+    "apex legends",
+    "among us",
+    "fall guys",
+    "rocket league",
+    "fifa",
+    "nba 2k",
+    "civilization",
+    "age of empires",
+    "starcraft",
+    "diablo",
+    "hearthstone",
+    // 社交媒体
+    "facebook",
+    "instagram",
+    "twitter",
+    "snapchat",
+    "linkedin",
+    "pinterest",
+    "reddit",
+    "tumblr",
+    "weibo",
+    "zhihu",
+    "xiaohongshu",
+    "douban",
+    // 购物
+    "amazon",
+    "ebay",
+    "taobao",
+    "tmall",
+    "jd",
+    "pinduoduo",
+    "shopify",
+    // 新闻和阅读（娱乐性）
+    "news",
+    "flipboard",
+    "pocket",
+    "instapaper",
+    "feedly",
+    "reeder",
+    // 其他娱乐
+    "photos",
+    "preview",
+    "image viewer",
+    "comic reader",
+    "manga reader",
+    "podcast",
+    "audiobook",
+    "kindle",
+    "ibooks",
+    "goodreads",
+];
+
+const BUILTIN_NEUTRAL: &[&str] = &[
+    // 系统工具
+    "finder",
+    "file explorer",
+    "explorer",
+    "nautilus",
+    "dolphin",
+    "thunar",
+    "activity monitor",
+    "task manager",
+    "system monitor",
+    "htop",
+    "top",
+    "system preferences",
+    "settings",
+    "control panel",
+    "registry editor",
+    // 网络工具
+    "network utility",
+    "wifi analyzer",
+    "speedtest",
+    "ping",
+    "traceroute",
+    // 文件管理
+    "7-zip",
+    "winrar",
+    "the unarchiver",
+    "keka",
+    "betterzip",
+    "archive utility",
+    "dropbox",
+    "google drive",
+    "onedrive",
+    "icloud",
+    "box",
+    "mega",
+    // 系统维护
+    "disk utility",
+    "cleanmymac",
+    "ccleaner",
+    "malwarebytes",
+    "antivirus",
+    "backup",
+    "time machine",
+    "carbon copy cloner",
+    "superduper",
+    // 其他工具
+    "pdf reader",
+    "adobe reader",
+    "preview",
+    "skim",
+    "foxit reader",
+    "text editor",
+    "notepad",
+    "textedit",
+    "gedit",
+    "nano",
+    "clock",
+    "timer",
+    "stopwatch",
+    "weather",
+    "maps",
+    "gps",
+];
@@ -0,0 +1,160 @@
+// 离开时段标注模块
+// 离开电脑（闲置、锁屏、休眠）超过一定时长后回来时，把离开的时间放入待标注队列，
+// 由用户在命令行或 TUI 中标注为会议、午餐或某个项目，保存为手动记录
+
+use crate::core::manual::ManualEntry;
+use crate::core::power::AwayPeriod;
+use crate::storage::atomic_write;
+use crate::utils::time::DayTimeZone;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 默认离开超过多少分钟后需要标注
+pub const DEFAULT_AWAY_REVIEW_MINUTES: u64 = 15;
+
+/// 标注离开时段时提示的常用名称
+pub const SUGGESTED_LABELS: [&str; 3] = ["会议", "午餐", "休息"];
+
+/// 数据文件对应的待标注队列文件（`timetracker.db` -> `timetracker.away.json`）
+pub fn queue_path(data_file: &Path) -> PathBuf {
+    data_file.with_extension("away.json")
+}
+
+/// 队列文件内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueFile {
+    #[serde(default)]
+    pending: Vec<AwayPeriod>,
+}
+
+/// 待标注的离开时段队列
+///
+/// 守护进程写入，命令行和 TUI 读取并移除。每次读写前都重新加载文件，以合并其他进程的修改。
+#[derive(Debug, Default)]
+pub struct AwayQueue {
+    /// 队列文件，为 None 时只保存在内存中
+    path: Option<PathBuf>,
+    pending: Vec<AwayPeriod>,
+}
+
+impl AwayQueue {
+    /// 只保存在内存中的队列
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// 保存在指定文件中的队列
+    pub fn open<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: Some(path.into()),
+            pending: Vec::new(),
+        }
+    }
+
+    /// 数据文件对应的队列，数据文件为空（自定义存储）时只保存在内存中
+    pub fn for_data_file(data_file: &str) -> Self {
+        if data_file.is_empty() {
+            Self::in_memory()
+        } else {
+            Self::open(queue_path(Path::new(data_file)))
+        }
+    }
+
+    /// 待标注的离开时段，按开始时间排序
+    pub fn pending(&mut self) -> Result<Vec<AwayPeriod>> {
+        self.reload()?;
+        Ok(self.pending.clone())
+    }
+
+    /// 加入一个已结束的离开时段，开始时间相同的时段只保留一个
+    pub fn push(&mut self, period: AwayPeriod) -> Result<()> {
+        if period.end.is_none() {
+            return Err(anyhow!("离开时段尚未结束"));
+        }
+        self.reload()?;
+        self.pending.retain(|pending| pending.start != period.start);
+        self.pending.push(period);
+        self.pending.sort_by_key(|pending| pending.start);
+        self.save()
+    }
+
+    /// 移除一个离开时段，返回它是否在队列中
+    pub fn remove(&mut self, period: &AwayPeriod) -> Result<bool> {
+        self.reload()?;
+        let before = self.pending.len();
+        self.pending.retain(|pending| pending != period);
+        if self.pending.len() == before {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !path.exists() {
+            self.pending.clear();
+            return Ok(());
+        }
+        let contents = fs::read_to_string(path)?;
+        let file: QueueFile = serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("待标注队列文件 {} 格式错误: {}", path.display(), e))?;
+        self.pending = file.pending;
+        self.pending.sort_by_key(|pending| pending.start);
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = QueueFile {
+            pending: self.pending.clone(),
+        };
+        atomic_write(path, serde_json::to_string_pretty(&file)?.as_bytes())
+    }
+}
+
+/// 解析标注输入 `名称[@项目]`
+///
+/// `客户会议@Acme` 标注为 Acme 项目的“客户会议”，`@Acme` 以项目名作为名称；输入为空时返回 None。
+pub fn parse_label(input: &str) -> Option<(String, Option<String>)> {
+    let (label, project) = match input.split_once('@') {
+        Some((label, project)) => (label.trim(), Some(project.trim())),
+        None => (input.trim(), None),
+    };
+    let project = project.filter(|project| !project.is_empty());
+    let label = if label.is_empty() { project? } else { label };
+    Some((label.to_string(), project.map(str::to_string)))
+}
+
+/// 把离开时段标注为手动记录
+pub fn label_entry(period: &AwayPeriod, label: &str, project: Option<&str>) -> Result<ManualEntry> {
+    let end = period.end.ok_or_else(|| anyhow!("离开时段尚未结束"))?;
+    Ok(ManualEntry::new(label, period.start, end)?.project(project.unwrap_or_default()))
+}
+
+/// 离开时段在配置时区中的简短说明，例如 `05-15 12:00 ~ 12:55（55m，闲置）`
+pub fn describe(period: &AwayPeriod, timezone: DayTimeZone) -> String {
+    let start = timezone.to_naive(period.start);
+    let end = timezone.to_naive(period.end.unwrap_or(period.start));
+    let end_format = if end.date() == start.date() {
+        "%H:%M"
+    } else {
+        "%m-%d %H:%M"
+    };
+    format!(
+        "{} ~ {}（{}，{}）",
+        start.format("%m-%d %H:%M"),
+        end.format(end_format),
+        crate::utils::time::format_duration_short(period.duration_at(period.start)),
+        period.reason.description()
+    )
+}
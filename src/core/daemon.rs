@@ -1,5 +1,7 @@
+use crate::storage::crypto::{Passphrase, PASSPHRASE_ENV};
 use anyhow::Result;
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use sysinfo::{Pid, System};
@@ -52,7 +54,16 @@ impl DaemonManager {
         None
     }
 
-    pub fn start_daemon(&mut self, interval: u64, data_file: &str) -> Result<()> {
+    /// 启动守护进程
+    ///
+    /// 数据文件加密时口令通过管道写入子进程的标准输入，不经过环境变量，
+    /// 避免同一用户的其他进程从 `/proc/<pid>/environ` 读到口令。
+    pub fn start_daemon(
+        &mut self,
+        interval: u64,
+        data_file: &str,
+        passphrase: Option<&Passphrase>,
+    ) -> Result<()> {
         if self.is_running() {
             return Err(anyhow::anyhow!("TimeTracker 守护进程已在运行"));
         }
@@ -61,7 +72,8 @@ impl DaemonManager {
         let current_exe = std::env::current_exe()?;
 
         // 启动守护进程，使用 --daemon-child 标志来避免无限递归
-        let mut child = Command::new(&current_exe)
+        let mut command = Command::new(&current_exe);
+        command
             .args([
                 "start",
                 "--interval",
@@ -70,10 +82,22 @@ impl DaemonManager {
                 data_file,
                 "--daemon-child",
             ])
+            .env_remove(PASSPHRASE_ENV)
             .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .stdin(Stdio::null())
-            .spawn()?;
+            .stderr(Stdio::null());
+        if passphrase.is_some() {
+            command.arg("--passphrase-stdin").stdin(Stdio::piped());
+        } else {
+            command.stdin(Stdio::null());
+        }
+        let mut child = command.spawn()?;
+
+        // 口令写入后立即关闭管道，子进程读到换行即可继续
+        if let (Some(passphrase), Some(mut stdin)) = (passphrase, child.stdin.take()) {
+            writeln!(stdin, "{}", passphrase.expose_secret())?;
+        }
+        // 口令已交给子进程，之后启动的其他子进程不应再继承
+        std::env::remove_var(PASSPHRASE_ENV);
 
         // 等待子进程完成守护化（短暂等待）
         std::thread::sleep(std::time::Duration::from_millis(500));
@@ -174,7 +198,12 @@ impl DaemonManager {
         Ok(())
     }
 
-    pub fn restart_daemon(&mut self, interval: u64, data_file: &str) -> Result<()> {
+    pub fn restart_daemon(
+        &mut self,
+        interval: u64,
+        data_file: &str,
+        passphrase: Option<&Passphrase>,
+    ) -> Result<()> {
         println!("重启 TimeTracker 守护进程...");
 
         // 停止现有守护进程
@@ -186,7 +215,7 @@ impl DaemonManager {
         }
 
         // 启动新的守护进程
-        self.start_daemon(interval, data_file)?;
+        self.start_daemon(interval, data_file, passphrase)?;
 
         Ok(())
     }
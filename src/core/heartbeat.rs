@@ -0,0 +1,217 @@
+// 心跳模块
+// 每个监控周期发出一次心跳，相同窗口的连续心跳在脉冲窗口内合并为同一个事件
+
+use crate::core::app_rules::{AppRules, WindowIdentity};
+use crate::core::monitor::EnhancedWindowInfo;
+use crate::core::platform::WindowInfo;
+use crate::core::title::TitleNormalizer;
+use crate::core::tracker::{new_record_id, ActivityRecord, RecordSource, WindowGeometry};
+use chrono::{DateTime, Utc};
+
+/// 默认脉冲窗口（秒）：相邻两次相同心跳的间隔不超过该值时合并
+pub const DEFAULT_PULSE_WINDOW_SECS: u64 = 5;
+
+/// 默认防抖时间（秒）：新窗口持续获得焦点达到该值后才成为当前活动
+pub const DEFAULT_DEBOUNCE_SECS: u64 = 2;
+
+/// 默认最短记录时长（秒）：短于该值的记录并入相邻记录
+pub const DEFAULT_MIN_RECORD_SECS: u64 = 2;
+
+/// 心跳：一次监控采样看到的前台窗口
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heartbeat {
+    pub timestamp: DateTime<Utc>,
+    pub app_name: String,
+    pub window_title: String,
+    /// 规范化前的原始窗口标题（未经规范化或标题未变化时为空）
+    pub raw_title: Option<String>,
+    pub process_id: u32,
+    pub app_path: Option<String>,
+    pub bundle_id: Option<String>,
+    pub window_geometry: Option<WindowGeometry>,
+    pub confidence: f32,
+    /// 窗口是否全屏
+    pub fullscreen: bool,
+}
+
+impl Heartbeat {
+    /// 由基础窗口信息创建心跳
+    pub fn from_window(window_info: WindowInfo, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            timestamp,
+            app_name: window_info.app_name,
+            window_title: window_info.window_title,
+            raw_title: None,
+            process_id: window_info.process_id,
+            app_path: None,
+            bundle_id: None,
+            window_geometry: None,
+            confidence: 0.5, // 旧系统的默认置信度
+            fullscreen: false,
+        }
+    }
+
+    /// 由增强窗口信息创建心跳
+    pub fn from_enhanced(window_info: EnhancedWindowInfo, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            timestamp,
+            app_name: window_info.app_name,
+            window_title: window_info.window_title,
+            raw_title: None,
+            process_id: window_info.process_id,
+            app_path: window_info.app_path,
+            bundle_id: window_info.bundle_id,
+            window_geometry: window_info.geometry.map(|g| WindowGeometry {
+                x: g.x,
+                y: g.y,
+                width: g.width,
+                height: g.height,
+            }),
+            confidence: window_info.confidence as f32,
+            fullscreen: window_info.fullscreen,
+        }
+    }
+
+    /// 按规则规范化窗口标题，原始标题有变化时保存在 `raw_title`
+    pub fn normalize_title(&mut self, normalizer: &TitleNormalizer) {
+        let normalized = normalizer.normalize(&self.app_name, &self.window_title);
+        if normalized != self.window_title {
+            let raw = std::mem::replace(&mut self.window_title, normalized);
+            self.raw_title = Some(raw);
+        }
+    }
+
+    /// 按应用规则把进程名、应用路径或 bundle id 统一为规则中的应用名
+    pub fn apply_app_rules(&mut self, rules: &AppRules) {
+        let identity = WindowIdentity {
+            app_name: &self.app_name,
+            window_title: &self.window_title,
+            app_path: self.app_path.as_deref(),
+            bundle_id: self.bundle_id.as_deref(),
+        };
+        if let Some(name) = rules.canonical_name(&identity) {
+            if name != self.app_name {
+                self.app_name = name.to_string();
+            }
+        }
+    }
+
+    /// 心跳是否与记录属于同一个窗口（进入或退出全屏时开始新的记录）
+    pub fn matches(&self, record: &ActivityRecord) -> bool {
+        record.app_name == self.app_name
+            && record.window_title == self.window_title
+            && record.fullscreen == self.fullscreen
+    }
+
+    /// 以该心跳开始一个新事件（开始和结束时间都是心跳时间）
+    pub fn into_record(self) -> ActivityRecord {
+        ActivityRecord {
+            id: new_record_id(),
+            app_name: self.app_name,
+            window_title: self.window_title,
+            start_time: self.timestamp,
+            end_time: Some(self.timestamp),
+            duration: 0,
+            process_id: self.process_id,
+            app_path: self.app_path,
+            bundle_id: self.bundle_id,
+            window_geometry: self.window_geometry,
+            confidence: self.confidence,
+            source: RecordSource::Tracked,
+            raw_title: self.raw_title,
+            project: None,
+            fullscreen: self.fullscreen,
+        }
+    }
+}
+
+/// 把心跳合并进当前事件，返回因此结束的事件
+///
+/// - 与当前事件相同、且距上次心跳不超过脉冲窗口：延长当前事件到心跳时间；
+/// - 窗口切换且在脉冲窗口内：当前事件在心跳时间结束，新事件从心跳时间开始，中间不留空隙；
+/// - 超过脉冲窗口（监控中断、时钟回拨）：当前事件停在最后一次心跳，中间的空档不计时。
+///
+/// 事件时长始终由心跳时间推算，不依赖当前时间。
+pub fn merge_heartbeat(
+    current: &mut Option<ActivityRecord>,
+    heartbeat: Heartbeat,
+    pulse_window: chrono::Duration,
+) -> Option<ActivityRecord> {
+    let Some(event) = current.as_mut() else {
+        *current = Some(heartbeat.into_record());
+        return None;
+    };
+
+    let last_heartbeat = event.effective_end();
+    let within_pulse = is_within_pulse(last_heartbeat, &heartbeat, pulse_window);
+    let same_key = heartbeat.matches(event);
+
+    if same_key && within_pulse {
+        event.finish_at(heartbeat.timestamp);
+        return None;
+    }
+
+    let end = if within_pulse {
+        heartbeat.timestamp
+    } else {
+        last_heartbeat
+    };
+    let mut closed = current.replace(heartbeat.into_record())?;
+    closed.finish_at(end);
+    Some(closed)
+}
+
+/// 带防抖的心跳合并，返回因此结束的事件
+///
+/// 窗口切换时新窗口先作为候选（`pending`），持续获得焦点达到 `debounce` 后才结束当前事件，
+/// 当前事件在候选的第一次心跳时结束。候选期间当前事件继续延长，因此切回原窗口或
+/// 切到其他窗口时，短暂闪过的窗口时间并入当前事件。`debounce` 为 0 时与 [`merge_heartbeat`] 相同。
+pub fn merge_heartbeat_debounced(
+    current: &mut Option<ActivityRecord>,
+    pending: &mut Option<ActivityRecord>,
+    heartbeat: Heartbeat,
+    pulse_window: chrono::Duration,
+    debounce: chrono::Duration,
+) -> Option<ActivityRecord> {
+    let Some(event) = current.as_mut() else {
+        *pending = None;
+        return merge_heartbeat(current, heartbeat, pulse_window);
+    };
+
+    // 超过脉冲窗口或相同窗口：没有需要防抖的切换
+    if !is_within_pulse(event.effective_end(), &heartbeat, pulse_window) || heartbeat.matches(event)
+    {
+        *pending = None;
+        return merge_heartbeat(current, heartbeat, pulse_window);
+    }
+
+    let timestamp = heartbeat.timestamp;
+    let candidate = match pending.take() {
+        Some(mut candidate) if heartbeat.matches(&candidate) => {
+            candidate.finish_at(timestamp);
+            candidate
+        }
+        _ => heartbeat.into_record(),
+    };
+
+    if candidate.effective_end() - candidate.start_time >= debounce {
+        let switched_at = candidate.start_time;
+        let mut closed = current.replace(candidate)?;
+        closed.finish_at(switched_at);
+        return Some(closed);
+    }
+
+    event.finish_at(timestamp);
+    *pending = Some(candidate);
+    None
+}
+
+/// 心跳距上次心跳是否在脉冲窗口内（时钟回拨视为超出）
+fn is_within_pulse(
+    last_heartbeat: DateTime<Utc>,
+    heartbeat: &Heartbeat,
+    pulse_window: chrono::Duration,
+) -> bool {
+    let gap = heartbeat.timestamp - last_heartbeat;
+    gap >= chrono::Duration::zero() && gap <= pulse_window
+}
@@ -0,0 +1,240 @@
+// 系统闲置时间模块
+// 在 Linux 上依次尝试 X11 MIT-SCREEN-SAVER 扩展、xprintidle/xssstate 和 systemd-logind 的 IdleHint，
+// 记住可用的来源，避免每次检测都重新尝试所有方式
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+#[cfg(target_os = "linux")]
+use std::process::Command;
+#[cfg(target_os = "linux")]
+use std::time::Instant;
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::screensaver,
+    rust_connection::RustConnection,
+};
+
+/// 所有来源都不可用时，重新尝试的间隔
+#[cfg(target_os = "linux")]
+const REPROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 系统闲置时间的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IdleSource {
+    /// X11 MIT-SCREEN-SAVER 扩展
+    XScreenSaver,
+    /// `xprintidle` 命令
+    Xprintidle,
+    /// `xssstate` 命令
+    Xssstate,
+    /// systemd-logind 会话的 IdleHint/IdleSinceHint
+    Logind,
+    /// macOS IOHIDSystem
+    IoKit,
+    /// Windows GetLastInputInfo
+    Win32,
+    /// 无法获取闲置时间，闲置检测不起作用
+    Unavailable,
+}
+
+impl IdleSource {
+    /// Linux 上按顺序尝试的来源
+    pub const LINUX: [IdleSource; 4] = [
+        IdleSource::XScreenSaver,
+        IdleSource::Xprintidle,
+        IdleSource::Xssstate,
+        IdleSource::Logind,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::XScreenSaver => "X11 屏幕保护扩展 (MIT-SCREEN-SAVER)",
+            Self::Xprintidle => "xprintidle",
+            Self::Xssstate => "xssstate",
+            Self::Logind => "systemd-logind IdleHint",
+            Self::IoKit => "IOHIDSystem",
+            Self::Win32 => "GetLastInputInfo",
+            Self::Unavailable => "不可用",
+        }
+    }
+
+    /// 是否能获取到闲置时间
+    pub fn is_available(&self) -> bool {
+        *self != Self::Unavailable
+    }
+}
+
+/// 解析 `busctl get-property ... IdleHint IdleSinceHint` 的输出，返回闲置时长
+///
+/// 输出形如 `b true` 和 `t 1715760000000000`（微秒，UNIX 时间）两行。会话未闲置时返回零，
+/// 输出无法识别时返回 None。
+pub fn parse_logind_idle(output: &str, now: SystemTime) -> Option<Duration> {
+    let mut lines = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let idle = match lines.next()? {
+        "b true" => true,
+        "b false" => false,
+        _ => return None,
+    };
+    let since_micros: u64 = lines.next()?.strip_prefix("t ")?.trim().parse().ok()?;
+    if !idle || since_micros == 0 {
+        return Some(Duration::ZERO);
+    }
+    let since = SystemTime::UNIX_EPOCH + Duration::from_micros(since_micros);
+    Some(now.duration_since(since).unwrap_or(Duration::ZERO))
+}
+
+/// 系统闲置时间探测器
+#[derive(Default)]
+pub struct IdleProbe {
+    /// 上次成功使用的来源
+    source: Option<IdleSource>,
+    /// 所有来源都不可用时上次尝试的时间
+    #[cfg(target_os = "linux")]
+    failed_at: Option<Instant>,
+    #[cfg(all(target_os = "linux", feature = "x11"))]
+    x11: Option<X11Idle>,
+}
+
+impl IdleProbe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前使用的来源，尚未探测时返回 None
+    pub fn source(&self) -> Option<IdleSource> {
+        self.source
+    }
+
+    /// 获取系统闲置时间及其来源，无法获取时返回零
+    #[cfg(target_os = "linux")]
+    pub fn query(&mut self) -> (Duration, IdleSource) {
+        // 优先使用上次可用的来源
+        if let Some(source) = self.source.filter(IdleSource::is_available) {
+            if let Some(idle) = self.query_source(source) {
+                return (idle, source);
+            }
+            log::info!("闲置时间来源 {} 不再可用，重新探测", source.description());
+        }
+
+        if self.source == Some(IdleSource::Unavailable)
+            && self
+                .failed_at
+                .is_some_and(|failed| failed.elapsed() < REPROBE_INTERVAL)
+        {
+            return (Duration::ZERO, IdleSource::Unavailable);
+        }
+
+        for source in IdleSource::LINUX {
+            if let Some(idle) = self.query_source(source) {
+                log::info!("使用 {} 获取系统闲置时间", source.description());
+                self.source = Some(source);
+                self.failed_at = None;
+                return (idle, source);
+            }
+        }
+
+        if self.source != Some(IdleSource::Unavailable) {
+            log::warn!("无法获取系统闲置时间，闲置检测不起作用");
+        }
+        self.source = Some(IdleSource::Unavailable);
+        self.failed_at = Some(Instant::now());
+        (Duration::ZERO, IdleSource::Unavailable)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn query_source(&mut self, source: IdleSource) -> Option<Duration> {
+        match source {
+            IdleSource::XScreenSaver => self.query_x11(),
+            IdleSource::Xprintidle => command_output("xprintidle", &[])?
+                .parse()
+                .ok()
+                .map(Duration::from_millis),
+            IdleSource::Xssstate => command_output("xssstate", &["-i"])?
+                .parse()
+                .ok()
+                .map(Duration::from_secs),
+            IdleSource::Logind => parse_logind_idle(
+                &command_output(
+                    "busctl",
+                    &[
+                        "--system",
+                        "get-property",
+                        "org.freedesktop.login1",
+                        "/org/freedesktop/login1/session/auto",
+                        "org.freedesktop.login1.Session",
+                        "IdleHint",
+                        "IdleSinceHint",
+                    ],
+                )?,
+                SystemTime::now(),
+            ),
+            IdleSource::IoKit | IdleSource::Win32 | IdleSource::Unavailable => None,
+        }
+    }
+
+    #[cfg(all(target_os = "linux", feature = "x11"))]
+    fn query_x11(&mut self) -> Option<Duration> {
+        if self.x11.is_none() {
+            self.x11 = X11Idle::connect();
+        }
+        let idle = self.x11.as_ref()?.query();
+        if idle.is_none() {
+            // 连接可能已断开，下次重新连接
+            self.x11 = None;
+        }
+        idle
+    }
+
+    #[cfg(all(target_os = "linux", not(feature = "x11")))]
+    fn query_x11(&mut self) -> Option<Duration> {
+        None
+    }
+}
+
+/// 运行命令并返回去掉首尾空白的标准输出，命令不存在或失败时返回 None
+#[cfg(target_os = "linux")]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 通过 MIT-SCREEN-SAVER 扩展查询闲置时间的 X11 连接
+#[cfg(all(target_os = "linux", feature = "x11"))]
+struct X11Idle {
+    connection: RustConnection,
+    root: u32,
+}
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+impl X11Idle {
+    fn connect() -> Option<Self> {
+        // Wayland 下 XWayland 只能看到 X11 程序的输入，闲置时间不可信
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() || std::env::var_os("DISPLAY").is_none() {
+            return None;
+        }
+        let (connection, screen) = x11rb::connect(None).ok()?;
+        connection
+            .extension_information(screensaver::X11_EXTENSION_NAME)
+            .ok()
+            .flatten()?;
+        let root = connection.setup().roots.get(screen)?.root;
+        Some(Self { connection, root })
+    }
+
+    fn query(&self) -> Option<Duration> {
+        let reply = screensaver::query_info(&self.connection, self.root)
+            .ok()?
+            .reply()
+            .ok()?;
+        Some(Duration::from_millis(reply.ms_since_user_input.into()))
+    }
+}
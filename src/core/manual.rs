@@ -0,0 +1,176 @@
+// 手动记录模块
+// 为离开电脑的工作（白板讨论、电话、外出拜访）创建手动记录，并处理与已有记录的时间重叠
+
+use crate::core::tracker::{new_record_id, ActivityRecord, RecordSource};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+
+/// 一条手动记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManualEntry {
+    /// 记录名称，作为应用名显示
+    pub label: String,
+    /// 补充说明，作为窗口标题显示，为空时使用名称
+    pub note: Option<String>,
+    /// 所属项目
+    pub project: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl ManualEntry {
+    pub fn new(label: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Self> {
+        let label = label.trim();
+        if label.is_empty() {
+            return Err(anyhow!("手动记录的名称不能为空"));
+        }
+        if end <= start {
+            return Err(anyhow!("手动记录的结束时间必须晚于开始时间"));
+        }
+        Ok(Self {
+            label: label.to_string(),
+            note: None,
+            project: None,
+            start,
+            end,
+        })
+    }
+
+    /// 设置补充说明
+    pub fn note(mut self, note: &str) -> Self {
+        self.note = Some(note.trim().to_string()).filter(|note| !note.is_empty());
+        self
+    }
+
+    /// 设置所属项目
+    pub fn project(mut self, project: &str) -> Self {
+        self.project = Some(project.trim().to_string()).filter(|project| !project.is_empty());
+        self
+    }
+
+    /// 时长（秒）
+    pub fn duration(&self) -> u64 {
+        (self.end - self.start).num_seconds().max(0) as u64
+    }
+
+    /// 转换为活动记录，来源标记为手动
+    pub fn to_record(&self) -> ActivityRecord {
+        ActivityRecord {
+            id: new_record_id(),
+            app_name: self.label.clone(),
+            window_title: self.note.clone().unwrap_or_else(|| self.label.clone()),
+            start_time: self.start,
+            end_time: Some(self.end),
+            duration: self.duration(),
+            process_id: 0,
+            app_path: None,
+            bundle_id: None,
+            window_geometry: None,
+            confidence: 1.0,
+            source: RecordSource::Manual,
+            raw_title: None,
+            project: self.project.clone(),
+            fullscreen: false,
+        }
+    }
+}
+
+/// 由开始时间、结束时间和时长中的任意两项（或只给开始时间/时长，结束于现在）确定时间段
+pub fn resolve_span(
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    duration: Option<u64>,
+    now: DateTime<Utc>,
+) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let duration = duration.map(|secs| Duration::seconds(secs as i64));
+    let (start, end) = match (start, end, duration) {
+        (Some(_), Some(_), Some(_)) => {
+            return Err(anyhow!("开始时间、结束时间和时长只能指定其中两项"));
+        }
+        (Some(start), Some(end), None) => (start, end),
+        (Some(start), None, Some(duration)) => (start, start + duration),
+        (None, Some(end), Some(duration)) => (end - duration, end),
+        (None, None, Some(duration)) => (now - duration, now),
+        (Some(start), None, None) => (start, now),
+        (None, _, None) => return Err(anyhow!("请指定开始时间或时长")),
+    };
+
+    if end <= start {
+        return Err(anyhow!("结束时间必须晚于开始时间"));
+    }
+    if end > now {
+        return Err(anyhow!("不能添加结束于未来的手动记录"));
+    }
+    Ok((start, end))
+}
+
+/// 与已有记录重叠时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlapPolicy {
+    /// 存在重叠时不添加
+    #[default]
+    Reject,
+    /// 保留重叠的记录，直接添加
+    Keep,
+    /// 裁剪重叠的记录，时间段内只保留手动记录
+    Trim,
+}
+
+impl OverlapPolicy {
+    /// 由名称解析（reject、keep、trim）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "reject" => Some(Self::Reject),
+            "keep" => Some(Self::Keep),
+            "trim" => Some(Self::Trim),
+            _ => None,
+        }
+    }
+}
+
+/// 与 [start, end) 重叠的记录
+pub fn find_overlaps(
+    records: &[ActivityRecord],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<&ActivityRecord> {
+    records
+        .iter()
+        .filter(|record| record.start_time < end && record.effective_end() > start)
+        .collect()
+}
+
+/// 裁剪重叠记录的结果
+#[derive(Debug, Clone, Default)]
+pub struct OverlapResolution {
+    /// 被裁剪的记录（同一 id，时间和时长已更新）
+    pub updated: Vec<ActivityRecord>,
+    /// 跨越整个时间段的记录被拆开后新增的后半段
+    pub added: Vec<ActivityRecord>,
+    /// 完全落在时间段内而删除的记录 id
+    pub removed: Vec<String>,
+}
+
+/// 把记录中落在 [start, end) 内的部分裁掉，时长按保留部分的比例折算
+pub fn trim_overlaps(
+    records: &[&ActivityRecord],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> OverlapResolution {
+    let mut resolution = OverlapResolution::default();
+    for record in records {
+        let before = record.clipped(record.start_time, start);
+        let after = record.clipped(end, record.effective_end().max(end));
+
+        match (before, after) {
+            (None, None) => resolution.removed.push(record.id.clone()),
+            (Some(piece), None) | (None, Some(piece)) => resolution.updated.push(piece),
+            (Some(before), Some(mut after)) => {
+                after.id = new_record_id();
+                resolution.updated.push(before);
+                resolution.added.push(after);
+            }
+        }
+    }
+    resolution
+}
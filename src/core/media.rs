@@ -0,0 +1,192 @@
+// 媒体播放检测模块
+// 通过 D-Bus 会话总线读取 MPRIS 媒体播放器的 PlaybackStatus，判断是否确实在播放媒体，
+// 并记录播放器名称和曲目
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// MPRIS 播放器在总线上的名称前缀
+pub const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// 正在播放的媒体
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaInfo {
+    /// 播放器名称（MPRIS Identity），例如 `VLC media player`
+    pub player: String,
+    /// 曲目，有艺术家时为 `艺术家 - 标题`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<String>,
+}
+
+impl MediaInfo {
+    /// 简短说明，例如 `VLC media player: Big Buck Bunny`
+    pub fn describe(&self) -> String {
+        match &self.track {
+            Some(track) => format!("{}: {}", self.player, track),
+            None => self.player.clone(),
+        }
+    }
+}
+
+/// MPRIS 播放状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+impl PlaybackStatus {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Playing" => Some(Self::Playing),
+            "Paused" => Some(Self::Paused),
+            "Stopped" => Some(Self::Stopped),
+            _ => None,
+        }
+    }
+}
+
+/// 取出 `dbus-send --print-reply` 输出行中的字符串值（`string "..."`）
+fn string_value(line: &str) -> Option<&str> {
+    let start = line.find("string \"")? + "string \"".len();
+    let end = line.rfind('"')?;
+    (end >= start).then(|| &line[start..end])
+}
+
+/// 解析 `ListNames` 的输出，返回 MPRIS 播放器的总线名称
+pub fn parse_player_names(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(string_value)
+        .filter(|name| name.starts_with(MPRIS_PREFIX))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 解析 `Properties.Get` 返回的字符串属性（`variant string "..."`）
+pub fn parse_string_property(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find(|line| line.trim_start().starts_with("variant"))
+        .and_then(string_value)
+        .map(str::to_string)
+}
+
+/// 解析 `Metadata` 属性，返回 `艺术家 - 标题` 或标题
+pub fn parse_track(output: &str) -> Option<String> {
+    let lines: Vec<&str> = output.lines().collect();
+    // 键所在行之后的第一个字符串值
+    let value_of = |key: &str| {
+        let index = lines
+            .iter()
+            .position(|line| string_value(line) == Some(key))?;
+        lines[index + 1..]
+            .iter()
+            .find_map(|line| string_value(line))
+            .filter(|value| !value.is_empty())
+    };
+
+    let title = value_of("xesam:title")?;
+    Some(match value_of("xesam:artist") {
+        Some(artist) => format!("{} - {}", artist, title),
+        None => title.to_string(),
+    })
+}
+
+/// MPRIS 客户端，通过 `dbus-send` 访问会话总线（或指定地址的总线）
+#[derive(Debug, Clone, Default)]
+pub struct MprisClient {
+    /// 总线地址，为 None 时使用会话总线
+    address: Option<String>,
+}
+
+impl MprisClient {
+    /// 使用当前用户的会话总线
+    pub fn session() -> Self {
+        Self::default()
+    }
+
+    /// 使用指定地址的总线，例如测试中的私有总线
+    pub fn with_address(address: impl Into<String>) -> Self {
+        Self {
+            address: Some(address.into()),
+        }
+    }
+
+    /// 正在播放的媒体，没有播放器在播放时返回 None；无法访问总线时返回错误
+    pub fn playing(&self) -> Result<Option<MediaInfo>> {
+        let names = parse_player_names(&self.call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus.ListNames",
+            &[],
+        )?);
+
+        for name in names {
+            // 单个播放器没有响应时跳过
+            let status = self
+                .property(&name, PLAYER_INTERFACE, "PlaybackStatus")
+                .ok()
+                .and_then(|output| parse_string_property(&output))
+                .and_then(|status| PlaybackStatus::parse(&status));
+            if status != Some(PlaybackStatus::Playing) {
+                continue;
+            }
+
+            let player = self
+                .property(&name, ROOT_INTERFACE, "Identity")
+                .ok()
+                .and_then(|output| parse_string_property(&output))
+                .unwrap_or_else(|| name.trim_start_matches(MPRIS_PREFIX).to_string());
+            let track = self
+                .property(&name, PLAYER_INTERFACE, "Metadata")
+                .ok()
+                .and_then(|output| parse_track(&output));
+            return Ok(Some(MediaInfo { player, track }));
+        }
+        Ok(None)
+    }
+
+    fn property(&self, name: &str, interface: &str, property: &str) -> Result<String> {
+        self.call(
+            name,
+            MPRIS_PATH,
+            "org.freedesktop.DBus.Properties.Get",
+            &[
+                &format!("string:{}", interface),
+                &format!("string:{}", property),
+            ],
+        )
+    }
+
+    fn call(&self, dest: &str, path: &str, method: &str, args: &[&str]) -> Result<String> {
+        let bus = match &self.address {
+            Some(address) => format!("--bus={}", address),
+            None => "--session".to_string(),
+        };
+        let output = Command::new("dbus-send")
+            .arg(bus)
+            .arg("--print-reply")
+            .arg("--reply-timeout=1000")
+            .arg(format!("--dest={}", dest))
+            .arg(path)
+            .arg(method)
+            .args(args)
+            .output()
+            .map_err(|e| anyhow!("无法运行 dbus-send: {}", e))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "D-Bus 调用 {} 失败: {}",
+                method,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
@@ -1,8 +1,17 @@
 // 核心模块 - 包含时间追踪的核心逻辑
 
 pub mod activity_detector;
+pub mod app_rules;
+pub mod away;
 pub mod daemon;
 pub mod enhanced_platform;
+pub mod heartbeat;
+pub mod idle;
+pub mod manual;
+pub mod media;
 pub mod monitor;
 pub mod platform;
+pub mod power;
+pub mod status;
+pub mod title;
 pub mod tracker;
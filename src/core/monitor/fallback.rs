@@ -319,6 +319,7 @@ impl EnhancedWindowMonitor for FallbackMonitor {
                     geometry,
                     timestamp: SystemTime::now(),
                     confidence,
+                    fullscreen: false,
                 };
 
                 // 更新缓存
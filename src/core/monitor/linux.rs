@@ -13,12 +13,44 @@ use std::time::{Duration, SystemTime};
 #[cfg(all(target_os = "linux", feature = "x11"))]
 use x11rb::{connection::Connection, protocol::xproto::*, rust_connection::RustConnection};
 
+/// 通过X11获取的活动窗口
+#[cfg(all(target_os = "linux", feature = "x11"))]
+struct X11Window {
+    title: String,
+    pid: u32,
+    geometry: Option<WindowGeometry>,
+    fullscreen: bool,
+}
+
+/// `_NET_WM_STATE` 和 `_NET_WM_STATE_FULLSCREEN` 原子
+#[cfg(all(target_os = "linux", feature = "x11"))]
+#[derive(Debug, Clone, Copy)]
+struct FullscreenAtoms {
+    state: Atom,
+    fullscreen: Atom,
+}
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+impl FullscreenAtoms {
+    fn intern(conn: &RustConnection) -> Option<Self> {
+        let state = conn.intern_atom(false, b"_NET_WM_STATE").ok()?;
+        let fullscreen = conn.intern_atom(false, b"_NET_WM_STATE_FULLSCREEN").ok()?;
+        Some(Self {
+            state: state.reply().ok()?.atom,
+            fullscreen: fullscreen.reply().ok()?.atom,
+        })
+    }
+}
+
 /// Linux平台窗口监控器
 pub struct LinuxMonitor {
     #[cfg(target_os = "linux")]
     system: sysinfo::System,
     #[cfg(all(target_os = "linux", feature = "x11"))]
     x11_connection: Option<RustConnection>,
+    /// 连接时预先获取的全屏状态原子，获取失败时为 None
+    #[cfg(all(target_os = "linux", feature = "x11"))]
+    fullscreen_atoms: Option<FullscreenAtoms>,
     #[cfg(target_os = "linux")]
     cache: Option<EnhancedWindowInfo>,
     #[cfg(target_os = "linux")]
@@ -48,12 +80,16 @@ impl LinuxMonitor {
         } else {
             None
         };
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        let fullscreen_atoms = x11_connection.as_ref().and_then(FullscreenAtoms::intern);
 
         Self {
             #[cfg(target_os = "linux")]
             system: sysinfo::System::new(),
             #[cfg(all(target_os = "linux", feature = "x11"))]
             x11_connection,
+            #[cfg(all(target_os = "linux", feature = "x11"))]
+            fullscreen_atoms,
             #[cfg(target_os = "linux")]
             cache: None,
             #[cfg(target_os = "linux")]
@@ -79,7 +115,7 @@ impl LinuxMonitor {
 
     /// 使用X11获取活动窗口信息
     #[cfg(all(target_os = "linux", feature = "x11"))]
-    fn get_active_window_x11(&self) -> Result<Option<(String, u32, Option<WindowGeometry>)>> {
+    fn get_active_window_x11(&self) -> Result<Option<X11Window>> {
         let conn = self
             .x11_connection
             .as_ref()
@@ -121,7 +157,15 @@ impl LinuxMonitor {
             // 获取窗口几何信息
             let geometry = self.get_window_geometry_x11(conn, window_id)?;
 
-            Ok(Some((title, pid, geometry)))
+            // 窗口是否全屏
+            let fullscreen = self.get_window_fullscreen_x11(conn, window_id);
+
+            Ok(Some(X11Window {
+                title,
+                pid,
+                geometry,
+                fullscreen,
+            }))
         } else {
             Ok(None)
         }
@@ -193,6 +237,22 @@ impl LinuxMonitor {
         }))
     }
 
+    /// 窗口的 `_NET_WM_STATE` 是否包含 `_NET_WM_STATE_FULLSCREEN`，无法读取时视为不全屏
+    #[cfg(all(target_os = "linux", feature = "x11"))]
+    fn get_window_fullscreen_x11(&self, conn: &RustConnection, window_id: u32) -> bool {
+        let Some(atoms) = self.fullscreen_atoms else {
+            return false;
+        };
+        conn.get_property(false, window_id, atoms.state, AtomEnum::ATOM, 0, 32)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .is_some_and(|reply| {
+                reply
+                    .value32()
+                    .is_some_and(|mut state| state.any(|atom| atom == atoms.fullscreen))
+            })
+    }
+
     /// 使用命令行工具获取活动窗口信息（回退方法）
     #[cfg(target_os = "linux")]
     #[allow(dead_code)] // 保留作为备用实现
@@ -278,16 +338,16 @@ impl EnhancedWindowMonitor for LinuxMonitor {
             return Ok(self.cache.clone());
         }
 
-        let (title, pid, geometry) = match self.display_server {
+        let (title, pid, geometry, fullscreen) = match self.display_server {
             DisplayServer::X11 => {
                 #[cfg(all(target_os = "linux", feature = "x11"))]
                 {
-                    if let Some((title, pid, geometry)) = self.get_active_window_x11()? {
-                        (title, pid, geometry)
+                    if let Some(window) = self.get_active_window_x11()? {
+                        (window.title, window.pid, window.geometry, window.fullscreen)
                     } else {
                         // X11失败，尝试命令行工具
                         if let Some((title, pid)) = self.get_active_window_fallback()? {
-                            (title, pid, None)
+                            (title, pid, None, false)
                         } else {
                             return Ok(None);
                         }
@@ -297,7 +357,7 @@ impl EnhancedWindowMonitor for LinuxMonitor {
                 {
                     // 没有X11支持，使用命令行工具
                     if let Some((title, pid)) = self.get_active_window_fallback()? {
-                        (title, pid, None)
+                        (title, pid, None, false)
                     } else {
                         return Ok(None);
                     }
@@ -306,7 +366,7 @@ impl EnhancedWindowMonitor for LinuxMonitor {
             DisplayServer::Wayland => {
                 // Wayland支持有限，使用命令行工具
                 if let Some((title, pid)) = self.get_active_window_fallback()? {
-                    (title, pid, None)
+                    (title, pid, None, false)
                 } else {
                     return Ok(None);
                 }
@@ -319,7 +379,7 @@ impl EnhancedWindowMonitor for LinuxMonitor {
         let (app_name, app_path) = self.get_process_info(pid);
 
         // 修正应用名称，提取真实的应用名称
-        let corrected_app_name = correct_app_name(&app_name, &title, pid);
+        let corrected_app_name = correct_app_name(&app_name, pid);
 
         // 计算置信度
         let confidence = match self.display_server {
@@ -338,6 +398,7 @@ impl EnhancedWindowMonitor for LinuxMonitor {
             geometry,
             timestamp: SystemTime::now(),
             confidence,
+            fullscreen,
         };
 
         // 更新缓存
@@ -414,6 +475,9 @@ impl EnhancedWindowMonitor for LinuxMonitor {
                         capabilities.push("Native X11 API".to_string());
                         capabilities.push("Window geometry".to_string());
                     }
+                    if self.fullscreen_atoms.is_some() {
+                        capabilities.push("Fullscreen state".to_string());
+                    }
                 }
             }
             DisplayServer::Wayland => {
@@ -170,7 +170,7 @@ impl MacOSMonitor {
             .map(|s| s.to_string());
 
         // 修正应用名称，提取真实的应用名称
-        let corrected_app_name = correct_app_name(&app_name, &window_title, process_id);
+        let corrected_app_name = correct_app_name(&app_name, process_id);
 
         // 计算置信度
         let confidence =
@@ -193,6 +193,7 @@ impl MacOSMonitor {
             geometry: None, // macOS几何信息需要额外的API调用
             timestamp: SystemTime::now(),
             confidence,
+            fullscreen: false,
         })
     }
 
@@ -275,6 +276,7 @@ impl MacOSMonitor {
     }
 }
 
+#[cfg(target_os = "macos")]
 impl EnhancedWindowMonitor for MacOSMonitor {
     fn get_active_window(&mut self) -> Result<Option<EnhancedWindowInfo>> {
         // 检查缓存
@@ -38,6 +38,8 @@ pub struct EnhancedWindowInfo {
     pub timestamp: SystemTime,
     /// 置信度 (0.0-1.0)
     pub confidence: f64,
+    /// 窗口是否全屏（X11 `_NET_WM_STATE_FULLSCREEN`）
+    pub fullscreen: bool,
 }
 
 /// 权限状态
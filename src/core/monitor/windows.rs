@@ -154,7 +154,7 @@ impl EnhancedWindowMonitor for WindowsMonitor {
             };
 
             // 修正应用名称，提取真实的应用名称
-            let corrected_app_name = correct_app_name(&app_name, &window_title, process_id);
+            let corrected_app_name = correct_app_name(&app_name, process_id);
 
             // 获取窗口几何信息
             let geometry = self.get_window_geometry(hwnd);
@@ -177,6 +177,7 @@ impl EnhancedWindowMonitor for WindowsMonitor {
                 geometry,
                 timestamp: SystemTime::now(),
                 confidence,
+                fullscreen: false,
             };
 
             // 更新缓存
@@ -1,6 +1,5 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 /// 窗口信息结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,7 +12,7 @@ pub struct WindowInfo {
 impl WindowInfo {
     pub fn new(app_name: String, window_title: String, process_id: u32) -> Self {
         // 修正应用名称
-        let corrected_app_name = correct_app_name(&app_name, &window_title, process_id);
+        let corrected_app_name = correct_app_name(&app_name, process_id);
 
         Self {
             app_name: corrected_app_name,
@@ -23,8 +22,10 @@ impl WindowInfo {
     }
 }
 
-/// 修正应用名称，将通用进程名映射为实际应用名
-pub fn correct_app_name(app_name: &str, window_title: &str, process_id: u32) -> String {
+/// 修正应用名称，尽量用进程路径得到实际应用名
+///
+/// electron、java 等通用进程到具体应用的映射由应用规则（[`crate::core::app_rules`]）在记录时完成。
+pub fn correct_app_name(app_name: &str, process_id: u32) -> String {
     // 首先尝试通过进程路径提取真实应用名称
     if let Some(extracted_name) = identify_app_by_process(process_id) {
         // 如果提取出的名称不是通用进程名，直接返回
@@ -47,80 +48,6 @@ pub fn correct_app_name(app_name: &str, window_title: &str, process_id: u32) ->
         }
     }
 
-    // 创建应用名称映射表
-    let mut app_mappings = HashMap::new();
-
-    // Electron应用映射
-    app_mappings.insert(
-        "electron",
-        vec![
-            ("Visual Studio Code", "VSCode"),
-            ("Code", "VSCode"),
-            ("Discord", "Discord"),
-            ("Slack", "Slack"),
-            ("WhatsApp", "WhatsApp"),
-            ("Figma", "Figma"),
-            ("Notion", "Notion"),
-            ("Obsidian", "Obsidian"),
-            ("Spotify", "Spotify"),
-        ],
-    );
-
-    // 其他通用进程名映射
-    app_mappings.insert("stable", vec![("Warp", "Warp")]);
-
-    app_mappings.insert(
-        "chrome",
-        vec![("Google Chrome", "Chrome"), ("Chromium", "Chromium")],
-    );
-
-    app_mappings.insert(
-        "firefox",
-        vec![("Mozilla Firefox", "Firefox"), ("Firefox", "Firefox")],
-    );
-
-    app_mappings.insert(
-        "java",
-        vec![
-            ("IntelliJ IDEA", "IntelliJ IDEA"),
-            ("PyCharm", "PyCharm"),
-            ("WebStorm", "WebStorm"),
-            ("Android Studio", "Android Studio"),
-            ("Eclipse", "Eclipse"),
-        ],
-    );
-
-    // 检查是否需要映射
-    let app_lower = app_name.to_lowercase();
-
-    // 首先检查直接映射
-    if let Some(mappings) = app_mappings.get(app_lower.as_str()) {
-        for (title_pattern, mapped_name) in mappings {
-            if window_title.contains(title_pattern) {
-                return mapped_name.to_string();
-            }
-        }
-    }
-
-    // 通过窗口标题进行智能识别
-    let title_lower = window_title.to_lowercase();
-
-    // VSCode相关
-    if (app_lower.contains("electron") || app_lower.contains("code"))
-        && (title_lower.contains("visual studio code")
-            || title_lower.contains("vscode")
-            || window_title.contains(" - Visual Studio Code"))
-    {
-        return "VSCode".to_string();
-    }
-
-    // Warp终端
-    if (app_lower == "stable" || app_lower.contains("warp"))
-        && (title_lower.contains("warp") || window_title.contains("Warp"))
-    {
-        return "Warp".to_string();
-    }
-
     // 通过进程路径进行识别
     if let Some(real_name) = identify_app_by_process(process_id) {
         return real_name;
@@ -424,7 +351,7 @@ fn get_active_window_legacy() -> Result<WindowInfo> {
             }
 
             // 修正应用名称，提取真实的应用名称
-            let corrected_app_name = correct_app_name(&app_name, &window_title, process_id);
+            let corrected_app_name = correct_app_name(&app_name, process_id);
 
             Ok(WindowInfo::new(
                 corrected_app_name,
@@ -925,7 +852,7 @@ mod linux {
 
             let app_name = if process_id > 0 {
                 if let Some(process) = self.system.process(sysinfo::Pid::from_u32(process_id)) {
-                    process.name().to_string()
+                    process.name().to_string_lossy().to_string()
                 } else {
                     "Unknown".to_string()
                 }
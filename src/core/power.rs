@@ -0,0 +1,263 @@
+// 电源和锁屏感知模块
+// 检测系统休眠、唤醒、锁屏和解锁，避免把休眠或锁屏期间的时间算作使用时间
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+/// 两次采样之间的墙上时间至少相差多少秒才视为时钟跳变
+pub const CLOCK_JUMP_MIN_SECS: i64 = 30;
+
+/// 电源或会话事件（来自 systemd-logind）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    /// 即将休眠（`PrepareForSleep(true)`）
+    Sleep,
+    /// 从休眠中唤醒（`PrepareForSleep(false)`）
+    Resume,
+    /// 会话锁屏（`Lock` 信号或 `LockedHint` 变为 true）
+    Lock,
+    /// 会话解锁（`Unlock` 信号或 `LockedHint` 变为 false）
+    Unlock,
+}
+
+/// 离开原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AwayReason {
+    /// 系统休眠
+    Sleep,
+    /// 屏幕锁定
+    Lock,
+    /// 两次采样之间墙上时间异常跳变（未收到信号的休眠、手动调整时钟等）
+    ClockJump,
+    /// 没有键盘鼠标输入超过闲置超时时间
+    Idle,
+}
+
+impl AwayReason {
+    /// 获取原因描述
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Sleep => "休眠",
+            Self::Lock => "锁屏",
+            Self::ClockJump => "时钟跳变",
+            Self::Idle => "闲置",
+        }
+    }
+}
+
+/// 离开时段：休眠、锁屏、时钟跳变或闲置造成的空档，不计入任何活动
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AwayPeriod {
+    pub start: DateTime<Utc>,
+    /// 尚未返回时为 None
+    pub end: Option<DateTime<Utc>>,
+    pub reason: AwayReason,
+}
+
+impl AwayPeriod {
+    /// 离开时长（秒），尚未返回时计算到 `now`
+    pub fn duration_at(&self, now: DateTime<Utc>) -> u64 {
+        (self.end.unwrap_or(now) - self.start).num_seconds().max(0) as u64
+    }
+}
+
+/// 两次采样之间的墙上时间是否发生跳变
+///
+/// 正常情况下间隔约等于采样间隔；系统休眠后唤醒或时钟被向前调整时间隔会远大于采样间隔，
+/// 时钟被向后调整时间隔为负。阈值为采样间隔的 3 倍，且不小于 [`CLOCK_JUMP_MIN_SECS`] 秒。
+pub fn is_clock_jump(last_tick: DateTime<Utc>, now: DateTime<Utc>, interval: Duration) -> bool {
+    let min = chrono::Duration::seconds(CLOCK_JUMP_MIN_SECS);
+    let threshold = chrono::Duration::from_std(interval * 3)
+        .unwrap_or(min)
+        .max(min);
+    let gap = now - last_tick;
+    gap > threshold || -gap > min
+}
+
+/// `dbus-monitor` 输出解析器
+///
+/// 信号行形如 `signal time=... path=/org/freedesktop/login1; interface=org.freedesktop.login1.Manager; member=PrepareForSleep`，
+/// `PrepareForSleep` 的参数在下一行（`boolean true` 表示即将休眠）。
+///
+/// 大多数锁屏程序解锁时只把会话的 `LockedHint` 改回 false，不会触发 `Unlock` 信号，
+/// 因此同时解析会话的 `PropertiesChanged` 信号中的 `LockedHint`。
+#[derive(Debug, Default)]
+pub struct LogindSignalParser {
+    awaiting_sleep_argument: bool,
+    /// 正在读取 `org.freedesktop.login1.Session` 的 `PropertiesChanged` 参数
+    in_session_properties: bool,
+    /// 上一行是 `LockedHint` 键，等待它的值
+    awaiting_locked_hint: bool,
+}
+
+impl LogindSignalParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 解析一行输出，识别出完整事件时返回
+    pub fn parse_line(&mut self, line: &str) -> Option<PowerEvent> {
+        let line = line.trim();
+
+        if line.starts_with("signal ") {
+            self.awaiting_sleep_argument = false;
+            self.in_session_properties = false;
+            self.awaiting_locked_hint = false;
+            let member = line
+                .split(';')
+                .find_map(|part| part.trim().strip_prefix("member="))?;
+            let interface = line
+                .split(';')
+                .find_map(|part| part.trim().strip_prefix("interface="))
+                .unwrap_or_default();
+            return match (interface, member) {
+                ("org.freedesktop.login1.Manager", "PrepareForSleep") => {
+                    self.awaiting_sleep_argument = true;
+                    None
+                }
+                ("org.freedesktop.login1.Session", "Lock") => Some(PowerEvent::Lock),
+                ("org.freedesktop.login1.Session", "Unlock") => Some(PowerEvent::Unlock),
+                ("org.freedesktop.DBus.Properties", "PropertiesChanged")
+                    if line.contains("path=/org/freedesktop/login1/session/") =>
+                {
+                    self.in_session_properties = true;
+                    None
+                }
+                _ => None,
+            };
+        }
+
+        if self.in_session_properties {
+            // 第一个参数是接口名，只关心会话接口
+            if line.starts_with("string \"org.freedesktop.") {
+                self.in_session_properties = line == "string \"org.freedesktop.login1.Session\"";
+                return None;
+            }
+            if line == "string \"LockedHint\"" {
+                self.awaiting_locked_hint = true;
+                return None;
+            }
+            if self.awaiting_locked_hint {
+                if let Some(value) = line.strip_prefix("variant") {
+                    self.awaiting_locked_hint = false;
+                    return match value.trim() {
+                        "boolean true" => Some(PowerEvent::Lock),
+                        "boolean false" => Some(PowerEvent::Unlock),
+                        _ => None,
+                    };
+                }
+            }
+            return None;
+        }
+
+        if self.awaiting_sleep_argument {
+            let event = match line {
+                "boolean true" => Some(PowerEvent::Sleep),
+                "boolean false" => Some(PowerEvent::Resume),
+                _ => return None,
+            };
+            self.awaiting_sleep_argument = false;
+            return event;
+        }
+
+        None
+    }
+}
+
+/// 电源和锁屏事件监听器
+///
+/// Linux 上在后台运行 `dbus-monitor --system` 订阅 systemd-logind 的
+/// `PrepareForSleep`、`Lock`、`Unlock` 信号和会话 `LockedHint` 属性的变化；其他平台或无法启动时不产生事件，
+/// 此时只依赖时钟跳变检测。
+pub struct PowerEventListener {
+    receiver: Option<Receiver<PowerEvent>>,
+    #[cfg(target_os = "linux")]
+    child: Option<std::process::Child>,
+}
+
+impl PowerEventListener {
+    /// 不产生任何事件的监听器
+    pub fn disabled() -> Self {
+        Self {
+            receiver: None,
+            #[cfg(target_os = "linux")]
+            child: None,
+        }
+    }
+
+    /// 启动监听，失败时返回不产生事件的监听器
+    #[cfg(target_os = "linux")]
+    pub fn spawn() -> Self {
+        use std::io::{BufRead, BufReader};
+        use std::process::{Command, Stdio};
+
+        let child = Command::new("dbus-monitor")
+            .args([
+                "--system",
+                "type='signal',interface='org.freedesktop.login1.Manager',member='PrepareForSleep'",
+                "type='signal',interface='org.freedesktop.login1.Session',member='Lock'",
+                "type='signal',interface='org.freedesktop.login1.Session',member='Unlock'",
+                "type='signal',interface='org.freedesktop.DBus.Properties',member='PropertiesChanged',arg0='org.freedesktop.login1.Session'",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                log::info!("无法启动 dbus-monitor，休眠和锁屏只通过时钟跳变检测: {}", e);
+                return Self::disabled();
+            }
+        };
+
+        let Some(stdout) = child.stdout.take() else {
+            return Self::disabled();
+        };
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut parser = LogindSignalParser::new();
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if let Some(event) = parser.parse_line(&line) {
+                    log::info!("收到系统事件: {:?}", event);
+                    if sender.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            receiver: Some(receiver),
+            child: Some(child),
+        }
+    }
+
+    /// 启动监听，失败时返回不产生事件的监听器
+    #[cfg(not(target_os = "linux"))]
+    pub fn spawn() -> Self {
+        Self::disabled()
+    }
+
+    /// 取出自上次调用以来收到的事件
+    pub fn poll(&self) -> Vec<PowerEvent> {
+        self.receiver
+            .as_ref()
+            .map(|receiver| receiver.try_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for PowerEventListener {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
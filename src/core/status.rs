@@ -0,0 +1,180 @@
+// 状态时间线模块
+// 把活跃、闲置、锁屏和被动观看视频记录为连续的时间段，用于统计开机时间和活跃时间
+
+use crate::core::media::MediaInfo;
+use crate::utils::time::DayBoundary;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 用户状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusKind {
+    /// 有键盘鼠标输入
+    Active,
+    /// 没有输入，但前台正在播放视频
+    VideoPassive,
+    /// 没有输入超过闲置超时时间
+    Idle,
+    /// 屏幕锁定
+    Locked,
+}
+
+impl StatusKind {
+    pub const ALL: [StatusKind; 4] = [
+        StatusKind::Active,
+        StatusKind::VideoPassive,
+        StatusKind::Idle,
+        StatusKind::Locked,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::VideoPassive => "video_passive",
+            Self::Idle => "idle",
+            Self::Locked => "locked",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Active => "活跃",
+            Self::VideoPassive => "观看视频",
+            Self::Idle => "闲置",
+            Self::Locked => "锁屏",
+        }
+    }
+
+    /// 时间线中使用的字符
+    pub fn symbol(&self) -> char {
+        match self {
+            Self::Active => '█',
+            Self::VideoPassive => '▓',
+            Self::Idle => '░',
+            Self::Locked => '▒',
+        }
+    }
+}
+
+/// 一段连续的相同状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusInterval {
+    pub status: StatusKind,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// 被动观看时正在播放的媒体
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<MediaInfo>,
+}
+
+impl StatusInterval {
+    pub fn new(status: StatusKind, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            status,
+            start,
+            end,
+            media: None,
+        }
+    }
+
+    /// 时长（秒）
+    pub fn duration(&self) -> u64 {
+        (self.end - self.start).num_seconds().max(0) as u64
+    }
+
+    /// 截取落在 [start, end) 内的部分，不相交时返回 None
+    pub fn clipped(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<StatusInterval> {
+        let clipped = StatusInterval {
+            start: self.start.max(start),
+            end: self.end.min(end),
+            ..self.clone()
+        };
+        (clipped.start < clipped.end).then_some(clipped)
+    }
+}
+
+/// 各状态的合计时长（秒）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusSummary {
+    pub active: u64,
+    pub video_passive: u64,
+    pub idle: u64,
+    pub locked: u64,
+}
+
+impl StatusSummary {
+    /// 统计时间段落在 [start, end) 内的部分
+    pub fn from_intervals(
+        intervals: &[StatusInterval],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Self {
+        let mut summary = Self::default();
+        for interval in intervals.iter().filter_map(|i| i.clipped(start, end)) {
+            summary.add(interval.status, interval.duration());
+        }
+        summary
+    }
+
+    pub fn add(&mut self, status: StatusKind, seconds: u64) {
+        match status {
+            StatusKind::Active => self.active += seconds,
+            StatusKind::VideoPassive => self.video_passive += seconds,
+            StatusKind::Idle => self.idle += seconds,
+            StatusKind::Locked => self.locked += seconds,
+        }
+    }
+
+    pub fn get(&self, status: StatusKind) -> u64 {
+        match status {
+            StatusKind::Active => self.active,
+            StatusKind::VideoPassive => self.video_passive,
+            StatusKind::Idle => self.idle,
+            StatusKind::Locked => self.locked,
+        }
+    }
+
+    /// 开机时间：所有状态的合计（休眠和关机不在时间线中）
+    pub fn computer_on(&self) -> u64 {
+        self.active + self.video_passive + self.idle + self.locked
+    }
+
+    /// 活跃时间占开机时间的比例（0.0 - 1.0）
+    pub fn active_ratio(&self) -> f64 {
+        match self.computer_on() {
+            0 => 0.0,
+            on => self.active as f64 / on as f64,
+        }
+    }
+
+    /// 时间线中是否没有任何记录
+    pub fn is_empty(&self) -> bool {
+        self.computer_on() == 0
+    }
+
+    /// 时长最长的状态（时长相同时优先活跃），没有记录时返回 None
+    pub fn dominant(&self) -> Option<StatusKind> {
+        StatusKind::ALL
+            .into_iter()
+            .rev()
+            .filter(|status| self.get(*status) > 0)
+            .max_by_key(|status| self.get(*status))
+    }
+}
+
+/// 按逻辑日统计各状态时长
+pub fn daily_summaries(
+    intervals: &[StatusInterval],
+    boundary: DayBoundary,
+) -> BTreeMap<NaiveDate, StatusSummary> {
+    let mut days: BTreeMap<NaiveDate, StatusSummary> = BTreeMap::new();
+    for interval in intervals {
+        for (date, start, end) in boundary.split_days(interval.start, interval.end) {
+            let seconds = (end - start).num_seconds().max(0) as u64;
+            days.entry(date).or_default().add(interval.status, seconds);
+        }
+    }
+    days
+}
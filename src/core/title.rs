@@ -0,0 +1,152 @@
+// 窗口标题规范化模块
+// 在计算活动键之前用正则规则清理窗口标题，去掉未读数、修改标记、播放进度等易变部分
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// 标题两端需要去掉的分隔符
+const SEPARATORS: &str = "-|–—·:";
+
+/// 标题规则：把匹配 `pattern` 的部分替换为 `replace`，`replace` 为空时即删除
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TitleRule {
+    /// 只对应用名匹配该正则的窗口生效，为空时对所有应用生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    /// 匹配窗口标题的正则
+    pub pattern: String,
+    /// 替换文本，可以使用 `$1` 等捕获组
+    #[serde(default)]
+    pub replace: String,
+}
+
+impl TitleRule {
+    /// 删除匹配部分的规则
+    pub fn strip(pattern: &str) -> Self {
+        Self {
+            app: None,
+            pattern: pattern.to_string(),
+            replace: String::new(),
+        }
+    }
+
+    /// 替换匹配部分的规则
+    pub fn replace(pattern: &str, replace: &str) -> Self {
+        Self {
+            app: None,
+            pattern: pattern.to_string(),
+            replace: replace.to_string(),
+        }
+    }
+
+    /// 限定规则只对匹配的应用生效
+    pub fn for_app(mut self, app: &str) -> Self {
+        self.app = Some(app.to_string());
+        self
+    }
+}
+
+/// 内置规则，覆盖常见应用的易变标题
+pub fn builtin_rules() -> Vec<TitleRule> {
+    vec![
+        // 未读数前缀："(3) Slack | general"、"(12+) Inbox - Gmail"
+        TitleRule::strip(r"^\s*[(\[]\d+\+?[)\]]\s*"),
+        // 编辑器未保存标记："● main.rs - Visual Studio Code"、"*notes.txt - gedit"
+        TitleRule::strip(r"^\s*[●•*]\s*"),
+        TitleRule::strip(r"\s*[●•]\s*$"),
+        // 播放器进度："Song - 12:34 / 45:00"
+        TitleRule::replace(
+            r"\s*\d{1,2}:\d{2}(?::\d{2})?\s*/\s*\d{1,2}:\d{2}(?::\d{2})?\s*",
+            " ",
+        ),
+        // 浏览器标签状态："Video - Audio playing - Google Chrome"
+        TitleRule::strip(
+            r"\s+-\s+(?:Audio playing|Playing audio|Audio muted|Camera or microphone recording)\b",
+        ),
+        // 终端、构建工具不断刷新的进度百分比："build [42%]"
+        TitleRule::strip(r"\s*\[\d{1,3}%\]"),
+    ]
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    app: Option<Regex>,
+    pattern: Regex,
+    replace: String,
+}
+
+/// 标题规范化流水线，按顺序应用内置规则和用户规则
+#[derive(Debug, Clone, Default)]
+pub struct TitleNormalizer {
+    rules: Vec<CompiledRule>,
+}
+
+impl TitleNormalizer {
+    /// 编译规则，`builtin` 为 true 时先应用内置规则
+    pub fn new(builtin: bool, rules: &[TitleRule]) -> Result<Self> {
+        let builtin = if builtin { builtin_rules() } else { Vec::new() };
+        let rules = builtin
+            .iter()
+            .chain(rules)
+            .map(compile_rule)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// 只使用内置规则
+    pub fn builtin() -> Self {
+        Self::new(true, &[]).expect("built-in title rules are valid")
+    }
+
+    /// 不做任何处理
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// 规范化窗口标题；规则把标题清空时保留原标题
+    pub fn normalize(&self, app_name: &str, title: &str) -> String {
+        let mut normalized = title.to_string();
+        for rule in &self.rules {
+            if rule.app.as_ref().is_some_and(|app| !app.is_match(app_name)) {
+                continue;
+            }
+            normalized = rule
+                .pattern
+                .replace_all(&normalized, rule.replace.as_str())
+                .into_owned();
+        }
+
+        // 合并多余空白，去掉规则删除内容后留在两端的分隔符
+        let normalized = normalized.split_whitespace().collect::<Vec<_>>().join(" ");
+        let normalized =
+            normalized.trim_matches(|c: char| c.is_whitespace() || SEPARATORS.contains(c));
+        if normalized.is_empty() {
+            title.to_string()
+        } else {
+            normalized.to_string()
+        }
+    }
+}
+
+/// 检查规则能否编译
+pub fn validate_rule(rule: &TitleRule) -> Result<()> {
+    compile_rule(rule).map(|_| ())
+}
+
+fn compile_rule(rule: &TitleRule) -> Result<CompiledRule> {
+    let app = rule
+        .app
+        .as_deref()
+        .filter(|app| !app.is_empty())
+        .map(|app| Regex::new(&format!("(?i){app}")))
+        .transpose()
+        .with_context(|| format!("标题规则的应用正则无效: {:?}", rule.app))?;
+    let pattern = Regex::new(&rule.pattern)
+        .with_context(|| format!("标题规则的正则无效: {}", rule.pattern))?;
+    Ok(CompiledRule {
+        app,
+        pattern,
+        replace: rule.replace.clone(),
+    })
+}
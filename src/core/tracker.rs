@@ -1,17 +1,40 @@
+use crate::core::activity_detector::{ActivityConfig, ActivityDetector, ActivityStatus};
+use crate::core::app_rules::AppRules;
+use crate::core::away::{label_entry, AwayQueue};
 use crate::core::enhanced_platform::get_best_monitor;
+use crate::core::heartbeat::{merge_heartbeat_debounced, Heartbeat, DEFAULT_PULSE_WINDOW_SECS};
+use crate::core::manual::{find_overlaps, trim_overlaps, ManualEntry, OverlapPolicy};
+use crate::core::media::MediaInfo;
 use crate::core::monitor::{EnhancedWindowInfo, EnhancedWindowMonitor, PermissionStatus};
 use crate::core::platform::{get_active_window, WindowInfo};
+use crate::core::power::{is_clock_jump, AwayPeriod, AwayReason, PowerEvent, PowerEventListener};
+use crate::core::status::{StatusInterval, StatusKind};
+use crate::core::title::TitleNormalizer;
+use crate::storage::compact::compact_store;
+use crate::storage::migration::CURRENT_SCHEMA_VERSION;
+use crate::storage::rollup::rollup_cutoff;
+use crate::storage::status::StatusLog;
+use crate::storage::{
+    open_store_with_options, ActivityStore, Passphrase, RetentionPolicy, StorageBackend,
+    StoreOptions,
+};
+use crate::utils::time::DayBoundary;
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
 use std::time::Duration;
 use tokio::time;
 
+/// 进行中的状态时间段至少每隔多少秒保存一次
+const STATUS_SAVE_INTERVAL_SECS: i64 = 30;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub struct ActivityRecord {
+    /// 记录的唯一标识（旧数据加载时自动生成）
+    #[serde(default = "new_record_id")]
+    pub id: String,
     pub app_name: String,
     pub window_title: String,
     pub start_time: DateTime<Utc>,
@@ -28,6 +51,68 @@ pub struct ActivityRecord {
     pub window_geometry: Option<WindowGeometry>,
     #[serde(default)]
     pub confidence: f32,
+    /// 记录来源（自动记录的记录不写入该字段）
+    #[serde(default, skip_serializing_if = "RecordSource::is_tracked")]
+    pub source: RecordSource,
+    /// 规范化前的原始窗口标题（与规范化后的标题相同时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_title: Option<String>,
+    /// 所属项目（手动记录时填写）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// 窗口是否全屏（全屏视频、演示文稿等）
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub fullscreen: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// 记录来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordSource {
+    /// 监控自动记录
+    #[default]
+    Tracked,
+    /// 异常退出后，下次启动时按最后更新时间结束的记录
+    Recovered,
+    /// 用户手动添加的记录（离开电脑的会议、电话等）
+    Manual,
+}
+
+impl RecordSource {
+    pub fn is_tracked(&self) -> bool {
+        *self == Self::Tracked
+    }
+
+    /// 存储使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tracked => "tracked",
+            Self::Recovered => "recovered",
+            Self::Manual => "manual",
+        }
+    }
+
+    /// 由存储中的名称解析，无法识别时视为自动记录
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "recovered" => Self::Recovered,
+            "manual" => Self::Manual,
+            _ => Self::Tracked,
+        }
+    }
+
+    /// 获取来源描述
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Tracked => "自动记录",
+            Self::Recovered => "异常退出后恢复",
+            Self::Manual => "手动添加",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
@@ -38,9 +123,15 @@ pub struct WindowGeometry {
     pub height: u32,
 }
 
+/// 生成新的记录标识
+pub fn new_record_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
 impl ActivityRecord {
     pub fn new(window_info: WindowInfo) -> Self {
         Self {
+            id: new_record_id(),
             app_name: window_info.app_name,
             window_title: window_info.window_title,
             start_time: Utc::now(),
@@ -51,11 +142,16 @@ impl ActivityRecord {
             bundle_id: None,
             window_geometry: None,
             confidence: 0.5, // 旧系统的默认置信度
+            source: RecordSource::Tracked,
+            raw_title: None,
+            project: None,
+            fullscreen: false,
         }
     }
 
     pub fn new_enhanced(window_info: EnhancedWindowInfo) -> Self {
         Self {
+            id: new_record_id(),
             app_name: window_info.app_name,
             window_title: window_info.window_title,
             start_time: Utc::now(),
@@ -71,13 +167,58 @@ impl ActivityRecord {
                 height: g.height,
             }),
             confidence: window_info.confidence as f32,
+            source: RecordSource::Tracked,
+            raw_title: None,
+            project: None,
+            fullscreen: window_info.fullscreen,
         }
     }
 
     pub fn finish(&mut self) {
-        let now = Utc::now();
-        self.end_time = Some(now);
-        self.duration = (now - self.start_time).num_seconds() as u64;
+        self.finish_at(Utc::now());
+    }
+
+    /// 在指定时间结束记录（早于开始时间时按开始时间结束）
+    pub fn finish_at(&mut self, end: DateTime<Utc>) {
+        let end = end.max(self.start_time);
+        self.end_time = Some(end);
+        self.duration = (end - self.start_time).num_seconds() as u64;
+    }
+
+    /// 记录的结束时间，未结束的记录按已记录的时长推算
+    pub fn effective_end(&self) -> DateTime<Utc> {
+        self.end_time
+            .unwrap_or_else(|| self.start_time + chrono::Duration::seconds(self.duration as i64))
+    }
+
+    /// 截取记录落在 [start, end) 内的部分，时长按重叠比例折算，不相交时返回 None
+    ///
+    /// 跨越零点（或日分割点）的记录据此拆分到各自的日期。
+    pub fn clipped(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<ActivityRecord> {
+        let record_end = self.effective_end();
+        if record_end <= self.start_time {
+            // 没有时间跨度的记录按开始时间归属
+            return (self.start_time >= start && self.start_time < end).then(|| self.clone());
+        }
+
+        let overlap_start = self.start_time.max(start);
+        let overlap_end = record_end.min(end);
+        if overlap_start >= overlap_end {
+            return None;
+        }
+        if overlap_start == self.start_time && overlap_end == record_end {
+            return Some(self.clone());
+        }
+
+        let span = (record_end - self.start_time).num_milliseconds() as u128;
+        let overlap = (overlap_end - overlap_start).num_milliseconds() as u128;
+        let mut piece = self.clone();
+        piece.start_time = overlap_start;
+        if overlap_end < record_end {
+            piece.end_time = Some(overlap_end);
+        }
+        piece.duration = (self.duration as u128 * overlap / span) as u64;
+        Some(piece)
     }
 }
 
@@ -89,6 +230,57 @@ pub struct TimeTrackerData {
     pub last_updated: DateTime<Utc>,
     #[serde(default = "default_version")]
     pub version: String,
+    /// 数据文件结构版本，加载旧文件时由 `storage::migration` 逐步升级
+    #[serde(default)]
+    pub schema_version: u32,
+    /// 已汇总的旧记录（每天、每个应用窗口一条）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rollups: Vec<DailyRollup>,
+    /// 状态时间线（活跃、闲置、锁屏、被动观看视频），加载数据时从状态时间线文件读取
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status_timeline: Vec<StatusInterval>,
+}
+
+/// 每日汇总：同一天内同一应用窗口的全部记录合并为一条
+///
+/// 旧记录汇总后只保留总时长、记录数和首末出现时间，用于长期趋势统计。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DailyRollup {
+    /// 逻辑日期（按记录开始时间和配置的日分割点）
+    pub date: NaiveDate,
+    pub app_name: String,
+    pub window_title: String,
+    /// 总时长（秒）
+    pub total_duration: u64,
+    /// 汇总的记录数
+    pub record_count: u64,
+    /// 最早一条记录的开始时间
+    pub first_seen: DateTime<Utc>,
+    /// 最晚一条记录的结束时间
+    pub last_seen: DateTime<Utc>,
+}
+
+impl DailyRollup {
+    /// 由一条记录创建汇总，日期按 `boundary` 的逻辑日计算
+    pub fn from_record(record: &ActivityRecord, boundary: DayBoundary) -> Self {
+        Self {
+            date: boundary.logical_date(record.start_time),
+            app_name: record.app_name.clone(),
+            window_title: record.window_title.clone(),
+            total_duration: record.duration,
+            record_count: 1,
+            first_seen: record.start_time,
+            last_seen: record.end_time.unwrap_or(record.start_time),
+        }
+    }
+
+    /// 合并同一天同一应用窗口的另一条汇总
+    pub fn absorb(&mut self, other: &DailyRollup) {
+        self.total_duration += other.total_duration;
+        self.record_count += other.record_count;
+        self.first_seen = self.first_seen.min(other.first_seen);
+        self.last_seen = self.last_seen.max(other.last_seen);
+    }
 }
 
 fn default_version() -> String {
@@ -102,6 +294,9 @@ impl Default for TimeTrackerData {
             current_activity: None,
             last_updated: Utc::now(),
             version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_version: CURRENT_SCHEMA_VERSION,
+            rollups: Vec::new(),
+            status_timeline: Vec::new(),
         }
     }
 }
@@ -124,11 +319,59 @@ pub struct TimeTracker {
     pub interval: Duration,
     pub enhanced_monitor: Option<Box<dyn EnhancedWindowMonitor + Send>>,
     pub use_enhanced_monitoring: bool,
+    store: Option<Box<dyn ActivityStore>>,
+    store_options: StoreOptions,
+    retention: Option<RetentionPolicy>,
+    /// 超过多少天的记录合并为每日汇总
+    rollup_after_days: Option<u32>,
+    /// 每日汇总按此划分逻辑日
+    day_boundary: DayBoundary,
+    /// 每日压缩记录时使用的合并间隔
+    compaction_gap: Option<chrono::Duration>,
+    /// 上次执行每日维护（压缩、汇总、保留策略）的逻辑日期
+    maintenance_checked: Option<NaiveDate>,
+    /// 上一次监控采样的时间，用于检测休眠造成的时钟跳变
+    last_tick: Option<DateTime<Utc>>,
+    /// 脉冲窗口：相同窗口的相邻心跳间隔不超过该值时合并
+    pulse_window: Duration,
+    /// 防抖时间：新窗口持续获得焦点达到该值后才成为当前活动
+    debounce: Duration,
+    /// 最短记录时长：短于该值的记录并入相邻记录
+    min_record: Duration,
+    /// 尚未通过防抖的候选窗口
+    pending_switch: Option<ActivityRecord>,
+    /// 计算活动键之前应用的窗口标题规范化规则
+    title_normalizer: TitleNormalizer,
+    /// 把进程名、应用路径或 bundle id 统一为应用名的规则
+    app_rules: AppRules,
+    /// 正在进行的离开时段（休眠或锁屏期间不记录活动）
+    away: Option<AwayPeriod>,
+    sleeping: bool,
+    /// 收到锁屏事件的时间，解锁后为 None
+    locked_since: Option<DateTime<Utc>>,
+    /// 本次监控期间已结束的离开时段
+    away_periods: Vec<AwayPeriod>,
+    /// 检测键盘鼠标闲置，闲置超时后开始离开时段
+    idle_detector: Option<ActivityDetector>,
+    /// 离开超过该时长后放入待标注队列
+    away_review: Option<chrono::Duration>,
+    /// 等待用户标注的离开时段
+    away_queue: AwayQueue,
+    /// 没有输入但正在播放的媒体
+    passive_media: Option<MediaInfo>,
+    /// 进行中的状态时间段
+    status: Option<StatusInterval>,
+    /// 上次保存进行中状态时间段的时间
+    status_saved_at: Option<DateTime<Utc>>,
+    /// 状态时间线
+    status_log: StatusLog,
 }
 
 impl TimeTracker {
     pub fn new(data_file: String, interval_seconds: u64) -> Self {
         let interval = Duration::from_secs(interval_seconds.max(1)); // 最小1秒
+        let away_queue = AwayQueue::for_data_file(&data_file);
+        let status_log = StatusLog::for_data_file(&data_file);
 
         // 延迟初始化增强监控器，避免在TUI启动时阻塞
         Self {
@@ -138,9 +381,85 @@ impl TimeTracker {
             interval,
             enhanced_monitor: None,
             use_enhanced_monitoring: false,
+            store: None,
+            store_options: StoreOptions::default(),
+            retention: None,
+            rollup_after_days: None,
+            day_boundary: DayBoundary::default(),
+            compaction_gap: None,
+            maintenance_checked: None,
+            last_tick: None,
+            pulse_window: Duration::from_secs(DEFAULT_PULSE_WINDOW_SECS),
+            debounce: Duration::ZERO,
+            min_record: Duration::ZERO,
+            pending_switch: None,
+            title_normalizer: TitleNormalizer::builtin(),
+            app_rules: AppRules::default(),
+            away: None,
+            sleeping: false,
+            locked_since: None,
+            away_periods: Vec::new(),
+            idle_detector: None,
+            away_review: None,
+            away_queue,
+            passive_media: None,
+            status: None,
+            status_saved_at: None,
+            status_log,
         }
     }
 
+    /// 使用自定义存储后端创建追踪器
+    ///
+    /// 此时 `data_file` 为空，所有读写都通过给定的存储完成。
+    pub fn with_store(store: Box<dyn ActivityStore>, interval_seconds: u64) -> Self {
+        let mut tracker = Self::new(String::new(), interval_seconds);
+        tracker.store = Some(store);
+        tracker
+    }
+
+    /// 设置加密存储的口令（需在首次读写数据前调用）
+    pub fn set_passphrase(&mut self, passphrase: Option<Passphrase>) {
+        self.store_options.passphrase = passphrase;
+    }
+
+    /// 设置打开存储的选项（口令、按日期分区，需在首次读写数据前调用）
+    pub fn set_store_options(&mut self, options: StoreOptions) {
+        self.store_options = options;
+    }
+
+    /// 设置数据保留策略，监控期间每天执行一次
+    pub fn set_retention(&mut self, policy: Option<RetentionPolicy>) {
+        self.retention = policy;
+    }
+
+    /// 设置每日汇总：超过 `after_days` 天的记录在监控期间每天合并一次
+    pub fn set_rollup(&mut self, after_days: Option<u32>) {
+        self.rollup_after_days = after_days;
+    }
+
+    /// 设置每日汇总使用的时区和日分割点
+    pub fn set_day_boundary(&mut self, day_boundary: DayBoundary) {
+        self.day_boundary = day_boundary;
+    }
+
+    /// 设置记录压缩：监控期间每天把间隔不超过 `gap_secs` 秒的相邻同窗口记录合并一次
+    pub fn set_compaction(&mut self, gap_secs: Option<u64>) {
+        self.compaction_gap = gap_secs.map(|secs| chrono::Duration::seconds(secs as i64));
+    }
+
+    /// 设置闲置检测：监控期间没有键盘鼠标输入超过闲置超时时间时，从最后一次输入开始记为离开
+    pub fn set_idle_detection(&mut self, config: Option<ActivityConfig>) {
+        self.idle_detector = config
+            .filter(|config| config.enabled)
+            .map(ActivityDetector::new);
+    }
+
+    /// 设置离开标注：离开超过 `minutes` 分钟的时段结束后放入待标注队列
+    pub fn set_away_review(&mut self, minutes: Option<u64>) {
+        self.away_review = minutes.map(|minutes| chrono::Duration::minutes(minutes as i64));
+    }
+
     /// 初始化增强监控器（延迟初始化）
     pub fn initialize_monitor(&mut self) {
         if self.enhanced_monitor.is_none() {
@@ -189,79 +508,402 @@ impl TimeTracker {
     }
 
     pub fn load_data(&mut self) -> Result<()> {
-        // 使用超时机制避免长时间阻塞
-        let path = Path::new(&self.data_file);
+        self.data = self.store()?.load()?;
+        self.data.status_timeline =
+            self.load_status_timeline(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC);
+        Ok(())
+    }
 
-        if !path.exists() {
-            // 文件不存在，使用默认数据
-            self.data = TimeTrackerData::default();
-            return Ok(());
+    /// 只加载开始时间落在 [start, end) 区间内的历史记录、每日汇总和当前活动
+    ///
+    /// 分区存储只读取与区间相交的分区。
+    pub fn load_range(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<()> {
+        let store = self.store()?;
+        let activities = store.query_range(start, end)?;
+        let rollups = store.query_rollups(start, end)?;
+        let current_activity = store.current()?;
+        self.data = TimeTrackerData {
+            activities,
+            current_activity,
+            rollups,
+            status_timeline: self.load_status_timeline(start, end),
+            ..TimeTrackerData::default()
+        };
+        Ok(())
+    }
+
+    /// 加载与 [start, end) 相交的历史记录、区间内的每日汇总和当前活动，记录截取到区间内
+    ///
+    /// 与统计和 TUI 的口径一致：开始于区间之前的记录也会加载，跨越区间边界的记录按比例截取，
+    /// 用于按时间范围导出。
+    pub fn load_range_clipped(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<()> {
+        let store = self.store()?;
+        let activities = store
+            .query_overlapping(start, end)?
+            .iter()
+            .filter_map(|record| record.clipped(start, end))
+            .collect();
+        let rollups = store.query_rollups(start, end)?;
+        let current_activity = store
+            .current()?
+            .and_then(|current| current.clipped(start, end));
+        self.data = TimeTrackerData {
+            activities,
+            current_activity,
+            rollups,
+            status_timeline: self.load_status_timeline(start, end),
+            ..TimeTrackerData::default()
+        };
+        Ok(())
+    }
+
+    /// 读取 [start, end) 内的状态时间线，读取失败时只记录警告
+    fn load_status_timeline(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<StatusInterval> {
+        self.status_log.query(start, end).unwrap_or_else(|e| {
+            log::warn!("读取状态时间线失败: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// 取出加载数据时产生的警告（例如损坏的数据文件已被隔离）
+    pub fn take_load_warnings(&mut self) -> Vec<String> {
+        match &mut self.store {
+            Some(store) => store.take_warnings(),
+            None => Vec::new(),
+        }
+    }
+
+    /// 获取存储后端（首次使用时根据数据文件扩展名打开）
+    fn store(&mut self) -> Result<&mut Box<dyn ActivityStore>> {
+        if self.store.is_none() {
+            self.store = Some(open_store_with_options(
+                &self.data_file,
+                &self.store_options,
+            )?);
+        }
+        Ok(self.store.as_mut().expect("store initialized above"))
+    }
+
+    /// 存储后端名称
+    pub fn storage_name(&self) -> &str {
+        match &self.store {
+            Some(store) => store.name(),
+            None => StorageBackend::from_path(Path::new(&self.data_file)).name(),
+        }
+    }
+
+    /// 保存全部数据（用内存中的历史记录替换存储中的记录）
+    pub fn save_data(&mut self) -> Result<()> {
+        let activities = self.data.activities.clone();
+        self.store()?.replace_all(&activities)?;
+        self.save_state()
+    }
+
+    /// 将存储中尚未写入的修改持久化，不改动历史记录和当前活动
+    pub fn flush(&mut self) -> Result<()> {
+        self.store()?.flush()
+    }
+
+    /// 保存当前状态（每个监控周期调用，只更新当前活动）
+    pub fn save_state(&mut self) -> Result<()> {
+        // 当前事件的结束时间和时长由最后一次心跳决定
+        let current = self.current_activity.clone();
+
+        let store = self.store()?;
+        store.set_current(current.as_ref())?;
+        store.flush()?;
+
+        self.data.current_activity = current;
+        self.data.last_updated = Utc::now();
+        Ok(())
+    }
+
+    /// 记录一条已结束的活动并保存当前状态
+    fn commit_activity(&mut self, record: ActivityRecord) -> Result<()> {
+        self.store()?.append(&record)?;
+        self.data.activities.push(record);
+        self.save_state()
+    }
+
+    /// 添加一条历史记录
+    pub fn add_record(&mut self, record: ActivityRecord) -> Result<()> {
+        let store = self.store()?;
+        store.append(&record)?;
+        store.flush()?;
+        self.data.activities.push(record);
+        Ok(())
+    }
+
+    /// 按 id 更新一条历史记录，返回是否找到该记录
+    pub fn update_record(&mut self, record: ActivityRecord) -> Result<bool> {
+        let store = self.store()?;
+        if !store.update(&record)? {
+            return Ok(false);
+        }
+        store.flush()?;
+
+        if let Some(existing) = self.data.activities.iter_mut().find(|r| r.id == record.id) {
+            *existing = record;
         }
+        Ok(true)
+    }
 
-        // 尝试读取文件，如果失败则使用默认数据
-        match fs::read_to_string(&self.data_file) {
-            Ok(file_content) => {
-                if file_content.trim().is_empty() {
-                    self.data = TimeTrackerData::default();
-                    return Ok(());
+    /// 按 id 删除历史记录，返回删除的条数
+    pub fn delete_records(&mut self, ids: &[String]) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let store = self.store()?;
+        let deleted = store.delete(ids)?;
+        store.flush()?;
+
+        self.data.activities.retain(|r| !ids.contains(&r.id));
+        Ok(deleted)
+    }
+
+    /// 查询与 [start, end) 重叠的记录，包括正在进行的当前活动
+    ///
+    /// 按开始和结束时间从存储中查询，开始得再早的长记录也会被找到。
+    pub fn find_overlaps(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ActivityRecord>> {
+        let mut overlaps = self.store()?.query_overlapping(start, end)?;
+        if let Some(current) = self.ongoing_activity() {
+            let overlapping = !find_overlaps(std::slice::from_ref(current), start, end).is_empty();
+            if overlapping && !overlaps.iter().any(|r| r.id == current.id) {
+                overlaps.push(current.clone());
+            }
+        }
+        Ok(overlaps)
+    }
+
+    /// 正在进行的活动：本进程监控中的当前活动，或存储中保存的（例如后台进程的）当前活动
+    fn ongoing_activity(&self) -> Option<&ActivityRecord> {
+        self.current_activity
+            .as_ref()
+            .or(self.data.current_activity.as_ref())
+    }
+
+    /// 添加手动记录，按 `policy` 处理与已有记录的重叠，返回重叠的记录
+    pub fn add_manual_entry(
+        &mut self,
+        entry: &ManualEntry,
+        policy: OverlapPolicy,
+    ) -> Result<Vec<ActivityRecord>> {
+        let overlaps = self.find_overlaps(entry.start, entry.end)?;
+        match policy {
+            OverlapPolicy::Reject if !overlaps.is_empty() => {
+                return Err(anyhow::anyhow!(
+                    "手动记录与 {} 条已有记录重叠",
+                    overlaps.len()
+                ));
+            }
+            OverlapPolicy::Trim => {
+                let ongoing_id = self.ongoing_activity().map(|current| current.id.clone());
+                let (ongoing, finished): (Vec<&ActivityRecord>, Vec<&ActivityRecord>) = overlaps
+                    .iter()
+                    .partition(|record| ongoing_id.as_ref() == Some(&record.id));
+                if !ongoing.is_empty() {
+                    self.trim_current_activity(entry.start, entry.end)?;
                 }
 
-                // 尝试解析数据，如果失败则使用默认数据
-                match serde_json::from_str::<TimeTrackerData>(&file_content) {
-                    Ok(data) => {
-                        self.data = data;
-                    }
-                    Err(_) => {
-                        // 尝试加载旧格式
-                        match serde_json::from_str::<Vec<ActivityRecord>>(&file_content) {
-                            Ok(activities) => {
-                                self.data = TimeTrackerData {
-                                    activities,
-                                    current_activity: None,
-                                    last_updated: Utc::now(),
-                                    version: env!("CARGO_PKG_VERSION").to_string(),
-                                };
-                                // 异步保存，避免阻塞
-                                let _ = self.save_data();
-                            }
-                            Err(_e) => {
-                                // 解析失败，使用默认数据，不备份以避免阻塞
-                                self.data = TimeTrackerData::default();
-                            }
-                        }
-                    }
+                let resolution = trim_overlaps(&finished, entry.start, entry.end);
+                for record in resolution.updated {
+                    self.update_record(record)?;
                 }
+                for record in resolution.added {
+                    self.add_record(record)?;
+                }
+                self.delete_records(&resolution.removed)?;
             }
-            Err(_e) => {
-                // 读取失败，使用默认数据
-                self.data = TimeTrackerData::default();
+            _ => {}
+        }
+
+        let record = entry.to_record();
+        log::info!(
+            "添加手动记录: {} {} - {}",
+            record.app_name,
+            entry.start,
+            entry.end
+        );
+        self.add_record(record)?;
+        Ok(overlaps)
+    }
+
+    /// 裁掉当前活动落在 [start, end) 内的部分
+    ///
+    /// 之前的部分作为已结束的记录写入历史，当前活动从 `end` 继续。当前活动属于其他进程
+    /// （例如后台监控）时无法修改，返回错误。
+    fn trim_current_activity(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<()> {
+        let Some(current) = self.current_activity.take() else {
+            return Err(anyhow::anyhow!(
+                "手动记录与正在进行的活动重叠，请先停止监控，或保留重叠"
+            ));
+        };
+
+        if let Some(mut before) = current.clipped(current.start_time, start) {
+            before.id = new_record_id();
+            self.add_record(before)?;
+        }
+        self.current_activity = current.clipped(end, current.effective_end().max(end));
+        self.save_state()
+    }
+
+    /// 查询开始时间落在 [start, end) 区间内的历史记录
+    pub fn query_range(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ActivityRecord>> {
+        self.store()?.query_range(start, end)
+    }
+
+    /// 执行每日维护：先压缩相邻记录，再把旧记录合并为每日汇总，最后执行数据保留策略
+    ///
+    /// 按配置的时区和日分割点，每个逻辑日最多执行一次。
+    fn run_maintenance_if_due(&mut self) {
+        if self.compaction_gap.is_none()
+            && self.rollup_after_days.is_none()
+            && self.retention.is_none()
+        {
+            return;
+        }
+        let today = self.day_boundary.logical_date(Utc::now());
+        if self.maintenance_checked == Some(today) {
+            return;
+        }
+        self.maintenance_checked = Some(today);
+
+        let mut changed = false;
+        if let Some(gap) = self.compaction_gap {
+            match self
+                .store()
+                .and_then(|store| compact_store(store.as_mut(), gap, false))
+            {
+                Ok(report) if !report.is_empty() => {
+                    log::info!(
+                        "记录压缩: 移除 {} 条记录，合并到 {} 条记录",
+                        report.removed,
+                        report.merged
+                    );
+                    changed = true;
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("压缩记录失败: {}", e),
             }
         }
 
-        Ok(())
+        if let Some(after_days) = self.rollup_after_days {
+            let boundary = self.day_boundary;
+            let before = rollup_cutoff(Utc::now(), after_days, boundary);
+            match self
+                .store()
+                .and_then(|store| store.rollup(before, boundary))
+            {
+                Ok(report) if !report.is_empty() => {
+                    log::info!(
+                        "每日汇总: {} 条记录合并为 {} 条汇总",
+                        report.records,
+                        report.rollups
+                    );
+                    changed = true;
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("合并每日汇总失败: {}", e),
+            }
+        }
+
+        if let Some(policy) = self.retention.clone() {
+            let result = self
+                .store()
+                .and_then(|store| store.apply_retention(&policy, Utc::now()));
+            match result {
+                Ok(report) if !report.is_empty() => {
+                    log::info!(
+                        "数据保留: 归档 {} 个分区，删除 {} 个分区",
+                        report.archived.len(),
+                        report.deleted.len()
+                    );
+                    changed = true;
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("执行数据保留策略失败: {}", e),
+            }
+
+            // 状态时间线同样只保留最近的数据
+            let cutoff = Utc::now() - chrono::Duration::days(policy.keep_days as i64);
+            match self.status_log.purge_before(cutoff) {
+                Ok(0) => {}
+                Ok(removed) => log::info!("数据保留: 删除 {} 个过期的状态时间段", removed),
+                Err(e) => log::error!("清理过期的状态时间线失败: {}", e),
+            }
+        }
+
+        // 内存中的历史记录与存储保持一致
+        if changed {
+            if let Err(e) = self.load_data() {
+                log::error!("执行每日维护后重新加载数据失败: {}", e);
+            }
+        }
     }
 
-    pub fn save_data(&self) -> Result<()> {
-        let mut data = self.data.clone();
+    /// 恢复上次异常退出时遗留的当前活动
+    ///
+    /// 进程被强制结束或系统崩溃时，存储中仍保留着未结束的当前活动。启动时将它在最后一次
+    /// 更新时间（而不是现在）结束，标记为恢复的记录写入历史，返回恢复出的记录。
+    pub fn recover_stale_activity(&mut self) -> Result<Option<ActivityRecord>> {
+        if self.current_activity.is_some() {
+            return Ok(None);
+        }
+        let Some(mut stale) = self.data.current_activity.clone() else {
+            return Ok(None);
+        };
 
-        // 如果有当前活动，动态计算其持续时间
-        if let Some(mut current) = self.current_activity.clone() {
-            current.duration = (Utc::now() - current.start_time).num_seconds() as u64;
-            data.current_activity = Some(current);
-        } else {
-            data.current_activity = None;
+        if self.data.activities.iter().any(|r| r.id == stale.id) {
+            // 已经写入过历史记录，只清除遗留的当前活动
+            self.save_state()?;
+            return Ok(None);
         }
 
-        data.last_updated = Utc::now();
-        let json = serde_json::to_string_pretty(&data)?;
-        fs::write(&self.data_file, json)?;
-        Ok(())
+        stale.finish_at(self.data.last_updated);
+        stale.source = RecordSource::Recovered;
+        log::warn!(
+            "恢复上次异常退出时未结束的活动: {} - {}，开始于 {}，按最后更新时间 {} 结束 ({}秒)",
+            stale.app_name,
+            stale.window_title,
+            stale.start_time,
+            self.data.last_updated,
+            stale.duration
+        );
+        self.commit_activity(stale.clone())?;
+        Ok(Some(stale))
     }
 
     pub async fn start_monitoring(&mut self) -> Result<()> {
         // 在开始监控时才初始化监控器
         self.initialize_monitor();
 
+        if let Err(e) = self.recover_stale_activity() {
+            log::error!("恢复遗留的当前活动失败: {}", e);
+        }
+        match self.status_log.recover_current() {
+            Ok(Some(stale)) => log::warn!(
+                "恢复上次异常退出时未结束的状态: {}，{} ~ {}",
+                stale.status.description(),
+                stale.start,
+                stale.end
+            ),
+            Ok(None) => {}
+            Err(e) => log::error!("恢复遗留的状态时间段失败: {}", e),
+        }
+
         log::info!("开始监控，间隔: {:?}", self.interval);
         log::info!("使用增强监控: {}", self.use_enhanced_monitoring);
 
@@ -274,10 +916,47 @@ impl TimeTracker {
         let mut interval_timer = time::interval(self.interval);
         let mut error_count = 0;
         const MAX_ERRORS: u32 = 10;
+        let power_events = PowerEventListener::spawn();
+
+        /// 与窗口更新失败一样计入错误次数，连续错误次数过多时返回错误退出监控
+        fn tolerate(error_count: &mut u32, context: &str, result: Result<()>) -> Result<()> {
+            if let Err(e) = result {
+                *error_count += 1;
+                log::error!(
+                    "{}失败 (错误 {}/{}): {}",
+                    context,
+                    error_count,
+                    MAX_ERRORS,
+                    e
+                );
+                if *error_count >= MAX_ERRORS {
+                    log::error!("连续错误次数过多，退出监控");
+                    return Err(e);
+                }
+            }
+            Ok(())
+        }
 
         loop {
             interval_timer.tick().await;
 
+            // 休眠、锁屏和时钟跳变：在上一次采样时结束当前活动，离开期间不记录
+            let now = Utc::now();
+            for event in power_events.poll() {
+                let result = self.handle_power_event(event, now);
+                tolerate(&mut error_count, "处理系统事件", result)?;
+            }
+            let result = self.observe_tick(now);
+            tolerate(&mut error_count, "检测时钟跳变", result)?;
+            let result = self.poll_idle(now);
+            tolerate(&mut error_count, "检测闲置状态", result)?;
+            self.update_status(now);
+            if self.is_away() {
+                continue;
+            }
+
+            self.run_maintenance_if_due();
+
             // 尝试使用增强监控系统
             let window_result = if self.use_enhanced_monitoring {
                 if let Some(monitor) = &mut self.enhanced_monitor {
@@ -356,121 +1035,171 @@ impl TimeTracker {
     }
 
     pub fn update_activity_enhanced(&mut self, window_info: EnhancedWindowInfo) -> Result<()> {
-        let activity_key = format!("{} - {}", window_info.app_name, window_info.window_title);
+        // 如果有应用路径或bundle ID，记录额外信息
+        if let Some(ref path) = window_info.app_path {
+            log::debug!("应用路径: {}", path);
+        }
+        if let Some(ref bundle_id) = window_info.bundle_id {
+            log::debug!("Bundle ID: {}", bundle_id);
+        }
+        if let Some(ref geometry) = window_info.geometry {
+            log::debug!(
+                "窗口位置: {}x{} at ({}, {})",
+                geometry.width,
+                geometry.height,
+                geometry.x,
+                geometry.y
+            );
+        }
 
-        // 检查是否需要切换活动
-        let should_switch = match &self.current_activity {
-            None => true,
-            Some(current) => {
-                let current_key = format!("{} - {}", current.app_name, current.window_title);
-                current_key != activity_key
-            }
-        };
+        self.record_heartbeat(Heartbeat::from_enhanced(window_info, Utc::now()))
+    }
 
-        if should_switch {
-            // 结束当前活动
-            if let Some(mut current) = self.current_activity.take() {
-                current.finish();
-                log::info!(
-                    "活动结束: {} - {} ({}秒, 置信度: {:.2})",
-                    current.app_name,
-                    current.window_title,
-                    current.duration,
-                    current.confidence
-                );
-                self.data.activities.push(current);
-                self.save_data()?;
-            }
+    pub fn update_activity(&mut self, window_info: WindowInfo) -> Result<()> {
+        self.record_heartbeat(Heartbeat::from_window(window_info, Utc::now()))
+    }
 
-            // 开始新活动
-            let new_activity = ActivityRecord::new_enhanced(window_info.clone());
-            log::info!(
-                "新活动开始: {} - {} (置信度: {:.2})",
-                new_activity.app_name,
-                new_activity.window_title,
-                new_activity.confidence
-            );
+    /// 设置脉冲窗口（秒）：相同窗口的相邻心跳间隔不超过该值时合并为同一个事件
+    pub fn set_pulse_window(&mut self, seconds: u64) {
+        self.pulse_window = Duration::from_secs(seconds.max(1));
+    }
 
-            // 如果有应用路径或bundle ID，记录额外信息
-            if let Some(ref path) = window_info.app_path {
-                log::debug!("应用路径: {}", path);
-            }
-            if let Some(ref bundle_id) = window_info.bundle_id {
-                log::debug!("Bundle ID: {}", bundle_id);
-            }
-            if let Some(ref geometry) = window_info.geometry {
-                log::debug!(
-                    "窗口位置: {}x{} at ({}, {})",
-                    geometry.width,
-                    geometry.height,
-                    geometry.x,
-                    geometry.y
-                );
-            }
+    /// 设置防抖时间（秒）：窗口切换后新窗口持续获得焦点达到该值才开始新记录，0 表示不防抖
+    pub fn set_debounce(&mut self, seconds: u64) {
+        self.debounce = Duration::from_secs(seconds);
+    }
 
-            self.current_activity = Some(new_activity);
+    /// 设置最短记录时长（秒）：短于该值的记录并入相邻记录，0 表示不合并
+    pub fn set_min_record_duration(&mut self, seconds: u64) {
+        self.min_record = Duration::from_secs(seconds);
+    }
 
-            // 立即保存数据，包含当前活动，以便TUI能实时看到
-            if let Err(e) = self.save_data() {
-                log::warn!("保存当前活动数据失败: {}", e);
-            }
-        } else {
-            // 即使没有切换活动，也要定期保存当前活动的状态
-            if let Err(e) = self.save_data() {
-                log::warn!("保存当前活动状态失败: {}", e);
-            }
-        }
+    /// 设置窗口标题规范化规则
+    pub fn set_title_normalizer(&mut self, normalizer: TitleNormalizer) {
+        self.title_normalizer = normalizer;
+    }
 
-        Ok(())
+    /// 设置应用别名规则
+    pub fn set_app_rules(&mut self, rules: AppRules) {
+        self.app_rules = rules;
     }
 
-    pub fn update_activity(&mut self, window_info: WindowInfo) -> Result<()> {
-        let activity_key = format!("{} - {}", window_info.app_name, window_info.window_title);
+    /// 实际使用的脉冲窗口，至少比监控间隔长 1 秒，避免正常采样被拆成多个事件
+    fn effective_pulse_window(&self) -> chrono::Duration {
+        let window = self
+            .pulse_window
+            .max(self.interval + Duration::from_secs(1));
+        chrono::Duration::from_std(window).unwrap_or_else(|_| chrono::Duration::seconds(1))
+    }
 
-        // 检查是否需要切换活动
-        let should_switch = match &self.current_activity {
-            None => true,
-            Some(current) => {
-                let current_key = format!("{} - {}", current.app_name, current.window_title);
-                current_key != activity_key
+    /// 处理一次心跳：延长当前事件，或结束当前事件并开始新事件
+    pub fn record_heartbeat(&mut self, mut heartbeat: Heartbeat) -> Result<()> {
+        // 先统一应用名、规范化标题，同一应用的别名和易变标题（未读数、播放进度等）才能合并为同一条记录
+        heartbeat.apply_app_rules(&self.app_rules);
+        heartbeat.normalize_title(&self.title_normalizer);
+        let pulse_window = self.effective_pulse_window();
+        let debounce = chrono::Duration::from_std(self.debounce).unwrap_or_default();
+        let closed = merge_heartbeat_debounced(
+            &mut self.current_activity,
+            &mut self.pending_switch,
+            heartbeat,
+            pulse_window,
+            debounce,
+        );
+        if let Some(closed) = closed {
+            if self.is_short_record(&closed) {
+                return self.fold_short_record(closed);
             }
-        };
-
-        if should_switch {
-            // 结束当前活动
-            if let Some(mut current) = self.current_activity.take() {
-                current.finish();
+            log::info!(
+                "活动结束: {} - {} ({}秒, 置信度: {:.2})",
+                closed.app_name,
+                closed.window_title,
+                closed.duration,
+                closed.confidence
+            );
+            if let Some(current) = &self.current_activity {
                 log::info!(
-                    "活动结束: {} - {} ({}秒)",
+                    "新活动开始: {} - {} (置信度: {:.2})",
                     current.app_name,
                     current.window_title,
-                    current.duration
+                    current.confidence
                 );
-                self.data.activities.push(current);
-                self.save_data()?;
             }
+            // 保存已结束的事件和新的当前事件，以便TUI能实时看到
+            return self.commit_activity(closed);
+        }
 
-            // 开始新活动
-            let new_activity = ActivityRecord::new(window_info);
-            log::info!(
-                "新活动开始: {} - {}",
-                new_activity.app_name,
-                new_activity.window_title
-            );
-            self.current_activity = Some(new_activity);
+        // 每次心跳都保存当前事件，异常退出时最多丢失一个心跳间隔
+        if let Err(e) = self.save_state() {
+            log::warn!("保存当前活动状态失败: {}", e);
+        }
+        Ok(())
+    }
 
-            // 立即保存数据，包含当前活动，以便TUI能实时看到
-            if let Err(e) = self.save_data() {
-                log::warn!("保存当前活动数据失败: {}", e);
-            }
+    fn is_short_record(&self, record: &ActivityRecord) -> bool {
+        record.duration < self.min_record.as_secs()
+    }
+
+    /// 记录一条已结束的活动，短于最短记录时长时并入相邻记录
+    fn commit_closed(&mut self, record: ActivityRecord) -> Result<()> {
+        if self.is_short_record(&record) {
+            self.fold_short_record(record)
         } else {
-            // 即使没有切换活动，也要定期保存当前活动的状态
-            if let Err(e) = self.save_data() {
-                log::warn!("保存当前活动状态失败: {}", e);
-            }
+            self.commit_activity(record)
         }
+    }
 
-        Ok(())
+    /// 把短于最短记录时长的记录并入相邻记录
+    ///
+    /// 优先并入紧挨在前面的历史记录，其次并入紧随其后的当前活动；
+    /// 前后都不相连时（例如监控中断前的最后一瞬间）直接丢弃。
+    fn fold_short_record(&mut self, short: ActivityRecord) -> Result<()> {
+        let short_end = short.effective_end();
+
+        let previous = self
+            .data
+            .activities
+            .last()
+            .filter(|r| r.effective_end() == short.start_time)
+            .cloned();
+        if let Some(mut previous) = previous {
+            log::debug!(
+                "短记录并入前一条: {} - {} ({}秒) -> {}",
+                short.app_name,
+                short.window_title,
+                short.duration,
+                previous.app_name
+            );
+            previous.finish_at(short_end);
+            self.update_record(previous)?;
+            return self.save_state();
+        }
+
+        if let Some(current) = self
+            .current_activity
+            .as_mut()
+            .filter(|c| c.start_time == short_end)
+        {
+            log::debug!(
+                "短记录并入后一条: {} - {} ({}秒) -> {}",
+                short.app_name,
+                short.window_title,
+                short.duration,
+                current.app_name
+            );
+            let end = current.effective_end();
+            current.start_time = short.start_time;
+            current.finish_at(end);
+            return self.save_state();
+        }
+
+        log::debug!(
+            "丢弃短记录: {} - {} ({}秒)",
+            short.app_name,
+            short.window_title,
+            short.duration
+        );
+        self.save_state()
     }
 
     pub fn get_statistics(&self) -> HashMap<String, u64> {
@@ -481,16 +1210,80 @@ impl TimeTracker {
             *stats.entry(key).or_insert(0) += activity.duration;
         }
 
-        // 包含当前活动的时间
+        // 已汇总的旧记录
+        for rollup in &self.data.rollups {
+            let key = format!("{} - {}", rollup.app_name, rollup.window_title);
+            *stats.entry(key).or_insert(0) += rollup.total_duration;
+        }
+
+        // 包含当前活动的时间（截至最后一次心跳）
         if let Some(current) = &self.current_activity {
             let key = format!("{} - {}", current.app_name, current.window_title);
-            let current_duration = (Utc::now() - current.start_time).num_seconds() as u64;
-            *stats.entry(key).or_insert(0) += current_duration;
+            *stats.entry(key).or_insert(0) += current.duration;
+        }
+
+        stats
+    }
+
+    /// 时间区间 [start, end) 内的记录（含当前活动），跨越区间边界的记录按比例截取
+    pub fn records_in(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<ActivityRecord> {
+        self.data
+            .activities
+            .iter()
+            .chain(self.current_activity.as_ref())
+            .filter_map(|activity| activity.clipped(start, end))
+            .collect()
+    }
+
+    /// 时间区间 [start, end) 内每个应用窗口的使用时长（含区间内的每日汇总）
+    pub fn get_statistics_in(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> HashMap<String, u64> {
+        let mut stats = HashMap::new();
+
+        for activity in self.records_in(start, end) {
+            let key = format!("{} - {}", activity.app_name, activity.window_title);
+            *stats.entry(key).or_insert(0) += activity.duration;
+        }
+
+        for rollup in self.rollups_in(start, end) {
+            let key = format!("{} - {}", rollup.app_name, rollup.window_title);
+            *stats.entry(key).or_insert(0) += rollup.total_duration;
         }
 
         stats
     }
 
+    /// 时间区间 [start, end) 内的总使用时长
+    pub fn get_total_time_in(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> u64 {
+        let records: u64 = self.records_in(start, end).iter().map(|a| a.duration).sum();
+        let rollups: u64 = self.rollups_in(start, end).map(|r| r.total_duration).sum();
+        records + rollups
+    }
+
+    /// 时间区间 [start, end) 内窗口全屏的时长（已汇总的旧记录不区分是否全屏，不计入）
+    pub fn get_fullscreen_time_in(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> u64 {
+        self.records_in(start, end)
+            .iter()
+            .filter(|a| a.fullscreen)
+            .map(|a| a.duration)
+            .sum()
+    }
+
+    /// 首次出现时间落在区间内的每日汇总
+    fn rollups_in(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> impl Iterator<Item = &DailyRollup> {
+        self.data
+            .rollups
+            .iter()
+            .filter(move |r| r.first_seen >= start && r.first_seen < end)
+    }
+
     pub fn get_recent_activities(&self, limit: usize) -> Vec<&ActivityRecord> {
         let mut recent: Vec<&ActivityRecord> = self.data.activities.iter().collect();
         recent.sort_by(|a, b| b.start_time.cmp(&a.start_time));
@@ -512,10 +1305,16 @@ impl TimeTracker {
 
     pub fn get_total_time(&self) -> u64 {
         let mut total = self.data.activities.iter().map(|a| a.duration).sum::<u64>();
-
-        // 加上当前活动的时间
+        total += self
+            .data
+            .rollups
+            .iter()
+            .map(|r| r.total_duration)
+            .sum::<u64>();
+
+        // 加上当前活动的时间（截至最后一次心跳）
         if let Some(current) = &self.current_activity {
-            total += (Utc::now() - current.start_time).num_seconds() as u64;
+            total += current.duration;
         }
 
         total
@@ -581,17 +1380,338 @@ impl TimeTracker {
         sessions
     }
 
+    /// 记录一次监控采样；与上次采样相比墙上时间发生跳变时（例如系统休眠后唤醒），
+    /// 在上次采样时结束当前活动，并把中间的空档记为离开
+    pub fn observe_tick(&mut self, now: DateTime<Utc>) -> Result<()> {
+        if let Some(last_tick) = self.last_tick {
+            if self.away.is_none() && is_clock_jump(last_tick, now, self.interval) {
+                log::info!(
+                    "检测到时钟跳变: {} -> {}，在上次采样时结束当前活动",
+                    last_tick,
+                    now
+                );
+                self.close_current_activity_at(last_tick)?;
+                self.finish_away(AwayPeriod {
+                    start: last_tick,
+                    end: Some(now.max(last_tick)),
+                    reason: AwayReason::ClockJump,
+                });
+            }
+        }
+        self.last_tick = Some(now);
+        Ok(())
+    }
+
+    /// 处理休眠、唤醒、锁屏和解锁事件
+    pub fn handle_power_event(&mut self, event: PowerEvent, now: DateTime<Utc>) -> Result<()> {
+        match event {
+            PowerEvent::Sleep => self.sleeping = true,
+            PowerEvent::Lock => {
+                self.locked_since.get_or_insert(now);
+            }
+            PowerEvent::Resume => self.sleeping = false,
+            PowerEvent::Unlock => self.locked_since = None,
+        }
+
+        match (event, &self.away) {
+            (PowerEvent::Sleep | PowerEvent::Lock, None) => {
+                // 事件可能在唤醒后才被处理，以上一次采样时间作为离开的开始
+                let start = self.last_tick.unwrap_or(now).min(now);
+                self.close_current_activity_at(start)?;
+                let reason = if event == PowerEvent::Sleep {
+                    AwayReason::Sleep
+                } else {
+                    AwayReason::Lock
+                };
+                log::info!("{}，在 {} 结束当前活动", reason.description(), start);
+                self.away = Some(AwayPeriod {
+                    start,
+                    end: None,
+                    reason,
+                });
+            }
+            (PowerEvent::Resume | PowerEvent::Unlock, Some(_))
+                if !self.sleeping && self.locked_since.is_none() =>
+            {
+                if let Some(mut away) = self.away.take() {
+                    away.end = Some(now.max(away.start));
+                    self.finish_away(away);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 检测键盘鼠标闲置状态（未设置闲置检测时不做任何事）
+    fn poll_idle(&mut self, now: DateTime<Utc>) -> Result<()> {
+        let Some(detector) = &mut self.idle_detector else {
+            return Ok(());
+        };
+        // 正在观看视频或全屏放映时即使没有输入也不算闲置
+        let current = self.current_activity.as_ref();
+        detector.set_fullscreen(current.is_some_and(|record| record.fullscreen));
+        let status = match detector.detect_activity(
+            current.map(|record| record.app_name.as_str()),
+            current.map(|record| record.window_title.as_str()),
+        ) {
+            Ok(status) => status,
+            Err(e) => {
+                log::debug!("检测闲置状态失败: {}", e);
+                return Ok(());
+            }
+        };
+        let last_input = DateTime::<Utc>::from(detector.last_input_time());
+        let idle_timeout = detector.get_stats().idle_timeout;
+        let passive = status == ActivityStatus::WatchingVideo
+            && (now - last_input)
+                .to_std()
+                .is_ok_and(|idle| idle > idle_timeout);
+        let media = passive.then(|| detector.current_media().cloned()).flatten();
+        self.observe_media(media);
+        self.observe_idle(status == ActivityStatus::Idle, last_input, now)
+    }
+
+    /// 记录没有输入时正在播放的媒体，为 None 表示没有在被动观看
+    pub fn observe_media(&mut self, media: Option<MediaInfo>) {
+        if media != self.passive_media {
+            match &media {
+                Some(media) => log::info!("没有输入，正在播放: {}", media.describe()),
+                None => log::debug!("停止被动观看"),
+            }
+        }
+        self.passive_media = media;
+    }
+
+    /// 按离开状态和视频播放情况更新状态时间线，写入失败时只记录错误
+    fn update_status(&mut self, now: DateTime<Utc>) {
+        let (status, since) = match &self.away {
+            Some(away) => {
+                let status = match away.reason {
+                    AwayReason::Lock => Some(StatusKind::Locked),
+                    AwayReason::Idle => Some(StatusKind::Idle),
+                    // 休眠期间电脑没有运行，不在时间线中
+                    AwayReason::Sleep | AwayReason::ClockJump => None,
+                };
+                (status, away.start)
+            }
+            None if self.passive_media.is_some() => (Some(StatusKind::VideoPassive), now),
+            None => (Some(StatusKind::Active), now),
+        };
+        if let Err(e) = self.record_status(status, since, now) {
+            log::error!("保存状态时间线失败: {}", e);
+        }
+    }
+
+    /// 记录一次状态采样
+    ///
+    /// 状态不变时把进行中的时间段延长到 `now`；状态改变时在 `since`（例如闲置开始时的最后一次
+    /// 输入）结束上一段并开始新的一段，`status` 为 None 表示电脑休眠。两次采样之间发生时钟
+    /// 跳变时在上次采样时结束，休眠的时间不计入任何状态。被动观看时记录正在播放的媒体，
+    /// 媒体改变时同样开始新的一段；时间线文件不加密，加密存储时只记录播放器，不记录曲目。
+    pub fn record_status(
+        &mut self,
+        status: Option<StatusKind>,
+        since: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let encrypted = self.store_options.passphrase.is_some();
+        let media = match status {
+            Some(StatusKind::VideoPassive) => self.passive_media.clone().map(|media| MediaInfo {
+                track: media.track.filter(|_| !encrypted),
+                ..media
+            }),
+            _ => None,
+        };
+        if let Some(current) = &mut self.status {
+            if Some(current.status) == status
+                && current.media == media
+                && !is_clock_jump(current.end, now, self.interval)
+            {
+                current.end = current.end.max(now);
+                let due = self.status_saved_at.is_none_or(|saved| {
+                    now - saved >= chrono::Duration::seconds(STATUS_SAVE_INTERVAL_SECS)
+                });
+                if due {
+                    self.save_current_status(now)?;
+                }
+                return Ok(());
+            }
+        }
+
+        let mut since = since.min(now);
+        if let Some(mut finished) = self.status.take() {
+            if is_clock_jump(finished.end, now, self.interval) {
+                since = now;
+            } else {
+                finished.end = since.max(finished.start);
+            }
+            since = since.max(finished.end);
+            if finished.end > finished.start {
+                self.status_log.append(&finished)?;
+            }
+        }
+        self.status = status.map(|status| StatusInterval {
+            media,
+            ..StatusInterval::new(status, since, now.max(since))
+        });
+        self.save_current_status(now)
+    }
+
+    fn save_current_status(&mut self, now: DateTime<Utc>) -> Result<()> {
+        self.status_log.set_current(self.status.as_ref())?;
+        self.status_saved_at = Some(now);
+        Ok(())
+    }
+
+    /// 查询 [start, end) 内的状态时间线
+    pub fn status_timeline(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<StatusInterval>> {
+        // 包括尚未保存的最新进展
+        if self.status.is_some() {
+            self.status_log.set_current(self.status.as_ref())?;
+        }
+        self.status_log.query(start, end)
+    }
+
+    /// 处理闲置状态变化
+    ///
+    /// 开始闲置时在最后一次输入时结束当前活动并开始离开时段；重新有输入时在该输入时结束离开。
+    /// 休眠造成的离开不受闲置状态影响；锁屏后又有新的输入时结束锁屏造成的离开，
+    /// 因为多数锁屏程序解锁时不发出 `Unlock` 信号。
+    pub fn observe_idle(
+        &mut self,
+        idle: bool,
+        last_input: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        match &self.away {
+            None if idle => {
+                let start = last_input.min(now);
+                self.close_current_activity_at(start)?;
+                log::info!("闲置超时，在最后一次输入 {} 时结束当前活动", start);
+                self.away = Some(AwayPeriod {
+                    start,
+                    end: None,
+                    reason: AwayReason::Idle,
+                });
+            }
+            Some(away) if !idle && away.reason == AwayReason::Idle => {
+                if let Some(mut away) = self.away.take() {
+                    away.end = Some(last_input.clamp(away.start, now.max(away.start)));
+                    self.finish_away(away);
+                }
+            }
+            // 锁屏程序解锁时不一定发出解锁信号：锁屏之后又有了输入，视为已经解锁
+            Some(away)
+                if !idle
+                    && away.reason == AwayReason::Lock
+                    && !self.sleeping
+                    && self.locked_since.is_some_and(|since| last_input > since) =>
+            {
+                log::info!("锁屏后检测到输入，视为已解锁");
+                self.locked_since = None;
+                if let Some(mut away) = self.away.take() {
+                    away.end = Some(last_input.clamp(away.start, now.max(away.start)));
+                    self.finish_away(away);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 是否处于离开状态（休眠、锁屏或闲置）
+    pub fn is_away(&self) -> bool {
+        self.away.is_some()
+    }
+
+    /// 本次监控期间已结束的离开时段
+    pub fn away_periods(&self) -> &[AwayPeriod] {
+        &self.away_periods
+    }
+
+    fn finish_away(&mut self, away: AwayPeriod) {
+        log::info!(
+            "离开时段（{}）: {} ~ {}，共 {} 秒",
+            away.reason.description(),
+            away.start,
+            away.end.unwrap_or(away.start),
+            away.duration_at(away.start)
+        );
+        let needs_review = self
+            .away_review
+            .is_some_and(|threshold| away.end.is_some_and(|end| end - away.start >= threshold));
+        if needs_review {
+            match self.away_queue.push(away.clone()) {
+                Ok(()) => log::info!("离开时段已加入待标注队列"),
+                Err(e) => log::error!("保存待标注的离开时段失败: {}", e),
+            }
+        }
+        self.away_periods.push(away);
+    }
+
+    /// 等待标注的离开时段，按开始时间排序
+    pub fn pending_away(&mut self) -> Result<Vec<AwayPeriod>> {
+        self.away_queue.pending()
+    }
+
+    /// 把离开时段标注为手动记录（例如会议、午餐或某个项目）并移出待标注队列
+    pub fn label_away(
+        &mut self,
+        period: &AwayPeriod,
+        label: &str,
+        project: Option<&str>,
+        policy: OverlapPolicy,
+    ) -> Result<ManualEntry> {
+        let entry = label_entry(period, label, project)?;
+        self.add_manual_entry(&entry, policy)?;
+        self.away_queue.remove(period)?;
+        Ok(entry)
+    }
+
+    /// 不标注，直接把离开时段移出待标注队列
+    pub fn dismiss_away(&mut self, period: &AwayPeriod) -> Result<bool> {
+        self.away_queue.remove(period)
+    }
+
+    /// 在指定时间结束当前活动并保存
+    pub fn close_current_activity_at(&mut self, at: DateTime<Utc>) -> Result<()> {
+        self.pending_switch = None;
+        if let Some(mut current) = self.current_activity.take() {
+            current.finish_at(at);
+            log::info!(
+                "活动结束: {} - {} ({}秒)",
+                current.app_name,
+                current.window_title,
+                current.duration
+            );
+            self.commit_closed(current)?;
+        }
+        Ok(())
+    }
+
     pub fn stop_monitoring(&mut self) -> Result<()> {
+        self.pending_switch = None;
+        if let Some(status) = self.status.take() {
+            if status.end > status.start {
+                self.status_log.append(&status)?;
+            }
+            self.status_log.set_current(None)?;
+        }
         if let Some(mut current) = self.current_activity.take() {
-            current.finish();
+            // 在最后一次心跳时结束
+            current.finish_at(current.effective_end());
             log::info!(
                 "监控停止，最后活动: {} - {} ({}秒)",
                 current.app_name,
                 current.window_title,
                 current.duration
             );
-            self.data.activities.push(current);
-            self.save_data()?;
+            self.commit_closed(current)?;
         }
         Ok(())
     }
@@ -610,7 +1730,9 @@ impl TimeTracker {
     /// 导出为 CSV 格式
     pub fn export_csv(&self) -> Result<String> {
         let mut csv = String::new();
-        csv.push_str("app_name,window_title,start_time,end_time,duration,process_id\n");
+        csv.push_str(
+            "app_name,window_title,start_time,end_time,duration,process_id,source,project\n",
+        );
 
         for activity in &self.data.activities {
             let end_time = activity
@@ -619,13 +1741,36 @@ impl TimeTracker {
                 .unwrap_or_else(|| "N/A".to_string());
 
             csv.push_str(&format!(
-                "{},{},{},{},{},{}\n",
+                "{},{},{},{},{},{},{},{}\n",
                 activity.app_name,
                 activity.window_title,
                 activity.start_time.format("%Y-%m-%d %H:%M:%S"),
                 end_time,
                 activity.duration,
-                activity.process_id
+                activity.process_id,
+                activity.source.as_str(),
+                activity.project.as_deref().unwrap_or("")
+            ));
+        }
+
+        Ok(csv)
+    }
+
+    /// 把状态时间线导出为 CSV 格式
+    pub fn export_status_csv(&self) -> Result<String> {
+        let mut csv = String::new();
+        csv.push_str("status,start_time,end_time,duration,player,track\n");
+
+        for interval in &self.data.status_timeline {
+            let media = interval.media.as_ref();
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                interval.status.as_str(),
+                interval.start.format("%Y-%m-%d %H:%M:%S"),
+                interval.end.format("%Y-%m-%d %H:%M:%S"),
+                interval.duration(),
+                media.map_or("", |m| m.player.as_str()),
+                media.and_then(|m| m.track.as_deref()).unwrap_or("")
             ));
         }
 
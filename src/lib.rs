@@ -4,6 +4,7 @@
 pub mod ai;
 pub mod config;
 pub mod core;
+pub mod storage;
 pub mod team; // v0.3.0 新增团队功能
 pub mod ui;
 pub mod utils;
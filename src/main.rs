@@ -10,6 +10,10 @@ use timetracker::ui::tui::TuiApp;
 
 // 快速响应模式 - 避免导入可能阻塞的模块
 
+/// 守护进程子进程从启动命令的管道中读到的口令
+static DAEMON_PASSPHRASE: std::sync::OnceLock<timetracker::storage::Passphrase> =
+    std::sync::OnceLock::new();
+
 /// 打印帮助信息
 fn print_help() {
     println!("timetracker {}", env!("CARGO_PKG_VERSION"));
@@ -26,6 +30,7 @@ fn print_help() {
     println!("    export       Export data to various formats");
     println!("    permissions  Check and manage permissions");
     println!("    activity     Manage user activity detection");
+    println!("    data         Manage stored tracking data");
     println!("    help         Print this message or the help of the given subcommand(s)");
     println!();
     println!("OPTIONS:");
@@ -45,6 +50,951 @@ fn print_short_help() {
     println!("  timetracker status   # Check status");
 }
 
+/// 默认数据目录（~/.timetracker）
+fn default_data_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".timetracker")
+}
+
+/// 根据存储配置得到数据目录中的数据文件路径
+fn default_data_file(data_dir: Option<PathBuf>) -> String {
+    use timetracker::config::app::AppConfig;
+
+    let storage = AppConfig::load().map(|c| c.storage).unwrap_or_default();
+    timetracker::storage::resolve_data_file(&data_dir.unwrap_or_else(default_data_dir), &storage)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 获取数据文件的加密口令（未启用加密且文件未加密时返回 None）
+fn storage_passphrase(
+    data_file: &str,
+    interactive: bool,
+) -> Result<Option<timetracker::storage::Passphrase>> {
+    use timetracker::config::app::AppConfig;
+
+    if let Some(passphrase) = DAEMON_PASSPHRASE.get() {
+        return Ok(Some(passphrase.clone()));
+    }
+    let storage = AppConfig::load().map(|c| c.storage).unwrap_or_default();
+    timetracker::storage::crypto::resolve_passphrase(
+        &storage,
+        std::path::Path::new(data_file),
+        interactive,
+    )
+}
+
+/// 根据存储配置得到打开数据文件的选项（加密口令、按日期分区）
+fn store_options(data_file: &str, interactive: bool) -> Result<timetracker::storage::StoreOptions> {
+    use timetracker::config::app::AppConfig;
+
+    let storage = AppConfig::load().map(|c| c.storage).unwrap_or_default();
+    let passphrase = storage_passphrase(data_file, interactive)?;
+    Ok(timetracker::storage::StoreOptions::from_config(
+        &storage,
+        passphrase,
+        day_boundary(),
+    ))
+}
+
+/// 配置中启用的数据保留策略
+fn retention_policy() -> Option<timetracker::storage::RetentionPolicy> {
+    use timetracker::config::app::AppConfig;
+
+    AppConfig::load().ok().and_then(|c| c.retention.policy())
+}
+
+/// 配置中启用的每日汇总天数
+fn rollup_after_days() -> Option<u32> {
+    use timetracker::config::app::AppConfig;
+
+    AppConfig::load().ok().and_then(|c| c.rollup.after_days())
+}
+
+/// 配置中启用的记录压缩间隔
+fn compaction_gap_secs() -> Option<u64> {
+    use timetracker::config::app::AppConfig;
+
+    AppConfig::load().ok().and_then(|c| c.compaction.gap_secs())
+}
+
+/// 按配置设置心跳脉冲窗口、切换防抖、最短记录时长、标题规范化规则、应用规则、闲置检测和离开标注
+fn apply_tracking_config(tracker: &mut TimeTracker) {
+    use timetracker::config::app::AppConfig;
+
+    let config = AppConfig::load().unwrap_or_default();
+    let tracking = config.tracking;
+    tracker.set_pulse_window(tracking.pulse_window_secs);
+    tracker.set_debounce(tracking.debounce_secs);
+    tracker.set_min_record_duration(tracking.min_record_secs);
+    tracker.set_title_normalizer(tracking.title_normalizer());
+    tracker.set_app_rules(timetracker::core::app_rules::AppRules::load_default());
+    tracker.set_idle_detection(Some(config.activity.to_activity_config()));
+    tracker.set_away_review(config.activity.away_review_minutes());
+}
+
+/// 配置中的时区和日分割点
+fn day_boundary() -> timetracker::utils::time::DayBoundary {
+    use timetracker::config::app::AppConfig;
+
+    AppConfig::load()
+        .map(|c| c.ui.day_boundary())
+        .unwrap_or_default()
+}
+
+/// 为子命令添加时间范围参数（--range、--from、--to）
+fn with_time_range_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("range")
+                .long("range")
+                .value_name("RANGE")
+                .help("Time range: today, yesterday, this-week, last-week, this-month, last-month, last-7-days, last-30-days, all, or YYYY-MM-DD[..YYYY-MM-DD]")
+                .conflicts_with_all(["from", "to"]),
+        )
+        .arg(
+            Arg::new("from")
+                .long("from")
+                .value_name("DATE")
+                .help("Start date (YYYY-MM-DD, inclusive)"),
+        )
+        .arg(
+            Arg::new("to")
+                .long("to")
+                .value_name("DATE")
+                .help("End date (YYYY-MM-DD, inclusive, default: today)")
+                .requires("from"),
+        )
+}
+
+/// 解析命令行指定的时间范围，未指定时返回 `None`
+fn time_range_arg(
+    matches: &clap::ArgMatches,
+) -> Result<Option<timetracker::ui::components::TimeRangeFilter>> {
+    use timetracker::ui::components::TimeRangeFilter;
+
+    if let Some(range) = matches.get_one::<String>("range") {
+        return TimeRangeFilter::parse(range).map(Some);
+    }
+
+    let Some(from) = matches.get_one::<String>("from") else {
+        return Ok(None);
+    };
+    let to = match matches.get_one::<String>("to") {
+        Some(to) => to.clone(),
+        None => day_boundary()
+            .logical_date(chrono::Utc::now())
+            .format("%Y-%m-%d")
+            .to_string(),
+    };
+    TimeRangeFilter::parse(&format!("{}..{}", from, to)).map(Some)
+}
+
+/// 守护进程在后台运行，无法提示输入口令：启动前验证口令，由启动命令通过管道传给子进程
+fn prepare_daemon_passphrase(data_file: &str) -> Result<Option<timetracker::storage::Passphrase>> {
+    let passphrase = storage_passphrase(data_file, true)?;
+    if let Some(passphrase) = &passphrase {
+        timetracker::storage::JsonFileStore::open_encrypted(data_file, passphrase)?;
+    }
+    Ok(passphrase)
+}
+
+/// 守护进程子进程从标准输入读取启动命令写入的口令（必须在守护化关闭标准输入之前调用）
+fn read_daemon_passphrase() -> Result<()> {
+    let mut line = zeroize::Zeroizing::new(String::new());
+    std::io::stdin().read_line(&mut line)?;
+    let passphrase = line.trim_end_matches(['\r', '\n']);
+    if passphrase.is_empty() {
+        return Err(anyhow::anyhow!("没有从启动命令读到口令"));
+    }
+    let _ = DAEMON_PASSPHRASE.set(timetracker::storage::Passphrase::new(passphrase));
+    Ok(())
+}
+
+/// 处理数据管理命令
+fn handle_data_command(sub_matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::storage::SqliteStore;
+
+    match sub_matches.subcommand() {
+        Some(("import-json", import_matches)) => {
+            let from = import_matches
+                .get_one::<PathBuf>("from")
+                .cloned()
+                .unwrap_or_else(|| default_data_dir().join("activities.json"));
+            let to = import_matches
+                .get_one::<PathBuf>("to")
+                .cloned()
+                .unwrap_or_else(|| default_data_dir().join("activities.db"));
+
+            if !from.exists() {
+                return Err(anyhow::anyhow!("JSON 数据文件不存在: {}", from.display()));
+            }
+
+            let mut store = SqliteStore::open(&to)?;
+            if store.count()? > 0 {
+                return Err(anyhow::anyhow!(
+                    "目标数据库 {} 已包含记录，为避免重复导入已中止",
+                    to.display()
+                ));
+            }
+
+            let imported = store.import_json(&from)?;
+            println!(
+                "✅ 已从 {} 导入 {} 条活动记录到 {}",
+                from.display(),
+                imported,
+                to.display()
+            );
+            println!("在配置文件中设置 [storage] backend = \"sqlite\" 以使用该数据库");
+        }
+        Some(("repair", repair_matches)) => {
+            use timetracker::storage::open_store_with_options;
+            use timetracker::storage::repair::{merge_records, quarantine_file, salvage_records};
+
+            let data_file = PathBuf::from(default_data_file(None));
+            let input = repair_matches
+                .get_one::<PathBuf>("input")
+                .cloned()
+                .unwrap_or_else(|| data_file.clone());
+            let output = repair_matches
+                .get_one::<PathBuf>("output")
+                .cloned()
+                .unwrap_or(data_file);
+            let dry_run = repair_matches.get_flag("dry-run");
+
+            let bytes = std::fs::read(&input)
+                .map_err(|e| anyhow::anyhow!("无法读取 {}: {}", input.display(), e))?;
+            if timetracker::storage::crypto::is_encrypted_contents(&bytes) {
+                return Err(anyhow::anyhow!(
+                    "{} 是加密的数据文件，无法直接修复",
+                    input.display()
+                ));
+            }
+            let report = salvage_records(&String::from_utf8_lossy(&bytes));
+
+            println!("🔧 修复数据文件: {}", input.display());
+            println!("可恢复的活动记录: {} 条", report.records.len());
+            if report.skipped > 0 {
+                println!("无法解析的记录片段: {} 个", report.skipped);
+            }
+
+            if report.intact && input == output {
+                println!("✅ 数据文件完好，无需修复");
+                return Ok(());
+            }
+            if dry_run {
+                println!("（预演模式，未写入任何文件）");
+                return Ok(());
+            }
+
+            if input == output {
+                let quarantined = quarantine_file(&input)?;
+                println!("原文件已备份到: {}", quarantined.display());
+            }
+
+            let options = store_options(&output.to_string_lossy(), true)?;
+            let mut store = open_store_with_options(&output, &options)?;
+            let mut data = store.load()?;
+            let added = merge_records(&mut data.activities, report.records);
+            store.replace_all(&data.activities)?;
+            store.flush()?;
+
+            println!("✅ 已将 {} 条记录写入 {}", added, output.display());
+        }
+        Some(("migrate", migrate_matches)) => {
+            let path = migrate_matches
+                .get_one::<PathBuf>("data-file")
+                .cloned()
+                .unwrap_or_else(|| PathBuf::from(default_data_file(None)));
+            let dry_run = migrate_matches.get_flag("dry-run");
+            handle_data_migrate(&path, dry_run)?;
+        }
+        Some(("encrypt", encrypt_matches)) => {
+            handle_data_encrypt(encrypt_matches)?;
+        }
+        Some(("decrypt", decrypt_matches)) => {
+            handle_data_decrypt(decrypt_matches)?;
+        }
+        Some(("rotate-key", rotate_matches)) => {
+            handle_data_rotate_key(rotate_matches)?;
+        }
+        Some(("rollup", rollup_matches)) => {
+            handle_data_rollup(rollup_matches)?;
+        }
+        Some(("compact", compact_matches)) => {
+            handle_data_compact(compact_matches)?;
+        }
+        Some(("doctor", doctor_matches)) => {
+            handle_data_doctor(doctor_matches)?;
+        }
+        Some(("apply-rules", rules_matches)) => {
+            handle_data_apply_rules(rules_matches)?;
+        }
+        _ => {
+            println!("使用 'timetracker data --help' 查看可用的数据管理命令");
+        }
+    }
+
+    Ok(())
+}
+
+/// 对已有历史记录重新应用应用别名规则
+fn handle_data_apply_rules(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::core::app_rules::AppRules;
+    use timetracker::storage::open_store_with_options;
+
+    let path = data_file_arg(matches);
+    let rules = match matches.get_one::<PathBuf>("rules") {
+        Some(rules_path) => AppRules::load(rules_path)?,
+        None => AppRules::load_default(),
+    };
+    let dry_run = matches.get_flag("dry-run");
+
+    if !path.exists() {
+        println!("数据文件不存在: {}", path.display());
+        return Ok(());
+    }
+
+    let options = store_options(&path.to_string_lossy(), true)?;
+    let mut store = open_store_with_options(&path, &options)?;
+    let mut data = store.load()?;
+
+    println!("🏷️ 数据文件: {}", path.display());
+    let renamed = rules.apply_to_records(&mut data.activities);
+    if renamed.is_empty() {
+        println!("✅ 所有记录的应用名都已符合规则");
+        return Ok(());
+    }
+
+    for ((from, to), count) in &renamed {
+        println!("  {} -> {}: {} 条", from, to, count);
+    }
+    let total: usize = renamed.values().sum();
+
+    if dry_run {
+        println!("将更新 {} 条记录", total);
+        println!("（预演模式，未写入任何文件）");
+        return Ok(());
+    }
+
+    store.replace_all(&data.activities)?;
+    store.flush()?;
+    println!("✅ 已更新 {} 条记录", total);
+    if !data.rollups.is_empty() {
+        println!("已汇总的旧记录不包含应用路径，未重新应用规则");
+    }
+    Ok(())
+}
+
+/// 把旧记录合并为每日汇总
+fn handle_data_rollup(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::config::app::AppConfig;
+    use timetracker::storage::open_store_with_options;
+    use timetracker::storage::rollup::{preview_rollup, rollup_cutoff};
+
+    let path = data_file_arg(matches);
+    let after_days = match matches.get_one::<u32>("older-than") {
+        Some(days) => *days,
+        None => AppConfig::load().unwrap_or_default().rollup.after_days,
+    };
+    let dry_run = matches.get_flag("dry-run");
+
+    if !path.exists() {
+        println!("数据文件不存在: {}", path.display());
+        return Ok(());
+    }
+
+    let boundary = day_boundary();
+    let before = rollup_cutoff(chrono::Utc::now(), after_days, boundary);
+    let options = store_options(&path.to_string_lossy(), true)?;
+    let mut store = open_store_with_options(&path, &options)?;
+
+    println!("📦 数据文件: {}", path.display());
+    println!(
+        "汇总 {} 之前（{} 天前）的记录",
+        boundary.logical_date(before).format("%Y-%m-%d"),
+        after_days
+    );
+
+    if dry_run {
+        let report = preview_rollup(store.as_mut(), before, boundary)?;
+        println!(
+            "将把 {} 条记录合并为 {} 条每日汇总",
+            report.records, report.rollups
+        );
+        println!("（预演模式，未写入任何文件）");
+        return Ok(());
+    }
+
+    let report = store.rollup(before, boundary)?;
+    store.flush()?;
+    if report.is_empty() {
+        println!("✅ 没有需要汇总的记录");
+    } else {
+        println!(
+            "✅ 已把 {} 条记录合并为 {} 条每日汇总",
+            report.records, report.rollups
+        );
+    }
+    Ok(())
+}
+
+/// 把相邻的同一应用窗口记录永久合并为一条
+fn handle_data_compact(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::config::app::AppConfig;
+    use timetracker::storage::compact::compact_store;
+    use timetracker::storage::open_store_with_options;
+
+    let path = data_file_arg(matches);
+    let gap_secs = match matches.get_one::<u64>("gap") {
+        Some(secs) => *secs,
+        None => AppConfig::load().unwrap_or_default().compaction.gap_secs,
+    };
+    let dry_run = matches.get_flag("dry-run");
+
+    if !path.exists() {
+        println!("数据文件不存在: {}", path.display());
+        return Ok(());
+    }
+
+    let options = store_options(&path.to_string_lossy(), true)?;
+    let mut store = open_store_with_options(&path, &options)?;
+
+    println!("📦 数据文件: {}", path.display());
+    println!("合并间隔不超过 {} 秒的相邻同窗口记录", gap_secs);
+
+    let gap = chrono::Duration::seconds(gap_secs as i64);
+    let report = compact_store(store.as_mut(), gap, dry_run)?;
+    if report.is_empty() {
+        println!("✅ 没有可以合并的记录");
+    } else if dry_run {
+        println!(
+            "将移除 {} 条记录，合并到 {} 条记录中",
+            report.removed, report.merged
+        );
+        println!("（预演模式，未写入任何文件）");
+    } else {
+        println!(
+            "✅ 已移除 {} 条记录，合并到 {} 条记录中",
+            report.removed, report.merged
+        );
+    }
+    Ok(())
+}
+
+/// 添加手动记录
+fn handle_add_command(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::core::manual::{resolve_span, ManualEntry, OverlapPolicy};
+    use timetracker::utils::time::{parse_duration_spec, parse_local_datetime};
+
+    let timezone = day_boundary().timezone;
+    let now = chrono::Utc::now();
+    let parse_time = |name: &str| {
+        matches
+            .get_one::<String>(name)
+            .map(|value| parse_local_datetime(value, now, timezone))
+            .transpose()
+    };
+    let start = parse_time("start")?;
+    let end = parse_time("end")?;
+    let duration = matches
+        .get_one::<String>("duration")
+        .map(|value| parse_duration_spec(value))
+        .transpose()?;
+    let (start, end) = resolve_span(start, end, duration, now)?;
+
+    let label = matches.get_one::<String>("label").unwrap();
+    let mut entry = ManualEntry::new(label, start, end)?;
+    if let Some(project) = matches.get_one::<String>("project") {
+        entry = entry.project(project);
+    }
+    if let Some(note) = matches.get_one::<String>("note") {
+        entry = entry.note(note);
+    }
+    let policy = matches
+        .get_one::<String>("on-overlap")
+        .and_then(|name| OverlapPolicy::from_name(name))
+        .unwrap_or_default();
+
+    let data_file = matches
+        .get_one::<String>("data-file")
+        .cloned()
+        .unwrap_or_else(|| default_data_file(None));
+    let options = store_options(&data_file, true)?;
+    let mut tracker = TimeTracker::new(data_file, 1);
+    tracker.set_store_options(options);
+    tracker.load_data()?;
+
+    let format_time = |time: chrono::DateTime<chrono::Utc>| {
+        timezone
+            .to_naive(time)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    };
+    let overlaps = tracker.find_overlaps(entry.start, entry.end)?;
+    if !overlaps.is_empty() {
+        println!("⚠️  与 {} 条已有记录重叠:", overlaps.len());
+        for record in &overlaps {
+            println!(
+                "  {} ~ {}  {} - {}",
+                format_time(record.start_time),
+                format_time(record.effective_end()),
+                record.app_name,
+                record.window_title
+            );
+        }
+        if policy == OverlapPolicy::Reject {
+            println!();
+            println!(
+                "未添加记录。使用 --on-overlap trim 裁剪重叠的记录，或 --on-overlap keep 保留重叠"
+            );
+            return Err(anyhow::anyhow!("手动记录与已有记录重叠"));
+        }
+    }
+
+    tracker.add_manual_entry(&entry, policy)?;
+    println!(
+        "✅ 已添加手动记录: {}（{} ~ {}，{}）",
+        entry.label,
+        format_time(entry.start),
+        format_time(entry.end),
+        timetracker::utils::time::format_duration(entry.duration())
+    );
+    if let Some(project) = &entry.project {
+        println!("   项目: {}", project);
+    }
+    if policy == OverlapPolicy::Trim && !overlaps.is_empty() {
+        println!("   已裁剪 {} 条重叠的记录", overlaps.len());
+    }
+    Ok(())
+}
+
+/// 标注离开电脑的时间：列出、标注或忽略待标注的离开时段，在终端中逐个询问
+fn handle_review_command(matches: &clap::ArgMatches) -> Result<()> {
+    use std::io::IsTerminal;
+    use timetracker::core::away::describe;
+    use timetracker::core::manual::OverlapPolicy;
+
+    let data_file = matches
+        .get_one::<String>("data-file")
+        .cloned()
+        .unwrap_or_else(|| default_data_file(None));
+    let options = store_options(&data_file, true)?;
+    let mut tracker = TimeTracker::new(data_file, 1);
+    tracker.set_store_options(options);
+    let timezone = day_boundary().timezone;
+
+    let pending = tracker.pending_away()?;
+    let pick = |matches: &clap::ArgMatches| {
+        let index = *matches.get_one::<usize>("index").unwrap();
+        pending.get(index.wrapping_sub(1)).cloned().ok_or_else(|| {
+            anyhow::anyhow!(
+                "没有编号为 {} 的离开时段，使用 'timetracker review list' 查看",
+                index
+            )
+        })
+    };
+
+    match matches.subcommand() {
+        Some(("label", sub_matches)) => {
+            let period = pick(sub_matches)?;
+            let label = sub_matches.get_one::<String>("label").unwrap();
+            let project = sub_matches.get_one::<String>("project");
+            let policy = sub_matches
+                .get_one::<String>("on-overlap")
+                .and_then(|name| OverlapPolicy::from_name(name))
+                .unwrap_or_default();
+            let entry = tracker.label_away(&period, label, project.map(String::as_str), policy)?;
+            println!(
+                "✅ 已保存为手动记录: {}（{}）",
+                entry.label,
+                describe(&period, timezone)
+            );
+            if let Some(project) = &entry.project {
+                println!("   项目: {}", project);
+            }
+        }
+        Some(("dismiss", sub_matches)) => {
+            let period = pick(sub_matches)?;
+            tracker.dismiss_away(&period)?;
+            println!("已忽略离开时段: {}", describe(&period, timezone));
+        }
+        Some(("list", _)) => print_pending_away(&pending, timezone),
+        _ if !pending.is_empty() && std::io::stdin().is_terminal() => {
+            review_away_interactively(&mut tracker, &pending, timezone)?;
+        }
+        _ => print_pending_away(&pending, timezone),
+    }
+    Ok(())
+}
+
+/// 列出待标注的离开时段
+fn print_pending_away(
+    pending: &[timetracker::core::power::AwayPeriod],
+    timezone: timetracker::utils::time::DayTimeZone,
+) {
+    use timetracker::core::away::describe;
+
+    if pending.is_empty() {
+        println!("✅ 没有待标注的离开时段");
+        return;
+    }
+    println!("⏸️  待标注的离开时段:");
+    for (index, period) in pending.iter().enumerate() {
+        println!("  {}. {}", index + 1, describe(period, timezone));
+    }
+    println!();
+    println!(
+        "使用 'timetracker review label <编号> <名称>' 标注，或 'timetracker review' 逐个标注"
+    );
+}
+
+/// 在终端中逐个询问离开时段的用途
+fn review_away_interactively(
+    tracker: &mut TimeTracker,
+    pending: &[timetracker::core::power::AwayPeriod],
+    timezone: timetracker::utils::time::DayTimeZone,
+) -> Result<()> {
+    use std::io::Write;
+    use timetracker::core::away::{describe, parse_label, SUGGESTED_LABELS};
+    use timetracker::core::manual::OverlapPolicy;
+
+    println!("⏸️  有 {} 个离开时段待标注", pending.len());
+    println!(
+        "输入 名称[@项目] 保存为手动记录（例如 {}），直接回车跳过，输入 - 忽略",
+        SUGGESTED_LABELS.join("、")
+    );
+    for period in pending {
+        print!("{} 标注为: ", describe(period, timezone));
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            break;
+        }
+
+        if input.trim() == "-" {
+            tracker.dismiss_away(period)?;
+            println!("  已忽略");
+            continue;
+        }
+        let Some((label, project)) = parse_label(&input) else {
+            continue;
+        };
+        match tracker.label_away(period, &label, project.as_deref(), OverlapPolicy::Reject) {
+            Ok(entry) => println!("  ✅ 已保存为手动记录: {}", entry.label),
+            Err(e) => println!("  ❌ 保存失败: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// 检查历史记录中的重叠、时间倒置和时长不符，可选自动修复
+fn handle_data_doctor(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::storage::doctor::doctor_store;
+    use timetracker::storage::open_store_with_options;
+
+    /// 每类问题最多列出的记录数
+    const MAX_LISTED: usize = 10;
+
+    let path = data_file_arg(matches);
+    let fix = matches.get_flag("fix");
+
+    if !path.exists() {
+        println!("数据文件不存在: {}", path.display());
+        return Ok(());
+    }
+
+    let options = store_options(&path.to_string_lossy(), true)?;
+    let mut store = open_store_with_options(&path, &options)?;
+    let timezone = day_boundary().timezone;
+
+    println!("🩺 数据文件: {}", path.display());
+    let report = doctor_store(store.as_mut(), fix, timezone)?;
+    if report.is_empty() {
+        println!("✅ 没有发现问题");
+        return Ok(());
+    }
+
+    for (kind, issues) in report.by_kind() {
+        println!();
+        println!(
+            "{}: {} 条（修复方式: {}）",
+            kind.description(),
+            issues.len(),
+            kind.fix_description()
+        );
+        for issue in issues.iter().take(MAX_LISTED) {
+            println!(
+                "  {} {} - {}",
+                timezone
+                    .to_naive(issue.start_time)
+                    .format("%Y-%m-%d %H:%M:%S"),
+                issue.app_name,
+                issue.detail
+            );
+        }
+        if issues.len() > MAX_LISTED {
+            println!("  ... 还有 {} 条", issues.len() - MAX_LISTED);
+        }
+    }
+
+    println!();
+    if fix {
+        println!(
+            "✅ 已修复 {} 条记录，删除 {} 条记录",
+            report.plan.updated.len(),
+            report.plan.removed.len()
+        );
+    } else {
+        println!(
+            "修复将更新 {} 条记录，删除 {} 条记录；使用 --fix 执行修复",
+            report.plan.updated.len(),
+            report.plan.removed.len()
+        );
+    }
+    Ok(())
+}
+
+/// 检查并升级数据文件的结构版本
+fn handle_data_migrate(path: &std::path::Path, dry_run: bool) -> Result<()> {
+    use anyhow::Context;
+    use timetracker::storage::migration::{backup_path, migrate_value};
+    use timetracker::storage::{crypto, open_store_with, SqliteStore, StorageBackend};
+
+    if !path.exists() {
+        println!("数据文件不存在: {}", path.display());
+        return Ok(());
+    }
+
+    println!("📦 数据文件: {}", path.display());
+
+    match StorageBackend::from_path(path) {
+        StorageBackend::Json => {
+            let passphrase = storage_passphrase(&path.to_string_lossy(), true)?;
+            let bytes = std::fs::read(path)?;
+            let content = match &passphrase {
+                Some(passphrase) if crypto::is_encrypted_contents(&bytes) => {
+                    let cipher = crypto::open_cipher(path, passphrase)?;
+                    String::from_utf8(cipher.decrypt_file(&bytes)?.to_vec())?
+                }
+                _ => String::from_utf8_lossy(&bytes).to_string(),
+            };
+            let mut value: serde_json::Value =
+                serde_json::from_str(&content).with_context(|| {
+                    format!(
+                        "无法解析数据文件，可先运行 'timetracker data repair --input {}'",
+                        path.display()
+                    )
+                })?;
+            let report = migrate_value(&mut value)?;
+
+            if report.is_empty() {
+                println!("✅ 已是最新结构版本 v{}，无需迁移", report.to_version);
+                return Ok(());
+            }
+
+            println!(
+                "结构版本: v{} → v{}",
+                report.from_version, report.to_version
+            );
+            for step in &report.steps {
+                println!("  v{} → v{}: {}", step.from, step.to, step.description);
+                for change in &step.changes {
+                    println!("    - {}", change);
+                }
+            }
+
+            if dry_run {
+                println!("（预演模式，未写入任何文件）");
+                return Ok(());
+            }
+
+            // 加载时会在文件锁内完成升级并写回
+            open_store_with(path, passphrase.as_ref())?.load()?;
+            println!(
+                "✅ 迁移完成，原文件已备份到 {}",
+                backup_path(path, report.from_version).display()
+            );
+        }
+        StorageBackend::Sqlite => {
+            let version = SqliteStore::read_schema_version(path)?;
+            let changes = SqliteStore::pending_schema_changes(version);
+
+            if changes.is_empty() {
+                println!("✅ 已是最新结构版本 v{}，无需迁移", version);
+                return Ok(());
+            }
+
+            println!(
+                "结构版本: v{} → v{}",
+                version,
+                timetracker::storage::sqlite::SCHEMA_VERSION
+            );
+            for (to, description) in &changes {
+                println!("  v{} → v{}: {}", to - 1, to, description);
+            }
+
+            if dry_run {
+                println!("（预演模式，未写入任何文件）");
+                return Ok(());
+            }
+
+            // 打开数据库时自动执行升级
+            SqliteStore::open(path)?;
+            println!("✅ 迁移完成");
+        }
+        StorageBackend::Jsonl => {
+            println!("JSONL 文件逐行保存记录，加载时自动补全缺失字段，无需迁移");
+        }
+    }
+
+    Ok(())
+}
+
+/// 数据管理子命令中指定的数据文件（默认为配置的数据文件）
+fn data_file_arg(matches: &clap::ArgMatches) -> PathBuf {
+    matches
+        .get_one::<PathBuf>("data-file")
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(default_data_file(None)))
+}
+
+/// 为已有数据启用加密
+fn handle_data_encrypt(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::storage::crypto::{self, PASSPHRASE_ENV};
+    use timetracker::storage::{open_store_with_options, StorageBackend, StoreOptions};
+
+    let path = data_file_arg(matches);
+    if StorageBackend::from_path(&path) != StorageBackend::Json {
+        return Err(anyhow::anyhow!("加密存储目前仅支持 JSON 数据文件"));
+    }
+    if crypto::is_encrypted(&path) {
+        println!("数据文件已加密: {}", path.display());
+        return Ok(());
+    }
+
+    let key_file = matches.get_one::<PathBuf>("key-file");
+    let passphrase = crypto::read_new_passphrase(key_file.map(PathBuf::as_path), PASSPHRASE_ENV)?;
+
+    // 加载时会把磁盘上的明文快照和日志（包括各个分区）加密重写
+    let options = StoreOptions {
+        passphrase: Some(passphrase),
+        partitioning: None,
+        day_boundary: day_boundary(),
+    };
+    let data = open_store_with_options(&path, &options)?.load()?;
+
+    println!(
+        "✅ 已加密 {}（{} 条记录）",
+        path.display(),
+        data.activities.len()
+    );
+    println!("请在配置文件中设置 [storage] encrypted = true");
+    match key_file {
+        Some(key_file) => println!("并设置 key_file = \"{}\"", key_file.display()),
+        None => println!(
+            "守护进程需要通过 key_file 或 {} 环境变量获取口令",
+            PASSPHRASE_ENV
+        ),
+    }
+    println!("注意：之前生成的备份文件（*.bak、*.corrupt-*）仍为明文，请自行确认后删除");
+    Ok(())
+}
+
+/// 解密数据：导出为明文 JSON 或取消加密
+fn handle_data_decrypt(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::storage::partition::{detect_partitioning, has_partitions};
+    use timetracker::storage::{
+        atomic_write, open_store_with_options, JsonFileStore, PartitionedStore, StoreOptions,
+    };
+
+    let path = data_file_arg(matches);
+    let passphrase = storage_passphrase(&path.to_string_lossy(), true)?
+        .ok_or_else(|| anyhow::anyhow!("数据文件未加密: {}", path.display()))?;
+
+    if matches.get_flag("in-place") {
+        if has_partitions(&path) {
+            PartitionedStore::open(
+                &path,
+                detect_partitioning(&path),
+                day_boundary(),
+                Some(&passphrase),
+            )?
+            .remove_encryption()?;
+        } else {
+            JsonFileStore::open_encrypted(&path, &passphrase)?.remove_encryption()?;
+        }
+        println!("✅ 已取消加密: {}", path.display());
+        println!("请在配置文件中设置 [storage] encrypted = false");
+        return Ok(());
+    }
+
+    if let Some(output) = matches.get_one::<PathBuf>("output") {
+        let options = StoreOptions {
+            passphrase: Some(passphrase),
+            partitioning: None,
+            day_boundary: day_boundary(),
+        };
+        let data = open_store_with_options(&path, &options)?.load()?;
+        atomic_write(output, serde_json::to_string_pretty(&data)?.as_bytes())?;
+        println!(
+            "✅ 已将 {} 条记录解密到 {}",
+            data.activities.len(),
+            output.display()
+        );
+    }
+    Ok(())
+}
+
+/// 更换加密口令
+fn handle_data_rotate_key(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::storage::crypto::{self, NEW_PASSPHRASE_ENV};
+    use timetracker::storage::partition::{detect_partitioning, has_partitions};
+    use timetracker::storage::{ActivityStore, JsonFileStore, PartitionedStore};
+
+    let path = data_file_arg(matches);
+    if !crypto::is_encrypted(&path) {
+        return Err(anyhow::anyhow!(
+            "数据文件未加密: {}，请先运行 'timetracker data encrypt'",
+            path.display()
+        ));
+    }
+
+    let old = storage_passphrase(&path.to_string_lossy(), true)?
+        .ok_or_else(|| anyhow::anyhow!("无法获取当前口令"))?;
+    let partitioned = has_partitions(&path);
+    let key_file = matches.get_one::<PathBuf>("new-key-file");
+    if partitioned {
+        let mut store = PartitionedStore::open(
+            &path,
+            detect_partitioning(&path),
+            day_boundary(),
+            Some(&old),
+        )?;
+        let new = crypto::read_new_passphrase(key_file.map(PathBuf::as_path), NEW_PASSPHRASE_ENV)?;
+        store.rotate_key(&new)?;
+    } else {
+        let mut store = JsonFileStore::open_encrypted(&path, &old)?;
+        store.load()?;
+        let new = crypto::read_new_passphrase(key_file.map(PathBuf::as_path), NEW_PASSPHRASE_ENV)?;
+        store.rotate_key(&new)?;
+    }
+
+    println!("✅ 已更换 {} 的加密口令", path.display());
+    if partitioned {
+        println!("注意：已归档的分区（archive 目录）仍使用原口令加密");
+    }
+    if let Some(key_file) = key_file {
+        println!(
+            "请在配置文件中设置 [storage] key_file = \"{}\"",
+            key_file.display()
+        );
+    }
+    println!("正在运行的守护进程和 TUI 需要使用新口令重新启动");
+    Ok(())
+}
+
 /// 处理活跃度检测命令
 fn handle_activity_command(sub_matches: &clap::ArgMatches) -> Result<()> {
     use timetracker::core::enhanced_platform::HybridWindowMonitor;
@@ -70,6 +1020,7 @@ fn handle_activity_command(sub_matches: &clap::ArgMatches) -> Result<()> {
 
             if activity_stats.detection_enabled {
                 println!("闲置超时: {}秒", activity_stats.idle_timeout.as_secs());
+                print_idle_source();
                 if activity_stats.idle_duration.as_secs() > 0 {
                     println!("闲置时长: {}", activity_stats.format_idle_duration());
                 }
@@ -94,6 +1045,10 @@ fn handle_activity_command(sub_matches: &clap::ArgMatches) -> Result<()> {
             );
             println!("闲置超时: {}秒", activity_config.idle_timeout);
             println!("检测间隔: {}毫秒", activity_config.check_interval);
+            match activity_config.away_review_minutes() {
+                Some(minutes) => println!("离开标注: 离开{}分钟以上", minutes),
+                None => println!("离开标注: 禁用"),
+            }
             println!("视频应用: {} 个", activity_config.video_apps.len());
             println!("视频网站: {} 个", activity_config.video_sites.len());
 
@@ -153,6 +1108,9 @@ fn handle_activity_command(sub_matches: &clap::ArgMatches) -> Result<()> {
                 }
             }
         }
+        Some(("report", report_matches)) => {
+            handle_activity_report(report_matches)?;
+        }
         Some(("enable", _)) => {
             println!("✅ 启用活跃度检测");
             // 这里可以添加修改配置的逻辑
@@ -171,6 +1129,108 @@ fn handle_activity_command(sub_matches: &clap::ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// 显示系统闲置时间的来源和当前闲置时长
+#[cfg(target_os = "linux")]
+fn print_idle_source() {
+    use timetracker::core::idle::{IdleProbe, IdleSource};
+
+    match IdleProbe::new().query() {
+        (_, IdleSource::Unavailable) => println!(
+            "闲置来源: 不可用（需要 X11 屏幕保护扩展、xprintidle 或 systemd-logind，闲置检测不起作用）"
+        ),
+        (idle, source) => {
+            println!("闲置来源: {}", source.description());
+            println!("系统闲置: {}秒", idle.as_secs());
+        }
+    }
+    match timetracker::core::media::MprisClient::session().playing() {
+        Ok(Some(media)) => println!("正在播放: {}", media.describe()),
+        Ok(None) => println!("正在播放: 无"),
+        Err(e) => println!("正在播放: 无法读取 MPRIS 播放状态（{}）", e),
+    }
+}
+
+/// 显示系统闲置时间的来源
+#[cfg(not(target_os = "linux"))]
+fn print_idle_source() {
+    use timetracker::core::idle::IdleSource;
+
+    let source = if cfg!(target_os = "macos") {
+        IdleSource::IoKit
+    } else {
+        IdleSource::Win32
+    };
+    println!("闲置来源: {}", source.description());
+}
+
+/// 按天显示开机时间和活跃、观看视频、闲置、锁屏时间
+fn handle_activity_report(matches: &clap::ArgMatches) -> Result<()> {
+    use timetracker::core::status::{daily_summaries, StatusKind, StatusSummary};
+    use timetracker::ui::components::TimeRangeFilter;
+    use timetracker::utils::time::format_duration_short;
+
+    let data_file = matches
+        .get_one::<String>("data-file")
+        .cloned()
+        .unwrap_or_else(|| default_data_file(None));
+    let boundary = day_boundary();
+    let (start, end) = time_range_arg(matches)?
+        .unwrap_or(TimeRangeFilter::Today)
+        .bounds(boundary)
+        .unwrap_or((chrono::DateTime::<chrono::Utc>::MIN_UTC, chrono::Utc::now()));
+
+    let options = store_options(&data_file, true)?;
+    let mut tracker = TimeTracker::new(data_file, 1);
+    tracker.set_store_options(options);
+    tracker.load_range(start, end)?;
+    let timeline = tracker.status_timeline(start, end)?;
+    if timeline.is_empty() {
+        println!("所选时间范围内没有状态记录（需要守护进程运行时记录）");
+        return Ok(());
+    }
+
+    println!("🖥️  开机时间与活跃时间");
+    println!("{}", "=".repeat(50));
+    let duration = |seconds: u64| match seconds {
+        0 => "-".to_string(),
+        seconds => format_duration_short(seconds),
+    };
+    let row = |label: &str, summary: &StatusSummary| {
+        let mut line = format!("{:<12} 开机 {:>8}", label, duration(summary.computer_on()));
+        for status in StatusKind::ALL {
+            line.push_str(&format!(
+                "  {} {:>8}",
+                status.description(),
+                duration(summary.get(status))
+            ));
+        }
+        line.push_str(&format!(
+            "  活跃占比 {:.0}%",
+            summary.active_ratio() * 100.0
+        ));
+        line
+    };
+
+    let days = daily_summaries(&timeline, boundary);
+    let mut total = StatusSummary::default();
+    for (date, summary) in &days {
+        println!("{}", row(&date.format("%Y-%m-%d").to_string(), summary));
+        for status in StatusKind::ALL {
+            total.add(status, summary.get(status));
+        }
+    }
+    if days.len() > 1 {
+        println!("{}", "-".repeat(50));
+        println!("{}", row("合计", &total));
+    }
+
+    let fullscreen = tracker.get_fullscreen_time_in(start, end);
+    if fullscreen > 0 {
+        println!("全屏时间 {}", format_duration_short(fullscreen));
+    }
+    Ok(())
+}
+
 /// 处理启动命令
 fn handle_start_command(sub_matches: &clap::ArgMatches) -> Result<()> {
     eprintln!("处理启动命令...");
@@ -187,6 +1247,14 @@ fn handle_start_command(sub_matches: &clap::ArgMatches) -> Result<()> {
 fn handle_daemon_child(sub_matches: &clap::ArgMatches) -> Result<()> {
     eprintln!("这是守护进程子进程");
 
+    // 守护化会关闭标准输入，先读取启动命令通过管道传来的口令
+    if sub_matches.get_flag("passphrase-stdin") {
+        if let Err(e) = read_daemon_passphrase() {
+            eprintln!("读取口令失败: {}", e);
+            std::process::exit(1);
+        }
+    }
+
     // 立即执行守护进程化，在任何其他操作之前
     if let Err(e) = daemonize_process() {
         eprintln!("守护进程化失败: {}", e);
@@ -199,14 +1267,7 @@ fn handle_daemon_child(sub_matches: &clap::ArgMatches) -> Result<()> {
     let data_file = sub_matches
         .get_one::<String>("data-file")
         .cloned()
-        .unwrap_or_else(|| {
-            dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".timetracker")
-                .join("activities.json")
-                .to_string_lossy()
-                .to_string()
-        });
+        .unwrap_or_else(|| default_data_file(None));
 
     // 确保数据目录存在
     if let Some(parent) = std::path::Path::new(&data_file).parent() {
@@ -238,16 +1299,10 @@ fn handle_daemon_start(sub_matches: &clap::ArgMatches) -> Result<()> {
     let data_file = sub_matches
         .get_one::<String>("data-file")
         .cloned()
-        .unwrap_or_else(|| {
-            dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".timetracker")
-                .join("activities.json")
-                .to_string_lossy()
-                .to_string()
-        });
+        .unwrap_or_else(|| default_data_file(sub_matches.get_one::<PathBuf>("data-dir").cloned()));
 
-    daemon_manager.start_daemon(interval, &data_file)
+    let passphrase = prepare_daemon_passphrase(&data_file)?;
+    daemon_manager.start_daemon(interval, &data_file, passphrase.as_ref())
 }
 
 /// 带超时的监控启动
@@ -263,7 +1318,24 @@ fn start_monitoring_with_timeout(data_file: String, interval: u64) -> Result<()>
         use timetracker::core::tracker::TimeTracker;
 
         let rt = tokio::runtime::Runtime::new().unwrap();
+        let options = match store_options(&data_file, false) {
+            Ok(options) => options,
+            Err(e) => {
+                let _ = tx.send(Err(e));
+                return;
+            }
+        };
         let mut tracker = TimeTracker::new(data_file, interval);
+        tracker.set_store_options(options);
+        tracker.set_retention(retention_policy());
+        tracker.set_rollup(rollup_after_days());
+        tracker.set_day_boundary(day_boundary());
+        tracker.set_compaction(compaction_gap_secs());
+        apply_tracking_config(&mut tracker);
+        if let Err(e) = tracker.load_data() {
+            let _ = tx.send(Err(e));
+            return;
+        }
 
         // 发送初始化完成信号
         let _ = tx.send(Ok(()));
@@ -472,11 +1544,18 @@ fn main() -> Result<()> {
                         .long("daemon-child")
                         .help("Internal flag for daemon child process")
                         .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("passphrase-stdin")
+                        .long("passphrase-stdin")
+                        .help("Internal flag: read the storage passphrase from stdin")
+                        .hide(true)
+                        .action(clap::ArgAction::SetTrue),
                 ),
         )
         .subcommand(Command::new("stop").about("Stop the time tracking daemon"))
         .subcommand(Command::new("status").about("Show the status of the time tracking daemon"))
-        .subcommand(
+        .subcommand(with_time_range_args(
             Command::new("tui")
                 .about("Launch the terminal user interface")
                 .arg(
@@ -485,16 +1564,133 @@ fn main() -> Result<()> {
                         .value_name("DIR")
                         .help("Directory to read tracking data from")
                         .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("data-file")
+                        .long("data-file")
+                        .value_name("FILE")
+                        .help("Data file path (.json or .db)")
+                        .value_parser(clap::value_parser!(String)),
+                ),
+        ))
+        .subcommand(
+            Command::new("add")
+                .about("Add a manual time entry for work done away from the computer")
+                .arg(
+                    Arg::new("label")
+                        .value_name("LABEL")
+                        .help("What the time was spent on, e.g. \"Phone call\"")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("start")
+                        .long("start")
+                        .value_name("TIME")
+                        .help("Start time (YYYY-MM-DD HH:MM or HH:MM today)"),
+                )
+                .arg(
+                    Arg::new("end")
+                        .long("end")
+                        .value_name("TIME")
+                        .help("End time (YYYY-MM-DD HH:MM or HH:MM today; default: now)"),
+                )
+                .arg(
+                    Arg::new("duration")
+                        .long("duration")
+                        .value_name("DURATION")
+                        .help("Duration, e.g. 1h30m, 45m or 90s (plain numbers are minutes)"),
+                )
+                .arg(
+                    Arg::new("project")
+                        .long("project")
+                        .value_name("PROJECT")
+                        .help("Project the time belongs to"),
+                )
+                .arg(
+                    Arg::new("note")
+                        .long("note")
+                        .value_name("TEXT")
+                        .help("Additional details, shown as the window title"),
+                )
+                .arg(
+                    Arg::new("on-overlap")
+                        .long("on-overlap")
+                        .value_name("POLICY")
+                        .help("What to do with overlapping records: reject, keep or trim")
+                        .value_parser(["reject", "keep", "trim"])
+                        .default_value("reject"),
+                )
+                .arg(
+                    Arg::new("data-file")
+                        .long("data-file")
+                        .value_name("FILE")
+                        .help("Data file to add the entry to (.json or .db)")
+                        .value_parser(clap::value_parser!(String)),
                 ),
         )
         .subcommand(
+            Command::new("review")
+                .about("Label time spent away from the computer, e.g. meetings or lunch")
+                .subcommand(Command::new("list").about("List away periods waiting for a label"))
+                .subcommand(
+                    Command::new("label")
+                        .about("Save an away period as a manual entry")
+                        .arg(
+                            Arg::new("index")
+                                .value_name("N")
+                                .help("Number of the away period, as shown by 'review list'")
+                                .required(true)
+                                .value_parser(clap::value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("label")
+                                .value_name("LABEL")
+                                .help("What the time was spent on, e.g. \"Lunch\"")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("project")
+                                .long("project")
+                                .value_name("PROJECT")
+                                .help("Project the time belongs to"),
+                        )
+                        .arg(
+                            Arg::new("on-overlap")
+                                .long("on-overlap")
+                                .value_name("POLICY")
+                                .help("What to do with overlapping records: reject, keep or trim")
+                                .value_parser(["reject", "keep", "trim"])
+                                .default_value("reject"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("dismiss")
+                        .about("Drop an away period without recording it")
+                        .arg(
+                            Arg::new("index")
+                                .value_name("N")
+                                .help("Number of the away period, as shown by 'review list'")
+                                .required(true)
+                                .value_parser(clap::value_parser!(usize)),
+                        ),
+                )
+                .arg(
+                    Arg::new("data-file")
+                        .long("data-file")
+                        .value_name("FILE")
+                        .help("Data file the away periods belong to (.json or .db)")
+                        .global(true)
+                        .value_parser(clap::value_parser!(String)),
+                ),
+        )
+        .subcommand(with_time_range_args(
             Command::new("export")
                 .about("Export tracking data")
                 .arg(
                     Arg::new("format")
                         .long("format")
                         .value_name("FORMAT")
-                        .help("Export format (json, csv)")
+                        .help("Export format (json, csv, status)")
                         .default_value("json"),
                 )
                 .arg(
@@ -504,6 +1700,230 @@ fn main() -> Result<()> {
                         .value_name("FILE")
                         .help("Output file path")
                         .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("data-file")
+                        .long("data-file")
+                        .value_name("FILE")
+                        .help("Data file to export from (.json or .db)")
+                        .value_parser(clap::value_parser!(String)),
+                ),
+        ))
+        .subcommand(
+            Command::new("data")
+                .about("Manage stored tracking data")
+                .subcommand(
+                    Command::new("import-json")
+                        .about("Import a JSON data file into a SQLite database")
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .value_name("FILE")
+                                .help("JSON data file (default: ~/.timetracker/activities.json)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("to")
+                                .long("to")
+                                .value_name("FILE")
+                                .help("SQLite database (default: ~/.timetracker/activities.db)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("repair")
+                        .about("Salvage activity records from a damaged data file")
+                        .arg(
+                            Arg::new("input")
+                                .long("input")
+                                .value_name("FILE")
+                                .help("Damaged file to read (default: the data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .value_name("FILE")
+                                .help("Data file to merge recovered records into (default: the data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Only report what would be recovered")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("encrypt")
+                        .about("Encrypt the data file with a passphrase")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Data file to encrypt (default: the configured data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("key-file")
+                                .long("key-file")
+                                .value_name("FILE")
+                                .help("Read the passphrase from this file instead of prompting")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("decrypt")
+                        .about("Decrypt the data file to plain JSON")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Encrypted data file (default: the configured data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .value_name("FILE")
+                                .help("Write the decrypted data to this file")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("in-place")
+                                .long("in-place")
+                                .help("Turn encryption off and rewrite the data file as plain JSON")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .group(
+                            clap::ArgGroup::new("target")
+                                .args(["output", "in-place"])
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    Command::new("rotate-key")
+                        .about("Re-encrypt the data file with a new passphrase")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Encrypted data file (default: the configured data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("new-key-file")
+                                .long("new-key-file")
+                                .value_name("FILE")
+                                .help("Read the new passphrase from this file instead of prompting")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("migrate")
+                        .about("Upgrade the data file to the current schema version")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Data file to migrate (default: the configured data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Only report what would change")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("apply-rules")
+                        .about("Re-apply app alias rules to existing history")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Data file to update (default: the configured data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("rules")
+                                .long("rules")
+                                .value_name("FILE")
+                                .help("Rules file (default: ~/.config/timetracker/app_rules.toml)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Only report which records would be renamed")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("compact")
+                        .about("Permanently merge adjacent records of the same app and window")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Data file to compact (default: the configured data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("gap")
+                                .long("gap")
+                                .value_name("SECS")
+                                .help("Merge records separated by at most this many seconds (default: [compaction] gap_secs)")
+                                .value_parser(clap::value_parser!(u64).range(0..=86400)),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Only report how many records would be removed")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("doctor")
+                        .about("Check history for overlapping, reversed or inconsistent records")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Data file to check (default: the configured data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("fix")
+                                .long("fix")
+                                .help("Trim overlaps, recompute durations and drop reversed records")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("rollup")
+                        .about("Collapse old records into daily per-app/window totals")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Data file to roll up (default: the configured data file)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("older-than")
+                                .long("older-than")
+                                .value_name("DAYS")
+                                .help("Roll up records older than this many days (default: [rollup] after_days)")
+                                .value_parser(clap::value_parser!(u32).range(1..)),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Only report what would be rolled up")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
                 ),
         )
         .subcommand(
@@ -520,7 +1940,18 @@ fn main() -> Result<()> {
                 .subcommand(Command::new("config").about("Show activity detection configuration"))
                 .subcommand(Command::new("test").about("Test activity detection"))
                 .subcommand(Command::new("enable").about("Enable activity detection"))
-                .subcommand(Command::new("disable").about("Disable activity detection")),
+                .subcommand(Command::new("disable").about("Disable activity detection"))
+                .subcommand(with_time_range_args(
+                    Command::new("report")
+                        .about("Show computer-on time versus active, idle and locked time")
+                        .arg(
+                            Arg::new("data-file")
+                                .long("data-file")
+                                .value_name("FILE")
+                                .help("Data file the status timeline belongs to (.json or .db)")
+                                .value_parser(clap::value_parser!(String)),
+                        ),
+                )),
         )
         .get_matches();
 
@@ -546,14 +1977,7 @@ fn main() -> Result<()> {
                 let data_file = sub_matches
                     .get_one::<String>("data-file")
                     .cloned()
-                    .unwrap_or_else(|| {
-                        dirs::home_dir()
-                            .unwrap_or_else(|| PathBuf::from("."))
-                            .join(".timetracker")
-                            .join("activities.json")
-                            .to_string_lossy()
-                            .to_string()
-                    });
+                    .unwrap_or_else(|| default_data_file(None));
 
                 // 确保数据目录存在
                 if let Some(parent) = std::path::Path::new(&data_file).parent() {
@@ -593,7 +2017,21 @@ fn main() -> Result<()> {
                 log::info!("监控间隔: {}秒", interval);
 
                 // 创建并启动时间追踪器
+                let options = match store_options(&data_file, false) {
+                    Ok(options) => options,
+                    Err(e) => {
+                        log::error!("获取加密口令失败: {}", e);
+                        eprintln!("获取加密口令失败: {}", e);
+                        std::process::exit(1);
+                    }
+                };
                 let mut tracker = TimeTracker::new(data_file, interval);
+                tracker.set_store_options(options);
+                tracker.set_retention(retention_policy());
+                tracker.set_rollup(rollup_after_days());
+                tracker.set_day_boundary(day_boundary());
+                tracker.set_compaction(compaction_gap_secs());
+                apply_tracking_config(&mut tracker);
                 if let Err(e) = tracker.load_data() {
                     log::error!("加载数据失败: {}", e);
                     eprintln!("加载数据失败: {}", e);
@@ -659,22 +2097,18 @@ fn main() -> Result<()> {
                 });
             } else {
                 // 这是用户调用的启动命令，启动守护进程
-                let data_dir = sub_matches
-                    .get_one::<PathBuf>("data-dir")
+                let data_file = sub_matches
+                    .get_one::<String>("data-file")
                     .cloned()
                     .unwrap_or_else(|| {
-                        dirs::home_dir()
-                            .unwrap_or_else(|| PathBuf::from("."))
-                            .join(".timetracker")
+                        default_data_file(sub_matches.get_one::<PathBuf>("data-dir").cloned())
                     });
 
                 let interval = sub_matches.get_one::<u64>("interval").copied().unwrap_or(1);
 
+                let passphrase = prepare_daemon_passphrase(&data_file)?;
                 let mut daemon_manager = DaemonManager::new();
-                daemon_manager.start_daemon(
-                    interval,
-                    &data_dir.join("activities.json").to_string_lossy(),
-                )?;
+                daemon_manager.start_daemon(interval, &data_file, passphrase.as_ref())?;
                 println!("Time tracking daemon started successfully");
             }
         }
@@ -688,20 +2122,18 @@ fn main() -> Result<()> {
             daemon_manager.status()?;
         }
         Some(("tui", sub_matches)) => {
-            let data_dir = sub_matches
-                .get_one::<PathBuf>("data-dir")
+            let data_file = sub_matches
+                .get_one::<String>("data-file")
                 .cloned()
                 .unwrap_or_else(|| {
-                    dirs::home_dir()
-                        .unwrap_or_else(|| PathBuf::from("."))
-                        .join(".timetracker")
+                    default_data_file(sub_matches.get_one::<PathBuf>("data-dir").cloned())
                 });
-
-            let data_file = data_dir
-                .join("activities.json")
-                .to_string_lossy()
-                .to_string();
-            let mut app = TuiApp::new(data_file)?;
+            let time_range = time_range_arg(sub_matches)?;
+            let options = store_options(&data_file, true)?;
+            let mut app = TuiApp::new(data_file, options)?;
+            if let Some(time_range) = time_range {
+                app.set_time_range(time_range)?;
+            }
             app.run()?;
 
             // 检查是否需要退出整个程序
@@ -719,20 +2151,34 @@ fn main() -> Result<()> {
                 println!("使用 'timetracker tui' 来重新打开界面");
             }
         }
+        Some(("add", sub_matches)) => {
+            handle_add_command(sub_matches)?;
+        }
+        Some(("review", sub_matches)) => {
+            handle_review_command(sub_matches)?;
+        }
         Some(("export", sub_matches)) => {
             let format = sub_matches.get_one::<String>("format").unwrap();
             let output = sub_matches.get_one::<PathBuf>("output");
 
-            let data_dir = dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".timetracker");
-
-            let data_file = data_dir
-                .join("activities.json")
-                .to_string_lossy()
-                .to_string();
+            let data_file = sub_matches
+                .get_one::<String>("data-file")
+                .cloned()
+                .unwrap_or_else(|| default_data_file(None));
+            let bounds = match time_range_arg(sub_matches)? {
+                Some(time_range) => time_range.bounds(day_boundary()),
+                None => None,
+            };
+            let options = store_options(&data_file, true)?;
             let mut tracker = TimeTracker::new(data_file, 5);
-            tracker.load_data()?;
+            tracker.set_store_options(options);
+            match bounds {
+                Some((start, end)) => tracker.load_range_clipped(start, end)?,
+                None => tracker.load_data()?,
+            }
+            for warning in tracker.take_load_warnings() {
+                eprintln!("⚠️  {}", warning);
+            }
 
             match format.as_str() {
                 "json" => {
@@ -753,6 +2199,15 @@ fn main() -> Result<()> {
                         println!("{}", csv_data);
                     }
                 }
+                "status" => {
+                    let csv_data = tracker.export_status_csv()?;
+                    if let Some(output_path) = output {
+                        std::fs::write(output_path, csv_data)?;
+                        println!("Data exported to {}", output_path.display());
+                    } else {
+                        println!("{}", csv_data);
+                    }
+                }
                 _ => {
                     eprintln!("Unsupported format: {}", format);
                     std::process::exit(1);
@@ -796,6 +2251,10 @@ fn main() -> Result<()> {
             handle_activity_command(sub_matches)?;
         }
 
+        Some(("data", sub_matches)) => {
+            handle_data_command(sub_matches)?;
+        }
+
         None => {
             // 没有子命令，显示简短帮助
             println!("TimeTracker - 时间追踪工具");
@@ -0,0 +1,115 @@
+// 记录压缩 - 把时间上相邻、应用和窗口相同的记录永久合并为一条
+
+use super::ActivityStore;
+use crate::core::tracker::{ActivityRecord, RecordSource};
+use anyhow::Result;
+use chrono::Duration;
+
+/// 默认合并间隔（秒）：同一窗口的相邻记录间隔不超过该值时合并
+pub const DEFAULT_COMPACT_GAP_SECS: u64 = 60;
+
+/// 压缩结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompactReport {
+    /// 被并入其他记录而移除的记录数
+    pub removed: usize,
+    /// 合并后保留下来、吸收了其他记录的记录数
+    pub merged: usize,
+}
+
+impl CompactReport {
+    /// 是否没有可合并的记录
+    pub fn is_empty(&self) -> bool {
+        self.removed == 0
+    }
+}
+
+/// 压缩计划：需要更新的记录和需要删除的记录 id
+#[derive(Debug, Clone, Default)]
+pub struct CompactPlan {
+    /// 吸收了后续记录的记录（结束时间、时长已更新）
+    pub updated: Vec<ActivityRecord>,
+    /// 被并入前一条记录的记录 id
+    pub removed: Vec<String>,
+}
+
+impl CompactPlan {
+    pub fn report(&self) -> CompactReport {
+        CompactReport {
+            removed: self.removed.len(),
+            merged: self.updated.len(),
+        }
+    }
+}
+
+/// 两条记录能否合并：应用、窗口、项目和全屏状态相同，且同为手动记录或同为监控记录
+///
+/// 恢复的记录仍是监控记录，可以与前后的监控记录合并。进入或退出全屏时心跳有意拆分的记录
+/// 不会被重新合并，以免全屏时间统计出错。
+fn same_key(a: &ActivityRecord, b: &ActivityRecord) -> bool {
+    let manual = |record: &ActivityRecord| record.source == RecordSource::Manual;
+    a.app_name == b.app_name
+        && a.window_title == b.window_title
+        && a.project == b.project
+        && a.fullscreen == b.fullscreen
+        && manual(a) == manual(b)
+}
+
+/// 计算压缩计划
+///
+/// 按开始时间排序后，只合并紧挨着的两条记录：应用、窗口、项目、来源和全屏状态相同，且后一条的开始时间
+/// 距前一条的结束时间不超过 `gap`。合并后的记录保留真实的开始和结束时间，时长为各条记录时长之和，
+/// 中间的空档不计时。不同窗口之间的同名记录不会跨越合并。
+pub fn plan_compaction(records: &[ActivityRecord], gap: Duration) -> CompactPlan {
+    let mut sorted: Vec<&ActivityRecord> = records.iter().collect();
+    sorted.sort_by_key(|record| record.start_time);
+
+    let mut plan = CompactPlan::default();
+    let mut current: Option<(ActivityRecord, bool)> = None;
+
+    for record in sorted {
+        if let Some((merged, absorbed)) = current.as_mut() {
+            if same_key(merged, record) && record.start_time - merged.effective_end() <= gap {
+                let end = merged.effective_end().max(record.effective_end());
+                merged.end_time = Some(end);
+                merged.duration += record.duration;
+                if record.source == RecordSource::Recovered {
+                    merged.source = RecordSource::Recovered;
+                }
+                plan.removed.push(record.id.clone());
+                *absorbed = true;
+                continue;
+            }
+        }
+
+        if let Some((merged, true)) = current.replace((record.clone(), false)) {
+            plan.updated.push(merged);
+        }
+    }
+    if let Some((merged, true)) = current {
+        plan.updated.push(merged);
+    }
+
+    plan
+}
+
+/// 压缩存储中的历史记录，`dry_run` 为 true 时只返回结果不写入
+pub fn compact_store(
+    store: &mut dyn ActivityStore,
+    gap: Duration,
+    dry_run: bool,
+) -> Result<CompactReport> {
+    let data = store.load()?;
+    let plan = plan_compaction(&data.activities, gap);
+    let report = plan.report();
+    if dry_run || report.is_empty() {
+        return Ok(report);
+    }
+
+    for record in &plan.updated {
+        store.update(record)?;
+    }
+    store.delete(&plan.removed)?;
+    store.flush()?;
+    Ok(report)
+}
@@ -0,0 +1,352 @@
+// 加密存储 - 用口令派生的密钥加密数据快照和日志，明文只存在于内存中
+
+use super::atomic_write;
+use crate::config::app::StorageConfig;
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// 加密快照文件的文件头
+pub const FILE_MAGIC: &[u8] = b"TTENC1\n";
+
+/// 加密日志行的前缀
+const LINE_PREFIX: &str = "enc:";
+
+/// 口令的环境变量名
+pub const PASSPHRASE_ENV: &str = "TIMETRACKER_PASSPHRASE";
+
+/// 更换口令时新口令的环境变量名
+pub const NEW_PASSPHRASE_ENV: &str = "TIMETRACKER_NEW_PASSPHRASE";
+
+/// 用于校验口令的固定明文
+const CHECK_PLAINTEXT: &[u8] = b"timetracker";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// 数据加密口令（离开作用域时清零）
+#[derive(Clone)]
+pub struct Passphrase(Zeroizing<String>);
+
+impl Passphrase {
+    pub fn new(passphrase: impl Into<String>) -> Self {
+        Self(Zeroizing::new(passphrase.into()))
+    }
+
+    /// 从密钥文件读取口令（忽略首尾空白）
+    pub fn from_key_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = Zeroizing::new(
+            std::fs::read_to_string(path)
+                .with_context(|| format!("无法读取密钥文件: {}", path.display()))?,
+        );
+        let passphrase = content.trim();
+        if passphrase.is_empty() {
+            return Err(anyhow::anyhow!("密钥文件为空: {}", path.display()));
+        }
+        Ok(Self::new(passphrase))
+    }
+
+    /// 口令明文，仅用于把口令传给守护进程子进程
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl std::fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Passphrase(***)")
+    }
+}
+
+/// 由口令和盐派生出的加密器（XChaCha20-Poly1305，密钥由 Argon2id 派生）
+pub struct Cipher {
+    aead: XChaCha20Poly1305,
+    salt: [u8; SALT_LEN],
+}
+
+impl Cipher {
+    /// 用随机盐创建新的加密器
+    pub fn generate(passphrase: &Passphrase) -> Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(passphrase, salt)
+    }
+
+    fn derive(passphrase: &Passphrase, salt: [u8; SALT_LEN]) -> Result<Self> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| anyhow::anyhow!("密钥派生失败: {}", e))?;
+
+        Ok(Self {
+            aead: XChaCha20Poly1305::new(key.as_ref().into()),
+            salt,
+        })
+    }
+
+    /// 加密，返回 随机数 + 密文
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .aead
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow::anyhow!("加密失败"))?;
+
+        let mut out = nonce.to_vec();
+        out.extend(ciphertext);
+        Ok(out)
+    }
+
+    /// 解密 `encrypt` 的输出
+    pub fn decrypt(&self, data: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        if data.len() < NONCE_LEN {
+            return Err(anyhow::anyhow!("密文长度不足"));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        self.aead
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map(Zeroizing::new)
+            .map_err(|_| anyhow::anyhow!("解密失败：口令错误或数据已损坏"))
+    }
+
+    /// 加密整个文件内容（带文件头）
+    pub fn encrypt_file(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut out = FILE_MAGIC.to_vec();
+        out.extend(self.encrypt(plaintext)?);
+        Ok(out)
+    }
+
+    /// 解密带文件头的文件内容
+    pub fn decrypt_file(&self, data: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        let body = data
+            .strip_prefix(FILE_MAGIC)
+            .ok_or_else(|| anyhow::anyhow!("不是加密的数据文件"))?;
+        self.decrypt(body)
+    }
+
+    /// 加密一行日志
+    pub fn encrypt_line(&self, line: &str) -> Result<String> {
+        Ok(format!(
+            "{LINE_PREFIX}{}",
+            BASE64.encode(self.encrypt(line.as_bytes())?)
+        ))
+    }
+
+    /// 解密一行日志
+    pub fn decrypt_line(&self, line: &str) -> Result<Zeroizing<String>> {
+        let encoded = line
+            .strip_prefix(LINE_PREFIX)
+            .ok_or_else(|| anyhow::anyhow!("不是加密的日志行"))?;
+        let data = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| anyhow::anyhow!("日志行编码错误: {}", e))?;
+        let plaintext = self.decrypt(&data)?;
+        Ok(Zeroizing::new(String::from_utf8(plaintext.to_vec())?))
+    }
+}
+
+/// 文件内容是否为加密格式
+pub fn is_encrypted_contents(data: &[u8]) -> bool {
+    data.starts_with(FILE_MAGIC)
+}
+
+/// 日志行是否为加密格式
+pub fn is_encrypted_line(line: &str) -> bool {
+    line.starts_with(LINE_PREFIX)
+}
+
+/// 密钥信息文件：保存盐和口令校验值，不包含密钥本身
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyCheck {
+    kdf: String,
+    salt: String,
+    check: String,
+}
+
+/// 数据文件对应的密钥信息文件 `<文件名>.keycheck`
+pub fn keycheck_path(path: &Path) -> PathBuf {
+    let mut keycheck = path.to_path_buf().into_os_string();
+    keycheck.push(".keycheck");
+    PathBuf::from(keycheck)
+}
+
+/// 数据文件是否已启用加密
+pub fn is_encrypted(path: &Path) -> bool {
+    keycheck_path(path).exists()
+}
+
+/// 更换口令过程中待生效的密钥信息文件
+fn pending_keycheck_path(path: &Path) -> PathBuf {
+    let mut pending = keycheck_path(path).into_os_string();
+    pending.push(".new");
+    PathBuf::from(pending)
+}
+
+/// 打开已加密数据文件的加密器，口令错误时返回错误
+///
+/// 更换口令中途中断时，新口令对应的密钥信息仍在待生效文件中；
+/// 如果数据已用新口令重新加密，则用它完成更换。
+pub fn open_cipher(path: &Path, passphrase: &Passphrase) -> Result<Cipher> {
+    let pending = pending_keycheck_path(path);
+    if pending.exists() {
+        if let Ok(cipher) = read_keycheck(&pending, path, passphrase) {
+            if snapshot_decrypts(path, &cipher) {
+                commit_pending_keycheck(path)?;
+                return Ok(cipher);
+            }
+        }
+    }
+    read_keycheck(&keycheck_path(path), path, passphrase)
+}
+
+/// 快照是否能用该加密器解密（快照不存在时视为可以）
+fn snapshot_decrypts(path: &Path, cipher: &Cipher) -> bool {
+    match std::fs::read(path) {
+        Ok(bytes) => is_encrypted_contents(&bytes) && cipher.decrypt_file(&bytes).is_ok(),
+        Err(_) => !path.exists(),
+    }
+}
+
+fn read_keycheck(keycheck_file: &Path, path: &Path, passphrase: &Passphrase) -> Result<Cipher> {
+    let content = std::fs::read_to_string(keycheck_file)
+        .with_context(|| format!("无法读取密钥信息: {}", keycheck_file.display()))?;
+    let keycheck: KeyCheck = serde_json::from_str(&content)
+        .with_context(|| format!("密钥信息已损坏: {}", keycheck_file.display()))?;
+
+    let salt: [u8; SALT_LEN] = BASE64
+        .decode(&keycheck.salt)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("密钥信息中的盐长度错误"))?;
+    let cipher = Cipher::derive(passphrase, salt)?;
+
+    let check = cipher
+        .decrypt(&BASE64.decode(&keycheck.check)?)
+        .map_err(|_| anyhow::anyhow!("口令错误，无法解密数据文件 {}", path.display()))?;
+    if check.as_slice() != CHECK_PLAINTEXT {
+        return Err(anyhow::anyhow!(
+            "口令错误，无法解密数据文件 {}",
+            path.display()
+        ));
+    }
+
+    Ok(cipher)
+}
+
+/// 保存加密器的盐和口令校验值
+pub fn write_keycheck(path: &Path, cipher: &Cipher) -> Result<()> {
+    save_keycheck(&keycheck_path(path), cipher)
+}
+
+/// 更换口令时先保存新口令的密钥信息，数据重新加密后再调用 `commit_pending_keycheck`
+pub fn write_pending_keycheck(path: &Path, cipher: &Cipher) -> Result<()> {
+    save_keycheck(&pending_keycheck_path(path), cipher)
+}
+
+/// 使待生效的密钥信息生效
+pub fn commit_pending_keycheck(path: &Path) -> Result<()> {
+    std::fs::rename(pending_keycheck_path(path), keycheck_path(path))?;
+    Ok(())
+}
+
+fn save_keycheck(keycheck_file: &Path, cipher: &Cipher) -> Result<()> {
+    let keycheck = KeyCheck {
+        kdf: "argon2id".to_string(),
+        salt: BASE64.encode(cipher.salt),
+        check: BASE64.encode(cipher.encrypt(CHECK_PLAINTEXT)?),
+    };
+    atomic_write(
+        keycheck_file,
+        serde_json::to_string_pretty(&keycheck)?.as_bytes(),
+    )
+}
+
+/// 获取数据文件的口令
+///
+/// 未启用加密且数据文件未加密时返回 `None`。口令依次从配置的密钥文件、
+/// `TIMETRACKER_PASSPHRASE` 环境变量读取，`interactive` 为真且在终端中运行时提示输入。
+pub fn resolve_passphrase(
+    config: &StorageConfig,
+    data_file: &Path,
+    interactive: bool,
+) -> Result<Option<Passphrase>> {
+    if !config.encrypted && !is_encrypted(data_file) {
+        return Ok(None);
+    }
+
+    if let Some(key_file) = &config.key_file {
+        return Passphrase::from_key_file(expand_home(key_file)).map(Some);
+    }
+
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        if !passphrase.is_empty() {
+            return Ok(Some(Passphrase::new(passphrase)));
+        }
+    }
+
+    if interactive && std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        let passphrase = rpassword::prompt_password("🔑 请输入数据加密口令: ")?;
+        if !passphrase.is_empty() {
+            return Ok(Some(Passphrase::new(passphrase)));
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "数据文件 {} 已加密，请在配置中设置 storage.key_file 或通过 {} 环境变量提供口令",
+        data_file.display(),
+        PASSPHRASE_ENV
+    ))
+}
+
+/// 获取新口令（启用加密或更换口令时使用）
+///
+/// 依次从给定的密钥文件、环境变量读取，都没有时在终端中提示输入两次。
+pub fn read_new_passphrase(key_file: Option<&Path>, env: &str) -> Result<Passphrase> {
+    if let Some(key_file) = key_file {
+        return Passphrase::from_key_file(key_file);
+    }
+
+    if let Ok(passphrase) = std::env::var(env) {
+        if !passphrase.is_empty() {
+            return Ok(Passphrase::new(passphrase));
+        }
+    }
+
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return Err(anyhow::anyhow!(
+            "请通过 --key-file 或 {} 环境变量提供新口令",
+            env
+        ));
+    }
+
+    let passphrase = Zeroizing::new(rpassword::prompt_password("🔑 请输入新口令: ")?);
+    if passphrase.is_empty() {
+        return Err(anyhow::anyhow!("口令不能为空"));
+    }
+    let confirm = Zeroizing::new(rpassword::prompt_password("🔑 请再次输入新口令: ")?);
+    if *passphrase != *confirm {
+        return Err(anyhow::anyhow!("两次输入的口令不一致"));
+    }
+    Ok(Passphrase::new(passphrase.as_str()))
+}
+
+/// 展开路径开头的 `~`
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
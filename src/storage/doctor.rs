@@ -0,0 +1,232 @@
+// 数据体检 - 检查历史记录中的重叠、时间倒置、时长为零和时长不符，并给出自动修复方案
+
+use super::ActivityStore;
+use crate::core::tracker::ActivityRecord;
+use crate::utils::time::DayTimeZone;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+/// 时长与起止时间比较时允许的误差（秒），时间戳带有不足一秒的部分
+const DURATION_TOLERANCE_SECS: u64 = 1;
+
+/// 问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IssueKind {
+    /// 结束时间早于开始时间
+    NegativeSpan,
+    /// 记录的时长超过起止时间之间的跨度
+    DurationMismatch,
+    /// 记录的时长短于起止时间之间的跨度
+    DurationShort,
+    /// 记录的时长为零
+    ZeroDuration,
+    /// 与更早开始的记录在时间上重叠
+    Overlap,
+}
+
+impl IssueKind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            IssueKind::NegativeSpan => "结束时间早于开始时间",
+            IssueKind::DurationMismatch => "时长超过起止时间跨度",
+            IssueKind::DurationShort => "时长短于起止时间跨度",
+            IssueKind::ZeroDuration => "时长为零",
+            IssueKind::Overlap => "与其他记录重叠",
+        }
+    }
+
+    /// 自动修复时的处理方式
+    pub fn fix_description(&self) -> &'static str {
+        match self {
+            IssueKind::NegativeSpan => "删除记录",
+            IssueKind::DurationMismatch => "按起止时间重新计算时长",
+            IssueKind::DurationShort => "不自动修复（压缩合并的记录不计中间空档，可能是正常情况）",
+            IssueKind::ZeroDuration => "有起止跨度时按跨度重新计算时长，否则删除记录",
+            IssueKind::Overlap => "把开始时间推迟到前一条记录结束，完全被覆盖的记录删除",
+        }
+    }
+}
+
+/// 一条违反数据约束的记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub record_id: String,
+    pub app_name: String,
+    pub start_time: DateTime<Utc>,
+    /// 问题说明
+    pub detail: String,
+}
+
+/// 自动修复方案：需要更新的记录和需要删除的记录 id
+#[derive(Debug, Clone, Default)]
+pub struct FixPlan {
+    pub updated: Vec<ActivityRecord>,
+    pub removed: Vec<String>,
+}
+
+impl FixPlan {
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// 体检结果
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    /// 发现的问题，按记录开始时间排序
+    pub issues: Vec<Issue>,
+    /// 修复这些问题的方案
+    pub plan: FixPlan,
+}
+
+impl DoctorReport {
+    /// 数据是否没有问题
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// 按问题类型分组
+    pub fn by_kind(&self) -> BTreeMap<IssueKind, Vec<&Issue>> {
+        let mut groups: BTreeMap<IssueKind, Vec<&Issue>> = BTreeMap::new();
+        for issue in &self.issues {
+            groups.entry(issue.kind).or_default().push(issue);
+        }
+        groups
+    }
+
+    /// 某类问题的数量
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.kind == kind)
+            .count()
+    }
+}
+
+/// 检查记录并生成修复方案
+///
+/// 按开始时间顺序检查：结束时间早于开始时间的记录直接删除；时长为零的记录按起止跨度重新
+/// 计算时长，没有跨度时删除；时长超过起止跨度的记录按跨度重新计算时长；时长短于跨度的记录
+/// 只报告不修复（压缩后的记录不计中间空档，属于正常情况）；与前面记录重叠的记录把开始时间
+/// 推迟到前面记录结束，被完全覆盖时删除。问题说明中的时间按 `timezone` 显示。
+pub fn diagnose(records: &[ActivityRecord], timezone: DayTimeZone) -> DoctorReport {
+    let mut sorted: Vec<&ActivityRecord> = records.iter().collect();
+    sorted.sort_by_key(|record| record.start_time);
+
+    let mut report = DoctorReport::default();
+    // 已检查记录中最晚的结束时间及对应记录
+    let mut latest: Option<(DateTime<Utc>, &ActivityRecord)> = None;
+
+    for original in sorted {
+        let issue = |kind: IssueKind, detail: String| Issue {
+            kind,
+            record_id: original.id.clone(),
+            app_name: original.app_name.clone(),
+            start_time: original.start_time,
+            detail,
+        };
+
+        if let Some(end) = original.end_time.filter(|end| *end < original.start_time) {
+            let seconds = (original.start_time - end).num_seconds();
+            report.issues.push(issue(
+                IssueKind::NegativeSpan,
+                format!("结束时间比开始时间早 {seconds} 秒"),
+            ));
+            report.plan.removed.push(original.id.clone());
+            continue;
+        }
+
+        let mut record = original.clone();
+        let mut changed = false;
+
+        let span = span_secs(&record);
+        if record.duration == 0 {
+            report.issues.push(issue(
+                IssueKind::ZeroDuration,
+                format!("时长 0 秒，起止时间 {span} 秒"),
+            ));
+            if span == 0 {
+                report.plan.removed.push(record.id.clone());
+                continue;
+            }
+            record.duration = span;
+            changed = true;
+        } else if record.end_time.is_some() && record.duration > span + DURATION_TOLERANCE_SECS {
+            report.issues.push(issue(
+                IssueKind::DurationMismatch,
+                format!("时长 {} 秒，起止时间只有 {} 秒", record.duration, span),
+            ));
+            record.duration = span;
+            changed = true;
+        } else if record.end_time.is_some() && record.duration + DURATION_TOLERANCE_SECS < span {
+            report.issues.push(issue(
+                IssueKind::DurationShort,
+                format!("时长 {} 秒，起止时间有 {} 秒", record.duration, span),
+            ));
+        }
+
+        if let Some((previous_end, previous)) = latest {
+            if record.start_time < previous_end {
+                let overlap =
+                    (previous_end.min(record.effective_end()) - record.start_time).num_seconds();
+                report.issues.push(issue(
+                    IssueKind::Overlap,
+                    format!(
+                        "与 {}（{}）重叠 {} 秒",
+                        previous.app_name,
+                        timezone
+                            .to_naive(previous.start_time)
+                            .format("%Y-%m-%d %H:%M:%S"),
+                        overlap
+                    ),
+                ));
+
+                if record.effective_end() <= previous_end {
+                    report.plan.removed.push(record.id.clone());
+                    continue;
+                }
+                record.start_time = previous_end;
+                record.duration = record.duration.min(span_secs(&record));
+                changed = true;
+            }
+        }
+
+        if latest.is_none_or(|(end, _)| record.effective_end() > end) {
+            latest = Some((record.effective_end(), original));
+        }
+        if changed {
+            report.plan.updated.push(record);
+        }
+    }
+
+    report
+}
+
+/// 检查存储中的历史记录，`fix` 为 true 时写入修复结果
+pub fn doctor_store(
+    store: &mut dyn ActivityStore,
+    fix: bool,
+    timezone: DayTimeZone,
+) -> Result<DoctorReport> {
+    let data = store.load()?;
+    let report = diagnose(&data.activities, timezone);
+    if !fix || report.plan.is_empty() {
+        return Ok(report);
+    }
+
+    for record in &report.plan.updated {
+        store.update(record)?;
+    }
+    store.delete(&report.plan.removed)?;
+    store.flush()?;
+    Ok(report)
+}
+
+/// 起止时间之间的整秒数
+fn span_secs(record: &ActivityRecord) -> u64 {
+    (record.effective_end() - record.start_time)
+        .num_seconds()
+        .max(0) as u64
+}
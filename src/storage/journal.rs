@@ -0,0 +1,271 @@
+// 追加写日志 - 记录活动开始/结束等事件，崩溃后通过重放恢复数据
+
+use super::crypto::{is_encrypted_line, Cipher};
+use crate::core::tracker::{ActivityRecord, TimeTrackerData};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 日志事件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JournalEvent {
+    /// 开始新的当前活动
+    Start { record: ActivityRecord },
+    /// 活动结束，写入历史记录
+    Finish { record: ActivityRecord },
+    /// 修改一条历史记录
+    Update { record: ActivityRecord },
+    /// 删除历史记录
+    Delete { ids: Vec<String> },
+    /// 当前活动仍在进行
+    Heartbeat { at: DateTime<Utc> },
+    /// 当前活动被清除（未写入历史记录）
+    ClearCurrent { at: DateTime<Utc> },
+}
+
+impl JournalEvent {
+    /// 将事件应用到数据上
+    ///
+    /// 所有事件都按 id 幂等地应用，重复重放同一段日志不会产生重复记录。
+    pub fn apply(self, data: &mut TimeTrackerData) {
+        match self {
+            JournalEvent::Start { record } => {
+                data.last_updated = record.start_time;
+                data.current_activity = Some(record);
+            }
+            JournalEvent::Finish { record } => {
+                if data.current_activity.as_ref().map(|c| &c.id) == Some(&record.id) {
+                    data.current_activity = None;
+                }
+                if let Some(end) = record.end_time {
+                    data.last_updated = data.last_updated.max(end);
+                }
+                upsert(&mut data.activities, record);
+            }
+            JournalEvent::Update { record } => upsert(&mut data.activities, record),
+            JournalEvent::Delete { ids } => data.activities.retain(|r| !ids.contains(&r.id)),
+            JournalEvent::Heartbeat { at } => {
+                if let Some(current) = &mut data.current_activity {
+                    current.duration = (at - current.start_time).num_seconds().max(0) as u64;
+                }
+                data.last_updated = at;
+            }
+            JournalEvent::ClearCurrent { at } => {
+                data.current_activity = None;
+                data.last_updated = at;
+            }
+        }
+    }
+}
+
+fn upsert(activities: &mut Vec<ActivityRecord>, record: ActivityRecord) {
+    match activities.iter_mut().find(|r| r.id == record.id) {
+        Some(existing) => *existing = record,
+        None => activities.push(record),
+    }
+}
+
+/// 追加写日志文件
+///
+/// 每条事件占一行，写入后立即 fsync。每次追加都重新打开文件，
+/// 以免日志被其他进程轮转后继续写入旧文件。
+/// 同时记录已读取到的字节位置，多个进程共享日志时只需读取其他进程新增的事件。
+/// 设置加密器后，每行事件单独加密。
+pub struct Journal {
+    path: PathBuf,
+    len: usize,
+    offset: u64,
+    cipher: Option<Arc<Cipher>>,
+    plaintext: bool,
+}
+
+impl Journal {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            len: 0,
+            offset: 0,
+            cipher: None,
+            plaintext: false,
+        }
+    }
+
+    /// 设置加密器，之后写入的事件都会加密
+    pub fn set_cipher(&mut self, cipher: Option<Arc<Cipher>>) {
+        self.cipher = cipher;
+    }
+
+    /// 上次重放时是否读到了未加密的事件（启用加密前写入的日志）
+    pub fn has_plaintext(&self) -> bool {
+        self.plaintext
+    }
+
+    /// 日志文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 轮转后等待压缩的日志文件路径
+    pub fn rotated_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".old");
+        PathBuf::from(path)
+    }
+
+    /// 自打开或上次轮转以来的事件数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 追加一条事件并同步到磁盘
+    pub fn append(&mut self, event: &JournalEvent) -> Result<()> {
+        let mut line = serde_json::to_string(event)?;
+        if let Some(cipher) = &self.cipher {
+            line = cipher.encrypt_line(&line)?;
+        }
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        // 上次崩溃可能留下写到一半的行，先补上换行，避免新事件与其粘连
+        if file.metadata()?.len() > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                line.insert(0, '\n');
+            }
+        }
+
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        self.len += 1;
+        self.offset = file.metadata()?.len();
+        Ok(())
+    }
+
+    /// 读取日志中的全部事件（轮转文件在前）
+    pub fn replay(&mut self) -> Result<Vec<JournalEvent>> {
+        let cipher = self.cipher.as_deref();
+        let (mut events, _, rotated_plain) = read_events(&self.rotated_path(), 0, cipher)?;
+        let (current, offset, current_plain) = read_events(&self.path, 0, cipher)?;
+        self.len = current.len();
+        self.offset = offset;
+        self.plaintext = rotated_plain || current_plain;
+        events.extend(current);
+        Ok(events)
+    }
+
+    /// 读取上次读取或写入之后新增的事件（例如其他进程追加的事件）
+    pub fn read_new(&mut self) -> Result<Vec<JournalEvent>> {
+        let (events, offset, _) = read_events(&self.path, self.offset, self.cipher.as_deref())?;
+        self.len += events.len();
+        self.offset = offset;
+        Ok(events)
+    }
+
+    /// 将当前日志轮转为 `.old` 文件，之后的事件写入新文件
+    pub fn rotate(&mut self) -> Result<()> {
+        if self.path.exists() {
+            fs::rename(&self.path, self.rotated_path())?;
+        }
+        self.len = 0;
+        self.offset = 0;
+        Ok(())
+    }
+
+    /// 删除当前日志和轮转日志（快照已包含全部事件时调用）
+    pub fn clear(&mut self) -> Result<()> {
+        for path in [self.path.clone(), self.rotated_path()] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        self.len = 0;
+        self.offset = 0;
+        Ok(())
+    }
+}
+
+/// 从指定字节位置开始读取日志文件，返回事件、读到的位置以及是否包含未加密的事件
+///
+/// 无法解析的行（例如崩溃时写到一半的行）会被跳过；遇到加密行但没有加密器时返回错误。
+fn read_events(
+    path: &Path,
+    offset: u64,
+    cipher: Option<&Cipher>,
+) -> Result<(Vec<JournalEvent>, u64, bool)> {
+    if !path.exists() {
+        return Ok((Vec::new(), 0, false));
+    }
+
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    // 文件比已读位置短，说明已被其他进程替换，从头读取
+    let offset = if len < offset { 0 } else { offset };
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let content = String::from_utf8_lossy(&bytes);
+
+    let mut events = Vec::new();
+    let mut plaintext = false;
+    for (line_no, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let decrypted;
+        let line = if is_encrypted_line(line) {
+            let Some(cipher) = cipher else {
+                return Err(anyhow::anyhow!(
+                    "日志文件 {} 已加密，需要提供口令",
+                    path.display()
+                ));
+            };
+            match cipher.decrypt_line(line) {
+                Ok(text) => {
+                    decrypted = text;
+                    decrypted.as_str()
+                }
+                Err(e) => {
+                    log::warn!(
+                        "跳过无法解密的日志事件 {}:{}: {}",
+                        path.display(),
+                        line_no + 1,
+                        e
+                    );
+                    continue;
+                }
+            }
+        } else {
+            plaintext = true;
+            line
+        };
+
+        match serde_json::from_str::<JournalEvent>(line) {
+            Ok(event) => events.push(event),
+            Err(e) => log::warn!(
+                "跳过无法解析的日志事件 {}:{}: {}",
+                path.display(),
+                line_no + 1,
+                e
+            ),
+        }
+    }
+    Ok((events, offset + bytes.len() as u64, plaintext))
+}
@@ -0,0 +1,595 @@
+// JSON 文件存储 - 快照文件 + 追加写日志
+
+use super::crypto::{self, is_encrypted_contents, Cipher, Passphrase};
+use super::lock::{LockGuard, StoreLock};
+use super::migration::{backup_path, migrate_value};
+use super::repair::quarantine_file;
+use super::rollup::{merge_rollups, rollup_data, RollupReport};
+use super::{atomic_write, ActivityStore, Journal, JournalEvent};
+use crate::core::tracker::{ActivityRecord, DailyRollup, TimeTrackerData};
+use crate::utils::time::DayBoundary;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+/// 日志累积到多少条事件后触发压缩
+const DEFAULT_COMPACT_THRESHOLD: usize = 500;
+
+/// 当前活动心跳事件的最小间隔
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// JSON 文件存储（默认后端）
+///
+/// 完整数据保存在 JSON 快照中，修改以事件形式追加到 `<文件名>.journal` 并立即 fsync。
+/// 日志达到阈值后在后台线程中压缩：先轮转日志，再原子地写入新快照。
+/// 加载时读取快照并重放日志，因此崩溃或断电最多丢失最后一个心跳间隔内的时长。
+///
+/// 多个进程（守护进程、TUI）可以同时打开同一个文件：每次写入都在 `<文件名>.lock`
+/// 锁内进行，写入前先读取其他进程追加的日志事件，快照被其他进程重写时则重新加载，
+/// 因此各进程的修改会合并，而不会互相覆盖。
+///
+/// 启用加密后，快照整体加密、日志逐行加密，明文只存在于内存中。
+pub struct JsonFileStore {
+    path: PathBuf,
+    data: TimeTrackerData,
+    loaded: bool,
+    journal: Journal,
+    lock: StoreLock,
+    generation: u64,
+    last_heartbeat: Option<Instant>,
+    compact_threshold: usize,
+    compaction: Option<JoinHandle<Result<()>>>,
+    warnings: Vec<String>,
+    cipher: Option<Arc<Cipher>>,
+}
+
+impl JsonFileStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let journal = Journal::new(journal_path(&path));
+        let lock = StoreLock::new(&path);
+
+        Self {
+            path,
+            data: TimeTrackerData::default(),
+            loaded: false,
+            journal,
+            lock,
+            generation: 0,
+            last_heartbeat: None,
+            compact_threshold: DEFAULT_COMPACT_THRESHOLD,
+            compaction: None,
+            warnings: Vec::new(),
+            cipher: None,
+        }
+    }
+
+    /// 使用加密器创建加密存储
+    pub fn encrypted<P: AsRef<Path>>(path: P, cipher: Cipher) -> Self {
+        let mut store = Self::new(path);
+        store.set_cipher(Some(Arc::new(cipher)));
+        store
+    }
+
+    /// 用口令打开加密存储；数据文件尚未加密时生成新的密钥信息，首次加载时加密已有数据
+    pub fn open_encrypted<P: AsRef<Path>>(path: P, passphrase: &Passphrase) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        // 避免两个进程同时为同一文件生成不同的密钥
+        let _guard = StoreLock::new(path).acquire()?;
+        let cipher = if crypto::is_encrypted(path) {
+            crypto::open_cipher(path, passphrase)?
+        } else {
+            let cipher = Cipher::generate(passphrase)?;
+            crypto::write_keycheck(path, &cipher)?;
+            log::info!("已为数据文件启用加密: {}", path.display());
+            cipher
+        };
+        Ok(Self::encrypted(path, cipher))
+    }
+
+    /// 使用共享的加密器创建存储（按日期分区时各分区共用主数据文件的密钥）
+    pub(crate) fn with_cipher<P: AsRef<Path>>(path: P, cipher: Option<Arc<Cipher>>) -> Self {
+        let mut store = Self::new(path);
+        store.set_cipher(cipher);
+        store
+    }
+
+    /// 是否为加密存储
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    pub(crate) fn cipher(&self) -> Option<Arc<Cipher>> {
+        self.cipher.clone()
+    }
+
+    fn set_cipher(&mut self, cipher: Option<Arc<Cipher>>) {
+        self.journal.set_cipher(cipher.clone());
+        self.cipher = cipher;
+    }
+
+    /// 更换加密口令：用新口令重新加密快照并清空日志
+    ///
+    /// 其他进程需要使用新口令重新打开数据文件。
+    pub fn rotate_key(&mut self, passphrase: &Passphrase) -> Result<()> {
+        let cipher = Arc::new(Cipher::generate(passphrase)?);
+        self.locked(|store, guard| {
+            // 先写入待生效的密钥信息，中途崩溃时新口令仍可解密新快照
+            crypto::write_pending_keycheck(&store.path, &cipher)?;
+            store.set_cipher(Some(cipher.clone()));
+            store.write_snapshot_locked(guard)?;
+            crypto::commit_pending_keycheck(&store.path)
+        })
+    }
+
+    /// 取消加密：以明文重写快照并删除密钥信息
+    pub fn remove_encryption(&mut self) -> Result<()> {
+        self.locked(|store, guard| {
+            store.set_cipher(None);
+            store.write_snapshot_locked(guard)?;
+            let keycheck = crypto::keycheck_path(&store.path);
+            if keycheck.exists() {
+                fs::remove_file(keycheck)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// 数据文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 日志文件路径
+    pub fn journal_path(&self) -> &Path {
+        self.journal.path()
+    }
+
+    /// 设置触发压缩的日志事件数
+    pub fn set_compact_threshold(&mut self, threshold: usize) {
+        self.compact_threshold = threshold.max(1);
+    }
+
+    /// 立即压缩：写入新快照并清空日志
+    pub fn compact(&mut self) -> Result<()> {
+        self.locked(|store, guard| store.write_snapshot_locked(guard))
+    }
+
+    /// 读取最新数据（已加载时只读取其他进程新增的日志事件）
+    pub(crate) fn read(&mut self) -> Result<TimeTrackerData> {
+        self.locked(|store, _| Ok(store.data.clone()))
+    }
+
+    /// 在同一次加锁内补充尚不存在（按 id）的记录并写入快照，返回新增的条数
+    pub(crate) fn merge_records(&mut self, records: &[ActivityRecord]) -> Result<usize> {
+        self.locked(|store, guard| {
+            let existing: HashSet<&str> = store
+                .data
+                .activities
+                .iter()
+                .map(|r| r.id.as_str())
+                .collect();
+            let missing: Vec<ActivityRecord> = records
+                .iter()
+                .filter(|r| !existing.contains(r.id.as_str()))
+                .cloned()
+                .collect();
+            if missing.is_empty() {
+                return Ok(0);
+            }
+            store.data.activities.extend(missing.iter().cloned());
+            store.write_snapshot_locked(guard)?;
+            Ok(missing.len())
+        })
+    }
+
+    /// 在同一次加锁内替换历史记录并导入每日汇总，写入快照
+    ///
+    /// 已存在完全相同的汇总时视为已经导入，中断后重新执行不会重复累加。
+    pub(crate) fn replace_with_rollups(
+        &mut self,
+        records: &[ActivityRecord],
+        rollups: &[DailyRollup],
+    ) -> Result<()> {
+        self.locked(|store, guard| {
+            store.data.activities = records.to_vec();
+            let missing: Vec<DailyRollup> = rollups
+                .iter()
+                .filter(|r| !store.data.rollups.contains(r))
+                .cloned()
+                .collect();
+            merge_rollups(&mut store.data.rollups, missing);
+            store.write_snapshot_locked(guard)
+        })
+    }
+
+    /// 用新的加密器把当前数据写入暂存文件 `<文件名>.rotating`，由 `apply_staged` 替换
+    pub(crate) fn stage_snapshot(&mut self, cipher: &Cipher) -> Result<()> {
+        self.locked(|store, _| write_snapshot(&staged_path(&store.path), &store.data, Some(cipher)))
+    }
+
+    /// 删除数据文件及其日志和锁文件
+    ///
+    /// 删除前在同一次加锁内写入完整快照，并把快照内容交给 `before_remove`（用于归档），
+    /// `before_remove` 失败时不会删除任何文件。
+    pub(crate) fn remove_files(
+        mut self,
+        before_remove: impl FnOnce(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        self.locked(|store, guard| {
+            store.write_snapshot_locked(guard)?;
+            before_remove(&fs::read(&store.path)?)?;
+            fs::remove_file(&store.path)?;
+            store.journal.clear()
+        })?;
+        let _ = fs::remove_file(self.lock.path());
+        Ok(())
+    }
+
+    /// 在锁内写入当前数据的快照并清空日志
+    fn write_snapshot_locked(&mut self, guard: &mut LockGuard) -> Result<()> {
+        write_snapshot(&self.path, &self.data, self.cipher.as_deref())?;
+        self.journal.clear()?;
+        self.generation = guard.bump_generation()?;
+        Ok(())
+    }
+
+    /// 等待后台压缩完成
+    pub fn wait_for_compaction(&mut self) -> Result<()> {
+        if let Some(handle) = self.compaction.take() {
+            handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("后台压缩线程异常退出"))??;
+        }
+        Ok(())
+    }
+
+    /// 在后台线程中压缩日志
+    fn start_compaction(&mut self) -> Result<()> {
+        let generation = self.locked(|store, guard| {
+            // 上次压缩未完成（例如进程崩溃）时轮转文件仍在，直接同步压缩
+            if store.journal.rotated_path().exists() {
+                store.write_snapshot_locked(guard)?;
+                return Ok(None);
+            }
+
+            store.journal.rotate()?;
+            store.generation = guard.bump_generation()?;
+            Ok(Some(store.generation))
+        })?;
+
+        let Some(generation) = generation else {
+            return Ok(());
+        };
+
+        let path = self.path.clone();
+        let lock = self.lock.clone();
+        let rotated = self.journal.rotated_path();
+        let snapshot = self.data.clone();
+        let cipher = self.cipher.clone();
+        self.compaction = Some(std::thread::spawn(move || {
+            let mut guard = lock.acquire()?;
+            // 其他进程已经重写了快照，这份快照已过时
+            if guard.generation()? != generation || !rotated.exists() {
+                return Ok(());
+            }
+            write_snapshot(&path, &snapshot, cipher.as_deref())?;
+            fs::remove_file(&rotated)?;
+            log::debug!("数据日志压缩完成: {}", path.display());
+            Ok(())
+        }));
+        Ok(())
+    }
+
+    /// 在锁内执行操作：先等待本进程的后台压缩完成，再与磁盘上的最新数据同步
+    fn locked<T>(&mut self, f: impl FnOnce(&mut Self, &mut LockGuard) -> Result<T>) -> Result<T> {
+        // 后台压缩线程同样需要锁，必须在加锁前等待
+        self.wait_for_compaction()?;
+
+        let mut guard = self.lock.acquire()?;
+        self.sync(&mut guard)?;
+        f(self, &mut guard)
+    }
+
+    /// 读取其他进程的修改：快照被重写时重新加载，否则只重放日志新增的事件
+    fn sync(&mut self, guard: &mut LockGuard) -> Result<()> {
+        let generation = guard.generation()?;
+        if !self.loaded || generation != self.generation {
+            self.data = self.read_from_disk(guard)?;
+            self.generation = guard.generation()?;
+            self.loaded = true;
+            return Ok(());
+        }
+
+        for event in self.journal.read_new()? {
+            event.apply(&mut self.data);
+        }
+        Ok(())
+    }
+
+    /// 从磁盘读取快照，旧结构版本的文件会先升级并写回（原文件另存备份）
+    ///
+    /// 无法解析的文件会被移动到隔离副本，并记录一条警告，随后以空数据继续运行。
+    /// 返回的布尔值表示启用加密后磁盘上是否仍是明文快照。
+    fn read_snapshot(&mut self, guard: &mut LockGuard) -> Result<(TimeTrackerData, bool)> {
+        if !self.path.exists() {
+            // 文件不存在，使用默认数据
+            return Ok((TimeTrackerData::default(), false));
+        }
+
+        let bytes = Zeroizing::new(
+            fs::read(&self.path)
+                .with_context(|| format!("无法读取数据文件: {}", self.path.display()))?,
+        );
+
+        // 解密失败（口令错误）时直接返回错误，不能当作损坏文件隔离
+        let encrypted = is_encrypted_contents(&bytes);
+        let plaintext = match (&self.cipher, encrypted) {
+            (Some(cipher), true) => cipher
+                .decrypt_file(&bytes)
+                .with_context(|| format!("无法解密数据文件: {}", self.path.display()))?,
+            (None, true) => {
+                return Err(anyhow::anyhow!(
+                    "数据文件 {} 已加密，需要提供口令",
+                    self.path.display()
+                ))
+            }
+            (_, false) => bytes.clone(),
+        };
+        let plain_on_disk = self.cipher.is_some() && !encrypted;
+
+        let file_content = String::from_utf8_lossy(&plaintext);
+        if file_content.trim().is_empty() {
+            return Ok((TimeTrackerData::default(), plain_on_disk));
+        }
+
+        let mut value = match serde_json::from_str::<Value>(&file_content) {
+            Ok(value) => value,
+            Err(e) => return Ok((self.quarantine(e)?, false)),
+        };
+        let report = migrate_value(&mut value)?;
+        let data = match serde_json::from_value::<TimeTrackerData>(value) {
+            Ok(data) => data,
+            Err(e) => return Ok((self.quarantine(e)?, false)),
+        };
+
+        if !report.is_empty() {
+            let backup = backup_path(&self.path, report.from_version);
+            if !backup.exists() {
+                // 备份与数据文件使用相同的加密方式
+                let contents = match &self.cipher {
+                    Some(cipher) => cipher.encrypt_file(&plaintext)?,
+                    None => plaintext.to_vec(),
+                };
+                atomic_write(&backup, &contents)
+                    .with_context(|| format!("无法备份数据文件: {}", backup.display()))?;
+            }
+            // 立即写回，保证生成的记录 id 在各进程之间保持一致
+            write_snapshot(&self.path, &data, self.cipher.as_deref())?;
+            self.generation = guard.bump_generation()?;
+            log::info!(
+                "数据文件已从结构版本 {} 升级到 {}，原文件备份在 {}",
+                report.from_version,
+                report.to_version,
+                backup.display()
+            );
+            return Ok((data, false));
+        }
+
+        Ok((data, plain_on_disk))
+    }
+
+    /// 将无法解析的数据文件移动到隔离副本，以空数据继续运行
+    fn quarantine(&mut self, parse_error: serde_json::Error) -> Result<TimeTrackerData> {
+        let quarantined = quarantine_file(&self.path)
+            .with_context(|| format!("无法隔离损坏的数据文件: {}", self.path.display()))?;
+        let warning = format!(
+            "数据文件 {} 解析失败（{}），已移动到 {}。可运行 'timetracker data repair --input {}' 恢复记录",
+            self.path.display(),
+            parse_error,
+            quarantined.display(),
+            quarantined.display()
+        );
+        log::error!("{}", warning);
+        self.warnings.push(warning);
+
+        Ok(TimeTrackerData::default())
+    }
+
+    /// 读取快照并重放日志（调用方需持有锁）
+    fn read_from_disk(&mut self, guard: &mut LockGuard) -> Result<TimeTrackerData> {
+        let (mut data, plain_snapshot) = self.read_snapshot(guard)?;
+        let events = self.journal.replay()?;
+        if !events.is_empty() {
+            log::debug!("重放 {} 条数据日志事件", events.len());
+        }
+        for event in events {
+            event.apply(&mut data);
+        }
+
+        // 刚启用加密时磁盘上还有明文，立即加密重写
+        if self.cipher.is_some() && (plain_snapshot || self.journal.has_plaintext()) {
+            write_snapshot(&self.path, &data, self.cipher.as_deref())?;
+            self.journal.clear()?;
+            self.generation = guard.bump_generation()?;
+            log::info!("已加密数据文件中的明文记录: {}", self.path.display());
+        }
+        Ok(data)
+    }
+
+    /// 写入一条日志事件（调用方需持有锁并已同步）
+    fn record_event(&mut self, event: JournalEvent) -> Result<()> {
+        self.journal.append(&event)?;
+        event.apply(&mut self.data);
+        Ok(())
+    }
+}
+
+impl ActivityStore for JsonFileStore {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn load(&mut self) -> Result<TimeTrackerData> {
+        // 强制重新读取完整数据
+        self.loaded = false;
+        self.locked(|store, _| Ok(store.data.clone()))
+    }
+
+    fn append(&mut self, record: &ActivityRecord) -> Result<()> {
+        self.locked(|store, _| {
+            store.record_event(JournalEvent::Finish {
+                record: record.clone(),
+            })
+        })
+    }
+
+    fn update(&mut self, record: &ActivityRecord) -> Result<bool> {
+        self.locked(|store, _| {
+            if !store.data.activities.iter().any(|r| r.id == record.id) {
+                return Ok(false);
+            }
+            store.record_event(JournalEvent::Update {
+                record: record.clone(),
+            })?;
+            Ok(true)
+        })
+    }
+
+    fn delete(&mut self, ids: &[String]) -> Result<usize> {
+        self.locked(|store, _| {
+            let deleted = store
+                .data
+                .activities
+                .iter()
+                .filter(|r| ids.contains(&r.id))
+                .count();
+            if deleted > 0 {
+                store.record_event(JournalEvent::Delete { ids: ids.to_vec() })?;
+            }
+            Ok(deleted)
+        })
+    }
+
+    fn replace_all(&mut self, records: &[ActivityRecord]) -> Result<()> {
+        // 整体替换无法用单个事件表达，直接写入新快照；其他进程会在下次写入前重新加载
+        self.locked(|store, guard| {
+            store.data.activities = records.to_vec();
+            store.write_snapshot_locked(guard)
+        })
+    }
+
+    fn set_current(&mut self, current: Option<&ActivityRecord>) -> Result<()> {
+        let previous_id = self.data.current_activity.as_ref().map(|c| c.id.clone());
+        let heartbeat_due = self
+            .last_heartbeat
+            .is_none_or(|t| t.elapsed() >= HEARTBEAT_INTERVAL);
+
+        // 大多数监控周期无需写入，避免每次都加锁
+        let event = match current {
+            Some(record) if previous_id.as_ref() != Some(&record.id) => JournalEvent::Start {
+                record: record.clone(),
+            },
+            Some(_) if heartbeat_due => JournalEvent::Heartbeat { at: Utc::now() },
+            None if previous_id.is_some() => JournalEvent::ClearCurrent { at: Utc::now() },
+            _ => return Ok(()),
+        };
+
+        self.locked(|store, _| store.record_event(event))?;
+        if current.is_some() {
+            self.last_heartbeat = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    fn current(&mut self) -> Result<Option<ActivityRecord>> {
+        self.locked(|store, _| Ok(store.data.current_activity.clone()))
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        // 日志事件在写入时已经 fsync，这里只负责按需压缩
+        if self.journal.len() >= self.compact_threshold {
+            self.start_compaction()?;
+        }
+        Ok(())
+    }
+
+    fn rollup(&mut self, before: DateTime<Utc>, boundary: DayBoundary) -> Result<RollupReport> {
+        self.locked(|store, guard| {
+            if !store.data.activities.iter().any(|r| r.start_time < before) {
+                return Ok(RollupReport::default());
+            }
+
+            // 汇总不是幂等事件，不能写入日志：先把日志合并进快照，
+            // 再直接写入汇总后的快照，保证不会有旧事件在汇总后被重放
+            store.write_snapshot_locked(guard)?;
+            let report = rollup_data(&mut store.data, before, boundary);
+            store.write_snapshot_locked(guard)?;
+            Ok(report)
+        })
+    }
+}
+
+impl Drop for JsonFileStore {
+    fn drop(&mut self) {
+        if let Err(e) = self.wait_for_compaction() {
+            log::warn!("等待数据压缩完成失败: {}", e);
+        }
+    }
+}
+
+/// 数据文件对应的日志文件路径
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut journal = path.to_path_buf().into_os_string();
+    journal.push(".journal");
+    PathBuf::from(journal)
+}
+
+/// 更换密钥时的暂存快照路径
+pub(crate) fn staged_path(path: &Path) -> PathBuf {
+    let mut staged = path.to_path_buf().into_os_string();
+    staged.push(".rotating");
+    PathBuf::from(staged)
+}
+
+/// 用暂存快照替换数据文件并清空日志，返回是否存在暂存快照
+///
+/// 日志中是旧密钥加密的事件，它们已经包含在暂存快照中。
+pub(crate) fn apply_staged(path: &Path) -> Result<bool> {
+    let staged = staged_path(path);
+    if !staged.exists() {
+        return Ok(false);
+    }
+
+    let mut guard = StoreLock::new(path).acquire()?;
+    fs::rename(&staged, path)?;
+    Journal::new(journal_path(path)).clear()?;
+    guard.bump_generation()?;
+    Ok(true)
+}
+
+/// 原子地写入 JSON 快照，提供加密器时写入加密内容
+fn write_snapshot(path: &Path, data: &TimeTrackerData, cipher: Option<&Cipher>) -> Result<()> {
+    let json = Zeroizing::new(serde_json::to_string_pretty(data)?);
+    match cipher {
+        Some(cipher) => atomic_write(path, &cipher.encrypt_file(json.as_bytes())?),
+        None => atomic_write(path, json.as_bytes()),
+    }
+}
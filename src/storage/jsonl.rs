@@ -0,0 +1,232 @@
+// JSONL 存储 - 每行一条活动记录，追加写入
+
+use super::rollup::{rollup_data, RollupReport};
+use super::{atomic_write, ActivityStore, StoreLock};
+use crate::core::tracker::{ActivityRecord, DailyRollup, TimeTrackerData};
+use crate::utils::time::DayBoundary;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 当前活动状态（保存在 `<文件名>.current.json` 中）
+#[derive(Debug, Serialize, Deserialize)]
+struct CurrentState {
+    current_activity: Option<ActivityRecord>,
+    last_updated: DateTime<Utc>,
+}
+
+/// 每日汇总行（与记录行保存在同一个文件中）
+#[derive(Debug, Serialize, Deserialize)]
+struct RollupLine {
+    rollup: DailyRollup,
+}
+
+/// 数据文件中的全部内容
+#[derive(Debug, Default)]
+struct JsonlContents {
+    records: Vec<ActivityRecord>,
+    rollups: Vec<DailyRollup>,
+    skipped: usize,
+}
+
+/// JSONL 文件存储
+///
+/// 已结束的记录逐行追加，当前活动单独保存，避免每个监控周期重写历史记录。
+/// 每日汇总以 `{"rollup": {...}}` 行的形式保存在同一个文件中。
+/// 所有读写都在 `<文件名>.lock` 锁内进行，编辑操作每次都基于磁盘上的最新内容。
+pub struct JsonlStore {
+    path: PathBuf,
+    lock: StoreLock,
+    current: Option<ActivityRecord>,
+    current_dirty: bool,
+    warnings: Vec<String>,
+}
+
+impl JsonlStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: StoreLock::new(path.as_ref()),
+            current: None,
+            current_dirty: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// 当前活动状态文件路径
+    pub fn current_path(&self) -> PathBuf {
+        self.path.with_extension("current.json")
+    }
+
+    fn read_contents(&self) -> Result<JsonlContents> {
+        let mut contents = JsonlContents::default();
+        if !self.path.exists() {
+            return Ok(contents);
+        }
+
+        let content = fs::read_to_string(&self.path)?;
+        for (line_no, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<ActivityRecord>(line) {
+                Ok(record) => contents.records.push(record),
+                Err(e) => match serde_json::from_str::<RollupLine>(line) {
+                    Ok(line) => contents.rollups.push(line.rollup),
+                    Err(_) => {
+                        contents.skipped += 1;
+                        log::warn!(
+                            "跳过无法解析的记录 {}:{}: {}",
+                            self.path.display(),
+                            line_no + 1,
+                            e
+                        );
+                    }
+                },
+            }
+        }
+        Ok(contents)
+    }
+
+    /// 整体重写数据文件（汇总行在前）
+    fn write_contents(&self, records: &[ActivityRecord], rollups: &[DailyRollup]) -> Result<()> {
+        let mut content = String::new();
+        for rollup in rollups {
+            content.push_str(&serde_json::to_string(&RollupLine {
+                rollup: rollup.clone(),
+            })?);
+            content.push('\n');
+        }
+        for record in records {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+        atomic_write(&self.path, content.as_bytes())
+    }
+}
+
+impl ActivityStore for JsonlStore {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    fn load(&mut self) -> Result<TimeTrackerData> {
+        let _guard = self.lock.acquire()?;
+        let state = fs::read_to_string(self.current_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<CurrentState>(&content).ok());
+
+        let (current_activity, last_updated) = match state {
+            Some(state) => (state.current_activity, state.last_updated),
+            None => (None, Utc::now()),
+        };
+        self.current = current_activity.clone();
+
+        let contents = self.read_contents()?;
+        if contents.skipped > 0 {
+            self.warnings.push(format!(
+                "数据文件 {} 中有 {} 行无法解析，已跳过",
+                self.path.display(),
+                contents.skipped
+            ));
+        }
+
+        Ok(TimeTrackerData {
+            activities: contents.records,
+            current_activity,
+            last_updated,
+            rollups: contents.rollups,
+            ..TimeTrackerData::default()
+        })
+    }
+
+    fn append(&mut self, record: &ActivityRecord) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    fn update(&mut self, record: &ActivityRecord) -> Result<bool> {
+        let _guard = self.lock.acquire()?;
+        let mut contents = self.read_contents()?;
+        match contents.records.iter_mut().find(|r| r.id == record.id) {
+            Some(existing) => {
+                *existing = record.clone();
+                self.write_contents(&contents.records, &contents.rollups)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn delete(&mut self, ids: &[String]) -> Result<usize> {
+        let _guard = self.lock.acquire()?;
+        let mut contents = self.read_contents()?;
+        let before = contents.records.len();
+        contents.records.retain(|r| !ids.contains(&r.id));
+        let deleted = before - contents.records.len();
+        if deleted > 0 {
+            self.write_contents(&contents.records, &contents.rollups)?;
+        }
+        Ok(deleted)
+    }
+
+    fn replace_all(&mut self, records: &[ActivityRecord]) -> Result<()> {
+        let _guard = self.lock.acquire()?;
+        let contents = self.read_contents()?;
+        self.write_contents(records, &contents.rollups)
+    }
+
+    fn set_current(&mut self, current: Option<&ActivityRecord>) -> Result<()> {
+        self.current = current.cloned();
+        self.current_dirty = true;
+        Ok(())
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if !self.current_dirty {
+            return Ok(());
+        }
+
+        let _guard = self.lock.acquire()?;
+        let state = CurrentState {
+            current_activity: self.current.clone(),
+            last_updated: Utc::now(),
+        };
+        atomic_write(
+            self.current_path(),
+            serde_json::to_string(&state)?.as_bytes(),
+        )?;
+        self.current_dirty = false;
+        Ok(())
+    }
+
+    fn rollup(&mut self, before: DateTime<Utc>, boundary: DayBoundary) -> Result<RollupReport> {
+        let _guard = self.lock.acquire()?;
+        let contents = self.read_contents()?;
+        let mut data = TimeTrackerData {
+            activities: contents.records,
+            rollups: contents.rollups,
+            ..TimeTrackerData::default()
+        };
+
+        // 汇总行和剩余记录一次性原子写入
+        let report = rollup_data(&mut data, before, boundary);
+        if !report.is_empty() {
+            self.write_contents(&data.activities, &data.rollups)?;
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,103 @@
+// 跨进程文件锁 - 守护进程和 TUI 同时写入同一数据文件时串行化修改
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 等待锁的最长时间
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 重试获取锁的间隔
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+/// 数据文件对应的锁文件 `<文件名>.lock`
+///
+/// 锁文件中保存一个代数（generation）：每次有进程重写快照（压缩、整体替换）时加一。
+/// 其他进程发现代数变化后重新读取完整数据，否则只需读取日志新增的部分。
+#[derive(Debug, Clone)]
+pub struct StoreLock {
+    path: PathBuf,
+}
+
+impl StoreLock {
+    pub fn new<P: AsRef<Path>>(data_path: P) -> Self {
+        Self {
+            path: lock_path(data_path.as_ref()),
+        }
+    }
+
+    /// 锁文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 获取排他锁，超时后返回错误
+    pub fn acquire(&self) -> Result<LockGuard> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)
+            .with_context(|| format!("无法打开锁文件: {}", self.path.display()))?;
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(LockGuard { file }),
+                Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_TIMEOUT => {
+                    std::thread::sleep(LOCK_RETRY_INTERVAL);
+                }
+                Err(TryLockError::WouldBlock) => {
+                    anyhow::bail!(
+                        "等待数据文件锁超时: {}（可能有其他 timetracker 进程正在写入）",
+                        self.path.display()
+                    );
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("无法锁定: {}", self.path.display()));
+                }
+            }
+        }
+    }
+}
+
+/// 持有中的锁，离开作用域时释放
+pub struct LockGuard {
+    file: File,
+}
+
+impl LockGuard {
+    /// 读取当前代数
+    pub fn generation(&mut self) -> Result<u64> {
+        let mut content = String::new();
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_to_string(&mut content)?;
+        Ok(content.trim().parse().unwrap_or(0))
+    }
+
+    /// 代数加一并返回新值（重写快照后调用）
+    pub fn bump_generation(&mut self) -> Result<u64> {
+        let generation = self.generation()? + 1;
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(generation.to_string().as_bytes())?;
+        self.file.sync_data()?;
+        Ok(generation)
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// 数据文件对应的锁文件路径
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut lock = path.to_path_buf().into_os_string();
+    lock.push(".lock");
+    PathBuf::from(lock)
+}
@@ -0,0 +1,75 @@
+// 内存存储 - 不做持久化，适合测试和嵌入场景
+
+use super::rollup::{rollup_data, RollupReport};
+use super::ActivityStore;
+use crate::core::tracker::{ActivityRecord, TimeTrackerData};
+use crate::utils::time::DayBoundary;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+/// 仅保存在内存中的活动记录存储
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    data: TimeTrackerData,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用已有数据创建存储
+    pub fn with_data(data: TimeTrackerData) -> Self {
+        Self { data }
+    }
+}
+
+impl ActivityStore for MemoryStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn load(&mut self) -> Result<TimeTrackerData> {
+        Ok(self.data.clone())
+    }
+
+    fn append(&mut self, record: &ActivityRecord) -> Result<()> {
+        self.data.activities.push(record.clone());
+        Ok(())
+    }
+
+    fn update(&mut self, record: &ActivityRecord) -> Result<bool> {
+        match self.data.activities.iter_mut().find(|r| r.id == record.id) {
+            Some(existing) => {
+                *existing = record.clone();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn delete(&mut self, ids: &[String]) -> Result<usize> {
+        let before = self.data.activities.len();
+        self.data.activities.retain(|r| !ids.contains(&r.id));
+        Ok(before - self.data.activities.len())
+    }
+
+    fn replace_all(&mut self, records: &[ActivityRecord]) -> Result<()> {
+        self.data.activities = records.to_vec();
+        Ok(())
+    }
+
+    fn set_current(&mut self, current: Option<&ActivityRecord>) -> Result<()> {
+        self.data.current_activity = current.cloned();
+        self.data.last_updated = Utc::now();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn rollup(&mut self, before: DateTime<Utc>, boundary: DayBoundary) -> Result<RollupReport> {
+        Ok(rollup_data(&mut self.data, before, boundary))
+    }
+}
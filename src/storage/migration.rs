@@ -0,0 +1,234 @@
+// 数据迁移 - 按结构版本逐步升级 JSON 数据文件
+
+use crate::core::tracker::{new_record_id, TimeTrackerData};
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// 当前数据文件结构版本（保存在 `schema_version` 字段中）
+pub const CURRENT_SCHEMA_VERSION: u32 = 3;
+
+/// 一个结构升级步骤：把 `from` 版本的数据升级到 `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    apply: fn(&mut Value) -> Result<Vec<String>>,
+}
+
+/// 全部升级步骤，按版本顺序排列
+///
+/// 修改 `TimeTrackerData` 或 `ActivityRecord` 的结构时，在这里追加一步并提升
+/// `CURRENT_SCHEMA_VERSION`。
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "旧的纯数组格式转换为 TimeTrackerData 对象",
+        apply: wrap_legacy_array,
+    },
+    Migration {
+        from: 1,
+        description: "duration 字段更名为 duration_seconds，补全增强窗口信息字段",
+        apply: upgrade_record_fields,
+    },
+    Migration {
+        from: 2,
+        description: "为每条记录生成唯一标识 id",
+        apply: assign_record_ids,
+    },
+];
+
+/// 已执行（或将要执行）的升级步骤
+#[derive(Debug, Clone)]
+pub struct MigrationStep {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+    /// 该步骤实际做出的修改
+    pub changes: Vec<String>,
+}
+
+/// 迁移结果
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub steps: Vec<MigrationStep>,
+}
+
+impl MigrationReport {
+    /// 是否无需迁移
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+/// 判断数据的结构版本
+///
+/// 纯数组为版本 0；没有 `schema_version` 字段的对象为版本 1。
+pub fn detect_schema_version(value: &Value) -> u32 {
+    match value {
+        Value::Array(_) => 0,
+        Value::Object(map) => map
+            .get("schema_version")
+            .and_then(Value::as_u64)
+            .map(|v| v as u32)
+            .unwrap_or(1),
+        _ => 0,
+    }
+}
+
+/// 将数据逐步升级到当前结构版本
+pub fn migrate_value(value: &mut Value) -> Result<MigrationReport> {
+    let from_version = detect_schema_version(value);
+    if from_version > CURRENT_SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "数据文件结构版本 {} 高于当前程序支持的版本 {}，请升级 timetracker",
+            from_version,
+            CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    let mut steps = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from_version) {
+        let changes = (migration.apply)(value)?;
+        steps.push(MigrationStep {
+            from: migration.from,
+            to: migration.from + 1,
+            description: migration.description,
+            changes,
+        });
+    }
+
+    if !steps.is_empty() {
+        if let Value::Object(map) = value {
+            map.insert(
+                "schema_version".to_string(),
+                Value::from(CURRENT_SCHEMA_VERSION),
+            );
+            map.insert(
+                "version".to_string(),
+                Value::from(env!("CARGO_PKG_VERSION")),
+            );
+        }
+    }
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: CURRENT_SCHEMA_VERSION,
+        steps,
+    })
+}
+
+/// 解析数据文件内容，必要时先升级到当前结构版本
+pub fn parse_data(content: &str) -> Result<(TimeTrackerData, MigrationReport)> {
+    let mut value: Value = serde_json::from_str(content)?;
+    let report = migrate_value(&mut value)?;
+    let data = serde_json::from_value(value)?;
+    Ok((data, report))
+}
+
+/// 迁移前原文件的备份路径 `<文件名>.schema-v<版本>.bak`
+pub fn backup_path(path: &Path, from_version: u32) -> PathBuf {
+    let mut backup = path.to_path_buf().into_os_string();
+    backup.push(format!(".schema-v{from_version}.bak"));
+    PathBuf::from(backup)
+}
+
+/// 对数据中的每条记录（历史记录和当前活动）执行操作
+fn for_each_record(value: &mut Value, mut f: impl FnMut(&mut Map<String, Value>)) {
+    if let Some(Value::Array(records)) = value.get_mut("activities") {
+        for record in records {
+            if let Value::Object(map) = record {
+                f(map);
+            }
+        }
+    }
+    if let Some(Value::Object(current)) = value.get_mut("current_activity") {
+        f(current);
+    }
+}
+
+/// v0 → v1：旧版本直接保存记录数组
+fn wrap_legacy_array(value: &mut Value) -> Result<Vec<String>> {
+    let Value::Array(records) = value.take() else {
+        return Err(anyhow::anyhow!("数据文件既不是数组也不是对象"));
+    };
+
+    let count = records.len();
+    let mut map = Map::new();
+    map.insert("activities".to_string(), Value::Array(records));
+    map.insert("current_activity".to_string(), Value::Null);
+    map.insert(
+        "last_updated".to_string(),
+        serde_json::to_value(Utc::now())?,
+    );
+    *value = Value::Object(map);
+
+    Ok(vec![format!("{} 条记录转换为对象格式", count)])
+}
+
+/// v1 → v2：时长字段更名，旧记录补全 0.2 版本新增的窗口信息字段
+fn upgrade_record_fields(value: &mut Value) -> Result<Vec<String>> {
+    let mut renamed = 0;
+    let mut filled = 0;
+
+    for_each_record(value, |record| {
+        if !record.contains_key("duration_seconds") {
+            if let Some(duration) = record.remove("duration") {
+                record.insert("duration_seconds".to_string(), duration);
+                renamed += 1;
+            }
+        }
+
+        let mut missing = false;
+        for (field, default) in [
+            ("app_path", Value::Null),
+            ("bundle_id", Value::Null),
+            ("window_geometry", Value::Null),
+            ("confidence", Value::from(0.0)),
+        ] {
+            if !record.contains_key(field) {
+                record.insert(field.to_string(), default);
+                missing = true;
+            }
+        }
+        if missing {
+            filled += 1;
+        }
+    });
+
+    let mut changes = Vec::new();
+    if renamed > 0 {
+        changes.push(format!(
+            "{} 条记录的 duration 字段更名为 duration_seconds",
+            renamed
+        ));
+    }
+    if filled > 0 {
+        changes.push(format!("{} 条记录补全了窗口信息字段", filled));
+    }
+    Ok(changes)
+}
+
+/// v2 → v3：记录按 id 编辑和删除，旧记录需要一个稳定的标识
+fn assign_record_ids(value: &mut Value) -> Result<Vec<String>> {
+    let mut assigned = 0;
+
+    for_each_record(value, |record| {
+        let has_id = record
+            .get("id")
+            .and_then(Value::as_str)
+            .is_some_and(|id| !id.is_empty());
+        if !has_id {
+            record.insert("id".to_string(), Value::from(new_record_id()));
+            assigned += 1;
+        }
+    });
+
+    Ok(if assigned > 0 {
+        vec![format!("为 {} 条记录生成了 id", assigned)]
+    } else {
+        Vec::new()
+    })
+}
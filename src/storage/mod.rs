@@ -0,0 +1,371 @@
+// 存储模块 - 负责活动记录的持久化后端
+
+pub mod compact;
+pub mod crypto;
+pub mod doctor;
+pub mod journal;
+pub mod json;
+pub mod jsonl;
+pub mod lock;
+pub mod memory;
+pub mod migration;
+pub mod partition;
+pub mod repair;
+pub mod rollup;
+pub mod sqlite;
+pub mod status;
+
+pub use compact::CompactReport;
+pub use crypto::Passphrase;
+pub use doctor::{DoctorReport, IssueKind};
+pub use journal::{Journal, JournalEvent};
+pub use json::JsonFileStore;
+pub use jsonl::JsonlStore;
+pub use lock::StoreLock;
+pub use memory::MemoryStore;
+pub use partition::{
+    PartitionedStore, Partitioning, RetentionAction, RetentionPolicy, RetentionReport,
+};
+pub use rollup::RollupReport;
+pub use sqlite::SqliteStore;
+
+use crate::config::app::StorageConfig;
+use crate::core::tracker::{ActivityRecord, DailyRollup, TimeTrackerData};
+use crate::utils::time::DayBoundary;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 活动记录存储接口
+///
+/// `TimeTracker`、TUI 的编辑操作和导出功能都通过该接口读写数据，
+/// 嵌入方可以实现自己的后端并通过 `TimeTracker::with_store` 使用。
+pub trait ActivityStore: Send {
+    /// 后端名称（用于日志和状态显示）
+    fn name(&self) -> &str;
+
+    /// 读取全部数据（历史记录 + 当前活动）
+    fn load(&mut self) -> Result<TimeTrackerData>;
+
+    /// 追加一条已结束的活动记录
+    fn append(&mut self, record: &ActivityRecord) -> Result<()>;
+
+    /// 查询开始时间落在 [start, end) 区间内的记录，按开始时间排序
+    fn query_range(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ActivityRecord>> {
+        let mut records: Vec<ActivityRecord> = self
+            .load()?
+            .activities
+            .into_iter()
+            .filter(|r| r.start_time >= start && r.start_time < end)
+            .collect();
+        records.sort_by_key(|r| r.start_time);
+        Ok(records)
+    }
+
+    /// 查询与 [start, end) 有交集的记录，按开始时间排序
+    ///
+    /// 与 `query_range` 不同，开始时间早于 `start` 但仍未结束的长记录也会返回。
+    fn query_overlapping(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ActivityRecord>> {
+        let mut records = self.query_range(DateTime::<Utc>::MIN_UTC, end)?;
+        records.retain(|r| r.effective_end() > start);
+        Ok(records)
+    }
+
+    /// 按 id 更新记录，返回是否找到该记录
+    fn update(&mut self, record: &ActivityRecord) -> Result<bool>;
+
+    /// 按 id 删除记录，返回删除的条数
+    fn delete(&mut self, ids: &[String]) -> Result<usize>;
+
+    /// 用给定记录替换全部历史记录
+    fn replace_all(&mut self, records: &[ActivityRecord]) -> Result<()>;
+
+    /// 更新当前（未结束的）活动
+    fn set_current(&mut self, current: Option<&ActivityRecord>) -> Result<()>;
+
+    /// 读取当前（未结束的）活动
+    fn current(&mut self) -> Result<Option<ActivityRecord>> {
+        Ok(self.load()?.current_activity)
+    }
+
+    /// 将尚未写入的修改持久化
+    fn flush(&mut self) -> Result<()>;
+
+    /// 取出加载过程中产生的警告（例如损坏的数据文件已被隔离）
+    fn take_warnings(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// 查询首次出现时间落在 [start, end) 区间内的每日汇总
+    fn query_rollups(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<DailyRollup>> {
+        Ok(self
+            .load()?
+            .rollups
+            .into_iter()
+            .filter(|r| r.first_seen >= start && r.first_seen < end)
+            .collect())
+    }
+
+    /// 把开始时间早于 `before` 的历史记录按 `boundary` 的逻辑日合并为每日汇总，
+    /// 不支持的后端不做任何处理
+    ///
+    /// 删除原始记录和写入汇总在同一次写入中完成。
+    fn rollup(&mut self, _before: DateTime<Utc>, _boundary: DayBoundary) -> Result<RollupReport> {
+        Ok(RollupReport::default())
+    }
+
+    /// 按保留策略归档或删除过期数据，不支持的后端不做任何处理
+    fn apply_retention(
+        &mut self,
+        _policy: &RetentionPolicy,
+        _now: DateTime<Utc>,
+    ) -> Result<RetentionReport> {
+        Ok(RetentionReport::default())
+    }
+}
+
+/// 打开存储后端的选项
+#[derive(Debug, Clone, Default)]
+pub struct StoreOptions {
+    /// 加密口令（仅 JSON 后端）
+    pub passphrase: Option<Passphrase>,
+    /// 按日期分区的粒度（仅 JSON 后端），为 `None` 时沿用数据文件现有的布局
+    pub partitioning: Option<Partitioning>,
+    /// 划分分区日期使用的时区和日分割点
+    pub day_boundary: DayBoundary,
+}
+
+impl StoreOptions {
+    /// 根据存储配置创建选项
+    pub fn from_config(
+        config: &StorageConfig,
+        passphrase: Option<Passphrase>,
+        day_boundary: DayBoundary,
+    ) -> Self {
+        Self {
+            passphrase,
+            partitioning: Partitioning::from_name(&config.partition),
+            day_boundary,
+        }
+    }
+}
+
+/// 存储后端类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// 单个 JSON 文件（默认）
+    Json,
+    /// 每行一条记录的 JSONL 文件
+    Jsonl,
+    /// SQLite 数据库
+    Sqlite,
+}
+
+impl StorageBackend {
+    /// 根据数据文件扩展名判断后端
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("db") | Some("sqlite") | Some("sqlite3") => StorageBackend::Sqlite,
+            Some("jsonl") => StorageBackend::Jsonl,
+            _ => StorageBackend::Json,
+        }
+    }
+
+    /// 根据配置名称解析后端
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(StorageBackend::Json),
+            "jsonl" => Some(StorageBackend::Jsonl),
+            "sqlite" => Some(StorageBackend::Sqlite),
+            _ => None,
+        }
+    }
+
+    /// 后端名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageBackend::Json => "json",
+            StorageBackend::Jsonl => "jsonl",
+            StorageBackend::Sqlite => "sqlite",
+        }
+    }
+
+    /// 该后端在数据目录中的默认文件名
+    pub fn default_file_name(&self) -> &'static str {
+        match self {
+            StorageBackend::Json => "activities.json",
+            StorageBackend::Jsonl => "activities.jsonl",
+            StorageBackend::Sqlite => "activities.db",
+        }
+    }
+}
+
+/// 根据数据文件扩展名打开对应的存储后端
+///
+/// SQLite 数据库首次创建时，如果同目录下存在同名的 JSON 数据文件，会一次性导入其中的记录。
+pub fn open_store<P: AsRef<Path>>(path: P) -> Result<Box<dyn ActivityStore>> {
+    open_store_with(path, None)
+}
+
+/// 打开存储后端，提供口令时使用加密存储
+///
+/// 已加密的数据文件必须提供口令；未加密的 JSON 文件在提供口令后会在首次加载时加密。
+pub fn open_store_with<P: AsRef<Path>>(
+    path: P,
+    passphrase: Option<&Passphrase>,
+) -> Result<Box<dyn ActivityStore>> {
+    open_store_with_options(
+        path,
+        &StoreOptions {
+            passphrase: passphrase.cloned(),
+            ..StoreOptions::default()
+        },
+    )
+}
+
+/// 按选项打开存储后端
+///
+/// JSON 后端启用分区时打开 `PartitionedStore`；关闭分区而数据已经分区时，
+/// 先把全部分区合并回主数据文件。
+pub fn open_store_with_options<P: AsRef<Path>>(
+    path: P,
+    options: &StoreOptions,
+) -> Result<Box<dyn ActivityStore>> {
+    let path = path.as_ref();
+    let backend = StorageBackend::from_path(path);
+    let passphrase = options.passphrase.as_ref();
+
+    if passphrase.is_some() && backend != StorageBackend::Json {
+        return Err(anyhow::anyhow!(
+            "加密存储目前仅支持 JSON 后端: {}",
+            path.display()
+        ));
+    }
+    if backend != StorageBackend::Json
+        && options.partitioning.is_some_and(|p| p != Partitioning::Off)
+    {
+        log::warn!("按日期分区目前仅支持 JSON 后端，已忽略: {}", path.display());
+    }
+
+    match backend {
+        StorageBackend::Json => {
+            let partitioning = options
+                .partitioning
+                .unwrap_or_else(|| partition::detect_partitioning(path));
+            if partitioning != Partitioning::Off {
+                return Ok(Box::new(PartitionedStore::open(
+                    path,
+                    partitioning,
+                    options.day_boundary,
+                    passphrase,
+                )?));
+            }
+
+            if partition::has_partitions(path) {
+                let merged = PartitionedStore::open(
+                    path,
+                    Partitioning::Month,
+                    options.day_boundary,
+                    passphrase,
+                )?
+                .consolidate()?;
+                log::info!(
+                    "已关闭按日期分区，{} 条记录合并回 {}",
+                    merged,
+                    path.display()
+                );
+            }
+
+            match passphrase {
+                Some(passphrase) => Ok(Box::new(JsonFileStore::open_encrypted(path, passphrase)?)),
+                None if crypto::is_encrypted(path) => Err(anyhow::anyhow!(
+                    "数据文件 {} 已加密，需要提供口令",
+                    path.display()
+                )),
+                None => Ok(Box::new(JsonFileStore::new(path))),
+            }
+        }
+        StorageBackend::Jsonl => Ok(Box::new(JsonlStore::new(path))),
+        StorageBackend::Sqlite => {
+            let is_new = !path.exists();
+            let mut store = SqliteStore::open(path)?;
+
+            let json_path = legacy_json_path(path);
+            if is_new && json_path.exists() {
+                let imported = store.import_json(&json_path)?;
+                log::info!(
+                    "已从 {} 导入 {} 条活动记录到 SQLite",
+                    json_path.display(),
+                    imported
+                );
+            }
+
+            Ok(Box::new(store))
+        }
+    }
+}
+
+/// 根据存储配置解析数据目录中的数据文件路径
+pub fn resolve_data_file(data_dir: &Path, config: &StorageConfig) -> PathBuf {
+    let backend = StorageBackend::from_name(&config.backend).unwrap_or(StorageBackend::Json);
+    data_dir.join(backend.default_file_name())
+}
+
+/// SQLite 数据库旁边的旧 JSON 数据文件（用于一次性迁移）
+pub fn legacy_json_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("json")
+}
+
+/// 原子写入文件：先写入同目录下的临时文件并 fsync，再重命名覆盖目标文件
+///
+/// 写入过程中崩溃时，目标文件要么是旧内容，要么是新内容，不会被截断。
+pub fn atomic_write<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("无效的文件路径: {}", path.display()))?;
+    let mut tmp_name = file_name.to_os_string();
+    tmp_name.push(format!(".tmp-{}", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)?;
+
+    // 同步目录项，确保重命名本身落盘
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let dir = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,615 @@
+// 按日期分区存储 - 每天或每月一个 JSON 数据文件，过期分区按保留策略归档或删除
+
+use super::crypto::{self, Cipher, Passphrase};
+use super::json::{apply_staged, staged_path, JsonFileStore};
+use super::rollup::RollupReport;
+use super::{atomic_write, ActivityStore};
+use crate::core::tracker::{ActivityRecord, DailyRollup, TimeTrackerData};
+use crate::utils::time::DayBoundary;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Months, NaiveDate, Utc};
+use flate2::{write::GzEncoder, Compression};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 分区粒度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Partitioning {
+    /// 不分区，全部记录保存在一个数据文件中
+    #[default]
+    Off,
+    /// 每天一个数据文件
+    Day,
+    /// 每月一个数据文件
+    Month,
+}
+
+impl Partitioning {
+    /// 根据配置名称解析分区粒度
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" | "off" => Some(Partitioning::Off),
+            "day" | "daily" => Some(Partitioning::Day),
+            "month" | "monthly" => Some(Partitioning::Month),
+            _ => None,
+        }
+    }
+
+    /// 配置名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Partitioning::Off => "none",
+            Partitioning::Day => "day",
+            Partitioning::Month => "month",
+        }
+    }
+
+    /// 记录所属分区的键（按 `boundary` 的逻辑日期，例如 `2024-05` 或 `2024-05-01`）
+    pub fn key(&self, time: DateTime<Utc>, boundary: DayBoundary) -> String {
+        let date = boundary.logical_date(time);
+        match self {
+            Partitioning::Day => date.format("%Y-%m-%d").to_string(),
+            Partitioning::Off | Partitioning::Month => date.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// 过期分区的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionAction {
+    /// 压缩为 `archive/<分区>.json.gz` 后从分区目录中删除
+    Archive,
+    /// 直接删除
+    Delete,
+}
+
+/// 数据保留策略：只保留最近 `keep_days` 天的分区
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub keep_days: u32,
+    pub action: RetentionAction,
+}
+
+/// 执行保留策略的结果
+#[derive(Debug, Clone, Default)]
+pub struct RetentionReport {
+    /// 已归档的分区及其归档文件
+    pub archived: Vec<(String, PathBuf)>,
+    /// 已删除的分区
+    pub deleted: Vec<String>,
+}
+
+impl RetentionReport {
+    /// 是否没有处理任何分区
+    pub fn is_empty(&self) -> bool {
+        self.archived.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// 按日期分区的 JSON 存储
+///
+/// 历史记录按开始时间（本地日期）写入分区目录中的 `<分区>.json`，每个分区都是一个
+/// 独立的 `JsonFileStore`，各自带有日志和文件锁，旧记录的每日汇总也保存在所属分区中。
+/// 主数据文件只保存当前活动、加密信息和启用分区前生成的每日汇总，
+/// 启用分区前写入主数据文件的记录会在首次访问时移入对应分区。
+/// 按时间范围查询时只读取与该范围相交的分区。
+pub struct PartitionedStore {
+    path: PathBuf,
+    dir: PathBuf,
+    granularity: Partitioning,
+    /// 划分分区日期使用的时区和日分割点
+    boundary: DayBoundary,
+    main: JsonFileStore,
+    partitions: BTreeMap<String, JsonFileStore>,
+    cipher: Option<Arc<Cipher>>,
+}
+
+impl PartitionedStore {
+    /// 打开分区存储，提供口令时使用加密存储（各分区共用主数据文件的密钥）
+    ///
+    /// 记录按 `boundary` 的逻辑日期放入分区，保留策略也按它判断分区是否过期。
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        granularity: Partitioning,
+        boundary: DayBoundary,
+        passphrase: Option<&Passphrase>,
+    ) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if granularity == Partitioning::Off {
+            return Err(anyhow::anyhow!("分区存储需要指定按天或按月分区"));
+        }
+
+        let main = match passphrase {
+            Some(passphrase) => JsonFileStore::open_encrypted(&path, passphrase)?,
+            None if crypto::is_encrypted(&path) => {
+                return Err(anyhow::anyhow!(
+                    "数据文件 {} 已加密，需要提供口令",
+                    path.display()
+                ))
+            }
+            None => JsonFileStore::new(&path),
+        };
+
+        let dir = partition_dir(&path);
+        fs::create_dir_all(&dir).with_context(|| format!("无法创建分区目录: {}", dir.display()))?;
+
+        let mut store = Self {
+            cipher: main.cipher(),
+            path,
+            dir,
+            granularity,
+            boundary,
+            main,
+            partitions: BTreeMap::new(),
+        };
+        store.recover_rotation()?;
+        Ok(store)
+    }
+
+    /// 主数据文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 分区目录
+    pub fn partition_dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 新记录使用的分区粒度
+    pub fn granularity(&self) -> Partitioning {
+        self.granularity
+    }
+
+    /// 是否为加密存储
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// 已有分区的键（按时间排序）
+    pub fn partitions(&self) -> Result<Vec<String>> {
+        list_partitions(&self.path)
+    }
+
+    /// 关闭分区：把全部分区的记录合并回主数据文件并删除分区文件，返回合并的记录数
+    pub fn consolidate(mut self) -> Result<usize> {
+        self.absorb_main()?;
+        let keys = self.partitions()?;
+        let (activities, rollups) = self.collect(&keys)?;
+        self.main.replace_with_rollups(&activities, &rollups)?;
+
+        for key in self.partitions()? {
+            self.take_partition(&key).remove_files(|_| Ok(()))?;
+        }
+        // 目录中仍有归档时保留
+        let _ = fs::remove_dir(&self.dir);
+
+        Ok(activities.len())
+    }
+
+    /// 更换加密口令：主数据文件和全部分区用新密钥重新加密
+    ///
+    /// 先把新快照写入暂存文件，再切换密钥信息，最后替换旧文件；中途中断时，
+    /// 下次打开会根据密钥信息是否已切换完成替换或丢弃暂存文件。
+    /// 已归档的分区仍使用归档时的密钥。
+    pub fn rotate_key(&mut self, passphrase: &Passphrase) -> Result<()> {
+        if self.cipher.is_none() {
+            return Err(anyhow::anyhow!("数据文件未加密: {}", self.path.display()));
+        }
+        self.absorb_main()?;
+
+        let cipher = Cipher::generate(passphrase)?;
+        for key in self.partitions()? {
+            self.partition(&key).stage_snapshot(&cipher)?;
+        }
+        // 主数据文件最后暂存：恢复时以它能否用当前密钥解密判断密钥信息是否已切换
+        self.main.stage_snapshot(&cipher)?;
+        crypto::write_keycheck(&self.path, &cipher)?;
+
+        let cipher = Arc::new(cipher);
+        self.partitions.clear();
+        self.main = JsonFileStore::with_cipher(&self.path, Some(cipher.clone()));
+        self.cipher = Some(cipher);
+        self.apply_staged_files()
+    }
+
+    /// 取消加密：以明文重写全部分区和主数据文件
+    ///
+    /// 主数据文件最后处理，中途中断时密钥信息仍在，重新运行即可完成。
+    pub fn remove_encryption(&mut self) -> Result<()> {
+        self.absorb_main()?;
+        for key in self.partitions()? {
+            self.partition(&key).remove_encryption()?;
+        }
+        self.main.remove_encryption()?;
+        self.cipher = None;
+        Ok(())
+    }
+
+    /// 打开（或创建）指定分区
+    fn partition(&mut self, key: &str) -> &mut JsonFileStore {
+        let path = self.dir.join(format!("{key}.json"));
+        let cipher = self.cipher.clone();
+        self.partitions
+            .entry(key.to_string())
+            .or_insert_with(|| JsonFileStore::with_cipher(path, cipher))
+    }
+
+    /// 从已打开的分区中取出指定分区（未打开时新建）
+    fn take_partition(&mut self, key: &str) -> JsonFileStore {
+        match self.partitions.remove(key) {
+            Some(store) => store,
+            None => JsonFileStore::with_cipher(
+                self.dir.join(format!("{key}.json")),
+                self.cipher.clone(),
+            ),
+        }
+    }
+
+    /// 把主数据文件中的历史记录移入分区
+    ///
+    /// 这些记录来自启用分区之前，或来自尚未按新配置重启的其他进程。
+    fn absorb_main(&mut self) -> Result<()> {
+        let records = self.main.read()?.activities;
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut groups: BTreeMap<String, Vec<ActivityRecord>> = BTreeMap::new();
+        for record in &records {
+            groups
+                .entry(self.granularity.key(record.start_time, self.boundary))
+                .or_default()
+                .push(record.clone());
+        }
+        // 按 id 合并，中途中断后重新执行不会产生重复记录
+        for (key, group) in groups {
+            self.partition(&key).merge_records(&group)?;
+        }
+
+        let ids: Vec<String> = records.iter().map(|r| r.id.clone()).collect();
+        self.main.delete(&ids)?;
+        self.main.compact()?;
+        log::info!(
+            "已将 {} 条记录移入分区目录 {}",
+            records.len(),
+            self.dir.display()
+        );
+        Ok(())
+    }
+
+    /// 与 [start, end) 有交集的分区
+    fn partitions_in(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<String>> {
+        Ok(self
+            .partitions()?
+            .into_iter()
+            .filter(|key| {
+                partition_span(key, self.boundary).is_some_and(|(s, e)| s < end && e > start)
+            })
+            .collect())
+    }
+
+    /// 读取指定分区中的全部记录和每日汇总
+    fn collect(&mut self, keys: &[String]) -> Result<(Vec<ActivityRecord>, Vec<DailyRollup>)> {
+        let mut records = Vec::new();
+        let mut rollups = Vec::new();
+        for key in keys {
+            let data = self.partition(key).read()?;
+            records.extend(data.activities);
+            rollups.extend(data.rollups);
+        }
+        Ok((records, rollups))
+    }
+
+    /// 全部分区和主数据文件的路径（主数据文件在最后）
+    fn data_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = self
+            .partitions()?
+            .iter()
+            .map(|key| self.dir.join(format!("{key}.json")))
+            .collect();
+        files.push(self.path.clone());
+        Ok(files)
+    }
+
+    /// 用暂存快照替换旧文件，主数据文件最后替换
+    fn apply_staged_files(&mut self) -> Result<()> {
+        for file in self.data_files()? {
+            apply_staged(&file)?;
+        }
+        Ok(())
+    }
+
+    /// 处理上次中断的更换密钥：密钥信息已切换时完成替换，否则删除暂存文件
+    fn recover_rotation(&mut self) -> Result<()> {
+        let Some(cipher) = self.cipher.clone() else {
+            return Ok(());
+        };
+
+        let switched = fs::read(staged_path(&self.path))
+            .is_ok_and(|bytes| cipher.decrypt_file(&bytes).is_ok());
+        if switched {
+            log::warn!("完成上次中断的更换密钥: {}", self.path.display());
+            return self.apply_staged_files();
+        }
+
+        for file in self.data_files()? {
+            let staged = staged_path(&file);
+            if staged.exists() {
+                fs::remove_file(staged)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ActivityStore for PartitionedStore {
+    fn name(&self) -> &str {
+        "partitioned-json"
+    }
+
+    fn load(&mut self) -> Result<TimeTrackerData> {
+        self.absorb_main()?;
+        let keys = self.partitions()?;
+        let (activities, rollups) = self.collect(&keys)?;
+        let mut main = self.main.read()?;
+        main.rollups.extend(rollups);
+        Ok(TimeTrackerData { activities, ..main })
+    }
+
+    fn append(&mut self, record: &ActivityRecord) -> Result<()> {
+        let key = self.granularity.key(record.start_time, self.boundary);
+        self.partition(&key).append(record)
+    }
+
+    fn query_range(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ActivityRecord>> {
+        self.absorb_main()?;
+        let keys = self.partitions_in(start, end)?;
+        let mut records: Vec<ActivityRecord> = self
+            .collect(&keys)?
+            .0
+            .into_iter()
+            .filter(|r| r.start_time >= start && r.start_time < end)
+            .collect();
+        records.sort_by_key(|r| r.start_time);
+        Ok(records)
+    }
+
+    fn query_rollups(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<DailyRollup>> {
+        self.absorb_main()?;
+        let keys = self.partitions_in(start, end)?;
+        let mut rollups = self.main.read()?.rollups;
+        rollups.extend(self.collect(&keys)?.1);
+        rollups.retain(|r| r.first_seen >= start && r.first_seen < end);
+        Ok(rollups)
+    }
+
+    fn rollup(&mut self, before: DateTime<Utc>, boundary: DayBoundary) -> Result<RollupReport> {
+        self.absorb_main()?;
+        let keys = self.partitions_in(DateTime::<Utc>::MIN_UTC, before)?;
+        let mut report = RollupReport::default();
+        for key in keys {
+            report.add(self.partition(&key).rollup(before, boundary)?);
+        }
+        Ok(report)
+    }
+
+    fn update(&mut self, record: &ActivityRecord) -> Result<bool> {
+        self.absorb_main()?;
+        let key = self.granularity.key(record.start_time, self.boundary);
+        if self.partition(&key).update(record)? {
+            return Ok(true);
+        }
+
+        // 开始时间被修改，或记录写入时使用的是另一种分区粒度
+        for other in self.partitions()? {
+            if other != key
+                && self
+                    .partition(&other)
+                    .delete(std::slice::from_ref(&record.id))?
+                    > 0
+            {
+                self.partition(&key).append(record)?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn delete(&mut self, ids: &[String]) -> Result<usize> {
+        self.absorb_main()?;
+        let mut deleted = 0;
+        for key in self.partitions()? {
+            deleted += self.partition(&key).delete(ids)?;
+        }
+        Ok(deleted)
+    }
+
+    fn replace_all(&mut self, records: &[ActivityRecord]) -> Result<()> {
+        self.absorb_main()?;
+
+        // 已有分区中不再包含任何记录的也要清空
+        let mut groups: BTreeMap<String, Vec<ActivityRecord>> = self
+            .partitions()?
+            .into_iter()
+            .map(|key| (key, Vec::new()))
+            .collect();
+        for record in records {
+            groups
+                .entry(self.granularity.key(record.start_time, self.boundary))
+                .or_default()
+                .push(record.clone());
+        }
+
+        for (key, group) in groups {
+            self.partition(&key).replace_all(&group)?;
+        }
+        Ok(())
+    }
+
+    fn set_current(&mut self, current: Option<&ActivityRecord>) -> Result<()> {
+        self.main.set_current(current)
+    }
+
+    fn current(&mut self) -> Result<Option<ActivityRecord>> {
+        self.main.current()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.main.flush()?;
+        for store in self.partitions.values_mut() {
+            store.flush()?;
+        }
+        Ok(())
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        let mut warnings = self.main.take_warnings();
+        for store in self.partitions.values_mut() {
+            warnings.extend(store.take_warnings());
+        }
+        warnings
+    }
+
+    fn apply_retention(
+        &mut self,
+        policy: &RetentionPolicy,
+        now: DateTime<Utc>,
+    ) -> Result<RetentionReport> {
+        self.absorb_main()?;
+        let cutoff = now - Duration::days(policy.keep_days as i64);
+        let archive_dir = self.dir.join("archive");
+        let mut report = RetentionReport::default();
+
+        for key in self.partitions()? {
+            // 只处理整个时间段都已过期的分区
+            if partition_span(&key, self.boundary).is_none_or(|(_, end)| end > cutoff) {
+                continue;
+            }
+
+            let store = self.take_partition(&key);
+            match policy.action {
+                RetentionAction::Archive => {
+                    let mut archive = PathBuf::new();
+                    store.remove_files(|contents| {
+                        archive = write_archive(&archive_dir, &key, contents)?;
+                        Ok(())
+                    })?;
+                    log::info!("分区 {} 已归档到 {}", key, archive.display());
+                    report.archived.push((key, archive));
+                }
+                RetentionAction::Delete => {
+                    store.remove_files(|_| Ok(()))?;
+                    log::info!("分区 {} 已按保留策略删除", key);
+                    report.deleted.push(key);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// 数据文件对应的分区目录（`activities.json` → `activities/`）
+pub fn partition_dir(path: &Path) -> PathBuf {
+    let dir = path.with_extension("");
+    if dir == path {
+        let mut dir = path.to_path_buf().into_os_string();
+        dir.push(".partitions");
+        return PathBuf::from(dir);
+    }
+    dir
+}
+
+/// 分区覆盖的时间区间 [start, end)（按 `boundary` 的逻辑日），无法识别的键返回 `None`
+pub fn partition_span(key: &str, boundary: DayBoundary) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (start, end) = partition_dates(key)?;
+    Some((boundary.day_start(start), boundary.day_start(end)))
+}
+
+/// 分区覆盖的日期区间 [start, end)
+fn partition_dates(key: &str) -> Option<(NaiveDate, NaiveDate)> {
+    match key.len() {
+        10 => {
+            let day = NaiveDate::parse_from_str(key, "%Y-%m-%d").ok()?;
+            Some((day, day.succ_opt()?))
+        }
+        7 => {
+            let first = NaiveDate::parse_from_str(&format!("{key}-01"), "%Y-%m-%d").ok()?;
+            Some((first, first.checked_add_months(Months::new(1))?))
+        }
+        _ => None,
+    }
+}
+
+/// 分区目录中已有的分区键（按时间排序）
+///
+/// 只有日志、尚未写入快照的分区同样会被列出。
+pub fn list_partitions(path: &Path) -> Result<Vec<String>> {
+    let dir = partition_dir(path);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut keys = BTreeSet::new();
+    for entry in fs::read_dir(&dir)? {
+        let name = entry?.file_name();
+        let Some((key, suffix)) = name.to_str().and_then(|n| n.split_once(".json")) else {
+            continue;
+        };
+        // 只看快照和日志，锁文件、备份和隔离副本不算分区
+        let is_data = matches!(suffix, "" | ".journal" | ".journal.old");
+        if is_data && partition_dates(key).is_some() {
+            keys.insert(key.to_string());
+        }
+    }
+    Ok(keys.into_iter().collect())
+}
+
+/// 数据文件是否已经按日期分区
+pub fn has_partitions(path: &Path) -> bool {
+    list_partitions(path).is_ok_and(|keys| !keys.is_empty())
+}
+
+/// 根据已有分区判断分区粒度（以最新的分区为准），没有分区时返回 `Off`
+pub fn detect_partitioning(path: &Path) -> Partitioning {
+    match list_partitions(path)
+        .ok()
+        .and_then(|keys| keys.last().cloned())
+    {
+        Some(key) if key.len() == 10 => Partitioning::Day,
+        Some(_) => Partitioning::Month,
+        None => Partitioning::Off,
+    }
+}
+
+/// 把分区快照压缩写入归档目录，返回归档文件路径
+///
+/// 归档保存快照的原始内容：加密存储的归档仍是密文，需要用归档时的口令解密。
+fn write_archive(archive_dir: &Path, key: &str, contents: &[u8]) -> Result<PathBuf> {
+    fs::create_dir_all(archive_dir)
+        .with_context(|| format!("无法创建归档目录: {}", archive_dir.display()))?;
+
+    let mut archive = archive_dir.join(format!("{key}.json.gz"));
+    let mut n = 1;
+    while archive.exists() {
+        archive = archive_dir.join(format!("{key}.{n}.json.gz"));
+        n += 1;
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(contents)?;
+    atomic_write(&archive, &encoder.finish()?)?;
+    Ok(archive)
+}
@@ -0,0 +1,169 @@
+// 数据修复 - 隔离损坏的数据文件，并从中尽可能恢复活动记录
+
+use crate::core::tracker::{ActivityRecord, TimeTrackerData};
+use anyhow::Result;
+use chrono::Local;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 从损坏文件中恢复记录的结果
+#[derive(Debug, Default)]
+pub struct SalvageReport {
+    /// 恢复出的活动记录（按开始时间排序，已去重）
+    pub records: Vec<ActivityRecord>,
+    /// 文件是否可以完整解析（无需修复）
+    pub intact: bool,
+    /// 看起来像活动记录但无法解析的片段数
+    pub skipped: usize,
+}
+
+/// 将损坏的数据文件移动到带时间戳的隔离副本 `<文件名>.corrupt-YYYYmmdd-HHMMSS`
+pub fn quarantine_file<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+
+    let mut target = quarantine_path(path, &stamp.to_string(), 0);
+    let mut attempt = 0;
+    while target.exists() {
+        attempt += 1;
+        target = quarantine_path(path, &stamp.to_string(), attempt);
+    }
+
+    std::fs::rename(path, &target)?;
+    Ok(target)
+}
+
+fn quarantine_path(path: &Path, stamp: &str, attempt: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".corrupt-{stamp}"));
+    if attempt > 0 {
+        name.push(format!("-{attempt}"));
+    }
+    PathBuf::from(name)
+}
+
+/// 从（可能损坏的）文件内容中恢复活动记录
+///
+/// 支持当前的 `TimeTrackerData` 格式、旧的纯数组格式以及 JSONL。
+/// 无法整体解析时，逐个扫描 JSON 对象，保留所有能解析为 `ActivityRecord` 的部分。
+/// 未结束的当前活动按已记录的时长收尾后一并恢复。
+pub fn salvage_records(content: &str) -> SalvageReport {
+    if let Ok(data) = serde_json::from_str::<TimeTrackerData>(content) {
+        return SalvageReport {
+            records: finish_all(data.activities, data.current_activity),
+            intact: true,
+            skipped: 0,
+        };
+    }
+    if let Ok(activities) = serde_json::from_str::<Vec<ActivityRecord>>(content) {
+        return SalvageReport {
+            records: finish_all(activities, None),
+            intact: true,
+            skipped: 0,
+        };
+    }
+
+    let mut report = SalvageReport::default();
+    let mut records = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = content[pos..].find('{') {
+        let start = pos + offset;
+        let mut stream = serde_json::Deserializer::from_str(&content[start..]).into_iter::<Value>();
+
+        if let Some(Ok(value)) = stream.next() {
+            let end = start + stream.byte_offset();
+            if collect_value(value, &mut records, &mut report.skipped) {
+                pos = end;
+                continue;
+            }
+        }
+        pos = start + 1;
+    }
+
+    report.records = finish_all(records, None);
+    report
+}
+
+/// 识别一个完整的 JSON 对象，返回是否已处理（处理过的对象不再扫描其内部）
+fn collect_value(value: Value, records: &mut Vec<ActivityRecord>, skipped: &mut usize) -> bool {
+    let Value::Object(map) = &value else {
+        return false;
+    };
+
+    // 完整的 TimeTrackerData 对象（例如文件末尾有多余内容）
+    if let Some(Value::Array(activities)) = map.get("activities") {
+        for item in activities {
+            match serde_json::from_value::<ActivityRecord>(item.clone()) {
+                Ok(record) => records.push(record),
+                Err(_) => *skipped += 1,
+            }
+        }
+        if let Some(current) = map.get("current_activity") {
+            if let Ok(record) = serde_json::from_value::<ActivityRecord>(current.clone()) {
+                records.push(record);
+            }
+        }
+        return true;
+    }
+
+    if map.contains_key("app_name") && map.contains_key("start_time") {
+        match serde_json::from_value::<ActivityRecord>(value) {
+            Ok(record) => records.push(record),
+            Err(_) => *skipped += 1,
+        }
+        return true;
+    }
+
+    false
+}
+
+/// 为未结束的记录补上结束时间，并按开始时间排序去重
+fn finish_all(
+    mut records: Vec<ActivityRecord>,
+    current: Option<ActivityRecord>,
+) -> Vec<ActivityRecord> {
+    records.extend(current);
+    for record in &mut records {
+        if record.end_time.is_none() {
+            record.end_time =
+                Some(record.start_time + chrono::Duration::seconds(record.duration as i64));
+        }
+    }
+
+    let mut seen_ids = HashSet::new();
+    let mut seen_keys = HashSet::new();
+    records.retain(|r| {
+        seen_ids.insert(r.id.clone())
+            && seen_keys.insert((r.app_name.clone(), r.window_title.clone(), r.start_time))
+    });
+    records.sort_by_key(|r| r.start_time);
+    records
+}
+
+/// 将恢复出的记录合并到已有记录中，跳过重复记录，返回新增条数
+pub fn merge_records(existing: &mut Vec<ActivityRecord>, salvaged: Vec<ActivityRecord>) -> usize {
+    let ids: HashSet<String> = existing.iter().map(|r| r.id.clone()).collect();
+    let keys: HashSet<(String, String, chrono::DateTime<chrono::Utc>)> = existing
+        .iter()
+        .map(|r| (r.app_name.clone(), r.window_title.clone(), r.start_time))
+        .collect();
+
+    let mut added = 0;
+    for record in salvaged {
+        let key = (
+            record.app_name.clone(),
+            record.window_title.clone(),
+            record.start_time,
+        );
+        if ids.contains(&record.id) || keys.contains(&key) {
+            continue;
+        }
+        existing.push(record);
+        added += 1;
+    }
+
+    existing.sort_by_key(|r| r.start_time);
+    added
+}
@@ -0,0 +1,123 @@
+// 每日汇总 - 把旧的原始记录合并为按天、按应用窗口的汇总
+
+use super::ActivityStore;
+use crate::core::tracker::{ActivityRecord, DailyRollup, TimeTrackerData};
+use crate::utils::time::DayBoundary;
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+
+/// 汇总结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollupReport {
+    /// 被汇总（从历史记录中移除）的原始记录数
+    pub records: usize,
+    /// 新增或更新的汇总条数
+    pub rollups: usize,
+}
+
+impl RollupReport {
+    /// 是否没有汇总任何记录
+    pub fn is_empty(&self) -> bool {
+        self.records == 0
+    }
+
+    /// 累加另一份结果（例如各个分区的结果）
+    pub fn add(&mut self, other: RollupReport) {
+        self.records += other.records;
+        self.rollups += other.rollups;
+    }
+}
+
+/// 汇总截止时间：`after_days` 天前的逻辑日开始时间，只汇总完整的逻辑日
+pub fn rollup_cutoff(now: DateTime<Utc>, after_days: u32, boundary: DayBoundary) -> DateTime<Utc> {
+    let date = boundary.logical_date(now) - Duration::days(after_days as i64);
+    boundary.day_start(date)
+}
+
+/// 把记录按（逻辑日期, 应用, 窗口）汇总
+pub fn build_rollups(records: &[ActivityRecord], boundary: DayBoundary) -> Vec<DailyRollup> {
+    let mut rollups = Vec::new();
+    merge_rollups(
+        &mut rollups,
+        records
+            .iter()
+            .map(|r| DailyRollup::from_record(r, boundary)),
+    );
+    rollups
+}
+
+/// 把汇总合并到已有汇总中（同一天同一应用窗口的相加），返回新增或更新的汇总条数
+///
+/// 合并后按日期排序。
+pub fn merge_rollups(
+    existing: &mut Vec<DailyRollup>,
+    new: impl IntoIterator<Item = DailyRollup>,
+) -> usize {
+    let mut index: HashMap<(NaiveDate, String, String), usize> = existing
+        .iter()
+        .enumerate()
+        .map(|(i, r)| ((r.date, r.app_name.clone(), r.window_title.clone()), i))
+        .collect();
+    let mut touched = HashSet::new();
+
+    for rollup in new {
+        let key = (
+            rollup.date,
+            rollup.app_name.clone(),
+            rollup.window_title.clone(),
+        );
+        let i = match index.get(&key) {
+            Some(&i) => {
+                existing[i].absorb(&rollup);
+                i
+            }
+            None => {
+                existing.push(rollup);
+                index.insert(key, existing.len() - 1);
+                existing.len() - 1
+            }
+        };
+        touched.insert(i);
+    }
+
+    existing.sort_by(|a, b| {
+        (a.date, &a.app_name, &a.window_title).cmp(&(b.date, &b.app_name, &b.window_title))
+    });
+    touched.len()
+}
+
+/// 把开始时间早于 `before` 的历史记录合并进汇总（内存中的数据）
+pub fn rollup_data(
+    data: &mut TimeTrackerData,
+    before: DateTime<Utc>,
+    boundary: DayBoundary,
+) -> RollupReport {
+    let (old, recent): (Vec<ActivityRecord>, Vec<ActivityRecord>) =
+        std::mem::take(&mut data.activities)
+            .into_iter()
+            .partition(|r| r.start_time < before);
+    data.activities = recent;
+
+    let rollups = merge_rollups(
+        &mut data.rollups,
+        old.iter().map(|r| DailyRollup::from_record(r, boundary)),
+    );
+    RollupReport {
+        records: old.len(),
+        rollups,
+    }
+}
+
+/// 预览汇总结果，不修改存储
+pub fn preview_rollup(
+    store: &mut dyn ActivityStore,
+    before: DateTime<Utc>,
+    boundary: DayBoundary,
+) -> Result<RollupReport> {
+    let records = store.query_range(DateTime::<Utc>::MIN_UTC, before)?;
+    Ok(RollupReport {
+        records: records.len(),
+        rollups: build_rollups(&records, boundary).len(),
+    })
+}
@@ -0,0 +1,599 @@
+// SQLite 存储后端 - 按 start_time、app_name、window_title 建立索引
+
+use super::json::journal_path;
+use super::migration::parse_data;
+use super::rollup::{build_rollups, RollupReport};
+use super::{ActivityStore, Journal};
+use crate::core::tracker::{
+    new_record_id, ActivityRecord, DailyRollup, RecordSource, TimeTrackerData, WindowGeometry,
+};
+use crate::utils::time::DayBoundary;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use std::path::Path;
+use std::time::Duration;
+
+/// 当前数据库结构版本（保存在 PRAGMA user_version 中）
+pub const SCHEMA_VERSION: i32 = 7;
+
+/// 各结构版本的升级说明（与 `init_schema` 中的步骤对应）
+const SCHEMA_CHANGES: &[(i32, &str)] = &[
+    (
+        1,
+        "创建 activities、meta 表以及 start_time/app_name/window_title 索引",
+    ),
+    (2, "新增 record_id 列并为已有记录生成唯一标识"),
+    (3, "新增 daily_rollups 每日汇总表"),
+    (4, "新增 source 列记录来源（自动记录、启动时恢复）"),
+    (5, "新增 raw_title 列保存规范化前的窗口标题"),
+    (6, "新增 project 列保存手动记录所属的项目"),
+    (7, "新增 fullscreen 列记录窗口是否全屏"),
+];
+
+const SELECT_COLUMNS: &str =
+    "record_id, app_name, window_title, start_time, end_time, duration_seconds, \
+     process_id, app_path, bundle_id, window_geometry, confidence, source, raw_title, \
+     project, fullscreen";
+
+const ROLLUP_COLUMNS: &str =
+    "date, app_name, window_title, total_duration, record_count, first_seen, last_seen";
+
+/// 基于 SQLite 的活动记录存储
+///
+/// 多进程并发写入由 SQLite 自身的事务和 WAL 模式保证，遇到锁冲突时最多等待 5 秒。
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    /// 打开（必要时创建）数据库
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("无法打开 SQLite 数据库: {}", path.display()))?;
+        Self::from_connection(conn)
+    }
+
+    /// 以只读方式读取数据库的结构版本，不执行升级
+    pub fn read_schema_version<P: AsRef<Path>>(path: P) -> Result<i32> {
+        let path = path.as_ref();
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("无法打开 SQLite 数据库: {}", path.display()))?;
+        Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    /// 从给定版本升级到当前版本需要执行的步骤说明
+    pub fn pending_schema_changes(version: i32) -> Vec<(i32, &'static str)> {
+        SCHEMA_CHANGES
+            .iter()
+            .filter(|(to, _)| *to > version)
+            .copied()
+            .collect()
+    }
+
+    /// 打开内存数据库（主要用于测试）
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        // TUI 与守护进程可能同时访问数据库
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+
+        let store = Self { conn };
+        store.init_schema()?;
+        Ok(store)
+    }
+
+    fn init_schema(&self) -> Result<()> {
+        let version: i32 = self
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        if version > SCHEMA_VERSION {
+            return Err(anyhow::anyhow!(
+                "数据库结构版本 {} 高于当前支持的版本 {}",
+                version,
+                SCHEMA_VERSION
+            ));
+        }
+
+        if version < 1 {
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS activities (
+                     id               INTEGER PRIMARY KEY AUTOINCREMENT,
+                     app_name         TEXT NOT NULL,
+                     window_title     TEXT NOT NULL,
+                     start_time       TEXT NOT NULL,
+                     end_time         TEXT,
+                     duration_seconds INTEGER NOT NULL DEFAULT 0,
+                     process_id       INTEGER NOT NULL DEFAULT 0,
+                     app_path         TEXT,
+                     bundle_id        TEXT,
+                     window_geometry  TEXT,
+                     confidence       REAL NOT NULL DEFAULT 0
+                 );
+                 CREATE INDEX IF NOT EXISTS idx_activities_start_time ON activities(start_time);
+                 CREATE INDEX IF NOT EXISTS idx_activities_app_name ON activities(app_name);
+                 CREATE INDEX IF NOT EXISTS idx_activities_window_title ON activities(window_title);
+                 CREATE TABLE IF NOT EXISTS meta (
+                     key   TEXT PRIMARY KEY,
+                     value TEXT NOT NULL
+                 );
+                 PRAGMA user_version = 1;",
+            )?;
+        }
+
+        if version < 2 {
+            // v2: 为每条记录增加稳定的 record_id，用于按 id 更新和删除
+            self.conn.execute_batch(
+                "ALTER TABLE activities ADD COLUMN record_id TEXT;
+                 UPDATE activities SET record_id = lower(hex(randomblob(16)))
+                     WHERE record_id IS NULL;
+                 CREATE UNIQUE INDEX IF NOT EXISTS idx_activities_record_id
+                     ON activities(record_id);
+                 PRAGMA user_version = 2;",
+            )?;
+        }
+
+        if version < 3 {
+            // v3: 旧记录按天汇总后保存在单独的表中
+            self.conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS daily_rollups (
+                     date           TEXT NOT NULL,
+                     app_name       TEXT NOT NULL,
+                     window_title   TEXT NOT NULL,
+                     total_duration INTEGER NOT NULL DEFAULT 0,
+                     record_count   INTEGER NOT NULL DEFAULT 0,
+                     first_seen     TEXT NOT NULL,
+                     last_seen      TEXT NOT NULL,
+                     PRIMARY KEY (date, app_name, window_title)
+                 );
+                 CREATE INDEX IF NOT EXISTS idx_daily_rollups_first_seen
+                     ON daily_rollups(first_seen);
+                 PRAGMA user_version = 3;",
+            )?;
+        }
+
+        if version < 4 {
+            // v4: 记录来源，已有记录都是自动记录
+            self.conn.execute_batch(
+                "ALTER TABLE activities ADD COLUMN source TEXT NOT NULL DEFAULT 'tracked';
+                 PRAGMA user_version = 4;",
+            )?;
+        }
+
+        if version < 5 {
+            // v5: 规范化前的原始窗口标题
+            self.conn.execute_batch(
+                "ALTER TABLE activities ADD COLUMN raw_title TEXT;
+                 PRAGMA user_version = 5;",
+            )?;
+        }
+
+        if version < 6 {
+            // v6: 手动记录所属的项目
+            self.conn.execute_batch(
+                "ALTER TABLE activities ADD COLUMN project TEXT;
+                 PRAGMA user_version = 6;",
+            )?;
+        }
+
+        if version < 7 {
+            // v7: 窗口是否全屏
+            self.conn.execute_batch(
+                "ALTER TABLE activities ADD COLUMN fullscreen INTEGER NOT NULL DEFAULT 0;
+                 PRAGMA user_version = 7;",
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// 数据库结构版本
+    pub fn schema_version(&self) -> Result<i32> {
+        Ok(self
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    /// 读取全部活动记录（按开始时间排序）
+    pub fn load_activities(&self) -> Result<Vec<ActivityRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM activities ORDER BY start_time, id"
+        ))?;
+        let records = stmt
+            .query_map([], row_to_record)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    /// 读取全部每日汇总（按日期排序）
+    pub fn load_rollups(&self) -> Result<Vec<DailyRollup>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ROLLUP_COLUMNS} FROM daily_rollups ORDER BY date, app_name, window_title"
+        ))?;
+        let rollups = stmt
+            .query_map([], row_to_rollup)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rollups)
+    }
+
+    /// 活动记录数量
+    pub fn count(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM activities", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// 保存当前活动与更新时间（每个监控周期调用，不重写历史记录）
+    pub fn save_current(
+        &self,
+        current: Option<&ActivityRecord>,
+        last_updated: DateTime<Utc>,
+    ) -> Result<()> {
+        match current {
+            Some(record) => self.set_meta("current_activity", &serde_json::to_string(record)?)?,
+            None => {
+                self.conn
+                    .execute("DELETE FROM meta WHERE key = 'current_activity'", [])?;
+            }
+        }
+        self.set_meta("last_updated", &last_updated.to_rfc3339())
+    }
+
+    /// 读取完整数据（历史记录 + 当前活动）
+    fn read_data(&self) -> Result<TimeTrackerData> {
+        let current_activity = self.read_current()?;
+        let last_updated = self
+            .get_meta("last_updated")?
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now);
+
+        Ok(TimeTrackerData {
+            activities: self.load_activities()?,
+            current_activity,
+            last_updated,
+            rollups: self.load_rollups()?,
+            ..TimeTrackerData::default()
+        })
+    }
+
+    /// 读取当前活动
+    fn read_current(&self) -> Result<Option<ActivityRecord>> {
+        Ok(match self.get_meta("current_activity")? {
+            Some(json) => serde_json::from_str(&json).ok(),
+            None => None,
+        })
+    }
+
+    /// 从 JSON 数据文件导入记录，返回导入的条数
+    ///
+    /// 支持当前的 `TimeTrackerData` 格式和旧的纯数组格式。
+    pub fn import_json<P: AsRef<Path>>(&mut self, json_path: P) -> Result<usize> {
+        let json_path = json_path.as_ref();
+        let content = std::fs::read_to_string(json_path)
+            .with_context(|| format!("无法读取 JSON 数据文件: {}", json_path.display()))?;
+
+        let mut data = if content.trim().is_empty() {
+            TimeTrackerData::default()
+        } else {
+            // 旧结构的文件先在内存中升级，不修改原文件
+            parse_data(&content)
+                .with_context(|| format!("无法解析 JSON 数据文件: {}", json_path.display()))?
+                .0
+        };
+
+        // 合并尚未压缩进快照的日志事件
+        for event in Journal::new(journal_path(json_path)).replay()? {
+            event.apply(&mut data);
+        }
+
+        let mut activities = data.activities;
+        // 未结束的当前活动按最后更新时间收尾后一并导入
+        if let Some(mut current) = data.current_activity {
+            current.finish_at(data.last_updated);
+            current.source = RecordSource::Recovered;
+            activities.push(current);
+        }
+
+        let tx = self.conn.transaction()?;
+        for record in &activities {
+            insert_record(&tx, record)?;
+        }
+        for rollup in &data.rollups {
+            upsert_rollup(&tx, rollup)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('imported_from', ?1)",
+            params![json_path.to_string_lossy()],
+        )?;
+        tx.commit()?;
+
+        Ok(activities.len())
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+}
+
+impl ActivityStore for SqliteStore {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    fn load(&mut self) -> Result<TimeTrackerData> {
+        self.read_data()
+    }
+
+    fn append(&mut self, record: &ActivityRecord) -> Result<()> {
+        insert_record(&self.conn, record)
+    }
+
+    fn query_range(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ActivityRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM activities
+             WHERE start_time >= ?1 AND start_time < ?2
+             ORDER BY start_time, id"
+        ))?;
+        let records = stmt
+            .query_map(params![start, end], row_to_record)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    fn query_overlapping(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ActivityRecord>> {
+        // 没有 end_time 的记录按 start_time + 时长判断
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SELECT_COLUMNS} FROM activities
+             WHERE start_time < ?2 AND (end_time IS NULL OR end_time > ?1)
+             ORDER BY start_time, id"
+        ))?;
+        let mut records = stmt
+            .query_map(params![start, end], row_to_record)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        records.retain(|r| r.effective_end() > start);
+        Ok(records)
+    }
+
+    fn update(&mut self, record: &ActivityRecord) -> Result<bool> {
+        let geometry = geometry_json(record)?;
+        let changed = self.conn.execute(
+            "UPDATE activities SET app_name = ?2, window_title = ?3, start_time = ?4,
+                 end_time = ?5, duration_seconds = ?6, process_id = ?7, app_path = ?8,
+                 bundle_id = ?9, window_geometry = ?10, confidence = ?11, source = ?12,
+                 raw_title = ?13, project = ?14, fullscreen = ?15
+             WHERE record_id = ?1",
+            params![
+                record.id,
+                record.app_name,
+                record.window_title,
+                record.start_time,
+                record.end_time,
+                record.duration as i64,
+                record.process_id,
+                record.app_path,
+                record.bundle_id,
+                geometry,
+                record.confidence as f64,
+                record.source.as_str(),
+                record.raw_title,
+                record.project,
+                record.fullscreen,
+            ],
+        )?;
+        Ok(changed > 0)
+    }
+
+    fn delete(&mut self, ids: &[String]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut deleted = 0;
+        for id in ids {
+            deleted += tx.execute("DELETE FROM activities WHERE record_id = ?1", params![id])?;
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    fn replace_all(&mut self, records: &[ActivityRecord]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM activities", [])?;
+        for record in records {
+            insert_record(&tx, record)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn set_current(&mut self, current: Option<&ActivityRecord>) -> Result<()> {
+        self.save_current(current, Utc::now())
+    }
+
+    fn current(&mut self) -> Result<Option<ActivityRecord>> {
+        self.read_current()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        // 每次修改都已直接写入数据库
+        Ok(())
+    }
+
+    fn query_rollups(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<DailyRollup>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ROLLUP_COLUMNS} FROM daily_rollups
+             WHERE first_seen >= ?1 AND first_seen < ?2
+             ORDER BY date, app_name, window_title"
+        ))?;
+        let rollups = stmt
+            .query_map(params![start, end], row_to_rollup)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rollups)
+    }
+
+    fn rollup(&mut self, before: DateTime<Utc>, boundary: DayBoundary) -> Result<RollupReport> {
+        let tx = self.conn.transaction()?;
+        let records = tx
+            .prepare(&format!(
+                "SELECT {SELECT_COLUMNS} FROM activities WHERE start_time < ?1"
+            ))?
+            .query_map(params![before], row_to_record)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if records.is_empty() {
+            return Ok(RollupReport::default());
+        }
+
+        let rollups = build_rollups(&records, boundary);
+        for rollup in &rollups {
+            upsert_rollup(&tx, rollup)?;
+        }
+        tx.execute(
+            "DELETE FROM activities WHERE start_time < ?1",
+            params![before],
+        )?;
+        tx.commit()?;
+
+        Ok(RollupReport {
+            records: records.len(),
+            rollups: rollups.len(),
+        })
+    }
+}
+
+fn geometry_json(record: &ActivityRecord) -> Result<Option<String>> {
+    Ok(match &record.window_geometry {
+        Some(geometry) => Some(serde_json::to_string(geometry)?),
+        None => None,
+    })
+}
+
+fn insert_record(conn: &Connection, record: &ActivityRecord) -> Result<()> {
+    let geometry = geometry_json(record)?;
+
+    conn.execute(
+        "INSERT INTO activities (record_id, app_name, window_title, start_time, end_time,
+             duration_seconds, process_id, app_path, bundle_id, window_geometry, confidence,
+             source, raw_title, project, fullscreen)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            record.id,
+            record.app_name,
+            record.window_title,
+            record.start_time,
+            record.end_time,
+            record.duration as i64,
+            record.process_id,
+            record.app_path,
+            record.bundle_id,
+            geometry,
+            record.confidence as f64,
+            record.source.as_str(),
+            record.raw_title,
+            record.project,
+            record.fullscreen,
+        ],
+    )?;
+    Ok(())
+}
+
+/// 写入一条每日汇总，已有同一天同一窗口的汇总时累加
+fn upsert_rollup(conn: &Connection, rollup: &DailyRollup) -> Result<()> {
+    conn.execute(
+        "INSERT INTO daily_rollups (date, app_name, window_title, total_duration,
+             record_count, first_seen, last_seen)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT (date, app_name, window_title) DO UPDATE SET
+             total_duration = total_duration + excluded.total_duration,
+             record_count = record_count + excluded.record_count,
+             first_seen = min(first_seen, excluded.first_seen),
+             last_seen = max(last_seen, excluded.last_seen)",
+        params![
+            rollup.date,
+            rollup.app_name,
+            rollup.window_title,
+            rollup.total_duration as i64,
+            rollup.record_count as i64,
+            rollup.first_seen,
+            rollup.last_seen,
+        ],
+    )?;
+    Ok(())
+}
+
+fn row_to_rollup(row: &Row) -> rusqlite::Result<DailyRollup> {
+    let total_duration: i64 = row.get(3)?;
+    let record_count: i64 = row.get(4)?;
+
+    Ok(DailyRollup {
+        date: row.get(0)?,
+        app_name: row.get(1)?,
+        window_title: row.get(2)?,
+        total_duration: total_duration.max(0) as u64,
+        record_count: record_count.max(0) as u64,
+        first_seen: row.get(5)?,
+        last_seen: row.get(6)?,
+    })
+}
+
+fn row_to_record(row: &Row) -> rusqlite::Result<ActivityRecord> {
+    let id: Option<String> = row.get(0)?;
+    let duration: i64 = row.get(5)?;
+    let geometry: Option<String> = row.get(9)?;
+    let confidence: f64 = row.get(10)?;
+    let source: String = row.get(11)?;
+
+    Ok(ActivityRecord {
+        id: id.unwrap_or_else(new_record_id),
+        app_name: row.get(1)?,
+        window_title: row.get(2)?,
+        start_time: row.get(3)?,
+        end_time: row.get(4)?,
+        duration: duration.max(0) as u64,
+        process_id: row.get(6)?,
+        app_path: row.get(7)?,
+        bundle_id: row.get(8)?,
+        window_geometry: geometry.and_then(|g| serde_json::from_str::<WindowGeometry>(&g).ok()),
+        confidence: confidence as f32,
+        source: RecordSource::from_name(&source),
+        raw_title: row.get(12)?,
+        project: row.get(13)?,
+        fullscreen: row.get(14)?,
+    })
+}
@@ -0,0 +1,182 @@
+// 状态时间线存储 - 已结束的状态时间段逐行追加到数据文件旁的 JSONL 文件，进行中的时间段单独保存
+
+use super::atomic_write;
+use crate::core::status::StatusInterval;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 数据文件对应的状态时间线文件（`timetracker.db` -> `timetracker.status.jsonl`）
+pub fn status_log_path(data_file: &Path) -> PathBuf {
+    data_file.with_extension("status.jsonl")
+}
+
+/// 状态时间线
+///
+/// 守护进程写入，命令行和 TUI 读取。进行中的时间段保存在 `<文件名>.current.json` 中，
+/// 其他进程读取时一并计入。
+#[derive(Debug, Default)]
+pub struct StatusLog {
+    /// 时间线文件，为 None 时只保存在内存中
+    path: Option<PathBuf>,
+    closed: Vec<StatusInterval>,
+    current: Option<StatusInterval>,
+}
+
+impl StatusLog {
+    /// 只保存在内存中的时间线
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// 保存在指定文件中的时间线
+    pub fn open<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..Self::default()
+        }
+    }
+
+    /// 数据文件对应的时间线，数据文件为空（自定义存储）时只保存在内存中
+    pub fn for_data_file(data_file: &str) -> Self {
+        if data_file.is_empty() {
+            Self::in_memory()
+        } else {
+            Self::open(status_log_path(Path::new(data_file)))
+        }
+    }
+
+    /// 进行中的时间段文件路径
+    pub fn current_path(&self) -> Option<PathBuf> {
+        self.path
+            .as_ref()
+            .map(|path| path.with_extension("current.json"))
+    }
+
+    /// 追加一个已结束的时间段
+    pub fn append(&mut self, interval: &StatusInterval) -> Result<()> {
+        let Some(path) = &self.path else {
+            self.closed.push(interval.clone());
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(interval)?)?;
+        Ok(())
+    }
+
+    /// 保存进行中的时间段
+    pub fn set_current(&mut self, current: Option<&StatusInterval>) -> Result<()> {
+        self.current = current.cloned();
+        let Some(path) = self.current_path() else {
+            return Ok(());
+        };
+        atomic_write(path, serde_json::to_string(&self.current)?.as_bytes())
+    }
+
+    /// 读取进行中的时间段（其他进程保存的）
+    pub fn current(&mut self) -> Result<Option<StatusInterval>> {
+        if let Some(path) = self.current_path() {
+            self.current = match fs::read_to_string(&path) {
+                Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                    log::warn!("忽略无法解析的状态文件 {}: {}", path.display(), e);
+                    None
+                }),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e.into()),
+            };
+        }
+        Ok(self.current.clone())
+    }
+
+    /// 把上次异常退出时遗留的进行中时间段写入时间线
+    pub fn recover_current(&mut self) -> Result<Option<StatusInterval>> {
+        let stale = self.current()?;
+        if let Some(interval) = &stale {
+            self.append(interval)?;
+            self.set_current(None)?;
+        }
+        Ok(stale)
+    }
+
+    /// 与 [start, end) 相交的时间段（截取到区间内，包括进行中的时间段），按开始时间排序
+    pub fn query(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<StatusInterval>> {
+        let mut intervals = self.read_closed()?;
+        intervals.extend(self.current()?);
+        let mut intervals: Vec<StatusInterval> = intervals
+            .iter()
+            .filter_map(|interval| interval.clipped(start, end))
+            .collect();
+        intervals.sort_by_key(|interval| interval.start);
+        Ok(intervals)
+    }
+
+    /// 删除在 `cutoff` 之前已经结束的时间段，返回删除的数量（用于数据保留策略）
+    ///
+    /// 无法解析的行原样保留。
+    pub fn purge_before(&mut self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let Some(path) = &self.path else {
+            let before = self.closed.len();
+            self.closed.retain(|interval| interval.end > cutoff);
+            return Ok(before - self.closed.len());
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut kept = String::with_capacity(content.len());
+        let mut removed = 0;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let expired = serde_json::from_str::<StatusInterval>(line)
+                .is_ok_and(|interval| interval.end <= cutoff);
+            if expired {
+                removed += 1;
+            } else {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+        }
+        if removed > 0 {
+            atomic_write(path, kept.as_bytes())?;
+        }
+        Ok(removed)
+    }
+
+    fn read_closed(&self) -> Result<Vec<StatusInterval>> {
+        let Some(path) = &self.path else {
+            return Ok(self.closed.clone());
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut intervals = Vec::new();
+        for (line_no, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(interval) => intervals.push(interval),
+                Err(e) => log::warn!(
+                    "跳过无法解析的状态记录 {}:{}: {}",
+                    path.display(),
+                    line_no + 1,
+                    e
+                ),
+            }
+        }
+        Ok(intervals)
+    }
+}
@@ -1,6 +1,13 @@
 // UI 组件和状态定义
 // 提供可重用的 UI 组件和状态管理
 
+use crate::core::manual::{resolve_span, ManualEntry};
+use crate::core::power::AwayPeriod;
+use crate::core::status::StatusSummary;
+use crate::utils::time::{
+    parse_duration_spec, parse_local_datetime, DayBoundary, DayTimeZone, TimeRange,
+};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 /// 标签页索引
@@ -157,6 +164,12 @@ pub enum InputMode {
     EditingTemperature,
     EditingMaxTokens,
     Search,
+    /// 输入自定义时间范围
+    EditingTimeRange,
+    /// 逐项填写手动记录
+    AddingManualEntry,
+    /// 逐个标注离开电脑的时间
+    LabelingAway,
 }
 
 impl InputMode {
@@ -174,10 +187,17 @@ pub enum TimeRangeFilter {
     LastWeek,
     ThisMonth,
     LastMonth,
+    /// 最近 7 天（含今天）
+    Last7Days,
+    /// 最近 30 天（含今天）
+    Last30Days,
+    /// 自定义日期范围（含首尾两天）
+    Custom(NaiveDate, NaiveDate),
     All,
 }
 
 impl TimeRangeFilter {
+    /// 可循环切换的时间范围（自定义范围需要单独输入）
     pub fn all() -> Vec<Self> {
         vec![
             Self::Today,
@@ -186,21 +206,125 @@ impl TimeRangeFilter {
             Self::LastWeek,
             Self::ThisMonth,
             Self::LastMonth,
+            Self::Last7Days,
+            Self::Last30Days,
             Self::All,
         ]
     }
 
-    pub fn title(&self) -> &'static str {
+    pub fn title(&self) -> String {
         match self {
-            Self::Today => "今天",
-            Self::Yesterday => "昨天",
-            Self::ThisWeek => "本周",
-            Self::LastWeek => "上周",
-            Self::ThisMonth => "本月",
-            Self::LastMonth => "上月",
-            Self::All => "全部",
+            Self::Today => "今天".to_string(),
+            Self::Yesterday => "昨天".to_string(),
+            Self::ThisWeek => "本周".to_string(),
+            Self::LastWeek => "上周".to_string(),
+            Self::ThisMonth => "本月".to_string(),
+            Self::LastMonth => "上月".to_string(),
+            Self::Last7Days => "最近7天".to_string(),
+            Self::Last30Days => "最近30天".to_string(),
+            Self::Custom(from, to) if from == to => from.format("%Y-%m-%d").to_string(),
+            Self::Custom(from, to) => {
+                format!("{} ~ {}", from.format("%Y-%m-%d"), to.format("%Y-%m-%d"))
+            }
+            Self::All => "全部".to_string(),
+        }
+    }
+
+    /// 根据名称获取预设的时间范围（today、last-7-days 等）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "today" => Some(Self::Today),
+            "yesterday" => Some(Self::Yesterday),
+            "this-week" | "week" => Some(Self::ThisWeek),
+            "last-week" => Some(Self::LastWeek),
+            "this-month" | "month" => Some(Self::ThisMonth),
+            "last-month" => Some(Self::LastMonth),
+            "last-7-days" | "7d" => Some(Self::Last7Days),
+            "last-30-days" | "30d" => Some(Self::Last30Days),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    /// 自定义日期范围（含首尾两天）
+    pub fn custom(from: NaiveDate, to: NaiveDate) -> anyhow::Result<Self> {
+        if from > to {
+            return Err(anyhow::anyhow!("开始日期 {} 晚于结束日期 {}", from, to));
+        }
+        Ok(Self::Custom(from, to))
+    }
+
+    /// 解析时间范围：预设名称、单个日期（YYYY-MM-DD）或日期范围（YYYY-MM-DD..YYYY-MM-DD）
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let input = input.trim();
+        if let Some(range) = Self::from_name(input) {
+            return Ok(range);
+        }
+
+        let parse_date = |text: &str| {
+            NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+                .map_err(|_| anyhow::anyhow!("无法识别的日期: {}，请使用 YYYY-MM-DD 格式", text))
+        };
+        match input.split_once("..") {
+            Some((from, to)) => Self::custom(parse_date(from)?, parse_date(to)?),
+            None => {
+                let date = parse_date(input)?;
+                Self::custom(date, date)
+            }
         }
     }
+
+    /// 对应的时间区间，`All` 返回 `None`
+    ///
+    /// 日期按配置的时区（`[ui] timezone`）和日分割点（`[ui] day_split_hour`）划分。
+    pub fn bounds(&self, boundary: DayBoundary) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        self.bounds_at(Utc::now(), boundary)
+    }
+
+    /// 以 `now` 为当前时间计算时间区间
+    pub fn bounds_at(
+        &self,
+        now: DateTime<Utc>,
+        boundary: DayBoundary,
+    ) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let range = match *self {
+            Self::Today => TimeRange::Today,
+            Self::Yesterday => TimeRange::Yesterday,
+            Self::ThisWeek => TimeRange::ThisWeek,
+            Self::LastWeek => TimeRange::LastWeek,
+            Self::ThisMonth => TimeRange::ThisMonth,
+            Self::LastMonth => TimeRange::LastMonth,
+            Self::Last7Days => TimeRange::LastDays(7),
+            Self::Last30Days => TimeRange::LastDays(30),
+            Self::Custom(from, to) => TimeRange::Custom(
+                boundary.day_start(from),
+                boundary.day_start(to + chrono::Duration::days(1)),
+            ),
+            Self::All => return None,
+        };
+        Some(range.bounds_at(now, boundary))
+    }
+}
+
+/// 概览页图表数据（按配置的时区和日分割点划分日期）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartStatistics {
+    /// 本周周一到周日每天的使用时长（秒）
+    pub week: [u64; 7],
+    /// 今天每小时的使用时长（秒），下标 0 对应日分割点所在的小时
+    pub hours: [u64; 24],
+    /// 今天各应用的使用时长（秒），按时长降序
+    pub today_by_app: Vec<(String, u64)>,
+    /// 日分割点，用于绘制小时标签
+    pub split_hour: u8,
+    /// 今天的逻辑日期
+    pub today: NaiveDate,
+    /// 今天各状态（活跃、闲置、锁屏等）的合计时长
+    pub status_today: StatusSummary,
+    /// 今天每小时各状态的时长，下标与 `hours` 相同
+    pub status_hours: [StatusSummary; 24],
+    /// 今天窗口全屏的时长（秒）
+    pub fullscreen_today: u64,
 }
 
 /// 应用程序表格项
@@ -231,6 +355,8 @@ pub struct RecentActivityItem {
     pub start_time: chrono::DateTime<chrono::Utc>,
     pub end_time: Option<chrono::DateTime<chrono::Utc>>,
     pub duration: u64,
+    pub recovered: bool, // 是否为异常退出后恢复的记录
+    pub manual: bool,    // 是否为手动添加的记录
 }
 
 /// 综合活动项 - 统一显示应用和窗口信息
@@ -245,6 +371,8 @@ pub struct UnifiedActivityItem {
     pub first_active: chrono::DateTime<chrono::Utc>,
     pub is_currently_active: bool,
     pub productivity_category: ProductivityCategory,
+    pub recovered_count: usize, // 异常退出后恢复的记录数
+    pub manual_count: usize,    // 手动添加的记录数
 }
 
 /// 生产力分类
@@ -366,6 +494,73 @@ impl Default for PaginationState {
     }
 }
 
+/// 手动记录表单：在控制栏中逐项输入，Enter 进入下一项
+#[derive(Debug, Clone, Default)]
+pub struct ManualEntryForm {
+    /// 已填写的字段
+    pub values: Vec<String>,
+}
+
+impl ManualEntryForm {
+    /// 表单字段及输入提示
+    pub const FIELDS: [(&'static str, &'static str); 4] = [
+        ("名称", "例如 电话会议"),
+        ("项目", "可留空"),
+        ("开始时间", "HH:MM 或 YYYY-MM-DD HH:MM"),
+        ("结束时间或时长", "HH:MM / 45m / 1h30m，留空为现在"),
+    ];
+
+    /// 当前字段序号
+    pub fn step(&self) -> usize {
+        self.values.len()
+    }
+
+    /// 当前字段的名称和提示
+    pub fn current_field(&self) -> (&'static str, &'static str) {
+        Self::FIELDS[self.step().min(Self::FIELDS.len() - 1)]
+    }
+
+    /// 填写当前字段，返回表单是否已填完
+    pub fn push(&mut self, value: String) -> bool {
+        if !self.is_complete() {
+            self.values.push(value.trim().to_string());
+        }
+        self.is_complete()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.values.len() >= Self::FIELDS.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// 由填写的内容生成手动记录，结束时间字段含 `:` 或 `-` 时按时间解析，否则按时长解析
+    /// 开始和结束时间按 `timezone` 解析。
+    pub fn to_entry(
+        &self,
+        now: DateTime<Utc>,
+        timezone: DayTimeZone,
+    ) -> anyhow::Result<ManualEntry> {
+        let field = |index: usize| self.values.get(index).map(String::as_str).unwrap_or("");
+        let start = if field(2).is_empty() {
+            None
+        } else {
+            Some(parse_local_datetime(field(2), now, timezone)?)
+        };
+        let (end, duration) = match field(3) {
+            "" => (None, None),
+            value if value.contains(':') || value.contains('-') => {
+                (Some(parse_local_datetime(value, now, timezone)?), None)
+            }
+            value => (None, Some(parse_duration_spec(value)?)),
+        };
+        let (start, end) = resolve_span(start, end, duration, now)?;
+        Ok(ManualEntry::new(field(0), start, end)?.project(field(1)))
+    }
+}
+
 /// 对话框状态
 #[derive(Debug, Clone)]
 pub struct DialogState {
@@ -491,6 +686,11 @@ pub struct UiState {
     pub chart_config: ChartConfiguration,
     pub pagination: PaginationState,
     pub dialog_state: DialogState,
+    pub manual_form: ManualEntryForm,
+    /// 等待标注的离开时段，第一个为正在询问的时段
+    pub pending_away: Vec<AwayPeriod>,
+    /// 显示和输入时间使用的时区（`[ui] timezone`）
+    pub timezone: DayTimeZone,
 }
 
 impl Default for UiState {
@@ -512,6 +712,9 @@ impl Default for UiState {
             chart_config: ChartConfiguration::default(),
             pagination: PaginationState::default(),
             dialog_state: DialogState::default(),
+            manual_form: ManualEntryForm::default(),
+            pending_away: Vec::new(),
+            timezone: DayTimeZone::default(),
         }
     }
 }
@@ -557,11 +760,11 @@ impl UiState {
     /// 切换时间范围
     pub fn toggle_time_range(&mut self) {
         let ranges = TimeRangeFilter::all();
-        let current_index = ranges
+        // 自定义范围不在循环列表中，切换后回到“今天”
+        let next_index = ranges
             .iter()
             .position(|&r| r == self.time_range)
-            .unwrap_or(0);
-        let next_index = (current_index + 1) % ranges.len();
+            .map_or(0, |i| (i + 1) % ranges.len());
         self.time_range = ranges[next_index];
     }
 }
@@ -1,34 +1,99 @@
-use crate::core::tracker::{ActivityRecord, TimeTracker};
+use crate::core::app_rules::{AppCategory, AppRules};
+use crate::core::manual::{ManualEntry, OverlapPolicy};
+use crate::core::power::AwayPeriod;
+use crate::core::tracker::{ActivityRecord, DailyRollup, RecordSource, TimeTracker};
+use crate::storage::StoreOptions;
 use crate::ui::components::{
-    AppTableItem, ProductivityCategory, RecentActivityItem, TimeRangeFilter, UnifiedActivityItem,
-    WindowItem,
+    AppTableItem, ChartStatistics, ProductivityCategory, RecentActivityItem, TimeRangeFilter,
+    UnifiedActivityItem, WindowItem,
 };
+use crate::utils::time::{DayBoundary, TimeRange};
 
 /// 数据管理器
 pub struct DataManager {
     tracker: TimeTracker,
+    /// 需要加载的时间范围
+    time_range: TimeRangeFilter,
+    /// 时区和日分割点，决定“今天”等范围的起止时间
+    day_boundary: DayBoundary,
+    /// 应用规则，决定应用的生产力分类
+    app_rules: AppRules,
+    loaded: bool,
 }
 
 impl DataManager {
-    pub fn new(data_file: String) -> anyhow::Result<Self> {
-        let tracker = TimeTracker::new(data_file, 1); // 改为1秒间隔，实现实时监控
-                                                      // 延迟加载数据，避免在TUI初始化时阻塞
-        Ok(Self { tracker })
+    pub fn new(data_file: String, options: StoreOptions) -> anyhow::Result<Self> {
+        let mut tracker = TimeTracker::new(data_file, 1); // 改为1秒间隔，实现实时监控
+                                                          // 延迟加载数据，避免在TUI初始化时阻塞
+        tracker.set_store_options(options);
+        Ok(Self {
+            tracker,
+            time_range: TimeRangeFilter::All,
+            day_boundary: DayBoundary::default(),
+            app_rules: AppRules::default(),
+            loaded: false,
+        })
     }
 
     /// 延迟初始化数据（在需要时调用）
     pub fn initialize_data(&mut self) -> anyhow::Result<()> {
-        self.tracker.load_data()
+        self.load()
+    }
+
+    /// 设置需要加载的时间范围，已加载数据时重新加载
+    pub fn set_time_range(&mut self, time_range: TimeRangeFilter) -> anyhow::Result<()> {
+        if self.time_range == time_range {
+            return Ok(());
+        }
+        self.time_range = time_range;
+        if self.loaded {
+            self.load()?;
+        }
+        Ok(())
+    }
+
+    /// 设置划分日期使用的时区和日分割点
+    pub fn set_day_boundary(&mut self, day_boundary: DayBoundary) {
+        self.day_boundary = day_boundary;
+    }
+
+    /// 设置应用规则
+    pub fn set_app_rules(&mut self, app_rules: AppRules) {
+        self.app_rules = app_rules;
+    }
+
+    /// 加载当前时间范围内的数据（分区存储只读取相关分区）
+    ///
+    /// 概览页图表需要本周的数据，因此加载范围总是包含本周。
+    fn load(&mut self) -> anyhow::Result<()> {
+        match self.time_range.bounds(self.day_boundary) {
+            // 前后各多加载一天，覆盖跨越边界的记录
+            Some((start, end)) => {
+                let (week_start, week_end) =
+                    TimeRange::ThisWeek.bounds_at(chrono::Utc::now(), self.day_boundary);
+                self.tracker.load_range(
+                    start.min(week_start) - chrono::Duration::days(1),
+                    end.max(week_end) + chrono::Duration::days(1),
+                )?
+            }
+            None => self.tracker.load_data()?,
+        }
+        self.loaded = true;
+        Ok(())
     }
 
     /// 获取统一活动数据 - 合并应用和窗口信息
     pub fn get_unified_activities(&self, time_filter: TimeRangeFilter) -> Vec<UnifiedActivityItem> {
         // 如果数据未初始化，返回空数据
-        if self.tracker.data.activities.is_empty() && self.tracker.data.current_activity.is_none() {
+        if self.tracker.data.activities.is_empty()
+            && self.tracker.data.rollups.is_empty()
+            && self.tracker.data.current_activity.is_none()
+        {
             return Vec::new();
         }
 
         let activities = self.get_filtered_activities(time_filter);
+        let rollups = self.get_filtered_rollups(time_filter);
         let mut activity_map: std::collections::HashMap<
             String,
             (
@@ -37,12 +102,16 @@ impl DataManager {
                 usize,                         // activity_count
                 chrono::DateTime<chrono::Utc>, // last_active
                 chrono::DateTime<chrono::Utc>, // first_active
+                usize,                         // recovered_count
+                usize,                         // manual_count
             ),
         > = std::collections::HashMap::new();
 
-        // 获取当前活动的应用和窗口（如果有的话）
-        let current_activity = self.tracker.data.current_activity.as_ref();
-        let current_key = current_activity.map(|a| format!("{} - {}", a.app_name, a.window_title));
+        // 获取当前活动的应用和窗口（如果有的话，且与时间范围相交）
+        let current_activity = self.get_filtered_current(time_filter);
+        let current_key = current_activity
+            .as_ref()
+            .map(|a| format!("{} - {}", a.app_name, a.window_title));
 
         // 处理已完成的活动
         for activity in activities {
@@ -53,10 +122,18 @@ impl DataManager {
                 0,
                 activity.start_time,
                 activity.start_time,
+                0,
+                0,
             ));
 
             entry.0 += activity.duration; // total_duration
             entry.2 += 1; // activity_count
+            if activity.source == RecordSource::Recovered {
+                entry.5 += 1; // recovered_count
+            }
+            if activity.source == RecordSource::Manual {
+                entry.6 += 1; // manual_count
+            }
 
             // 更新最近一次的时长（最新的活动记录）
             if activity.start_time >= entry.3 {
@@ -70,6 +147,26 @@ impl DataManager {
             }
         }
 
+        // 已汇总的旧记录：只计入总时长、次数和首末活动时间
+        for rollup in rollups {
+            let key = format!("{} - {}", rollup.app_name, rollup.window_title);
+            let entry = activity_map.entry(key).or_insert((
+                0,
+                rollup.total_duration,
+                0,
+                rollup.last_seen,
+                rollup.first_seen,
+                0,
+                0,
+            ));
+
+            entry.0 += rollup.total_duration;
+            entry.2 += rollup.record_count as usize;
+            if rollup.first_seen < entry.4 {
+                entry.4 = rollup.first_seen;
+            }
+        }
+
         // 如果有当前活动，也加入统计
         if let Some(current) = current_activity {
             let key = format!("{} - {}", current.app_name, current.window_title);
@@ -81,6 +178,8 @@ impl DataManager {
                 0,
                 current.start_time,
                 current.start_time,
+                0,
+                0,
             ));
 
             entry.0 += current_duration; // total_duration
@@ -101,7 +200,15 @@ impl DataManager {
             .map(
                 |(
                     key,
-                    (total_duration, recent_duration, activity_count, last_active, first_active),
+                    (
+                        total_duration,
+                        recent_duration,
+                        activity_count,
+                        last_active,
+                        first_active,
+                        recovered_count,
+                        manual_count,
+                    ),
                 )| {
                     let parts: Vec<&str> = key.splitn(2, " - ").collect();
                     let app_name = parts[0].to_string();
@@ -120,6 +227,8 @@ impl DataManager {
                         first_active,
                         is_currently_active,
                         productivity_category,
+                        recovered_count,
+                        manual_count,
                     }
                 },
             )
@@ -133,25 +242,41 @@ impl DataManager {
     /// 获取应用程序表格数据
     pub fn get_app_table_data(&self, time_filter: TimeRangeFilter) -> Vec<AppTableItem> {
         // 如果数据未初始化，返回空数据
-        if self.tracker.data.activities.is_empty() && self.tracker.current_activity.is_none() {
+        if self.tracker.data.activities.is_empty()
+            && self.tracker.data.rollups.is_empty()
+            && self.tracker.current_activity.is_none()
+        {
             return Vec::new();
         }
 
         let activities = self.get_filtered_activities(time_filter);
-        let mut app_map: std::collections::HashMap<String, (u64, Vec<&ActivityRecord>)> =
-            std::collections::HashMap::new();
+        let rollups = self.get_filtered_rollups(time_filter);
+        let mut app_map: std::collections::HashMap<
+            String,
+            (u64, Vec<&ActivityRecord>, Vec<&DailyRollup>),
+        > = std::collections::HashMap::new();
 
-        for activity in activities {
-            let entry = app_map
-                .entry(activity.app_name.clone())
-                .or_insert((0, Vec::new()));
+        for activity in &activities {
+            let entry =
+                app_map
+                    .entry(activity.app_name.clone())
+                    .or_insert((0, Vec::new(), Vec::new()));
             entry.0 += activity.duration;
             entry.1.push(activity);
         }
 
+        for rollup in rollups {
+            let entry =
+                app_map
+                    .entry(rollup.app_name.clone())
+                    .or_insert((0, Vec::new(), Vec::new()));
+            entry.0 += rollup.total_duration;
+            entry.2.push(rollup);
+        }
+
         let mut items: Vec<AppTableItem> = app_map
             .into_iter()
-            .map(|(app_name, (total_duration, activities))| {
+            .map(|(app_name, (total_duration, activities, rollups))| {
                 // 创建窗口项
                 let mut window_map: std::collections::HashMap<
                     String,
@@ -171,6 +296,19 @@ impl DataManager {
                     }
                 }
 
+                for rollup in &rollups {
+                    let entry = window_map.entry(rollup.window_title.clone()).or_insert((
+                        0,
+                        0,
+                        rollup.last_seen,
+                    ));
+                    entry.0 += rollup.total_duration;
+                    entry.1 += rollup.record_count as usize;
+                    if rollup.last_seen > entry.2 {
+                        entry.2 = rollup.last_seen;
+                    }
+                }
+
                 let windows: Vec<WindowItem> = window_map
                     .into_iter()
                     .map(
@@ -186,6 +324,7 @@ impl DataManager {
                 let last_active = activities
                     .iter()
                     .map(|a| a.start_time)
+                    .chain(rollups.iter().map(|r| r.last_seen))
                     .max()
                     .unwrap_or_else(chrono::Utc::now);
 
@@ -207,6 +346,7 @@ impl DataManager {
     /// 获取窗口数据
     pub fn get_window_data(&self, time_filter: TimeRangeFilter) -> Vec<WindowItem> {
         let activities = self.get_filtered_activities(time_filter);
+        let rollups = self.get_filtered_rollups(time_filter);
         let mut window_map: std::collections::HashMap<
             String,
             (u64, usize, chrono::DateTime<chrono::Utc>),
@@ -225,6 +365,18 @@ impl DataManager {
             }
         }
 
+        for rollup in rollups {
+            let entry =
+                window_map
+                    .entry(rollup.window_title.clone())
+                    .or_insert((0, 0, rollup.last_seen));
+            entry.0 += rollup.total_duration;
+            entry.1 += rollup.record_count as usize;
+            if rollup.last_seen > entry.2 {
+                entry.2 = rollup.last_seen;
+            }
+        }
+
         let mut items: Vec<WindowItem> = window_map
             .into_iter()
             .map(
@@ -252,75 +404,109 @@ impl DataManager {
                 start_time: activity.start_time,
                 end_time: activity.end_time,
                 duration: activity.duration,
+                recovered: activity.source == RecordSource::Recovered,
+                manual: activity.source == RecordSource::Manual,
             })
             .collect()
     }
 
     /// 删除指定的活动记录
     pub fn delete_activity(&mut self, index: usize) -> anyhow::Result<bool> {
-        if index < self.tracker.data.activities.len() {
-            let removed_activity = self.tracker.data.activities.remove(index);
-            log::info!(
-                "删除活动记录: {} - {} ({}秒)",
-                removed_activity.app_name,
-                removed_activity.window_title,
-                removed_activity.duration
-            );
+        let Some(activity) = self.tracker.data.activities.get(index).cloned() else {
+            return Ok(false);
+        };
 
-            // 保存数据到文件
-            self.tracker.save_data()?;
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        self.tracker
+            .delete_records(std::slice::from_ref(&activity.id))?;
+        log::info!(
+            "删除活动记录: {} - {} ({}秒)",
+            activity.app_name,
+            activity.window_title,
+            activity.duration
+        );
+        Ok(true)
     }
 
     /// 批量删除活动记录
     pub fn delete_activities(&mut self, indices: Vec<usize>) -> anyhow::Result<usize> {
-        let mut sorted_indices = indices;
-        sorted_indices.sort_by(|a, b| b.cmp(a)); // 从大到小排序，避免索引错位
-
-        let mut deleted_count = 0;
-        for index in sorted_indices {
-            if index < self.tracker.data.activities.len() {
-                let removed_activity = self.tracker.data.activities.remove(index);
+        let mut ids = Vec::new();
+        for index in indices {
+            if let Some(activity) = self.tracker.data.activities.get(index) {
                 log::info!(
                     "批量删除活动记录: {} - {}",
-                    removed_activity.app_name,
-                    removed_activity.window_title
+                    activity.app_name,
+                    activity.window_title
                 );
-                deleted_count += 1;
+                ids.push(activity.id.clone());
             }
         }
+        ids.dedup();
 
-        if deleted_count > 0 {
-            self.tracker.save_data()?;
-        }
-
-        Ok(deleted_count)
+        self.tracker.delete_records(&ids)
     }
 
     /// 删除指定应用的所有活动记录
     pub fn delete_app_activities(&mut self, app_name: &str) -> anyhow::Result<usize> {
-        let initial_count = self.tracker.data.activities.len();
-        self.tracker
+        let ids: Vec<String> = self
+            .tracker
             .data
             .activities
-            .retain(|activity| activity.app_name != app_name);
-        let deleted_count = initial_count - self.tracker.data.activities.len();
+            .iter()
+            .filter(|activity| activity.app_name == app_name)
+            .map(|activity| activity.id.clone())
+            .collect();
 
+        let deleted_count = self.tracker.delete_records(&ids)?;
         if deleted_count > 0 {
             log::info!(
                 "删除应用 {} 的所有活动记录，共 {} 条",
                 app_name,
                 deleted_count
             );
-            self.tracker.save_data()?;
         }
 
         Ok(deleted_count)
     }
 
+    /// 查询与手动记录时间段重叠的记录
+    pub fn find_overlaps(&mut self, entry: &ManualEntry) -> anyhow::Result<Vec<ActivityRecord>> {
+        self.tracker.find_overlaps(entry.start, entry.end)
+    }
+
+    /// 添加手动记录，按 `policy` 处理重叠后重新加载当前时间范围的数据
+    pub fn add_manual_entry(
+        &mut self,
+        entry: &ManualEntry,
+        policy: OverlapPolicy,
+    ) -> anyhow::Result<()> {
+        self.tracker.add_manual_entry(entry, policy)?;
+        self.load()
+    }
+
+    /// 等待标注的离开时段
+    pub fn pending_away(&mut self) -> anyhow::Result<Vec<AwayPeriod>> {
+        self.tracker.pending_away()
+    }
+
+    /// 把离开时段标注为手动记录后重新加载当前时间范围的数据
+    pub fn label_away(
+        &mut self,
+        period: &AwayPeriod,
+        label: &str,
+        project: Option<&str>,
+    ) -> anyhow::Result<ManualEntry> {
+        let entry = self
+            .tracker
+            .label_away(period, label, project, OverlapPolicy::Reject)?;
+        self.load()?;
+        Ok(entry)
+    }
+
+    /// 不标注，直接把离开时段移出待标注队列
+    pub fn dismiss_away(&mut self, period: &AwayPeriod) -> anyhow::Result<bool> {
+        self.tracker.dismiss_away(period)
+    }
+
     /// 删除指定应用和窗口的最近一条活动记录
     pub fn delete_recent_activity_by_app_window(
         &mut self,
@@ -328,16 +514,18 @@ impl DataManager {
         window_title: &str,
     ) -> anyhow::Result<bool> {
         // 找到匹配的最近一条记录（按开始时间倒序）
-        let mut found_index = None;
-        for (index, activity) in self.tracker.data.activities.iter().enumerate().rev() {
-            if activity.app_name == app_name && activity.window_title == window_title {
-                found_index = Some(index);
-                break;
-            }
-        }
-
-        if let Some(index) = found_index {
-            let removed_activity = self.tracker.data.activities.remove(index);
+        let found = self
+            .tracker
+            .data
+            .activities
+            .iter()
+            .rev()
+            .find(|activity| activity.app_name == app_name && activity.window_title == window_title)
+            .cloned();
+
+        if let Some(removed_activity) = found {
+            self.tracker
+                .delete_records(std::slice::from_ref(&removed_activity.id))?;
             log::info!(
                 "删除最近的活动记录: {} - {} ({}秒, 开始时间: {})",
                 removed_activity.app_name,
@@ -345,9 +533,6 @@ impl DataManager {
                 removed_activity.duration,
                 removed_activity.start_time.format("%Y-%m-%d %H:%M:%S")
             );
-
-            // 保存数据到文件
-            self.tracker.save_data()?;
             Ok(true)
         } else {
             log::warn!("未找到匹配的活动记录: {} - {}", app_name, window_title);
@@ -361,13 +546,16 @@ impl DataManager {
         start_time: chrono::DateTime<chrono::Utc>,
         end_time: chrono::DateTime<chrono::Utc>,
     ) -> anyhow::Result<usize> {
-        let initial_count = self.tracker.data.activities.len();
-        self.tracker
+        let ids: Vec<String> = self
+            .tracker
             .data
             .activities
-            .retain(|activity| activity.start_time < start_time || activity.start_time > end_time);
-        let deleted_count = initial_count - self.tracker.data.activities.len();
+            .iter()
+            .filter(|activity| activity.start_time >= start_time && activity.start_time <= end_time)
+            .map(|activity| activity.id.clone())
+            .collect();
 
+        let deleted_count = self.tracker.delete_records(&ids)?;
         if deleted_count > 0 {
             log::info!(
                 "删除时间范围 {} 到 {} 的活动记录，共 {} 条",
@@ -375,7 +563,6 @@ impl DataManager {
                 end_time.format("%Y-%m-%d %H:%M:%S"),
                 deleted_count
             );
-            self.tracker.save_data()?;
         }
 
         Ok(deleted_count)
@@ -389,11 +576,9 @@ impl DataManager {
         new_window_title: Option<String>,
         new_duration: Option<u64>,
     ) -> anyhow::Result<bool> {
-        if index >= self.tracker.data.activities.len() {
+        let Some(mut activity) = self.tracker.data.activities.get(index).cloned() else {
             return Ok(false);
-        }
-
-        let activity = &mut self.tracker.data.activities[index];
+        };
         let mut changed = false;
 
         if let Some(app_name) = new_app_name {
@@ -433,7 +618,7 @@ impl DataManager {
         }
 
         if changed {
-            self.tracker.save_data()?;
+            self.tracker.update_record(activity)?;
         }
 
         Ok(changed)
@@ -445,14 +630,16 @@ impl DataManager {
         app_name: &str,
         window_title: &str,
     ) -> anyhow::Result<usize> {
-        let mut activities_to_merge: Vec<usize> = Vec::new();
-
         // 找到所有匹配的活动记录
-        for (index, activity) in self.tracker.data.activities.iter().enumerate() {
-            if activity.app_name == app_name && activity.window_title == window_title {
-                activities_to_merge.push(index);
-            }
-        }
+        let activities_to_merge: Vec<&ActivityRecord> = self
+            .tracker
+            .data
+            .activities
+            .iter()
+            .filter(|activity| {
+                activity.app_name == app_name && activity.window_title == window_title
+            })
+            .collect();
 
         if activities_to_merge.len() <= 1 {
             return Ok(0); // 没有需要合并的记录
@@ -463,8 +650,7 @@ impl DataManager {
         let mut earliest_start = chrono::Utc::now();
         let mut latest_end = chrono::DateTime::<chrono::Utc>::from_timestamp(0, 0).unwrap();
 
-        for &index in &activities_to_merge {
-            let activity = &self.tracker.data.activities[index];
+        for activity in &activities_to_merge {
             total_duration += activity.duration;
 
             if activity.start_time < earliest_start {
@@ -479,34 +665,31 @@ impl DataManager {
         }
 
         // 创建合并后的活动记录
+        let first = activities_to_merge[0];
         let merged_activity = ActivityRecord {
+            id: crate::core::tracker::new_record_id(),
             app_name: app_name.to_string(),
             window_title: window_title.to_string(),
             start_time: earliest_start,
             end_time: Some(latest_end),
             duration: total_duration,
-            process_id: self.tracker.data.activities[activities_to_merge[0]].process_id,
-            app_path: self.tracker.data.activities[activities_to_merge[0]]
-                .app_path
-                .clone(),
-            bundle_id: self.tracker.data.activities[activities_to_merge[0]]
-                .bundle_id
-                .clone(),
+            process_id: first.process_id,
+            app_path: first.app_path.clone(),
+            bundle_id: first.bundle_id.clone(),
             window_geometry: None, // 合并活动不保留窗口几何信息
             confidence: 1.0,       // 合并活动的置信度设为1.0
+            source: RecordSource::Tracked,
+            raw_title: None,
+            project: None,
+            fullscreen: activities_to_merge.iter().all(|a| a.fullscreen),
         };
 
-        // 删除原有记录（从后往前删除避免索引错位）
-        let mut sorted_indices = activities_to_merge.clone();
-        sorted_indices.sort_by(|a, b| b.cmp(a));
-        for index in sorted_indices {
-            self.tracker.data.activities.remove(index);
-        }
-
-        // 添加合并后的记录
-        self.tracker.data.activities.push(merged_activity);
+        // 删除原有记录并添加合并后的记录
+        let ids: Vec<String> = activities_to_merge.iter().map(|a| a.id.clone()).collect();
+        let merged_count = ids.len();
+        self.tracker.delete_records(&ids)?;
+        self.tracker.add_record(merged_activity)?;
 
-        let merged_count = activities_to_merge.len();
         log::info!(
             "合并 {} 条相同的活动记录: {} - {}",
             merged_count,
@@ -514,7 +697,6 @@ impl DataManager {
             window_title
         );
 
-        self.tracker.save_data()?;
         Ok(merged_count)
     }
 
@@ -529,373 +711,141 @@ impl DataManager {
     }
 
     /// 保存数据到文件
-    pub fn save_data(&self) -> anyhow::Result<()> {
-        self.tracker.save_data()
+    ///
+    /// 编辑操作已经逐条写入存储，这里只刷新存储，
+    /// 不会用内存中的记录覆盖守护进程在此期间追加的记录。
+    pub fn save_data(&mut self) -> anyhow::Result<()> {
+        self.tracker.flush()
+    }
+
+    /// 取出加载数据时产生的警告
+    pub fn take_load_warnings(&mut self) -> Vec<String> {
+        self.tracker.take_load_warnings()
     }
 
     /// 重新加载数据
     pub fn reload_data(&mut self) -> anyhow::Result<()> {
-        self.tracker.load_data()
+        self.load()
     }
 
     /// 根据时间过滤器获取活动数据
-    fn get_filtered_activities(&self, time_filter: TimeRangeFilter) -> Vec<&ActivityRecord> {
-        match time_filter {
-            TimeRangeFilter::All => self.tracker.get_activities().iter().collect(),
-            _ => {
-                // 对于其他时间过滤器，我们需要实现时间过滤逻辑
-                // 这里先返回所有活动，后续可以根据需要实现具体的时间过滤
-                self.tracker.get_activities().iter().collect()
-            }
+    ///
+    /// 跨越区间边界（例如跨越零点）的记录只保留区间内的部分，时长按比例截取。
+    fn get_filtered_activities(&self, time_filter: TimeRangeFilter) -> Vec<ActivityRecord> {
+        let activities = self.tracker.get_activities().iter();
+        match time_filter.bounds(self.day_boundary) {
+            Some((start, end)) => activities.filter_map(|a| a.clipped(start, end)).collect(),
+            None => activities.cloned().collect(),
         }
     }
 
-    /// 应用分类（用于生产力评分）
-    fn categorize_app(&self, app_name: &str) -> AppCategory {
-        // 创建应用分类预设表
-        let productive_apps = [
-            // 开发工具
-            "vscode",
-            "visual studio code",
-            "code",
-            "xcode",
-            "intellij",
-            "idea",
-            "pycharm",
-            "webstorm",
-            "android studio",
-            "eclipse",
-            "vim",
-            "emacs",
-            "neovim",
-            "sublime text",
-            "atom",
-            "brackets",
-            "phpstorm",
-            "clion",
-            "rider",
-            // 终端和命令行工具
-            "terminal",
-            "iterm",
-            "iterm2",
-            "warp",
-            "hyper",
-            "alacritty",
-            "kitty",
-            "powershell",
-            "cmd",
-            "bash",
-            "zsh",
-            "fish",
-            // 开发相关工具
-            "git",
-            "github desktop",
-            "sourcetree",
-            "tower",
-            "fork",
-            "gitkraken",
-            "docker",
-            "docker desktop",
-            "kubernetes",
-            "postman",
-            "insomnia",
-            "paw",
-            "tableplus",
-            "sequel pro",
-            "dbeaver",
-            "navicat",
-            "mongodb compass",
-            // 设计工具
-            "figma",
-            "sketch",
-            "adobe xd",
-            "photoshop",
-            "illustrator",
-            "indesign",
-            "after effects",
-            "premiere pro",
-            "final cut pro",
-            "davinci resolve",
-            "blender",
-            "maya",
-            "3ds max",
-            "cinema 4d",
-            "zbrush",
-            // 办公软件
-            "microsoft word",
-            "word",
-            "microsoft excel",
-            "excel",
-            "microsoft powerpoint",
-            "powerpoint",
-            "microsoft outlook",
-            "outlook",
-            "microsoft teams",
-            "teams",
-            "onenote",
-            "google docs",
-            "google sheets",
-            "google slides",
-            "google drive",
-            "pages",
-            "numbers",
-            "keynote",
-            "libreoffice",
-            "openoffice",
-            // 笔记和文档
-            "notion",
-            "obsidian",
-            "typora",
-            "bear",
-            "ulysses",
-            "scrivener",
-            "evernote",
-            "onenote",
-            "joplin",
-            "logseq",
-            "roam research",
-            "markdown editor",
-            "marktext",
-            "zettlr",
-            // 通讯和协作
-            "slack",
-            "microsoft teams",
-            "zoom",
-            "skype",
-            "discord",
-            "telegram",
-            "whatsapp",
-            "wechat",
-            "dingtalk",
-            "feishu",
-            "lark",
-            // 项目管理
-            "jira",
-            "trello",
-            "asana",
-            "monday",
-            "clickup",
-            "linear",
-            "height",
-            "todoist",
-            "things",
-            "omnifocus",
-            "taskwarrior",
-            "org-mode",
-            // 浏览器（工作相关）
-            "chrome",
-            "firefox",
-            "safari",
-            "edge",
-            "brave",
-            "opera",
-            "vivaldi",
-            // 其他生产力工具
-            "alfred",
-            "raycast",
-            "spotlight",
-            "launcher",
-            "quicksilver",
-            "1password",
-            "bitwarden",
-            "lastpass",
-            "keychain access",
-            "calculator",
-            "calendar",
-            "contacts",
-            "mail",
-            "notes",
-        ];
-
-        let unproductive_apps = [
-            // 视频娱乐
-            "youtube",
-            "netflix",
-            "hulu",
-            "disney+",
-            "amazon prime",
-            "hbo max",
-            "twitch",
-            "bilibili",
-            "iqiyi",
-            "youku",
-            "tencent video",
-            "douyin",
-            "tiktok",
-            "vlc",
-            "quicktime",
-            "mpv",
-            "plex",
-            "kodi",
-            "infuse",
-            // 音乐娱乐
-            "spotify",
-            "apple music",
-            "youtube music",
-            "soundcloud",
-            "pandora",
-            "tidal",
-            "deezer",
-            "qq music",
-            "netease music",
-            "xiami music",
-            // 游戏
-            "steam",
-            "epic games",
-            "origin",
-            "uplay",
-            "battle.net",
-            "gog galaxy",
-            "minecraft",
-            "world of warcraft",
-            "league of legends",
-            "dota 2",
-            "counter-strike",
-            "valorant",
-            "overwatch",
-            "fortnite",
-            "apex legends",
-            "among us",
-            "fall guys",
-            "rocket league",
-            "fifa",
-            "nba 2k",
-            "civilization",
-            "age of empires",
-            "starcraft",
-            "diablo",
-            "hearthstone",
-            // 社交媒体
-            "facebook",
-            "instagram",
-            "twitter",
-            "snapchat",
-            "linkedin",
-            "pinterest",
-            "reddit",
-            "tumblr",
-            "weibo",
-            "zhihu",
-            "xiaohongshu",
-            "douban",
-            // 购物
-            "amazon",
-            "ebay",
-            "taobao",
-            "tmall",
-            "jd",
-            "pinduoduo",
-            "shopify",
-            // 新闻和阅读（娱乐性）
-            "news",
-            "flipboard",
-            "pocket",
-            "instapaper",
-            "feedly",
-            "reeder",
-            // 其他娱乐
-            "photos",
-            "preview",
-            "image viewer",
-            "comic reader",
-            "manga reader",
-            "podcast",
-            "audiobook",
-            "kindle",
-            "ibooks",
-            "goodreads",
-        ];
-
-        let neutral_apps = [
-            // 系统工具
-            "finder",
-            "file explorer",
-            "explorer",
-            "nautilus",
-            "dolphin",
-            "thunar",
-            "activity monitor",
-            "task manager",
-            "system monitor",
-            "htop",
-            "top",
-            "system preferences",
-            "settings",
-            "control panel",
-            "registry editor",
-            // 网络工具
-            "network utility",
-            "wifi analyzer",
-            "speedtest",
-            "ping",
-            "traceroute",
-            // 文件管理
-            "7-zip",
-            "winrar",
-            "the unarchiver",
-            "keka",
-            "betterzip",
-            "archive utility",
-            "dropbox",
-            "google drive",
-            "onedrive",
-            "icloud",
-            "box",
-            "mega",
-            // 系统维护
-            "disk utility",
-            "cleanmymac",
-            "ccleaner",
-            "malwarebytes",
-            "antivirus",
-            "backup",
-            "time machine",
-            "carbon copy cloner",
-            "superduper",
-            // 其他工具
-            "pdf reader",
-            "adobe reader",
-            "preview",
-            "skim",
-            "foxit reader",
-            "text editor",
-            "notepad",
-            "textedit",
-            "gedit",
-            "nano",
-            "clock",
-            "timer",
-            "stopwatch",
-            "weather",
-            "maps",
-            "gps",
-        ];
-
-        let app_lower = app_name.to_lowercase();
-
-        // 检查生产力应用
-        for app in &productive_apps {
-            if app_lower.contains(app) || app.contains(&app_lower) {
-                return AppCategory::Productive;
+    /// 根据时间过滤器获取每日汇总（按首次出现时间筛选）
+    fn get_filtered_rollups(&self, time_filter: TimeRangeFilter) -> Vec<&DailyRollup> {
+        let rollups = self.tracker.data.rollups.iter();
+        match time_filter.bounds(self.day_boundary) {
+            Some((start, end)) => rollups
+                .filter(|r| r.first_seen >= start && r.first_seen < end)
+                .collect(),
+            None => rollups.collect(),
+        }
+    }
+
+    /// 当前活动落在时间范围内的部分
+    fn get_filtered_current(&self, time_filter: TimeRangeFilter) -> Option<ActivityRecord> {
+        let current = self.tracker.data.current_activity.as_ref()?;
+        match time_filter.bounds(self.day_boundary) {
+            Some((start, end)) => current.clipped(start, end),
+            None => Some(current.clone()),
+        }
+    }
+
+    /// 概览页图表数据：本周每天、今天每小时和今天各应用的使用时长
+    ///
+    /// 跨越日分割点或整点的记录按时间比例拆分到对应的日期和小时。
+    pub fn get_chart_statistics(&self) -> ChartStatistics {
+        let boundary = self.day_boundary;
+        let now = chrono::Utc::now();
+        let (week_start, week_end) = TimeRange::ThisWeek.bounds_at(now, boundary);
+        let (today_start, today_end) = TimeRange::Today.bounds_at(now, boundary);
+        let monday = boundary.logical_date(week_start);
+
+        let mut stats = ChartStatistics {
+            split_hour: boundary.split_hour,
+            today: boundary.logical_date(now),
+            ..Default::default()
+        };
+        let mut by_app: std::collections::HashMap<String, u64> = std::collections::HashMap::new();
+
+        let records = self
+            .tracker
+            .get_activities()
+            .iter()
+            .chain(self.tracker.data.current_activity.as_ref())
+            .filter_map(|a| a.clipped(week_start, week_end));
+        for record in records {
+            let end = record.effective_end();
+            for (date, start, end) in boundary.split_days(record.start_time, end) {
+                let index = (date - monday).num_days();
+                if let (Ok(index @ 0..=6), Some(piece)) =
+                    (usize::try_from(index), record.clipped(start, end))
+                {
+                    stats.week[index] += piece.duration;
+                }
+            }
+
+            if let Some(today) = record.clipped(today_start, today_end) {
+                *by_app.entry(today.app_name.clone()).or_insert(0) += today.duration;
+                if today.fullscreen {
+                    stats.fullscreen_today += today.duration;
+                }
+                for (hour, start, end) in
+                    boundary.split_hours(today.start_time, today.effective_end())
+                {
+                    if let Some(piece) = today.clipped(start, end) {
+                        stats.hours[hour] += piece.duration;
+                    }
+                }
             }
         }
 
-        // 检查娱乐应用
-        for app in &unproductive_apps {
-            if app_lower.contains(app) || app.contains(&app_lower) {
-                return AppCategory::Unproductive;
+        // 本周已汇总的旧记录按日期计入（通常只有保留期很短时才会出现）
+        for rollup in self
+            .tracker
+            .data
+            .rollups
+            .iter()
+            .filter(|r| r.first_seen >= week_start && r.first_seen < week_end)
+        {
+            let index = (boundary.logical_date(rollup.first_seen) - monday).num_days();
+            if let Ok(index @ 0..=6) = usize::try_from(index) {
+                stats.week[index] += rollup.total_duration;
             }
         }
 
-        // 检查中性应用
-        for app in &neutral_apps {
-            if app_lower.contains(app) || app.contains(&app_lower) {
-                return AppCategory::Neutral;
+        stats.today_by_app = by_app.into_iter().collect();
+        stats
+            .today_by_app
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        for interval in self
+            .tracker
+            .data
+            .status_timeline
+            .iter()
+            .filter_map(|i| i.clipped(today_start, today_end))
+        {
+            stats.status_today.add(interval.status, interval.duration());
+            for (hour, start, end) in boundary.split_hours(interval.start, interval.end) {
+                let seconds = (end - start).num_seconds().max(0) as u64;
+                stats.status_hours[hour].add(interval.status, seconds);
             }
         }
+        stats
+    }
 
-        // 默认为中性
-        AppCategory::Neutral
+    /// 应用分类（用于生产力评分），分类关键字来自应用规则
+    fn categorize_app(&self, app_name: &str) -> AppCategory {
+        self.app_rules.category(app_name)
     }
 
     /// 应用生产力分类（用于UI显示）
@@ -965,8 +915,7 @@ impl DataManager {
     /// 刷新数据
     pub fn refresh(&mut self) -> anyhow::Result<()> {
         // 重新加载数据文件，以获取最新的活动记录
-        self.tracker.load_data()?;
-        Ok(())
+        self.load()
     }
 
     /// 获取当前正在进行的活动
@@ -974,11 +923,3 @@ impl DataManager {
         self.tracker.current_activity.as_ref()
     }
 }
-
-/// 应用分类枚举
-#[derive(Debug, Clone, PartialEq)]
-enum AppCategory {
-    Productive,   // 生产力应用
-    Neutral,      // 中性应用
-    Unproductive, // 娱乐/非生产力应用
-}
@@ -154,6 +154,9 @@ impl EventHandler {
                     KeyCode::Char('s') => EventResult::ToggleSortBy,
                     KeyCode::Char('o') => EventResult::ToggleSortOrder,
                     KeyCode::Char('f') => EventResult::ToggleTimeRange,
+                    KeyCode::Char('F') => EventResult::StartEditing(InputMode::EditingTimeRange),
+                    KeyCode::Char('a') => EventResult::StartEditing(InputMode::AddingManualEntry),
+                    KeyCode::Char('l') => EventResult::StartEditing(InputMode::LabelingAway),
                     // 使用更通用的键位替代PgUp/PgDn，确保Mac兼容性
                     KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                         EventResult::PrevPage
@@ -175,6 +178,7 @@ impl EventHandler {
             TabIndex::Dashboard => match key.code {
                 KeyCode::Char('c') => EventResult::ToggleChartMode,
                 KeyCode::Char('f') => EventResult::ToggleTimeRange,
+                KeyCode::Char('F') => EventResult::StartEditing(InputMode::EditingTimeRange),
                 KeyCode::Char('b') => EventResult::ToggleBarChart,
                 KeyCode::Char('l') => EventResult::ToggleSparkline,
                 KeyCode::Char('p') => EventResult::TogglePieChart,
@@ -1,11 +1,14 @@
+use crate::core::away::{describe, SUGGESTED_LABELS};
+use crate::core::status::{StatusKind, StatusSummary};
 use crate::ui::components::{
-    AppTableItem, ProductivityCategory, RecentActivityItem, SortBy, SortOrder, TabIndex,
-    TimeRangeFilter, UiState, UnifiedActivityItem, ViewMode, WindowItem,
+    AppTableItem, ChartStatistics, InputMode, ManualEntryForm, ProductivityCategory,
+    RecentActivityItem, SortBy, SortOrder, TabIndex, UiState, UnifiedActivityItem, ViewMode,
+    WindowItem,
 };
 use crate::ui::layout::{ResponsiveLayout, ScreenSize};
 use crate::ui::themes::Theme;
 use crate::ui::widgets::{ContextHelpWidget, DialogWidget};
-use crate::utils::time::format_duration;
+use crate::utils::time::{format_duration, format_duration_short};
 use ratatui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -17,6 +20,12 @@ use ratatui::{
     Frame,
 };
 
+/// 异常退出后恢复的记录在窗口标题前显示的标记
+const RECOVERED_MARKER: &str = "⚠ ";
+
+/// 手动添加的记录在窗口标题前显示的标记
+const MANUAL_MARKER: &str = "✍ ";
+
 /// 渲染器
 pub struct Renderer<'a> {
     theme: &'a Theme,
@@ -36,7 +45,7 @@ impl<'a> Renderer<'a> {
         _window_items: &[WindowItem],
         _recent_activities: &[RecentActivityItem],
         unified_activities: &[UnifiedActivityItem],
-        _statistics: &(),
+        statistics: &ChartStatistics,
     ) {
         // 检测屏幕尺寸
         let screen_size = ScreenSize::from_rect(f.area());
@@ -57,7 +66,7 @@ impl<'a> Renderer<'a> {
             _window_items,
             _recent_activities,
             unified_activities,
-            _statistics,
+            statistics,
             screen_size,
         );
 
@@ -98,14 +107,14 @@ impl<'a> Renderer<'a> {
         _window_items: &[WindowItem],
         _recent_activities: &[RecentActivityItem],
         unified_activities: &[UnifiedActivityItem],
-        _statistics: &(),
+        statistics: &ChartStatistics,
         screen_size: ScreenSize,
     ) {
         match ui_state.current_tab {
             TabIndex::Dashboard => self.render_overview::<B>(
                 f,
                 area,
-                _statistics,
+                statistics,
                 app_items,
                 ui_state,
                 unified_activities,
@@ -126,7 +135,7 @@ impl<'a> Renderer<'a> {
         &self,
         f: &mut Frame,
         area: Rect,
-        _statistics: &(),
+        statistics: &ChartStatistics,
         app_items: &[AppTableItem],
         ui_state: &UiState,
         unified_activities: &[UnifiedActivityItem],
@@ -140,17 +149,17 @@ impl<'a> Renderer<'a> {
             .split(area);
 
         // 渲染各个部分
-        self.render_time_header::<B>(f, main_chunks[0], _statistics, ui_state, screen_size);
+        self.render_time_header::<B>(f, main_chunks[0], statistics, ui_state, screen_size);
 
         if main_chunks.len() > 1 {
-            self.render_week_chart::<B>(f, main_chunks[1], _statistics, ui_state, screen_size);
+            self.render_week_chart::<B>(f, main_chunks[1], statistics, ui_state, screen_size);
         }
 
         if main_chunks.len() > 2 {
             self.render_daily_chart_and_categories::<B>(
                 f,
                 main_chunks[2],
-                _statistics,
+                statistics,
                 ui_state,
                 screen_size,
             );
@@ -158,7 +167,13 @@ impl<'a> Renderer<'a> {
 
         // 大屏幕上显示扇形图（使用文本表示）
         if screen_size.is_large() && main_chunks.len() > 3 {
-            self.render_pie_charts::<B>(f, main_chunks[3], unified_activities, screen_size);
+            self.render_pie_charts::<B>(
+                f,
+                main_chunks[3],
+                statistics,
+                unified_activities,
+                screen_size,
+            );
         }
 
         // 应用列表
@@ -184,29 +199,25 @@ impl<'a> Renderer<'a> {
         &self,
         f: &mut Frame,
         area: Rect,
-        _statistics: &(),
+        statistics: &ChartStatistics,
         ui_state: &UiState,
         _screen_size: ScreenSize,
     ) {
-        // 日期选择器
-        let date_text = match ui_state.time_range {
-            TimeRangeFilter::Today => "今天",
-            TimeRangeFilter::Yesterday => "昨天",
-            TimeRangeFilter::ThisWeek => "本周",
-            TimeRangeFilter::LastWeek => "上周",
-            TimeRangeFilter::ThisMonth => "本月",
-            TimeRangeFilter::LastMonth => "上月",
-            TimeRangeFilter::All => "全部",
+        // 日期选择器（输入自定义范围时显示输入框）
+        let date_text = if ui_state.input_mode == InputMode::EditingTimeRange {
+            time_range_prompt(ui_state)
+        } else {
+            format!(
+                "{} ◀ {} ▶",
+                statistics.today.format("%m月%d日"),
+                ui_state.time_range.title()
+            )
         };
 
-        let date_paragraph = Paragraph::new(format!(
-            "{} ◀ {} ▶",
-            chrono::Local::now().format("%m月%d日"),
-            date_text
-        ))
-        .style(self.theme.table_row_style())
-        .block(Block::default().borders(Borders::NONE))
-        .alignment(Alignment::Center);
+        let date_paragraph = Paragraph::new(date_text)
+            .style(self.theme.table_row_style())
+            .block(Block::default().borders(Borders::NONE))
+            .alignment(Alignment::Center);
         f.render_widget(date_paragraph, area);
     }
 
@@ -215,26 +226,21 @@ impl<'a> Renderer<'a> {
         &self,
         f: &mut Frame,
         area: Rect,
-        _statistics: &(),
+        statistics: &ChartStatistics,
         _ui_state: &UiState,
         screen_size: ScreenSize,
     ) {
-        // 模拟一周的数据（实际应该从统计数据中获取）
-        let week_data = vec![
-            ("一", 6),
-            ("二", 8),
-            ("三", 7),
-            ("四", 9),
-            ("五", 5),
-            ("六", 3),
-            ("日", 4),
-        ];
+        // 周一到周日每天的使用时长（分钟）
+        let week_data: Vec<(&str, u64)> = ["一", "二", "三", "四", "五", "六", "日"]
+            .into_iter()
+            .zip(statistics.week.iter().map(|seconds| seconds / 60))
+            .collect();
 
         let bar_width = ResponsiveLayout::bar_chart_width(screen_size);
         let title = if screen_size.is_small() {
             "本周"
         } else {
-            "本周使用情况"
+            "本周使用情况（分钟）"
         };
 
         let chart = BarChart::default()
@@ -252,7 +258,7 @@ impl<'a> Renderer<'a> {
         &self,
         f: &mut Frame,
         area: Rect,
-        _statistics: &(),
+        statistics: &ChartStatistics,
         _ui_state: &UiState,
         screen_size: ScreenSize,
     ) {
@@ -264,45 +270,73 @@ impl<'a> Renderer<'a> {
             .split(area);
 
         // 左侧：24小时使用情况图表
-        self.render_24hour_chart::<B>(f, chunks[0], screen_size);
+        self.render_24hour_chart::<B>(f, chunks[0], statistics, screen_size);
 
         // 右侧：分类统计（仅在中等和大屏幕上显示）
         if chunks.len() > 1 && !screen_size.is_small() {
-            self.render_category_stats::<B>(f, chunks[1], _statistics, screen_size);
+            self.render_category_stats::<B>(f, chunks[1], statistics, screen_size);
         }
     }
 
     /// 渲染24小时图表
-    fn render_24hour_chart<B: Backend>(&self, f: &mut Frame, area: Rect, screen_size: ScreenSize) {
-        // 模拟24小时数据
-        let data_points = match screen_size {
-            ScreenSize::Small => 12, // 每2小时一个点
-            _ => 24,                 // 每小时一个点
+    fn render_24hour_chart<B: Backend>(
+        &self,
+        f: &mut Frame,
+        area: Rect,
+        statistics: &ChartStatistics,
+        screen_size: ScreenSize,
+    ) {
+        // 今天每小时的使用时长（分钟），小屏幕上每2小时一个点
+        let hours_per_point = match screen_size {
+            ScreenSize::Small => 2,
+            _ => 1,
         };
-
-        let hour_data: Vec<u64> = (0..data_points)
-            .map(|i| {
-                let h = if data_points == 12 { i * 2 } else { i };
-                match h {
-                    6..=8 => 2,   // 早上
-                    9..=11 => 4,  // 上午
-                    12..=13 => 2, // 午休
-                    14..=17 => 5, // 下午
-                    18..=20 => 3, // 晚上
-                    21..=23 => 2, // 夜晚
-                    _ => 0,       // 深夜
-                }
-            })
+        let hour_data: Vec<u64> = statistics
+            .hours
+            .chunks(hours_per_point)
+            .map(|seconds| seconds.iter().sum::<u64>() / 60)
             .collect();
 
-        let title = if screen_size.is_small() {
-            "24小时分布"
-        } else {
-            "24小时使用分布"
+        let mut title = match (screen_size.is_small(), statistics.split_hour) {
+            (true, _) => "24小时分布".to_string(),
+            (false, 0) => "24小时使用分布".to_string(),
+            (false, hour) => format!("24小时使用分布（{}点起）", hour),
         };
 
+        let mut block = Block::default().borders(Borders::ALL);
+        let status = &statistics.status_today;
+        if !status.is_empty() {
+            // 开机时间与活跃时间对比，底部按小时显示主要状态
+            title.push_str(&format!(
+                " 开机 {} 活跃 {} ({:.0}%)",
+                format_duration_short(status.computer_on()),
+                format_duration_short(status.active),
+                status.active_ratio() * 100.0
+            ));
+            if statistics.fullscreen_today > 0 {
+                title.push_str(&format!(
+                    " 全屏 {}",
+                    format_duration_short(statistics.fullscreen_today)
+                ));
+            }
+            let strip: String = statistics
+                .status_hours
+                .chunks(hours_per_point)
+                .map(|hours| {
+                    let mut summary = StatusSummary::default();
+                    for hour in hours {
+                        for kind in StatusKind::ALL {
+                            summary.add(kind, hour.get(kind));
+                        }
+                    }
+                    summary.dominant().map_or('·', |kind| kind.symbol())
+                })
+                .collect();
+            block = block.title_bottom(strip);
+        }
+
         let sparkline = Sparkline::default()
-            .block(Block::default().borders(Borders::ALL).title(title))
+            .block(block.title(title))
             .data(&hour_data)
             .style(self.theme.chart_style());
 
@@ -314,7 +348,7 @@ impl<'a> Renderer<'a> {
         &self,
         f: &mut Frame,
         area: Rect,
-        _statistics: &(),
+        _statistics: &ChartStatistics,
         screen_size: ScreenSize,
     ) {
         // 计算各类别的时间（简化版本）
@@ -359,6 +393,7 @@ impl<'a> Renderer<'a> {
         &self,
         f: &mut Frame,
         area: Rect,
+        statistics: &ChartStatistics,
         unified_activities: &[UnifiedActivityItem],
         screen_size: ScreenSize,
     ) {
@@ -369,7 +404,7 @@ impl<'a> Renderer<'a> {
             .split(area);
 
         // 左侧：应用使用时间分布
-        self.render_app_pie_chart::<B>(f, chunks[0], unified_activities, screen_size);
+        self.render_app_pie_chart::<B>(f, chunks[0], statistics, screen_size);
 
         // 右侧：生产力分布
         self.render_productivity_pie_chart::<B>(f, chunks[1], unified_activities, screen_size);
@@ -380,38 +415,11 @@ impl<'a> Renderer<'a> {
         &self,
         f: &mut Frame,
         area: Rect,
-        unified_activities: &[UnifiedActivityItem],
+        statistics: &ChartStatistics,
         _screen_size: ScreenSize,
     ) {
-        // 获取今日的开始时间（0点为分割点，可配置）
-        // TODO: 从配置中读取 day_split_hour
-        let split_hour = 0u32; // 默认0点分割
-        let today_start = chrono::Local::now()
-            .date_naive()
-            .and_hms_opt(split_hour, 0, 0)
-            .unwrap()
-            .and_local_timezone(chrono::Local)
-            .unwrap()
-            .with_timezone(&chrono::Utc);
-
-        // 过滤今日的活动数据
-        let mut app_data: std::collections::HashMap<String, u64> = std::collections::HashMap::new();
-
-        for activity in unified_activities.iter() {
-            // 检查活动是否在今日
-            if activity.last_active >= today_start {
-                *app_data.entry(activity.app_name.clone()).or_insert(0) += activity.total_duration;
-            }
-        }
-
-        // 按时长排序，取前5个应用
-        let mut sorted_apps: Vec<_> = app_data.iter().collect();
-        sorted_apps.sort_by(|a, b| b.1.cmp(a.1));
-        let top_apps: std::collections::HashMap<String, u64> = sorted_apps
-            .into_iter()
-            .take(5)
-            .map(|(k, v)| (k.clone(), *v))
-            .collect();
+        // 今日（按配置的时区和日分割点）时长最多的5个应用
+        let top_apps: Vec<&(String, u64)> = statistics.today_by_app.iter().take(5).collect();
 
         if top_apps.is_empty() {
             let empty_text = Paragraph::new("今日暂无应用数据")
@@ -423,14 +431,10 @@ impl<'a> Renderer<'a> {
         }
 
         // 简化的饼状图显示（使用文本表示）
-        let total: u64 = top_apps.values().sum();
+        let total: u64 = top_apps.iter().map(|(_, duration)| duration).sum();
         let mut items = Vec::new();
 
-        // 按时长排序显示
-        let mut sorted_display: Vec<_> = top_apps.iter().collect();
-        sorted_display.sort_by(|a, b| b.1.cmp(a.1));
-
-        for (app, duration) in sorted_display {
+        for (app, duration) in top_apps {
             let percentage = if total > 0 {
                 (*duration as f64 / total as f64) * 100.0
             } else {
@@ -746,6 +750,10 @@ impl<'a> Renderer<'a> {
 
                 let status = if item.is_currently_active {
                     "🟢 活跃"
+                } else if item.recovered_count > 0 {
+                    "🟠 已恢复"
+                } else if item.manual_count > 0 {
+                    "✍ 手动"
                 } else {
                     "⚪ 空闲"
                 };
@@ -760,6 +768,14 @@ impl<'a> Renderer<'a> {
                 let app_name = ResponsiveLayout::truncate_text(&item.app_name, 15, screen_size);
                 let window_title =
                     ResponsiveLayout::truncate_text(&item.window_title, 35, screen_size);
+                // 异常退出后恢复的记录在标题前加标记，结束时间是推算出来的
+                let window_title = if item.recovered_count > 0 {
+                    format!("{RECOVERED_MARKER}{window_title}")
+                } else if item.manual_count > 0 {
+                    format!("{MANUAL_MARKER}{window_title}")
+                } else {
+                    window_title
+                };
 
                 // 根据屏幕大小构建单元格
                 let mut cells = vec![
@@ -890,24 +906,22 @@ impl<'a> Renderer<'a> {
             SortOrder::Descending => "降序",
         };
 
-        let time_range_text = match ui_state.time_range {
-            TimeRangeFilter::Today => "今天",
-            TimeRangeFilter::Yesterday => "昨天",
-            TimeRangeFilter::ThisWeek => "本周",
-            TimeRangeFilter::LastWeek => "上周",
-            TimeRangeFilter::ThisMonth => "本月",
-            TimeRangeFilter::LastMonth => "上月",
-            TimeRangeFilter::All => "全部",
-        };
+        let time_range_text = ui_state.time_range.title();
 
-        let control_text = if screen_size.is_small() {
+        let control_text = if ui_state.input_mode == InputMode::EditingTimeRange {
+            time_range_prompt(ui_state)
+        } else if ui_state.input_mode == InputMode::AddingManualEntry {
+            manual_entry_prompt(ui_state)
+        } else if ui_state.input_mode == InputMode::LabelingAway {
+            away_label_prompt(ui_state)
+        } else if screen_size.is_small() {
             format!(
                 "视图: {} | 排序: {} | 时间: {}",
                 view_mode_text, sort_by_text, time_range_text
             )
         } else {
             format!(
-                "视图: {} | 排序: {} ({}) | 时间: {} | v:切换视图 s:排序 o:顺序 f:时间范围",
+                "视图: {} | 排序: {} ({}) | 时间: {} | v:切换视图 s:排序 o:顺序 f:时间范围 F:自定义 a:手动记录 l:标注离开",
                 view_mode_text, sort_by_text, sort_order_text, time_range_text
             )
         };
@@ -1030,9 +1044,16 @@ impl<'a> Renderer<'a> {
             let start_time = chrono::DateTime::<chrono::Local>::from(item.start_time)
                 .format("%H:%M:%S")
                 .to_string();
+            let window_title = if item.recovered {
+                format!("{RECOVERED_MARKER}{}", item.window_title)
+            } else if item.manual {
+                format!("{MANUAL_MARKER}{}", item.window_title)
+            } else {
+                item.window_title.clone()
+            };
             let cells = vec![
                 Cell::from(item.app_name.clone()),
-                Cell::from(item.window_title.clone()),
+                Cell::from(window_title),
                 Cell::from(start_time),
                 Cell::from(format_duration(item.duration)),
             ];
@@ -1094,3 +1115,39 @@ impl<'a> Renderer<'a> {
         dialog_widget.render::<B>(f, area);
     }
 }
+
+/// 自定义时间范围的输入提示
+fn time_range_prompt(ui_state: &UiState) -> String {
+    format!(
+        "时间范围: {}_ (YYYY-MM-DD..YYYY-MM-DD / 7d / 30d，Enter确认 Esc取消)",
+        ui_state.input_buffer
+    )
+}
+
+/// 标注离开时段的输入提示
+fn away_label_prompt(ui_state: &UiState) -> String {
+    let Some(period) = ui_state.pending_away.first() else {
+        return String::new();
+    };
+    format!(
+        "离开 {}，标注为 ({}个待标注): {}_ (名称[@项目]，如 {}；- 忽略，Enter确认 Esc稍后)",
+        describe(period, ui_state.timezone),
+        ui_state.pending_away.len(),
+        ui_state.input_buffer,
+        SUGGESTED_LABELS.join("/")
+    )
+}
+
+/// 手动记录表单的输入提示
+fn manual_entry_prompt(ui_state: &UiState) -> String {
+    let form = &ui_state.manual_form;
+    let (name, hint) = form.current_field();
+    format!(
+        "手动记录 ({}/{}) {}: {}_ ({}，Enter下一项 Esc取消)",
+        form.step() + 1,
+        ManualEntryForm::FIELDS.len(),
+        name,
+        ui_state.input_buffer,
+        hint
+    )
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
@@ -7,12 +8,18 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
+use std::collections::HashSet;
 use std::io;
 use std::time::{Duration, Instant};
 
 use crate::ai::manager::AIConfigManager;
 use crate::config::manager::ConfigManager;
-use crate::ui::components::{InputMode, RecentActivityItem, UiState};
+use crate::core::app_rules::AppRules;
+use crate::core::away::parse_label;
+use crate::core::manual::{ManualEntry, OverlapPolicy};
+use crate::core::power::AwayPeriod;
+use crate::storage::StoreOptions;
+use crate::ui::components::{InputMode, RecentActivityItem, TabIndex, TimeRangeFilter, UiState};
 use crate::ui::data::DataManager;
 use crate::ui::events::{EventHandler, EventResult};
 use crate::ui::renderer::Renderer;
@@ -31,19 +38,31 @@ pub struct TuiApp {
     last_tick: Instant,
     tick_counter: u32,
     data_initialized: bool,
+    /// 与已有记录重叠、等待用户确认的手动记录
+    pending_manual_entry: Option<ManualEntry>,
+    /// 已经询问过的离开时段（按开始时间），避免反复弹出
+    offered_away: HashSet<DateTime<Utc>>,
 }
 
 impl TuiApp {
     /// 创建新的 TUI 应用程序
-    pub fn new(data_file: String) -> anyhow::Result<Self> {
+    pub fn new(data_file: String, options: StoreOptions) -> anyhow::Result<Self> {
         let config_manager = ConfigManager::new()?;
         let ai_manager = AIConfigManager::new()?;
         let theme = Theme::default();
         let event_handler = EventHandler::new();
-        let data_manager = DataManager::new(data_file)?;
+        let day_boundary = config_manager.app_config.ui.day_boundary();
+        let ui_state = UiState {
+            timezone: day_boundary.timezone,
+            ..UiState::default()
+        };
+        let mut data_manager = DataManager::new(data_file, options)?;
+        data_manager.set_day_boundary(day_boundary);
+        data_manager.set_app_rules(AppRules::load_default());
+        data_manager.set_time_range(ui_state.time_range)?;
 
         Ok(Self {
-            ui_state: UiState::default(),
+            ui_state,
             config_manager,
             ai_manager,
             theme,
@@ -54,9 +73,17 @@ impl TuiApp {
             last_tick: Instant::now(),
             tick_counter: 0,
             data_initialized: false,
+            pending_manual_entry: None,
+            offered_away: HashSet::new(),
         })
     }
 
+    /// 设置初始时间范围（例如命令行指定的 `--range`）
+    pub fn set_time_range(&mut self, time_range: TimeRangeFilter) -> anyhow::Result<()> {
+        self.ui_state.time_range = time_range;
+        self.data_manager.set_time_range(time_range)
+    }
+
     /// 运行 TUI 应用程序
     pub fn run(&mut self) -> anyhow::Result<()> {
         // 设置终端
@@ -133,6 +160,8 @@ impl TuiApp {
                 // 不要因为数据初始化失败就退出TUI
             }
             self.data_initialized = true;
+            self.show_load_warnings();
+            self.prompt_pending_away();
         }
 
         // 获取数据
@@ -144,14 +173,16 @@ impl TuiApp {
 
         // 如果有当前活动，将其添加到最近活动列表的开头
         if let Some(current_activity) = self.data_manager.get_current_activity() {
-            let current_duration =
-                (chrono::Utc::now() - current_activity.start_time).num_seconds() as u64;
+            // 当前活动的时长由守护进程按心跳更新
+            let current_duration = current_activity.duration;
             let current_item = RecentActivityItem {
                 app_name: current_activity.app_name.clone(),
                 window_title: current_activity.window_title.clone(),
                 start_time: current_activity.start_time,
                 end_time: None, // 当前活动还没有结束
                 duration: current_duration,
+                recovered: false,
+                manual: false,
             };
             recent_activities.insert(0, current_item);
         }
@@ -168,7 +199,7 @@ impl TuiApp {
             .pagination
             .set_total_items(unified_activities.len());
 
-        let statistics = (); // 已删除统计功能
+        let statistics = self.data_manager.get_chart_statistics();
 
         // 创建渲染器并渲染
         let renderer = Renderer::new(&self.theme);
@@ -225,6 +256,9 @@ impl TuiApp {
                 | crate::ui::components::InputMode::EditingMaxTokens
                 | crate::ui::components::InputMode::EditingModel
                 | crate::ui::components::InputMode::Search
+                | crate::ui::components::InputMode::EditingTimeRange
+                | crate::ui::components::InputMode::AddingManualEntry
+                | crate::ui::components::InputMode::LabelingAway
         ) {
             self.ui_state.input_mode = crate::ui::components::InputMode::Normal;
         }
@@ -364,6 +398,9 @@ impl TuiApp {
                 self.cancel_delete();
             }
             KeyCode::Enter => match self.ui_state.dialog_state.dialog_type {
+                DialogType::Confirmation if self.pending_manual_entry.is_some() => {
+                    self.confirm_manual_entry();
+                }
                 DialogType::Confirmation => {
                     self.confirm_delete();
                 }
@@ -441,9 +478,33 @@ impl TuiApp {
             EventResult::ToggleSortBy => self.ui_state.toggle_sort_by(),
             EventResult::ToggleSortOrder => self.ui_state.toggle_sort_order(),
             EventResult::ToggleChartMode => self.ui_state.toggle_chart_mode(),
-            EventResult::ToggleTimeRange => self.ui_state.toggle_time_range(),
-            EventResult::StartEditing(mode) => self.ui_state.input_mode = mode,
-            EventResult::StopEditing => self.ui_state.input_mode = InputMode::Normal,
+            EventResult::ToggleTimeRange => {
+                self.ui_state.toggle_time_range();
+                if let Err(e) = self.data_manager.set_time_range(self.ui_state.time_range) {
+                    log::warn!("加载时间范围数据失败: {}", e);
+                }
+            }
+            EventResult::StartEditing(InputMode::LabelingAway) => self.review_pending_away(),
+            EventResult::StartEditing(mode) => {
+                self.ui_state.input_buffer.clear();
+                self.ui_state.manual_form.clear();
+                self.ui_state.input_mode = mode;
+            }
+            EventResult::StopEditing => {
+                self.ui_state.input_buffer.clear();
+                self.ui_state.manual_form.clear();
+                self.ui_state.pending_away.clear();
+                self.ui_state.input_mode = InputMode::Normal;
+            }
+            EventResult::SaveInput if self.ui_state.input_mode == InputMode::EditingTimeRange => {
+                self.apply_time_range_input();
+            }
+            EventResult::SaveInput if self.ui_state.input_mode == InputMode::AddingManualEntry => {
+                self.apply_manual_entry_input();
+            }
+            EventResult::SaveInput if self.ui_state.input_mode == InputMode::LabelingAway => {
+                self.apply_away_label_input();
+            }
             EventResult::SaveInput => {}      // 已删除AI配置功能
             EventResult::TestConnection => {} // 已删除AI配置功能
             EventResult::SaveConfig => self.save_config(),
@@ -491,6 +552,107 @@ impl TuiApp {
         self.ui_state.show_help = !self.ui_state.show_help;
     }
 
+    /// 应用输入的自定义时间范围
+    fn apply_time_range_input(&mut self) {
+        let input = std::mem::take(&mut self.ui_state.input_buffer);
+        self.ui_state.input_mode = InputMode::Normal;
+
+        match TimeRangeFilter::parse(&input) {
+            Ok(time_range) => {
+                self.ui_state.time_range = time_range;
+                if let Err(e) = self.data_manager.set_time_range(time_range) {
+                    log::warn!("加载时间范围数据失败: {}", e);
+                }
+            }
+            Err(e) => self
+                .ui_state
+                .dialog_state
+                .show_error("时间范围无效", &e.to_string()),
+        }
+    }
+
+    /// 填写手动记录表单的当前字段，填完后添加记录
+    ///
+    /// 与已有记录重叠时先弹出确认框，确认后裁剪重叠的记录。
+    fn apply_manual_entry_input(&mut self) {
+        let input = std::mem::take(&mut self.ui_state.input_buffer);
+        if !self.ui_state.manual_form.push(input) {
+            return;
+        }
+        self.ui_state.input_mode = InputMode::Normal;
+
+        let form = std::mem::take(&mut self.ui_state.manual_form);
+        let entry = match form.to_entry(chrono::Utc::now(), self.ui_state.timezone) {
+            Ok(entry) => entry,
+            Err(e) => {
+                self.ui_state
+                    .dialog_state
+                    .show_error("手动记录无效", &e.to_string());
+                return;
+            }
+        };
+
+        match self.data_manager.find_overlaps(&entry) {
+            Ok(overlaps) if overlaps.is_empty() => {
+                self.add_manual_entry(&entry, OverlapPolicy::Reject)
+            }
+            Ok(overlaps) => {
+                let listed: Vec<String> = overlaps
+                    .iter()
+                    .take(3)
+                    .map(|record| {
+                        format!(
+                            "{} {}",
+                            chrono::DateTime::<chrono::Local>::from(record.start_time)
+                                .format("%H:%M"),
+                            record.app_name
+                        )
+                    })
+                    .collect();
+                self.ui_state.dialog_state.show_confirmation(
+                    "时间重叠",
+                    &format!(
+                        "手动记录与 {} 条已有记录重叠:\n{}\n\n确认后裁剪重叠部分，只保留手动记录。",
+                        overlaps.len(),
+                        listed.join("\n")
+                    ),
+                );
+                self.pending_manual_entry = Some(entry);
+            }
+            Err(e) => self
+                .ui_state
+                .dialog_state
+                .show_error("查询重叠记录失败", &e.to_string()),
+        }
+    }
+
+    /// 确认添加与已有记录重叠的手动记录
+    fn confirm_manual_entry(&mut self) {
+        let confirmed = self.ui_state.dialog_state.selected_option == 0;
+        self.ui_state.dialog_state.hide();
+        if let Some(entry) = self.pending_manual_entry.take().filter(|_| confirmed) {
+            self.add_manual_entry(&entry, OverlapPolicy::Trim);
+        }
+    }
+
+    fn add_manual_entry(&mut self, entry: &ManualEntry, policy: OverlapPolicy) {
+        match self.data_manager.add_manual_entry(entry, policy) {
+            Ok(()) => self.ui_state.dialog_state.show_info(
+                "已添加手动记录",
+                &format!(
+                    "{}\n{} ~ {}",
+                    entry.label,
+                    chrono::DateTime::<chrono::Local>::from(entry.start).format("%m-%d %H:%M"),
+                    chrono::DateTime::<chrono::Local>::from(entry.end).format("%m-%d %H:%M")
+                ),
+            ),
+            Err(e) => self
+                .ui_state
+                .dialog_state
+                .show_error("添加手动记录失败", &e.to_string()),
+        }
+    }
+
     /// 定时更新
     fn on_tick(&mut self) {
         // 降低刷新频率，避免界面闪动
@@ -502,6 +664,95 @@ impl TuiApp {
                 // 静默处理错误，避免在TUI中显示错误信息
                 log::debug!("定时刷新数据失败: {}", e);
             }
+            self.show_load_warnings();
+            self.prompt_pending_away();
+        }
+    }
+
+    /// 出现新的待标注离开时段时（离开后回到电脑前）切换到活动页询问
+    fn prompt_pending_away(&mut self) {
+        if self.ui_state.input_mode != InputMode::Normal || self.ui_state.dialog_state.is_visible {
+            return;
+        }
+        let pending = match self.data_manager.pending_away() {
+            Ok(pending) => pending,
+            Err(e) => {
+                log::debug!("读取待标注的离开时段失败: {}", e);
+                return;
+            }
+        };
+        if pending
+            .iter()
+            .all(|period| self.offered_away.contains(&period.start))
+        {
+            return;
+        }
+        self.offered_away
+            .extend(pending.iter().map(|period| period.start));
+        self.start_away_review(pending);
+    }
+
+    /// 按 `l` 标注所有待标注的离开时段
+    fn review_pending_away(&mut self) {
+        match self.data_manager.pending_away() {
+            Ok(pending) if pending.is_empty() => self
+                .ui_state
+                .dialog_state
+                .show_info("离开标注", "没有待标注的离开时段"),
+            Ok(pending) => self.start_away_review(pending),
+            Err(e) => self
+                .ui_state
+                .dialog_state
+                .show_error("读取待标注的离开时段失败", &e.to_string()),
+        }
+    }
+
+    fn start_away_review(&mut self, pending: Vec<AwayPeriod>) {
+        self.ui_state.current_tab = TabIndex::Activities;
+        self.ui_state.input_buffer.clear();
+        self.ui_state.pending_away = pending;
+        self.ui_state.input_mode = InputMode::LabelingAway;
+    }
+
+    /// 标注正在询问的离开时段后询问下一个
+    ///
+    /// 输入 `名称[@项目]` 保存为手动记录，`-` 忽略该时段，留空跳过（下次仍会询问）。
+    fn apply_away_label_input(&mut self) {
+        let input = std::mem::take(&mut self.ui_state.input_buffer);
+        if self.ui_state.pending_away.is_empty() {
+            self.ui_state.input_mode = InputMode::Normal;
+            return;
+        }
+        let period = self.ui_state.pending_away.remove(0);
+        if self.ui_state.pending_away.is_empty() {
+            self.ui_state.input_mode = InputMode::Normal;
+        }
+
+        let result = if input.trim() == "-" {
+            self.data_manager.dismiss_away(&period).map(|_| ())
+        } else if let Some((label, project)) = parse_label(&input) {
+            self.data_manager
+                .label_away(&period, &label, project.as_deref())
+                .map(|_| ())
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            self.ui_state.pending_away.clear();
+            self.ui_state.input_mode = InputMode::Normal;
+            self.ui_state
+                .dialog_state
+                .show_error("标注离开时段失败", &e.to_string());
+        }
+    }
+
+    /// 显示加载数据时产生的警告（例如损坏的数据文件已被隔离）
+    fn show_load_warnings(&mut self) {
+        let warnings = self.data_manager.take_load_warnings();
+        if !warnings.is_empty() && !self.ui_state.dialog_state.is_visible {
+            self.ui_state
+                .dialog_state
+                .show_warning("数据文件异常", &warnings.join("\n"));
         }
     }
 
@@ -635,6 +886,7 @@ impl TuiApp {
 
     /// 取消删除
     fn cancel_delete(&mut self) {
+        self.pending_manual_entry = None;
         self.ui_state.dialog_state.hide();
     }
 
@@ -1,7 +1,12 @@
 // 时间相关的工具函数
 // 采用函数式编程风格
 
-use chrono::{DateTime, Datelike, Duration as ChronoDuration, Utc};
+use anyhow::{anyhow, Result};
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, TimeZone,
+    Timelike, Utc,
+};
+use chrono_tz::Tz;
 
 /// 格式化持续时间为可读字符串
 ///
@@ -42,30 +47,247 @@ pub fn duration_between(start: DateTime<Utc>, end: DateTime<Utc>) -> u64 {
     (end - start).num_seconds().max(0) as u64
 }
 
-/// 获取今天的开始时间
-pub fn today_start() -> DateTime<Utc> {
-    let now = Utc::now();
-    now.date_naive()
-        .and_hms_opt(0, 0, 0)
-        .map(|dt| dt.and_utc())
-        .unwrap_or(now)
+/// 解析时长：`1h30m`、`45m`、`90s`，不带单位的数字按分钟计算
+///
+/// # 示例
+/// ```
+/// use timetracker::utils::time::parse_duration_spec;
+///
+/// assert_eq!(parse_duration_spec("1h30m").unwrap(), 5400);
+/// assert_eq!(parse_duration_spec("45").unwrap(), 2700);
+/// assert_eq!(parse_duration_spec("90s").unwrap(), 90);
+/// ```
+pub fn parse_duration_spec(input: &str) -> Result<u64> {
+    let input = input.trim().to_lowercase();
+    if let Ok(minutes) = input.parse::<u64>() {
+        return Ok(minutes * 60);
+    }
+
+    let invalid = || anyhow!("无法识别的时长: {}，请使用 1h30m、45m 或 90s", input);
+    let mut total = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' if !number.is_empty() => {
+                let value: u64 = number.parse().map_err(|_| invalid())?;
+                total += value
+                    * match c {
+                        'h' => 3600,
+                        'm' => 60,
+                        _ => 1,
+                    };
+                number.clear();
+            }
+            c if c.is_whitespace() => {}
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+/// 按配置的时区解析当地时间：`YYYY-MM-DD HH:MM[:SS]`，或只写 `HH:MM[:SS]` 表示 `now`
+/// 在该时区的当天
+pub fn parse_local_datetime(
+    input: &str,
+    now: DateTime<Utc>,
+    timezone: DayTimeZone,
+) -> Result<DateTime<Utc>> {
+    let input = input.trim();
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+    .or_else(|| {
+        ["%H:%M:%S", "%H:%M"]
+            .iter()
+            .find_map(|format| chrono::NaiveTime::parse_from_str(input, format).ok())
+            .map(|time| timezone.to_naive(now).date().and_time(time))
+    })
+    .ok_or_else(|| {
+        anyhow!(
+            "无法识别的时间: {}，请使用 YYYY-MM-DD HH:MM 或 HH:MM",
+            input
+        )
+    })?;
+
+    timezone
+        .to_utc(naive)
+        .ok_or_else(|| anyhow!("本地时间 {} 不存在（夏令时切换）", input))
+}
+
+/// 划分日期使用的时区
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DayTimeZone {
+    /// 系统本地时区
+    #[default]
+    Local,
+    /// IANA 时区，例如 `Asia/Shanghai`
+    Named(Tz),
+}
+
+impl DayTimeZone {
+    /// 解析时区名称，空字符串或 `local` 表示系统本地时区
+    pub fn from_name(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() || name.eq_ignore_ascii_case("local") {
+            return Ok(DayTimeZone::Local);
+        }
+        name.parse::<Tz>()
+            .map(DayTimeZone::Named)
+            .map_err(|_| anyhow!("无效的时区: {name}（应为 IANA 时区名称，如 Asia/Shanghai）"))
+    }
+
+    /// 时区名称
+    pub fn name(&self) -> String {
+        match self {
+            DayTimeZone::Local => "local".to_string(),
+            DayTimeZone::Named(tz) => tz.name().to_string(),
+        }
+    }
+
+    /// UTC 时间对应的当地时间
+    pub fn to_naive(self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            DayTimeZone::Local => time.with_timezone(&Local).naive_local(),
+            DayTimeZone::Named(tz) => time.with_timezone(&tz).naive_local(),
+        }
+    }
+
+    /// 当地时间对应的 UTC 时间；夏令时重复的时刻取较早的一个，跳过的时刻返回 None
+    pub fn to_utc(self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            DayTimeZone::Local => Local
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+            DayTimeZone::Named(tz) => tz
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+        }
+    }
+
+    /// 当地整点对应的 UTC 时间，夏令时跳过的整点取其后第一个存在的整点
+    fn hour_to_utc(self, time: NaiveDateTime) -> DateTime<Utc> {
+        self.to_utc(time)
+            .or_else(|| self.to_utc(time + ChronoDuration::hours(1)))
+            .unwrap_or_else(|| time.and_utc())
+    }
+}
+
+/// 日期划分规则：时区 + 日分割点
+///
+/// 例如时区为 `Asia/Shanghai`、日分割点为 4 时，北京时间凌晨 3 点仍属于前一天。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DayBoundary {
+    pub timezone: DayTimeZone,
+    /// 一天开始的整点（0-23）
+    pub split_hour: u8,
+}
+
+impl DayBoundary {
+    pub fn new(timezone: DayTimeZone, split_hour: u8) -> Self {
+        Self {
+            timezone,
+            split_hour: split_hour.min(23),
+        }
+    }
+
+    /// 逻辑日期：当地时间减去日分割点后的日期
+    pub fn logical_date(&self, time: DateTime<Utc>) -> NaiveDate {
+        let local = self.timezone.to_naive(time);
+        (local - ChronoDuration::hours(self.split_hour as i64)).date()
+    }
+
+    /// 逻辑日期的开始时间（当天当地时间的日分割点）
+    pub fn day_start(&self, date: NaiveDate) -> DateTime<Utc> {
+        let hour = u32::from(self.split_hour.min(23));
+        let start = date.and_hms_opt(hour, 0, 0).expect("hour is within 0-23");
+        self.timezone.hour_to_utc(start)
+    }
+
+    /// 时间在逻辑日中的小时序号（0 对应日分割点所在的小时）
+    pub fn hour_of_day(&self, time: DateTime<Utc>) -> usize {
+        let hour = self.timezone.to_naive(time).hour() as usize;
+        (hour + 24 - self.split_hour as usize) % 24
+    }
+
+    /// 按逻辑日切分 [start, end) 区间，返回每一天及其中的时间段
+    pub fn split_days(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<(NaiveDate, DateTime<Utc>, DateTime<Utc>)> {
+        let mut pieces = Vec::new();
+        let mut cursor = start;
+        while cursor < end {
+            let date = self.logical_date(cursor);
+            let next = self.day_start(date + ChronoDuration::days(1));
+            let piece_end = if next > cursor { next.min(end) } else { end };
+            pieces.push((date, cursor, piece_end));
+            cursor = piece_end;
+        }
+        pieces
+    }
+
+    /// 按当地整点切分 [start, end) 区间，返回每段的小时序号及其中的时间段
+    pub fn split_hours(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<(usize, DateTime<Utc>, DateTime<Utc>)> {
+        let mut pieces = Vec::new();
+        let mut cursor = start;
+        while cursor < end {
+            let local = self.timezone.to_naive(cursor);
+            let hour = local
+                .date()
+                .and_hms_opt(local.hour(), 0, 0)
+                .expect("hour is within 0-23");
+            let next = self.timezone.hour_to_utc(hour + ChronoDuration::hours(1));
+            let piece_end = if next > cursor {
+                next.min(end)
+            } else {
+                (cursor + ChronoDuration::hours(1)).min(end)
+            };
+            pieces.push((self.hour_of_day(cursor), cursor, piece_end));
+            cursor = piece_end;
+        }
+        pieces
+    }
+}
+
+/// 获取今天的开始时间（按给定时区和日分割点）
+pub fn today_start(boundary: DayBoundary) -> DateTime<Utc> {
+    TimeRange::Today.bounds(boundary).0
+}
+
+/// 获取本周的开始时间（周一的日分割点）
+pub fn week_start(boundary: DayBoundary) -> DateTime<Utc> {
+    TimeRange::ThisWeek.bounds(boundary).0
+}
+
+/// 获取本月的开始时间（1 日的日分割点）
+pub fn month_start(boundary: DayBoundary) -> DateTime<Utc> {
+    TimeRange::ThisMonth.bounds(boundary).0
 }
 
-/// 获取本周的开始时间（周一）
-pub fn week_start() -> DateTime<Utc> {
-    let now = Utc::now();
-    let days_since_monday = now.weekday().num_days_from_monday();
-    now - ChronoDuration::days(days_since_monday as i64)
+/// 日期所在周的周一
+pub fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - ChronoDuration::days(date.weekday().num_days_from_monday() as i64)
 }
 
-/// 获取本月的开始时间
-pub fn month_start() -> DateTime<Utc> {
-    let now = Utc::now();
-    now.date_naive()
-        .with_day(1)
-        .and_then(|date| date.and_hms_opt(0, 0, 0))
-        .map(|dt| dt.and_utc())
-        .unwrap_or(now)
+/// 日期所在月的 1 日
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("day 1 exists in every month")
 }
 
 /// 时间范围枚举
@@ -77,56 +299,65 @@ pub enum TimeRange {
     LastWeek,
     ThisMonth,
     LastMonth,
+    /// 最近 N 天（含今天）
+    LastDays(u32),
     Custom(DateTime<Utc>, DateTime<Utc>),
 }
 
 impl TimeRange {
-    /// 获取时间范围的开始和结束时间
-    pub fn bounds(self) -> (DateTime<Utc>, DateTime<Utc>) {
-        let now = Utc::now();
+    /// 以当前时间、按给定时区和日分割点划分日期，获取 [开始, 结束) 区间
+    pub fn bounds(self, boundary: DayBoundary) -> (DateTime<Utc>, DateTime<Utc>) {
+        self.bounds_at(Utc::now(), boundary)
+    }
+
+    /// 以 `now` 为当前时间、按给定时区和日分割点划分日期，获取 [开始, 结束) 区间
+    ///
+    /// 今天、本周、本月等当前周期的结束时间是下一个周期的开始时间。
+    pub fn bounds_at(
+        self,
+        now: DateTime<Utc>,
+        boundary: DayBoundary,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        let today = boundary.logical_date(now);
+        let start = |date: NaiveDate| boundary.day_start(date);
+        let tomorrow = today + ChronoDuration::days(1);
 
         match self {
-            TimeRange::Today => {
-                let start = today_start();
-                (start, now)
-            }
-            TimeRange::Yesterday => {
-                let today = today_start();
-                let yesterday = today - ChronoDuration::days(1);
-                (yesterday, today)
-            }
+            TimeRange::Today => (start(today), start(tomorrow)),
+            TimeRange::Yesterday => (start(today - ChronoDuration::days(1)), start(today)),
             TimeRange::ThisWeek => {
-                let start = week_start();
-                (start, now)
+                let monday = monday_of(today);
+                (start(monday), start(monday + ChronoDuration::days(7)))
             }
             TimeRange::LastWeek => {
-                let this_week = week_start();
-                let last_week = this_week - ChronoDuration::days(7);
-                (last_week, this_week)
+                let monday = monday_of(today);
+                (start(monday - ChronoDuration::days(7)), start(monday))
             }
             TimeRange::ThisMonth => {
-                let start = month_start();
-                (start, now)
+                let first = first_of_month(today);
+                let next = first_of_month(first + ChronoDuration::days(31));
+                (start(first), start(next))
             }
             TimeRange::LastMonth => {
-                let this_month = month_start();
-                let last_month = this_month - ChronoDuration::days(1);
-                let last_month_start = last_month
-                    .date_naive()
-                    .with_day(1)
-                    .and_then(|date| date.and_hms_opt(0, 0, 0))
-                    .map(|dt| dt.and_utc())
-                    .unwrap_or(last_month);
-                (last_month_start, this_month)
+                let first = first_of_month(today);
+                let previous = first_of_month(first - ChronoDuration::days(1));
+                (start(previous), start(first))
+            }
+            TimeRange::LastDays(days) => {
+                let days = days.max(1) as i64;
+                (
+                    start(tomorrow - ChronoDuration::days(days)),
+                    start(tomorrow),
+                )
             }
             TimeRange::Custom(start, end) => (start, end),
         }
     }
 
-    /// 检查给定时间是否在范围内
-    pub fn contains(self, time: DateTime<Utc>) -> bool {
-        let (start, end) = self.bounds();
-        time >= start && time <= end
+    /// 检查给定时间是否在范围内（按给定时区和日分割点划分日期）
+    pub fn contains(self, time: DateTime<Utc>, boundary: DayBoundary) -> bool {
+        let (start, end) = self.bounds(boundary);
+        time >= start && time < end
     }
 }
 
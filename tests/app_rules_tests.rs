@@ -0,0 +1,176 @@
+// 应用规则测试
+// 测试按进程名、应用路径、bundle id 统一应用名，内置规则、生产力分类，以及对历史记录重新应用规则
+
+mod common;
+
+use anyhow::Result;
+use common::titled_heartbeat;
+use timetracker::{
+    core::{
+        app_rules::{glob_match, AppAlias, AppCategory, AppRules, WindowIdentity},
+        tracker::TimeTracker,
+    },
+    storage::MemoryStore,
+};
+
+fn window<'a>(app: &'a str, title: &'a str) -> WindowIdentity<'a> {
+    WindowIdentity {
+        app_name: app,
+        window_title: title,
+        app_path: None,
+        bundle_id: None,
+    }
+}
+
+fn user_rules() -> Result<AppRules> {
+    Ok(toml::from_str(
+        r#"
+        [[alias]]
+        name = "VS Code"
+        process = ["code", "Code - OSS", "VSCode"]
+        app_path = ["/opt/vscodium/*"]
+        bundle_id = ["com.microsoft.VSCode"]
+        category = "productive"
+
+        [[alias]]
+        name = "Steam"
+        process = ["steamwebhelper"]
+
+        [categories]
+        unproductive = ["hacker news"]
+        "#,
+    )?)
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match(
+        "*/Visual Studio Code.app/*",
+        "/Applications/Visual Studio Code.app/Contents/MacOS/Electron"
+    ));
+    assert!(glob_match("/opt/*/bin/app?", "/opt/foo/bin/app2"));
+    assert!(glob_match(
+        "*\\code.exe",
+        "C:\\Program Files\\Microsoft VS Code\\Code.exe"
+    ));
+    assert!(!glob_match("/opt/*/bin/app?", "/opt/foo/bin/app"));
+    assert!(!glob_match("/usr/bin/*", "/opt/bin/code"));
+}
+
+#[test]
+fn test_user_aliases_merge_names() -> Result<()> {
+    let rules = user_rules()?;
+
+    for app in ["code", "Code - OSS", "vscode"] {
+        assert_eq!(
+            rules.canonical_name(&window(app, "main.rs")),
+            Some("VS Code")
+        );
+    }
+
+    let by_path = WindowIdentity {
+        app_path: Some("/opt/vscodium/codium"),
+        ..window("codium", "main.rs")
+    };
+    assert_eq!(rules.canonical_name(&by_path), Some("VS Code"));
+
+    let by_bundle = WindowIdentity {
+        bundle_id: Some("com.microsoft.VSCode"),
+        ..window("Electron", "main.rs")
+    };
+    assert_eq!(rules.canonical_name(&by_bundle), Some("VS Code"));
+
+    // 用户规则之后仍使用内置规则
+    assert_eq!(
+        rules.canonical_name(&window("electron", "general - Slack")),
+        Some("Slack")
+    );
+    assert_eq!(rules.canonical_name(&window("electron", "Untitled")), None);
+    Ok(())
+}
+
+#[test]
+fn test_builtin_rules_can_be_disabled() -> Result<()> {
+    let rules = AppRules::default();
+    assert_eq!(
+        rules.canonical_name(&window("java", "Project - IntelliJ IDEA")),
+        Some("IntelliJ IDEA")
+    );
+    assert_eq!(
+        rules.canonical_name(&window("Code - OSS", "main.rs")),
+        Some("VSCode")
+    );
+
+    let rules = AppRules {
+        builtin: false,
+        aliases: vec![AppAlias::new("Terminal").processes(&["gnome-terminal-server"])],
+        ..AppRules::default()
+    };
+    assert_eq!(
+        rules.canonical_name(&window("java", "Project - IntelliJ IDEA")),
+        None
+    );
+    assert_eq!(
+        rules.canonical_name(&window("gnome-terminal-server", "bash")),
+        Some("Terminal")
+    );
+    assert_eq!(rules.category("VSCode"), AppCategory::Neutral);
+    Ok(())
+}
+
+#[test]
+fn test_categories() -> Result<()> {
+    let rules = user_rules()?;
+    assert_eq!(rules.category("VS Code"), AppCategory::Productive);
+    assert_eq!(
+        rules.category("Hacker News Reader"),
+        AppCategory::Unproductive
+    );
+    // 内置分类
+    assert_eq!(rules.category("Spotify"), AppCategory::Unproductive);
+    assert_eq!(rules.category("Finder"), AppCategory::Neutral);
+    assert_eq!(rules.category("Obsidian"), AppCategory::Productive);
+    Ok(())
+}
+
+#[test]
+fn test_rules_apply_at_capture_time() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.set_app_rules(user_rules()?);
+    tracker.load_data()?;
+
+    tracker.record_heartbeat(titled_heartbeat("code", "main.rs", 0))?;
+    let mut by_bundle = titled_heartbeat("Electron", "main.rs", 1);
+    by_bundle.bundle_id = Some("com.microsoft.VSCode".to_string());
+    tracker.record_heartbeat(by_bundle)?;
+
+    // 两个别名合并为同一条记录
+    assert!(tracker.data.activities.is_empty());
+    let current = tracker.current_activity.as_ref().unwrap();
+    assert_eq!(current.app_name, "VS Code");
+    assert_eq!(current.duration, 1);
+    Ok(())
+}
+
+#[test]
+fn test_apply_rules_to_history() -> Result<()> {
+    let rules = user_rules()?;
+    let mut records: Vec<_> = ["code", "Code - OSS", "VS Code", "steamwebhelper", "Firefox"]
+        .iter()
+        .map(|app| titled_heartbeat(app, "Window", 0).into_record())
+        .collect();
+
+    let renamed = rules.apply_to_records(&mut records);
+    assert_eq!(renamed.values().sum::<usize>(), 3);
+    assert_eq!(
+        renamed.get(&("code".to_string(), "VS Code".to_string())),
+        Some(&1)
+    );
+
+    let apps: Vec<_> = records.iter().map(|r| r.app_name.as_str()).collect();
+    assert_eq!(apps, ["VS Code", "VS Code", "VS Code", "Steam", "Firefox"]);
+
+    // 再次应用没有变化
+    assert!(rules.apply_to_records(&mut records).is_empty());
+    Ok(())
+}
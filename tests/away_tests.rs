@@ -0,0 +1,257 @@
+// 离开时段标注测试
+// 测试闲置超时后记为离开、回来后放入待标注队列、锁屏后有输入时视为解锁、队列文件持久化、按配置时区显示，
+// 以及标注为手动记录
+
+mod common;
+
+use anyhow::Result;
+use chrono::Duration;
+use common::{at_minute, temp_dir};
+use std::path::Path;
+use timetracker::{
+    config::app::ActivityDetectionConfig,
+    core::{
+        away::{describe, parse_label, queue_path, AwayQueue},
+        manual::OverlapPolicy,
+        platform::WindowInfo,
+        power::{AwayPeriod, AwayReason, PowerEvent},
+        tracker::{RecordSource, TimeTracker},
+    },
+    storage::MemoryStore,
+    utils::time::DayTimeZone,
+};
+
+fn period(start: i64, end: i64) -> AwayPeriod {
+    AwayPeriod {
+        start: at_minute(start),
+        end: Some(at_minute(end)),
+        reason: AwayReason::Idle,
+    }
+}
+
+fn tracker_with_activity(review_minutes: Option<u64>) -> Result<TimeTracker> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.set_away_review(review_minutes);
+    tracker.load_data()?;
+    tracker.update_activity(WindowInfo::new(
+        "Editor".to_string(),
+        "main.rs".to_string(),
+        42,
+    ))?;
+    Ok(tracker)
+}
+
+#[test]
+fn test_idle_becomes_away_period() -> Result<()> {
+    let mut tracker = tracker_with_activity(Some(15))?;
+    let start = tracker.current_activity.as_ref().unwrap().start_time;
+
+    // 闲置超时后，在最后一次输入时结束当前活动
+    let last_input = start + Duration::seconds(30);
+    tracker.observe_idle(true, last_input, last_input + Duration::minutes(5))?;
+    assert!(tracker.is_away());
+    assert!(tracker.current_activity.is_none());
+    assert_eq!(tracker.data.activities[0].end_time, Some(last_input));
+
+    // 持续闲置不产生新的离开时段
+    tracker.observe_idle(true, last_input, last_input + Duration::minutes(20))?;
+    assert!(tracker.away_periods().is_empty());
+
+    // 40 分钟后回来，离开时段在恢复输入时结束并放入待标注队列
+    let back = last_input + Duration::minutes(40);
+    tracker.observe_idle(false, back, back + Duration::seconds(1))?;
+    assert!(!tracker.is_away());
+    let expected = AwayPeriod {
+        start: last_input,
+        end: Some(back),
+        reason: AwayReason::Idle,
+    };
+    assert_eq!(tracker.pending_away()?, vec![expected.clone()]);
+    assert_eq!(tracker.away_periods(), [expected]);
+    Ok(())
+}
+
+#[test]
+fn test_short_or_unreviewed_away_is_not_queued() -> Result<()> {
+    let mut tracker = tracker_with_activity(Some(15))?;
+    let start = tracker.current_activity.as_ref().unwrap().start_time;
+    tracker.observe_idle(true, start, start + Duration::minutes(5))?;
+    tracker.observe_idle(
+        false,
+        start + Duration::minutes(10),
+        start + Duration::minutes(10),
+    )?;
+    assert_eq!(tracker.away_periods().len(), 1);
+    assert!(tracker.pending_away()?.is_empty());
+
+    // 未启用离开标注
+    let mut tracker = tracker_with_activity(None)?;
+    tracker.observe_idle(true, start, start + Duration::minutes(5))?;
+    tracker.observe_idle(
+        false,
+        start + Duration::hours(2),
+        start + Duration::hours(2),
+    )?;
+    assert!(tracker.pending_away()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_idle_does_not_end_lock() -> Result<()> {
+    let mut tracker = tracker_with_activity(Some(15))?;
+    let start = tracker.current_activity.as_ref().unwrap().start_time;
+
+    tracker.handle_power_event(PowerEvent::Lock, start + Duration::minutes(1))?;
+    tracker.observe_idle(true, start, start + Duration::minutes(10))?;
+    // 锁屏之前的输入不结束锁屏
+    tracker.observe_idle(false, start, start + Duration::minutes(20))?;
+    assert!(tracker.is_away());
+
+    let unlocked = start + Duration::minutes(30);
+    tracker.handle_power_event(PowerEvent::Unlock, unlocked)?;
+    assert!(!tracker.is_away());
+    let pending = tracker.pending_away()?;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].reason, AwayReason::Lock);
+    assert_eq!(pending[0].end, Some(unlocked));
+    Ok(())
+}
+
+#[test]
+fn test_input_after_lock_ends_lock_without_unlock_signal() -> Result<()> {
+    let mut tracker = tracker_with_activity(Some(15))?;
+    let start = tracker.current_activity.as_ref().unwrap().start_time;
+
+    // 锁屏程序解锁时只清除 LockedHint，没有 Unlock 信号
+    let locked = start + Duration::minutes(1);
+    tracker.handle_power_event(PowerEvent::Lock, locked)?;
+    tracker.observe_idle(true, start, start + Duration::minutes(10))?;
+    assert!(tracker.is_away());
+
+    let back = start + Duration::minutes(40);
+    tracker.observe_idle(false, back, back + Duration::seconds(1))?;
+    assert!(!tracker.is_away());
+    let pending = tracker.pending_away()?;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].reason, AwayReason::Lock);
+    assert_eq!((pending[0].start, pending[0].end), (locked, Some(back)));
+
+    // 已经解锁后迟到的 Unlock 信号不产生新的离开时段
+    tracker.handle_power_event(PowerEvent::Unlock, back + Duration::seconds(2))?;
+    assert!(!tracker.is_away());
+    assert_eq!(tracker.pending_away()?.len(), 1);
+
+    // 再次锁屏后需要新的输入才算解锁
+    tracker.handle_power_event(PowerEvent::Lock, back + Duration::minutes(5))?;
+    tracker.observe_idle(false, back, back + Duration::minutes(6))?;
+    assert!(tracker.is_away());
+    Ok(())
+}
+
+#[test]
+fn test_queue_file_is_shared() -> Result<()> {
+    let dir = temp_dir();
+    let data_file = dir.join("activities.db");
+    let path = queue_path(&data_file);
+    assert_eq!(path, dir.join("activities.away.json"));
+
+    // 守护进程写入，另一个进程读取并移除
+    let mut daemon = AwayQueue::for_data_file(&data_file.to_string_lossy());
+    daemon.push(period(60, 90))?;
+    daemon.push(period(0, 45))?;
+    // 开始时间相同的时段只保留一个
+    daemon.push(period(0, 45))?;
+
+    let mut review = AwayQueue::open(&path);
+    assert_eq!(review.pending()?, vec![period(0, 45), period(60, 90)]);
+    assert!(review.remove(&period(0, 45))?);
+    assert!(!review.remove(&period(0, 45))?);
+    assert_eq!(daemon.pending()?, vec![period(60, 90)]);
+
+    // 尚未结束的时段不能加入队列
+    let open = AwayPeriod {
+        end: None,
+        ..period(100, 100)
+    };
+    assert!(daemon.push(open).is_err());
+    assert!(Path::new(&path).exists());
+    Ok(())
+}
+
+#[test]
+fn test_parse_label() {
+    assert_eq!(parse_label("午餐"), Some(("午餐".to_string(), None)));
+    assert_eq!(
+        parse_label(" Client call @ Acme "),
+        Some(("Client call".to_string(), Some("Acme".to_string())))
+    );
+    assert_eq!(
+        parse_label("@Acme"),
+        Some(("Acme".to_string(), Some("Acme".to_string())))
+    );
+    assert_eq!(parse_label("Meeting@"), Some(("Meeting".to_string(), None)));
+    assert_eq!(parse_label("  "), None);
+    assert_eq!(parse_label("@"), None);
+}
+
+#[test]
+fn test_describe_uses_configured_timezone() -> Result<()> {
+    // UTC 12:00 ~ 12:55 是上海时间 20:00 ~ 20:55，跨过 UTC 午夜的时段显示结束日期
+    let shanghai = DayTimeZone::from_name("Asia/Shanghai")?;
+    assert_eq!(
+        describe(&period(0, 55), shanghai),
+        "05-15 20:00 ~ 20:55（55m，闲置）"
+    );
+    assert_eq!(
+        describe(&period(0, 55), DayTimeZone::from_name("UTC")?),
+        "05-15 12:00 ~ 12:55（55m，闲置）"
+    );
+    assert_eq!(
+        describe(&period(180, 300), shanghai),
+        "05-15 23:00 ~ 05-16 01:00（2h0m，闲置）"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_label_away_saves_manual_record() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.set_away_review(Some(15));
+    tracker.load_data()?;
+    tracker.handle_power_event(PowerEvent::Lock, at_minute(0))?;
+    tracker.handle_power_event(PowerEvent::Unlock, at_minute(50))?;
+    tracker.handle_power_event(PowerEvent::Lock, at_minute(120))?;
+    tracker.handle_power_event(PowerEvent::Unlock, at_minute(150))?;
+
+    let pending = tracker.pending_away()?;
+    assert_eq!(pending.len(), 2);
+
+    let entry = tracker.label_away(&pending[0], "Standup", Some("Acme"), OverlapPolicy::Reject)?;
+    assert_eq!((entry.start, entry.end), (at_minute(0), at_minute(50)));
+    let record = tracker.data.activities.last().unwrap();
+    assert_eq!(record.source, RecordSource::Manual);
+    assert_eq!(record.app_name, "Standup");
+    assert_eq!(record.project.as_deref(), Some("Acme"));
+    assert_eq!(record.duration, 50 * 60);
+
+    assert!(tracker.dismiss_away(&pending[1])?);
+    assert!(tracker.pending_away()?.is_empty());
+    assert_eq!(tracker.data.activities.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_away_review_config() -> Result<()> {
+    let mut config = ActivityDetectionConfig::default();
+    assert_eq!(config.away_review_minutes(), Some(15));
+
+    config.away_review_minutes = 0;
+    assert_eq!(config.away_review_minutes(), None);
+    assert!(config.validate().is_ok());
+
+    config.away_review_minutes = 2000;
+    assert!(config.validate().is_err());
+    assert_eq!(config.fix().len(), 1);
+    assert_eq!(config.away_review_minutes, 15);
+    Ok(())
+}
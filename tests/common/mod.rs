@@ -0,0 +1,110 @@
+// 测试公共工具
+// 各集成测试共用的临时目录、测试时间、心跳和活动记录构造函数
+
+#![allow(dead_code)]
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use timetracker::core::heartbeat::Heartbeat;
+use timetracker::core::platform::WindowInfo;
+use timetracker::core::tracker::{ActivityRecord, RecordSource};
+
+/// 独立的临时目录，离开作用域时（包括断言失败时）自动删除
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("timetracker-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// 创建独立的临时目录
+pub fn temp_dir() -> TempDir {
+    TempDir::new()
+}
+
+/// 测试基准时间（2024-05-15 12:00 UTC）之后第 `seconds` 秒
+pub fn at(seconds: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, 15, 12, 0, 0).unwrap() + Duration::seconds(seconds)
+}
+
+/// 测试基准时间之后第 `minutes` 分钟
+pub fn at_minute(minutes: i64) -> DateTime<Utc> {
+    at(minutes * 60)
+}
+
+/// Unix 纪元之后第 `seconds` 秒的系统时间
+pub fn system_time(seconds: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds)
+}
+
+/// 测试基准时间之后第 `seconds` 秒的心跳，窗口标题为 `<app> window`
+pub fn heartbeat(app: &str, seconds: i64) -> Heartbeat {
+    titled_heartbeat(app, &format!("{app} window"), seconds)
+}
+
+/// 测试基准时间之后第 `seconds` 秒、指定窗口标题的心跳
+pub fn titled_heartbeat(app: &str, title: &str, seconds: i64) -> Heartbeat {
+    Heartbeat::from_window(
+        WindowInfo {
+            app_name: app.to_string(),
+            window_title: title.to_string(),
+            process_id: 42,
+        },
+        at(seconds),
+    )
+}
+
+/// 从 `start` 开始、持续 `duration` 秒的自动记录，窗口标题为 `<app> window`
+///
+/// 其他字段使用固定的默认值，需要时用结构体更新语法覆盖：
+/// `ActivityRecord { id: "...".to_string(), ..record("Editor", at(0), 60) }`
+pub fn record(app: &str, start: DateTime<Utc>, duration: u64) -> ActivityRecord {
+    ActivityRecord {
+        id: format!("{app}-{}", start.timestamp()),
+        app_name: app.to_string(),
+        window_title: format!("{app} window"),
+        start_time: start,
+        end_time: Some(start + Duration::seconds(duration as i64)),
+        duration,
+        process_id: 42,
+        app_path: None,
+        bundle_id: None,
+        window_geometry: None,
+        confidence: 0.9,
+        source: RecordSource::Tracked,
+        raw_title: None,
+        project: None,
+        fullscreen: false,
+    }
+}
@@ -0,0 +1,199 @@
+// 记录压缩测试
+// 测试相邻同窗口记录按间隔合并并保留真实起止时间、手动记录按项目和来源区分、全屏拆分的记录不合并，
+// 预演模式不修改存储，以及压缩配置
+
+mod common;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use common::{at, temp_dir};
+use timetracker::{
+    config::app::{AppConfig, CompactionConfig},
+    core::tracker::{ActivityRecord, RecordSource},
+    storage::{
+        compact::{compact_store, plan_compaction},
+        ActivityStore, JsonFileStore, MemoryStore, SqliteStore,
+    },
+};
+
+/// 从第 `start` 秒开始、持续 `duration` 秒的记录
+fn record(app: &str, start: i64, duration: u64) -> ActivityRecord {
+    ActivityRecord {
+        id: format!("{app}-{start}"),
+        ..common::record(app, at(start), duration)
+    }
+}
+
+/// Editor 三段（间隔 10 秒、30 秒、120 秒），随后 Firefox，再回到 Editor
+fn sample() -> Vec<ActivityRecord> {
+    vec![
+        record("Editor", 0, 100),
+        record("Editor", 110, 50),
+        record("Editor", 190, 60),
+        record("Editor", 370, 30),
+        record("Firefox", 400, 20),
+        record("Editor", 425, 10),
+    ]
+}
+
+/// 应用名、开始时间、结束时间、时长
+type Span<'a> = (&'a str, DateTime<Utc>, Option<DateTime<Utc>>, u64);
+
+fn summary(data: &[ActivityRecord]) -> Vec<Span<'_>> {
+    data.iter()
+        .map(|r| (r.app_name.as_str(), r.start_time, r.end_time, r.duration))
+        .collect()
+}
+
+#[test]
+fn test_plan_merges_adjacent_records_within_gap() {
+    let mut records = sample();
+    // 顺序打乱也按开始时间处理
+    records.reverse();
+    let plan = plan_compaction(&records, Duration::seconds(60));
+
+    assert_eq!(plan.removed, vec!["Editor-110", "Editor-190"]);
+    assert_eq!(plan.updated.len(), 1);
+    let merged = &plan.updated[0];
+    assert_eq!(merged.id, "Editor-0");
+    // 保留真实起止时间，时长不计入中间空档
+    assert_eq!(merged.start_time, at(0));
+    assert_eq!(merged.end_time, Some(at(250)));
+    assert_eq!(merged.duration, 210);
+
+    // Firefox 隔开的 Editor 记录不合并
+    let plan = plan_compaction(&records, Duration::seconds(3600));
+    assert_eq!(plan.report().removed, 3);
+    assert_eq!(plan.report().merged, 1);
+}
+
+#[test]
+fn test_recovered_part_marks_merged_record() {
+    let mut records = vec![record("Editor", 0, 100), record("Editor", 100, 50)];
+    records[1].source = RecordSource::Recovered;
+
+    let plan = plan_compaction(&records, Duration::seconds(60));
+    assert_eq!(plan.updated[0].source, RecordSource::Recovered);
+}
+
+#[test]
+fn test_manual_entries_keep_project_and_source() {
+    let manual = |start: i64, project: &str| ActivityRecord {
+        source: RecordSource::Manual,
+        project: Some(project.to_string()),
+        ..record("会议", start, 600)
+    };
+
+    // 不同项目的手动记录不合并
+    let records = vec![manual(0, "Acme"), manual(600, "Beta")];
+    assert!(plan_compaction(&records, Duration::seconds(60))
+        .report()
+        .is_empty());
+
+    // 手动记录不并入相同应用和窗口的监控记录
+    let mut tracked = manual(600, "Acme");
+    tracked.source = RecordSource::Tracked;
+    tracked.project = None;
+    let records = vec![manual(0, "Acme"), tracked];
+    assert!(plan_compaction(&records, Duration::seconds(60))
+        .report()
+        .is_empty());
+
+    // 同一项目的手动记录照常合并
+    let records = vec![manual(0, "Acme"), manual(620, "Acme")];
+    let plan = plan_compaction(&records, Duration::seconds(60));
+    assert_eq!(plan.removed, vec!["会议-620"]);
+    assert_eq!(plan.updated[0].source, RecordSource::Manual);
+    assert_eq!(plan.updated[0].project.as_deref(), Some("Acme"));
+}
+
+#[test]
+fn test_fullscreen_split_is_kept() {
+    // 视频先窗口播放，随后全屏，再退出全屏
+    let mut records = vec![
+        record("mpv", 0, 100),
+        record("mpv", 100, 600),
+        record("mpv", 700, 50),
+    ];
+    records[1].fullscreen = true;
+
+    let plan = plan_compaction(&records, Duration::seconds(60));
+    assert!(plan.report().is_empty());
+
+    // 连续的全屏记录照常合并
+    records[2].fullscreen = true;
+    let plan = plan_compaction(&records, Duration::seconds(60));
+    assert_eq!(plan.removed, vec!["mpv-700"]);
+    assert!(plan.updated[0].fullscreen);
+    assert_eq!(plan.updated[0].duration, 650);
+}
+
+#[test]
+fn test_dry_run_does_not_modify_store() -> Result<()> {
+    let mut store = MemoryStore::new();
+    for record in sample() {
+        store.append(&record)?;
+    }
+
+    let report = compact_store(&mut store, Duration::seconds(60), true)?;
+    assert_eq!((report.removed, report.merged), (2, 1));
+    assert_eq!(store.load()?.activities.len(), 6);
+
+    let report = compact_store(&mut store, Duration::seconds(60), false)?;
+    assert_eq!((report.removed, report.merged), (2, 1));
+    let data = store.load()?;
+    assert_eq!(
+        summary(&data.activities),
+        vec![
+            ("Editor", at(0), Some(at(250)), 210),
+            ("Editor", at(370), Some(at(400)), 30),
+            ("Firefox", at(400), Some(at(420)), 20),
+            ("Editor", at(425), Some(at(435)), 10),
+        ]
+    );
+
+    // 再次压缩没有变化
+    assert!(compact_store(&mut store, Duration::seconds(60), false)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_compaction_persists() -> Result<()> {
+    let dir = temp_dir();
+
+    let json_path = dir.join("activities.json");
+    let mut store = JsonFileStore::new(&json_path);
+    for record in sample() {
+        store.append(&record)?;
+    }
+    compact_store(&mut store, Duration::seconds(60), false)?;
+    drop(store);
+    assert_eq!(JsonFileStore::new(&json_path).load()?.activities.len(), 4);
+
+    let db_path = dir.join("activities.db");
+    let mut store = SqliteStore::open(&db_path)?;
+    for record in sample() {
+        store.append(&record)?;
+    }
+    compact_store(&mut store, Duration::seconds(60), false)?;
+    drop(store);
+    let data = SqliteStore::open(&db_path)?.load()?;
+    assert_eq!(data.activities.len(), 4);
+    assert_eq!(data.activities[0].end_time, Some(at(250)));
+    Ok(())
+}
+
+#[test]
+fn test_compaction_config_validation() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.compaction.gap_secs(), None);
+
+    config.compaction = CompactionConfig {
+        enabled: true,
+        gap_secs: 0,
+    };
+    assert!(config.validate().is_err());
+    assert_eq!(config.fix().len(), 1);
+    assert!(config.validate().is_ok());
+    assert_eq!(config.compaction.gap_secs(), Some(60));
+}
@@ -0,0 +1,158 @@
+// 窗口切换防抖测试
+// 测试新窗口需持续获得焦点才开始新记录、快速切换的闪烁并入相邻记录，以及最短记录时长
+
+mod common;
+
+use anyhow::Result;
+use chrono::Duration;
+use common::{at, heartbeat};
+use timetracker::{
+    config::app::{AppConfig, TrackingConfig},
+    core::{app_rules::AppRules, heartbeat::merge_heartbeat_debounced, tracker::TimeTracker},
+    storage::MemoryStore,
+};
+
+fn tracker(debounce: u64, min_record: u64) -> Result<TimeTracker> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.set_debounce(debounce);
+    tracker.set_min_record_duration(min_record);
+    // 不使用内置别名，测试中的应用名保持原样
+    tracker.set_app_rules(AppRules {
+        builtin: false,
+        ..AppRules::default()
+    });
+    tracker.load_data()?;
+    Ok(tracker)
+}
+
+#[test]
+fn test_switch_waits_for_debounce() {
+    let pulse = Duration::seconds(5);
+    let debounce = Duration::seconds(2);
+    let (mut current, mut pending) = (None, None);
+
+    for seconds in 0..=10 {
+        merge_heartbeat_debounced(
+            &mut current,
+            &mut pending,
+            heartbeat("Code", seconds),
+            pulse,
+            debounce,
+        );
+    }
+
+    // 新窗口前两次心跳只是候选，当前事件继续延长
+    for seconds in [11, 12] {
+        let closed = merge_heartbeat_debounced(
+            &mut current,
+            &mut pending,
+            heartbeat("Firefox", seconds),
+            pulse,
+            debounce,
+        );
+        assert!(closed.is_none());
+        assert_eq!(current.as_ref().unwrap().app_name, "Code");
+    }
+
+    // 持续 2 秒后切换，原事件在新窗口第一次出现时结束
+    let closed = merge_heartbeat_debounced(
+        &mut current,
+        &mut pending,
+        heartbeat("Firefox", 13),
+        pulse,
+        debounce,
+    )
+    .unwrap();
+    assert_eq!(closed.end_time, Some(at(11)));
+    assert_eq!(closed.duration, 11);
+
+    let current = current.unwrap();
+    assert_eq!(current.app_name, "Firefox");
+    assert_eq!(current.start_time, at(11));
+    assert_eq!(current.duration, 2);
+    assert!(pending.is_none());
+}
+
+#[test]
+fn test_alt_tab_flicker_folds_into_current() -> Result<()> {
+    let mut tracker = tracker(2, 0)?;
+
+    tracker.record_heartbeat(heartbeat("Code", 0))?;
+    tracker.record_heartbeat(heartbeat("Code", 1))?;
+    // Alt-Tab 依次经过几个窗口后回到原窗口
+    tracker.record_heartbeat(heartbeat("Slack", 2))?;
+    tracker.record_heartbeat(heartbeat("Firefox", 3))?;
+    tracker.record_heartbeat(heartbeat("Code", 4))?;
+
+    assert!(tracker.data.activities.is_empty());
+    let current = tracker.current_activity.as_ref().unwrap();
+    assert_eq!(current.app_name, "Code");
+    assert_eq!(current.duration, 4);
+    Ok(())
+}
+
+#[test]
+fn test_short_record_folds_into_previous() -> Result<()> {
+    let mut tracker = tracker(0, 3)?;
+
+    for seconds in [0, 1, 2, 3, 4] {
+        tracker.record_heartbeat(heartbeat("Code", seconds))?;
+    }
+    tracker.record_heartbeat(heartbeat("Slack", 5))?;
+    tracker.record_heartbeat(heartbeat("Firefox", 6))?;
+    for seconds in [7, 8, 9, 10] {
+        tracker.record_heartbeat(heartbeat("Firefox", seconds))?;
+    }
+    tracker.record_heartbeat(heartbeat("Code", 11))?;
+
+    // Slack 只停留 1 秒，并入前面的 Code 记录
+    let apps: Vec<_> = tracker
+        .data
+        .activities
+        .iter()
+        .map(|r| (r.app_name.as_str(), r.duration))
+        .collect();
+    assert_eq!(apps, vec![("Code", 6), ("Firefox", 5)]);
+    assert_eq!(tracker.data.activities[0].end_time, Some(at(6)));
+    Ok(())
+}
+
+#[test]
+fn test_short_first_record_folds_into_next() -> Result<()> {
+    let mut tracker = tracker(0, 3)?;
+
+    tracker.record_heartbeat(heartbeat("Slack", 0))?;
+    tracker.record_heartbeat(heartbeat("Code", 1))?;
+    tracker.record_heartbeat(heartbeat("Code", 2))?;
+
+    // 前面没有相连的记录时，并入随后的当前活动
+    assert!(tracker.data.activities.is_empty());
+    let current = tracker.current_activity.as_ref().unwrap();
+    assert_eq!(current.app_name, "Code");
+    assert_eq!(current.start_time, at(0));
+    assert_eq!(current.duration, 2);
+    assert_eq!(tracker.get_total_time(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_tracking_switch_filter_config_validation() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.tracking.debounce_secs, 2);
+    assert_eq!(config.tracking.min_record_secs, 2);
+
+    config.tracking = TrackingConfig {
+        debounce_secs: 0,
+        min_record_secs: 0,
+        ..TrackingConfig::default()
+    };
+    assert!(config.validate().is_ok());
+
+    config.tracking.debounce_secs = 1000;
+    config.tracking.min_record_secs = 1000;
+    assert!(config.validate().is_err());
+    assert_eq!(config.fix().len(), 2);
+    assert_eq!(config.tracking.debounce_secs, 2);
+    assert_eq!(config.tracking.min_record_secs, 2);
+    assert!(config.validate().is_ok());
+}
@@ -0,0 +1,142 @@
+// 数据体检测试
+// 测试发现重叠、时间倒置、时长为零和时长与起止跨度不符（两个方向）的记录，按类型分组，按时区显示，以及自动修复
+
+mod common;
+
+use anyhow::Result;
+use common::at;
+use timetracker::{
+    core::tracker::ActivityRecord,
+    storage::{
+        doctor::{diagnose, doctor_store, IssueKind},
+        ActivityStore, MemoryStore,
+    },
+    utils::time::DayTimeZone,
+};
+
+const UTC: DayTimeZone = DayTimeZone::Named(chrono_tz::UTC);
+
+/// 第 `start` 秒到第 `end` 秒、时长为 `duration` 的记录
+fn record(app: &str, start: i64, end: i64, duration: u64) -> ActivityRecord {
+    ActivityRecord {
+        id: format!("{app}-{start}"),
+        end_time: Some(at(end)),
+        ..common::record(app, at(start), duration)
+    }
+}
+
+fn sample() -> Vec<ActivityRecord> {
+    vec![
+        record("Editor", 0, 100, 100),
+        // 时长超过起止跨度
+        record("Firefox", 100, 160, 300),
+        // 与 Firefox 重叠 20 秒
+        record("Slack", 140, 200, 60),
+        // 完全落在 Slack 之内
+        record("Terminal", 150, 180, 30),
+        // 结束时间早于开始时间
+        record("Mail", 300, 250, 10),
+        // 压缩后的记录时长小于跨度，只报告不修复
+        record("Editor", 400, 600, 120),
+        // 时长为零：有跨度时按跨度重新计算，没有跨度时删除
+        record("Zoom", 700, 760, 0),
+        record("Ping", 800, 800, 0),
+    ]
+}
+
+#[test]
+fn test_diagnose_groups_issues_by_kind() {
+    let report = diagnose(&sample(), UTC);
+
+    assert_eq!(report.count(IssueKind::NegativeSpan), 1);
+    assert_eq!(report.count(IssueKind::DurationMismatch), 1);
+    assert_eq!(report.count(IssueKind::DurationShort), 1);
+    assert_eq!(report.count(IssueKind::ZeroDuration), 2);
+    assert_eq!(report.count(IssueKind::Overlap), 2);
+
+    let groups = report.by_kind();
+    assert_eq!(
+        groups.keys().copied().collect::<Vec<_>>(),
+        vec![
+            IssueKind::NegativeSpan,
+            IssueKind::DurationMismatch,
+            IssueKind::DurationShort,
+            IssueKind::ZeroDuration,
+            IssueKind::Overlap
+        ]
+    );
+    assert_eq!(groups[&IssueKind::DurationShort][0].record_id, "Editor-400");
+    assert_eq!(
+        groups[&IssueKind::DurationShort][0].detail,
+        "时长 120 秒，起止时间有 200 秒"
+    );
+    let overlaps: Vec<_> = groups[&IssueKind::Overlap]
+        .iter()
+        .map(|issue| issue.record_id.as_str())
+        .collect();
+    assert_eq!(overlaps, vec!["Slack-140", "Terminal-150"]);
+    assert_eq!(
+        groups[&IssueKind::Overlap][0].detail,
+        "与 Firefox（2024-05-15 12:01:40）重叠 20 秒"
+    );
+
+    assert!(diagnose(&[record("Editor", 0, 100, 100)], UTC).is_empty());
+}
+
+#[test]
+fn test_issue_times_use_given_timezone() -> Result<()> {
+    let shanghai = DayTimeZone::from_name("Asia/Shanghai")?;
+    let report = diagnose(&sample(), shanghai);
+    let overlap = &report.by_kind()[&IssueKind::Overlap][0];
+    assert_eq!(
+        overlap.detail,
+        "与 Firefox（2024-05-15 20:01:40）重叠 20 秒"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_fix_plan() {
+    let report = diagnose(&sample(), UTC);
+    let plan = &report.plan;
+
+    assert_eq!(plan.removed, vec!["Terminal-150", "Mail-300", "Ping-800"]);
+    let updated: Vec<_> = plan
+        .updated
+        .iter()
+        .map(|r| (r.id.as_str(), r.start_time, r.end_time, r.duration))
+        .collect();
+    assert_eq!(
+        updated,
+        vec![
+            ("Firefox-100", at(100), Some(at(160)), 60),
+            ("Slack-140", at(160), Some(at(200)), 40),
+            ("Zoom-700", at(700), Some(at(760)), 60),
+        ]
+    );
+}
+
+#[test]
+fn test_doctor_store_fixes_only_when_requested() -> Result<()> {
+    let mut store = MemoryStore::new();
+    for record in sample() {
+        store.append(&record)?;
+    }
+
+    let report = doctor_store(&mut store, false, UTC)?;
+    assert_eq!(report.issues.len(), 7);
+    assert_eq!(store.load()?.activities.len(), 8);
+
+    doctor_store(&mut store, true, UTC)?;
+    let data = store.load()?;
+    assert_eq!(data.activities.len(), 5);
+    // 只剩下不自动修复的压缩记录
+    let remaining = diagnose(&data.activities, UTC);
+    assert_eq!(remaining.issues.len(), 1);
+    assert_eq!(remaining.count(IssueKind::DurationShort), 1);
+
+    // 修复后的记录合计时长与起止时间一致（压缩记录除外）
+    let total: u64 = data.activities.iter().map(|r| r.duration).sum();
+    assert_eq!(total, 100 + 60 + 40 + 120 + 60);
+    Ok(())
+}
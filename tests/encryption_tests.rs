@@ -0,0 +1,198 @@
+// 加密存储测试
+// 测试数据文件和日志的加密读写、口令校验、更换口令以及取消加密
+
+mod common;
+
+use anyhow::Result;
+use chrono::{Duration, TimeZone, Utc};
+use common::temp_dir;
+use std::path::Path;
+use timetracker::{
+    config::app::StorageConfig,
+    core::tracker::ActivityRecord,
+    storage::{
+        crypto::{self, Cipher},
+        json::journal_path,
+        open_store, open_store_with, ActivityStore, JsonFileStore, Passphrase,
+    },
+};
+
+fn record(app: &str, title: &str, start_offset_minutes: i64) -> ActivityRecord {
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap()
+        + Duration::minutes(start_offset_minutes);
+    ActivityRecord {
+        id: format!("{app}-{start_offset_minutes}"),
+        window_title: title.to_string(),
+        ..common::record(app, start, 60)
+    }
+}
+
+/// 文件内容中是否出现了指定的明文
+fn contains_plaintext(path: &Path, needle: &str) -> bool {
+    std::fs::read(path)
+        .map(|bytes| String::from_utf8_lossy(&bytes).contains(needle))
+        .unwrap_or(false)
+}
+
+#[test]
+fn test_cipher_roundtrip() -> Result<()> {
+    let cipher = Cipher::generate(&Passphrase::new("correct horse"))?;
+
+    let file = cipher.encrypt_file(b"{\"activities\":[]}")?;
+    assert!(crypto::is_encrypted_contents(&file));
+    assert_eq!(&cipher.decrypt_file(&file)?[..], b"{\"activities\":[]}");
+
+    let line = cipher.encrypt_line("secret title")?;
+    assert!(crypto::is_encrypted_line(&line));
+    assert_eq!(&*cipher.decrypt_line(&line)?, "secret title");
+
+    // 相同内容每次使用不同的随机数
+    assert_ne!(cipher.encrypt_line("secret title")?, line);
+    Ok(())
+}
+
+#[test]
+fn test_encrypted_store_has_no_plaintext_on_disk() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+    let passphrase = Passphrase::new("correct horse");
+
+    let mut store = JsonFileStore::open_encrypted(&path, &passphrase)?;
+    store.load()?;
+    store.append(&record("Firefox", "Bank statement", 0))?;
+    store.set_current(Some(&record("Terminal", "ssh prod", 1)))?;
+
+    assert!(!contains_plaintext(&journal_path(&path), "Bank statement"));
+    assert!(!contains_plaintext(&journal_path(&path), "ssh prod"));
+
+    store.compact()?;
+    assert!(crypto::is_encrypted_contents(&std::fs::read(&path)?));
+    assert!(!contains_plaintext(&path, "Bank statement"));
+    drop(store);
+
+    let data = JsonFileStore::open_encrypted(&path, &passphrase)?.load()?;
+    assert_eq!(data.activities.len(), 1);
+    assert_eq!(data.activities[0].window_title, "Bank statement");
+    assert_eq!(data.current_activity.unwrap().window_title, "ssh prod");
+    Ok(())
+}
+
+#[test]
+fn test_wrong_or_missing_passphrase_is_rejected() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut store = JsonFileStore::open_encrypted(&path, &Passphrase::new("correct horse"))?;
+    store.append(&record("Firefox", "Bank statement", 0))?;
+    store.compact()?;
+    drop(store);
+
+    assert!(JsonFileStore::open_encrypted(&path, &Passphrase::new("wrong")).is_err());
+    assert!(open_store(&path).is_err());
+    assert!(JsonFileStore::new(&path).load().is_err());
+
+    // 口令错误不是数据损坏，文件不会被隔离
+    let quarantined = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.file_name().to_string_lossy().contains(".corrupt-"));
+    assert!(!quarantined);
+    assert!(crypto::is_encrypted_contents(&std::fs::read(&path)?));
+    Ok(())
+}
+
+#[test]
+fn test_enabling_encryption_rewrites_plain_data() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut plain = JsonFileStore::new(&path);
+    plain.append(&record("Firefox", "Bank statement", 0))?;
+    plain.compact()?;
+    plain.append(&record("Code", "salary.xlsx", 1))?;
+    drop(plain);
+    assert!(contains_plaintext(&path, "Bank statement"));
+
+    let passphrase = Passphrase::new("correct horse");
+    let data = JsonFileStore::open_encrypted(&path, &passphrase)?.load()?;
+    assert_eq!(data.activities.len(), 2);
+
+    assert!(crypto::is_encrypted(&path));
+    assert!(!contains_plaintext(&path, "Bank statement"));
+    assert!(!contains_plaintext(&path, "salary.xlsx"));
+    assert!(!contains_plaintext(&journal_path(&path), "salary.xlsx"));
+
+    let reopened = open_store_with(&path, Some(&passphrase))?.load()?;
+    assert_eq!(reopened.activities.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_rotate_key_and_remove_encryption() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+    let old = Passphrase::new("old passphrase");
+    let new = Passphrase::new("new passphrase");
+
+    let mut store = JsonFileStore::open_encrypted(&path, &old)?;
+    store.append(&record("Firefox", "Bank statement", 0))?;
+    store.rotate_key(&new)?;
+    store.append(&record("Code", "salary.xlsx", 1))?;
+    drop(store);
+
+    assert!(JsonFileStore::open_encrypted(&path, &old).is_err());
+    let mut store = JsonFileStore::open_encrypted(&path, &new)?;
+    assert_eq!(store.load()?.activities.len(), 2);
+
+    store.remove_encryption()?;
+    drop(store);
+    assert!(!crypto::is_encrypted(&path));
+    assert!(contains_plaintext(&path, "salary.xlsx"));
+    assert_eq!(JsonFileStore::new(&path).load()?.activities.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_key_file_and_resolution() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+    let key_file = dir.join("key");
+    std::fs::write(&key_file, "from key file\n")?;
+
+    // 未启用加密且文件未加密时不需要口令
+    let mut config = StorageConfig::default();
+    assert!(crypto::resolve_passphrase(&config, &path, false)?.is_none());
+
+    config.encrypted = true;
+    config.key_file = Some(key_file.to_string_lossy().to_string());
+    let passphrase = crypto::resolve_passphrase(&config, &path, false)?.unwrap();
+    assert_eq!(passphrase.expose_secret(), "from key file");
+
+    std::fs::write(&key_file, "  \n")?;
+    assert!(Passphrase::from_key_file(&key_file).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_encryption_config_validation() {
+    let mut config = StorageConfig {
+        encrypted: true,
+        ..Default::default()
+    };
+    assert!(config.validate().is_ok());
+
+    config.backend = "sqlite".to_string();
+    assert!(config.validate().is_err());
+    let fixes = config.fix();
+    assert_eq!(config.backend, "json");
+    assert!(!fixes.is_empty());
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_passphrase_requires_json_backend() -> Result<()> {
+    let dir = temp_dir();
+    let passphrase = Passphrase::new("correct horse");
+    assert!(open_store_with(dir.join("activities.db"), Some(&passphrase)).is_err());
+    assert!(open_store_with(dir.join("activities.jsonl"), Some(&passphrase)).is_err());
+    Ok(())
+}
@@ -0,0 +1,56 @@
+#!/usr/bin/env python3
+# 模拟 MPRIS 媒体播放器，用于在私有 D-Bus 总线上测试播放状态检测
+#
+# 用法: mock_mpris.py <总线地址> <名称后缀> <PlaybackStatus> <Identity> [标题] [艺术家]
+
+import sys
+
+import dbus
+import dbus.service
+from dbus.mainloop.glib import DBusGMainLoop
+from gi.repository import GLib
+
+ROOT_IFACE = "org.mpris.MediaPlayer2"
+PLAYER_IFACE = "org.mpris.MediaPlayer2.Player"
+
+
+class MockPlayer(dbus.service.Object):
+    def __init__(self, bus, status, identity, title, artist):
+        super().__init__(bus, "/org/mpris/MediaPlayer2")
+        metadata = {"mpris:trackid": dbus.ObjectPath("/org/mpris/MediaPlayer2/Track/1")}
+        if title:
+            metadata["xesam:title"] = title
+        if artist:
+            metadata["xesam:artist"] = dbus.Array([artist], signature="s")
+        self.properties = {
+            ROOT_IFACE: {"Identity": identity},
+            PLAYER_IFACE: {
+                "PlaybackStatus": status,
+                "Metadata": dbus.Dictionary(metadata, signature="sv"),
+            },
+        }
+
+    @dbus.service.method(dbus.PROPERTIES_IFACE, in_signature="ss", out_signature="v")
+    def Get(self, interface, name):
+        return self.properties[interface][name]
+
+    @dbus.service.method(dbus.PROPERTIES_IFACE, in_signature="s", out_signature="a{sv}")
+    def GetAll(self, interface):
+        return self.properties[interface]
+
+
+def main():
+    address, suffix, status, identity = sys.argv[1:5]
+    title = sys.argv[5] if len(sys.argv) > 5 else ""
+    artist = sys.argv[6] if len(sys.argv) > 6 else ""
+
+    DBusGMainLoop(set_as_default=True)
+    bus = dbus.bus.BusConnection(address)
+    name = dbus.service.BusName(f"org.mpris.MediaPlayer2.{suffix}", bus)
+    player = MockPlayer(bus, status, identity, title, artist)
+    print("ready", flush=True)
+    GLib.MainLoop().run()
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,180 @@
+// 全屏检测测试
+// 测试全屏窗口闲置时不算闲置、进入或退出全屏时开始新的记录、全屏状态的序列化和 SQLite 存储，
+// 以及全屏时间统计
+
+mod common;
+
+use anyhow::Result;
+use chrono::Duration as ChronoDuration;
+use common::{at, titled_heartbeat};
+use std::time::Duration;
+use timetracker::{
+    core::{
+        activity_detector::{ActivityConfig, ActivityDetector, ActivityStatus},
+        heartbeat::{merge_heartbeat, Heartbeat},
+        media::{MediaInfo, MprisClient},
+        tracker::{ActivityRecord, TimeTracker},
+    },
+    storage::{sqlite::SCHEMA_VERSION, ActivityStore, MemoryStore, SqliteStore},
+};
+
+const IDLE: Duration = Duration::from_secs(600);
+const ACTIVE: Duration = Duration::from_secs(5);
+
+fn heartbeat(app: &str, title: &str, seconds: i64, fullscreen: bool) -> Heartbeat {
+    Heartbeat {
+        fullscreen,
+        ..titled_heartbeat(app, title, seconds)
+    }
+}
+
+fn record(app: &str, start: i64, duration: u64, fullscreen: bool) -> ActivityRecord {
+    ActivityRecord {
+        fullscreen,
+        ..common::record(app, at(start), duration)
+    }
+}
+
+#[test]
+fn test_fullscreen_window_is_not_idle() {
+    let mut detector = ActivityDetector::new(ActivityConfig::default());
+    detector.set_mpris(None);
+
+    // 放映幻灯片时标题不含视频关键词，不全屏时算闲置
+    assert_eq!(
+        detector.classify(IDLE, Some("LibreOffice Impress"), Some("slides.odp")),
+        ActivityStatus::Idle
+    );
+
+    detector.set_fullscreen(true);
+    assert_eq!(
+        detector.classify(IDLE, Some("LibreOffice Impress"), Some("slides.odp")),
+        ActivityStatus::WatchingVideo
+    );
+    assert_eq!(
+        detector.current_media(),
+        Some(&MediaInfo {
+            player: "LibreOffice Impress".to_string(),
+            track: Some("slides.odp".to_string()),
+        })
+    );
+    assert!(detector.get_stats().fullscreen);
+
+    // 有输入时仍然算活跃
+    assert_eq!(
+        detector.classify(ACTIVE, Some("LibreOffice Impress"), Some("slides.odp")),
+        ActivityStatus::Active
+    );
+
+    // 退出全屏后立即重新判断
+    detector.set_fullscreen(false);
+    assert_eq!(
+        detector.classify(IDLE, Some("LibreOffice Impress"), Some("slides.odp")),
+        ActivityStatus::Idle
+    );
+
+    // 无法访问 MPRIS 时全屏窗口同样不算闲置
+    let mut detector = ActivityDetector::new(ActivityConfig::default());
+    detector.set_mpris(Some(MprisClient::with_address(
+        "unix:path=/nonexistent/timetracker-test-bus",
+    )));
+    detector.set_fullscreen(true);
+    assert_eq!(
+        detector.classify(IDLE, Some("mpv"), Some("lecture.mp4")),
+        ActivityStatus::WatchingVideo
+    );
+}
+
+#[test]
+fn test_fullscreen_toggle_starts_new_record() {
+    let pulse = ChronoDuration::seconds(5);
+    let mut current = None;
+    merge_heartbeat(
+        &mut current,
+        heartbeat("mpv", "lecture.mp4", 0, false),
+        pulse,
+    );
+    merge_heartbeat(
+        &mut current,
+        heartbeat("mpv", "lecture.mp4", 4, false),
+        pulse,
+    );
+
+    let closed = merge_heartbeat(
+        &mut current,
+        heartbeat("mpv", "lecture.mp4", 6, true),
+        pulse,
+    )
+    .expect("进入全屏应该结束之前的记录");
+    assert!(!closed.fullscreen);
+    assert_eq!(closed.duration, 6);
+
+    assert!(merge_heartbeat(
+        &mut current,
+        heartbeat("mpv", "lecture.mp4", 10, true),
+        pulse
+    )
+    .is_none());
+    let current = current.unwrap();
+    assert!(current.fullscreen);
+    assert_eq!(current.start_time, at(6));
+    assert_eq!(current.duration, 4);
+}
+
+#[test]
+fn test_fullscreen_serialization() -> Result<()> {
+    // 不全屏时不写入字段，旧数据缺少字段时视为不全屏
+    let windowed = serde_json::to_value(record("Code", 0, 60, false))?;
+    assert!(windowed.get("fullscreen").is_none());
+    let restored: ActivityRecord = serde_json::from_value(windowed)?;
+    assert!(!restored.fullscreen);
+
+    let fullscreen = serde_json::to_value(record("mpv", 0, 60, true))?;
+    assert_eq!(fullscreen["fullscreen"], true);
+    assert!(serde_json::from_value::<ActivityRecord>(fullscreen)?.fullscreen);
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_stores_fullscreen() -> Result<()> {
+    let mut store = SqliteStore::open_in_memory()?;
+    assert_eq!(store.schema_version()?, SCHEMA_VERSION);
+    store.append(&record("Code", 0, 60, false))?;
+    store.append(&record("mpv", 60, 600, true))?;
+
+    let mut current = record("Impress", 660, 30, true);
+    current.end_time = None;
+    store.set_current(Some(&current))?;
+
+    let activities = store.load_activities()?;
+    assert_eq!(
+        activities.iter().map(|a| a.fullscreen).collect::<Vec<_>>(),
+        vec![false, true]
+    );
+    assert!(store.current()?.unwrap().fullscreen);
+
+    // 更新记录时同样保存全屏状态
+    let mut updated = activities[1].clone();
+    updated.fullscreen = false;
+    assert!(store.update(&updated)?);
+    assert!(!store.load_activities()?[1].fullscreen);
+    Ok(())
+}
+
+#[test]
+fn test_fullscreen_time_in_range() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.load_data()?;
+    tracker.data.activities = vec![
+        record("Code", 0, 600, false),
+        record("mpv", 600, 1200, true),
+        record("Impress", 1800, 300, true),
+    ];
+
+    assert_eq!(tracker.get_fullscreen_time_in(at(0), at(3600)), 1500);
+    // 跨越区间边界的记录按比例截取
+    assert_eq!(tracker.get_fullscreen_time_in(at(0), at(900)), 300);
+    assert_eq!(tracker.get_fullscreen_time_in(at(2100), at(3600)), 0);
+    assert_eq!(tracker.get_total_time_in(at(0), at(3600)), 2100);
+    Ok(())
+}
@@ -0,0 +1,125 @@
+// 心跳模型测试
+// 测试相同窗口心跳在脉冲窗口内合并、窗口切换和监控中断时事件的结束时间，以及时长由心跳推算
+
+mod common;
+
+use anyhow::Result;
+use chrono::Duration;
+use common::{at, heartbeat};
+use timetracker::{
+    config::app::{AppConfig, TrackingConfig},
+    core::{
+        heartbeat::merge_heartbeat,
+        tracker::{ActivityRecord, TimeTracker},
+    },
+    storage::MemoryStore,
+};
+
+#[test]
+fn test_identical_heartbeats_extend_event() {
+    let pulse = Duration::seconds(5);
+    let mut current: Option<ActivityRecord> = None;
+
+    for seconds in [0, 1, 2, 3] {
+        assert!(merge_heartbeat(&mut current, heartbeat("Code", seconds), pulse).is_none());
+    }
+
+    let event = current.unwrap();
+    assert_eq!(event.start_time, at(0));
+    assert_eq!(event.end_time, Some(at(3)));
+    assert_eq!(event.duration, 3);
+}
+
+#[test]
+fn test_switch_within_pulse_window_is_continuous() {
+    let pulse = Duration::seconds(5);
+    let mut current = None;
+    merge_heartbeat(&mut current, heartbeat("Code", 0), pulse);
+    merge_heartbeat(&mut current, heartbeat("Code", 4), pulse);
+
+    let closed = merge_heartbeat(&mut current, heartbeat("Firefox", 6), pulse).unwrap();
+    assert_eq!(closed.app_name, "Code");
+    assert_eq!(closed.end_time, Some(at(6)));
+    assert_eq!(closed.duration, 6);
+
+    let current = current.unwrap();
+    assert_eq!(current.app_name, "Firefox");
+    assert_eq!(current.start_time, at(6));
+    assert_eq!(current.duration, 0);
+}
+
+#[test]
+fn test_gap_beyond_pulse_window_ends_at_last_heartbeat() {
+    let pulse = Duration::seconds(5);
+    let mut current = None;
+    merge_heartbeat(&mut current, heartbeat("Code", 0), pulse);
+    merge_heartbeat(&mut current, heartbeat("Code", 2), pulse);
+
+    // 监控中断 10 分钟后，同一个窗口开始新的事件，空档不计时
+    let closed = merge_heartbeat(&mut current, heartbeat("Code", 602), pulse).unwrap();
+    assert_eq!(closed.end_time, Some(at(2)));
+    assert_eq!(closed.duration, 2);
+    assert_eq!(current.as_ref().unwrap().start_time, at(602));
+
+    // 时钟回拨同样结束当前事件
+    let closed = merge_heartbeat(&mut current, heartbeat("Code", 500), pulse).unwrap();
+    assert_eq!(closed.end_time, Some(at(602)));
+    assert_eq!(current.unwrap().start_time, at(500));
+}
+
+#[test]
+fn test_tracker_durations_come_from_heartbeats() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.set_pulse_window(5);
+    tracker.load_data()?;
+
+    for seconds in [0, 1, 2, 3, 4] {
+        tracker.record_heartbeat(heartbeat("Code", seconds))?;
+    }
+    tracker.record_heartbeat(heartbeat("Firefox", 5))?;
+    tracker.record_heartbeat(heartbeat("Firefox", 8))?;
+
+    assert_eq!(tracker.data.activities.len(), 1);
+    assert_eq!(tracker.data.activities[0].duration, 5);
+    // 当前事件截至最后一次心跳，与现在的时间无关
+    assert_eq!(tracker.current_activity.as_ref().unwrap().duration, 3);
+    assert_eq!(tracker.get_total_time(), 8);
+
+    // 持久化的当前事件带有最后一次心跳的结束时间
+    let persisted = tracker.data.current_activity.clone().unwrap();
+    assert_eq!(persisted.end_time, Some(at(8)));
+
+    tracker.stop_monitoring()?;
+    assert_eq!(tracker.data.activities[1].end_time, Some(at(8)));
+    Ok(())
+}
+
+#[test]
+fn test_pulse_window_is_at_least_one_interval() -> Result<()> {
+    // 采样间隔 10 秒、脉冲窗口 5 秒时，正常采样仍然合并
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 10);
+    tracker.set_pulse_window(5);
+    tracker.load_data()?;
+
+    for seconds in [0, 10, 20] {
+        tracker.record_heartbeat(heartbeat("Code", seconds))?;
+    }
+    assert!(tracker.data.activities.is_empty());
+    assert_eq!(tracker.get_total_time(), 20);
+    Ok(())
+}
+
+#[test]
+fn test_tracking_config_validation() {
+    let mut config = AppConfig::default();
+    assert!(config.validate().is_ok());
+
+    config.tracking = TrackingConfig {
+        pulse_window_secs: 0,
+        ..TrackingConfig::default()
+    };
+    assert!(config.validate().is_err());
+    assert!(!config.fix().is_empty());
+    assert_eq!(config.tracking.pulse_window_secs, 5);
+    assert!(config.validate().is_ok());
+}
@@ -0,0 +1,354 @@
+// 手动记录测试
+// 测试时长和时间解析、时间段推算、手动记录的来源和项目、与已有记录（包括很早开始的长记录和当前活动）重叠时的处理，以及 TUI 表单
+
+mod common;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone, Utc};
+use common::at_minute;
+use timetracker::{
+    core::{
+        manual::{resolve_span, ManualEntry, OverlapPolicy},
+        tracker::{ActivityRecord, RecordSource, TimeTracker},
+    },
+    storage::{ActivityStore, MemoryStore, SqliteStore},
+    ui::components::ManualEntryForm,
+    utils::time::{parse_duration_spec, parse_local_datetime, DayTimeZone},
+};
+
+/// 第 `start` 分钟到第 `end` 分钟的自动记录
+fn tracked(app: &str, start: i64, end: i64) -> ActivityRecord {
+    ActivityRecord {
+        id: format!("{app}-{start}"),
+        ..common::record(app, at_minute(start), ((end - start) * 60) as u64)
+    }
+}
+
+fn tracker_with(records: &[ActivityRecord]) -> Result<TimeTracker> {
+    let mut store = MemoryStore::new();
+    for record in records {
+        store.append(record)?;
+    }
+    let mut tracker = TimeTracker::with_store(Box::new(store), 1);
+    tracker.load_data()?;
+    Ok(tracker)
+}
+
+#[test]
+fn test_parse_duration_and_time() -> Result<()> {
+    assert_eq!(parse_duration_spec("1h30m")?, 5400);
+    assert_eq!(parse_duration_spec("2h")?, 7200);
+    assert_eq!(parse_duration_spec("45")?, 2700);
+    assert_eq!(parse_duration_spec("1h 5s")?, 3605);
+    assert!(parse_duration_spec("abc").is_err());
+    assert!(parse_duration_spec("10x").is_err());
+    assert!(parse_duration_spec("0m").is_err());
+
+    let local = DayTimeZone::Local;
+    let now = Local
+        .with_ymd_and_hms(2024, 5, 15, 18, 0, 0)
+        .unwrap()
+        .with_timezone(&Utc);
+    assert_eq!(
+        parse_local_datetime("14:30", now, local)?,
+        Local.with_ymd_and_hms(2024, 5, 15, 14, 30, 0).unwrap()
+    );
+    assert_eq!(
+        parse_local_datetime("2024-05-14 09:15", now, local)?,
+        Local.with_ymd_and_hms(2024, 5, 14, 9, 15, 0).unwrap()
+    );
+    assert!(parse_local_datetime("yesterday", now, local).is_err());
+
+    // 按配置的时区解析，而不是系统时区；只写时间时取该时区的当天
+    let shanghai = DayTimeZone::from_name("Asia/Shanghai")?;
+    let now = Utc.with_ymd_and_hms(2024, 5, 15, 17, 0, 0).unwrap();
+    assert_eq!(
+        parse_local_datetime("09:00", now, shanghai)?,
+        Utc.with_ymd_and_hms(2024, 5, 16, 1, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse_local_datetime("2024-05-14 09:15", now, shanghai)?,
+        Utc.with_ymd_and_hms(2024, 5, 14, 1, 15, 0).unwrap()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_resolve_span() -> Result<()> {
+    let now = at_minute(600);
+    let hour = Some(3600);
+    assert_eq!(
+        resolve_span(Some(at_minute(0)), Some(at_minute(30)), None, now)?,
+        (at_minute(0), at_minute(30))
+    );
+    assert_eq!(
+        resolve_span(Some(at_minute(0)), None, hour, now)?,
+        (at_minute(0), at_minute(60))
+    );
+    assert_eq!(
+        resolve_span(None, Some(at_minute(120)), hour, now)?,
+        (at_minute(60), at_minute(120))
+    );
+    assert_eq!(resolve_span(None, None, hour, now)?, (at_minute(540), now));
+    assert_eq!(
+        resolve_span(Some(at_minute(0)), None, None, now)?,
+        (at_minute(0), now)
+    );
+
+    assert!(resolve_span(Some(at_minute(0)), Some(at_minute(30)), hour, now).is_err());
+    assert!(resolve_span(Some(at_minute(30)), Some(at_minute(0)), None, now).is_err());
+    assert!(resolve_span(None, None, None, now).is_err());
+    // 不能结束于未来
+    assert!(resolve_span(Some(at_minute(590)), None, hour, now).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_manual_record_is_flagged() -> Result<()> {
+    let entry = ManualEntry::new("Phone call", at_minute(0), at_minute(45))?
+        .project("Acme")
+        .note("Quarterly review");
+    let record = entry.to_record();
+    assert_eq!(record.source, RecordSource::Manual);
+    assert_eq!(record.app_name, "Phone call");
+    assert_eq!(record.window_title, "Quarterly review");
+    assert_eq!(record.project.as_deref(), Some("Acme"));
+    assert_eq!(record.duration, 45 * 60);
+
+    assert!(ManualEntry::new("  ", at_minute(0), at_minute(45)).is_err());
+    assert!(ManualEntry::new("Call", at_minute(45), at_minute(45)).is_err());
+    assert_eq!(
+        ManualEntry::new("Call", at_minute(0), at_minute(1))?
+            .project(" ")
+            .project,
+        None
+    );
+
+    // SQLite 保存来源和项目
+    let mut store = SqliteStore::open_in_memory()?;
+    store.append(&record)?;
+    assert_eq!(store.load()?.activities, vec![record.clone()]);
+
+    let json = serde_json::to_string(&record)?;
+    assert!(json.contains(r#""source":"manual""#));
+    assert!(json.contains(r#""project":"Acme""#));
+    Ok(())
+}
+
+#[test]
+fn test_overlap_policies() -> Result<()> {
+    let records = [tracked("Editor", 0, 60), tracked("Firefox", 60, 90)];
+    let entry = ManualEntry::new("Whiteboard", at_minute(30), at_minute(70))?;
+
+    // 默认拒绝重叠
+    let mut tracker = tracker_with(&records)?;
+    assert_eq!(tracker.find_overlaps(entry.start, entry.end)?.len(), 2);
+    assert!(tracker
+        .add_manual_entry(&entry, OverlapPolicy::Reject)
+        .is_err());
+    assert_eq!(tracker.data.activities.len(), 2);
+
+    // 保留重叠
+    let mut tracker = tracker_with(&records)?;
+    tracker.add_manual_entry(&entry, OverlapPolicy::Keep)?;
+    assert_eq!(tracker.get_total_time(), (60 + 30 + 40) * 60);
+
+    // 裁剪重叠：时间段内只保留手动记录
+    let mut tracker = tracker_with(&records)?;
+    let overlaps = tracker.add_manual_entry(&entry, OverlapPolicy::Trim)?;
+    assert_eq!(overlaps.len(), 2);
+    let mut spans: Vec<_> = tracker
+        .data
+        .activities
+        .iter()
+        .map(|r| {
+            (
+                r.app_name.as_str(),
+                r.start_time,
+                r.effective_end(),
+                r.source,
+            )
+        })
+        .collect();
+    spans.sort_by_key(|span| span.1);
+    assert_eq!(
+        spans,
+        vec![
+            ("Editor", at_minute(0), at_minute(30), RecordSource::Tracked),
+            (
+                "Whiteboard",
+                at_minute(30),
+                at_minute(70),
+                RecordSource::Manual
+            ),
+            (
+                "Firefox",
+                at_minute(70),
+                at_minute(90),
+                RecordSource::Tracked
+            ),
+        ]
+    );
+    assert_eq!(tracker.get_total_time(), 90 * 60);
+    Ok(())
+}
+
+#[test]
+fn test_trim_splits_enclosing_record() -> Result<()> {
+    let mut tracker = tracker_with(&[tracked("Editor", 0, 120)])?;
+    let entry = ManualEntry::new("Phone call", at_minute(30), at_minute(60))?;
+    tracker.add_manual_entry(&entry, OverlapPolicy::Trim)?;
+
+    let mut editor: Vec<_> = tracker
+        .data
+        .activities
+        .iter()
+        .filter(|r| r.app_name == "Editor")
+        .map(|r| (r.start_time, r.effective_end(), r.duration))
+        .collect();
+    editor.sort();
+    assert_eq!(
+        editor,
+        vec![
+            (at_minute(0), at_minute(30), 30 * 60),
+            (at_minute(60), at_minute(120), 60 * 60)
+        ]
+    );
+    assert_eq!(tracker.get_total_time(), 120 * 60);
+    Ok(())
+}
+
+#[test]
+fn test_overlaps_with_long_records() -> Result<()> {
+    // 两天前开始、一直持续到手动记录之后的记录
+    let long = tracked("Render", -3 * 24 * 60, 120);
+    let entry = ManualEntry::new("Call", at_minute(30), at_minute(60))?;
+
+    let mut tracker = tracker_with(std::slice::from_ref(&long))?;
+    assert_eq!(
+        tracker.find_overlaps(entry.start, entry.end)?,
+        vec![long.clone()]
+    );
+    assert!(tracker
+        .add_manual_entry(&entry, OverlapPolicy::Reject)
+        .is_err());
+
+    let mut store = SqliteStore::open_in_memory()?;
+    store.append(&long)?;
+    store.append(&tracked("Editor", -3 * 24 * 60 - 60, -3 * 24 * 60))?;
+    let mut tracker = TimeTracker::with_store(Box::new(store), 1);
+    assert_eq!(tracker.find_overlaps(entry.start, entry.end)?, vec![long]);
+    Ok(())
+}
+
+#[test]
+fn test_overlaps_with_current_activity() -> Result<()> {
+    let entry = ManualEntry::new("Call", at_minute(30), at_minute(60))?;
+
+    // 存储中保存的（后台进程的）当前活动只能报告重叠，不能裁剪
+    let mut store = MemoryStore::new();
+    store.set_current(Some(&tracked("Editor", 0, 90)))?;
+    let mut tracker = TimeTracker::with_store(Box::new(store), 1);
+    tracker.load_data()?;
+    assert_eq!(tracker.find_overlaps(entry.start, entry.end)?.len(), 1);
+    assert!(tracker
+        .add_manual_entry(&entry, OverlapPolicy::Reject)
+        .is_err());
+    assert!(tracker
+        .add_manual_entry(&entry, OverlapPolicy::Trim)
+        .is_err());
+    assert!(tracker.data.activities.is_empty());
+
+    // 本进程的当前活动：之前的部分写入历史，当前活动从手动记录结束时继续
+    let mut tracker = tracker_with(&[])?;
+    tracker.current_activity = Some(tracked("Editor", 0, 90));
+    let overlaps = tracker.add_manual_entry(&entry, OverlapPolicy::Trim)?;
+    assert_eq!(overlaps.len(), 1);
+
+    let current = tracker.current_activity.clone().unwrap();
+    assert_eq!(current.id, "Editor-0");
+    assert_eq!(
+        (
+            current.start_time,
+            current.effective_end(),
+            current.duration
+        ),
+        (at_minute(60), at_minute(90), 30 * 60)
+    );
+    let mut spans: Vec<_> = tracker
+        .data
+        .activities
+        .iter()
+        .map(|r| (r.app_name.as_str(), r.start_time, r.effective_end()))
+        .collect();
+    spans.sort_by_key(|span| span.1);
+    assert_eq!(
+        spans,
+        vec![
+            ("Editor", at_minute(0), at_minute(30)),
+            ("Call", at_minute(30), at_minute(60))
+        ]
+    );
+    assert_eq!(tracker.data.current_activity, Some(current));
+    Ok(())
+}
+
+#[test]
+fn test_tui_form() -> Result<()> {
+    let now = Local
+        .with_ymd_and_hms(2024, 5, 15, 18, 0, 0)
+        .unwrap()
+        .with_timezone(&Utc);
+    let local = DayTimeZone::Local;
+
+    let mut form = ManualEntryForm::default();
+    assert!(!form.push("Site visit".to_string()));
+    assert!(!form.push("".to_string()));
+    assert_eq!(form.current_field().0, "开始时间");
+    assert!(!form.push("14:00".to_string()));
+    assert!(form.push("1h30m".to_string()));
+
+    let entry = form.to_entry(now, local)?;
+    assert_eq!(entry.label, "Site visit");
+    assert_eq!(entry.project, None);
+    assert_eq!(
+        entry.start,
+        Local.with_ymd_and_hms(2024, 5, 15, 14, 0, 0).unwrap()
+    );
+    assert_eq!(entry.duration(), 5400);
+
+    // 结束时间字段也可以填写时间，留空时结束于现在
+    let form = ManualEntryForm {
+        values: vec!["Call".into(), "Acme".into(), "17:00".into(), "17:20".into()],
+    };
+    assert_eq!(form.to_entry(now, local)?.duration(), 20 * 60);
+    let form = ManualEntryForm {
+        values: vec!["Call".into(), "Acme".into(), "17:00".into(), "".into()],
+    };
+    let entry = form.to_entry(now, local)?;
+    assert_eq!(entry.end, now);
+    assert_eq!(entry.project.as_deref(), Some("Acme"));
+
+    // 配置了时区时按该时区解析
+    let now = Utc.with_ymd_and_hms(2024, 5, 15, 18, 0, 0).unwrap();
+    let entry = form.to_entry(now, DayTimeZone::from_name("UTC")?)?;
+    assert_eq!(
+        entry.start,
+        Utc.with_ymd_and_hms(2024, 5, 15, 17, 0, 0).unwrap()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_csv_export_includes_source_and_project() -> Result<()> {
+    let mut tracker = tracker_with(&[tracked("Editor", 0, 30)])?;
+    let entry = ManualEntry::new("Call", at_minute(30), at_minute(45))?.project("Acme");
+    tracker.add_manual_entry(&entry, OverlapPolicy::Reject)?;
+
+    let csv = tracker.export_csv()?;
+    let lines: Vec<_> = csv.lines().collect();
+    assert!(lines[0].ends_with(",source,project"));
+    assert!(lines[1].ends_with(",tracked,"));
+    assert!(lines[2].ends_with(",manual,Acme"));
+    Ok(())
+}
@@ -0,0 +1,317 @@
+// 媒体播放检测测试
+// 测试 dbus-send 输出的解析、闲置时按 MPRIS 播放状态判断是否在观看、标题关键词的整词匹配、
+// 加密存储时时间线不记录曲目，以及在私有 D-Bus 总线上使用模拟的 MPRIS 播放器（默认忽略，需要 dbus-daemon 和 python3-dbus）
+
+mod common;
+
+use anyhow::Result;
+use common::at;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use timetracker::{
+    core::{
+        activity_detector::{ActivityConfig, ActivityDetector, ActivityStatus},
+        media::{
+            parse_player_names, parse_string_property, parse_track, MediaInfo, MprisClient,
+            PlaybackStatus,
+        },
+        status::StatusKind,
+        tracker::TimeTracker,
+    },
+    storage::{crypto::Passphrase, MemoryStore, StoreOptions},
+};
+
+const LIST_NAMES: &str = r#"method return time=1715760000.1 sender=org.freedesktop.DBus -> destination=:1.1 serial=3 reply_serial=2
+   array [
+      string "org.freedesktop.DBus"
+      string ":1.0"
+      string "org.mpris.MediaPlayer2.vlc"
+      string "org.mpris.MediaPlayer2.firefox.instance_1_42"
+   ]
+"#;
+
+const METADATA: &str = r#"method return time=1715760000.1 sender=:1.0 -> destination=:1.4 serial=5 reply_serial=2
+   variant       array [
+         dict entry(
+            string "mpris:trackid"
+            variant                object path "/org/mpris/MediaPlayer2/Track/1"
+         )
+         dict entry(
+            string "xesam:title"
+            variant                string "Big Buck Bunny"
+         )
+         dict entry(
+            string "xesam:artist"
+            variant                array [
+                  string "Blender"
+               ]
+         )
+      ]
+"#;
+
+const IDLE: Duration = Duration::from_secs(600);
+const ACTIVE: Duration = Duration::from_secs(5);
+
+/// 只按窗口标题判断的检测器
+fn detector(mpris: Option<MprisClient>) -> ActivityDetector {
+    let mut detector = ActivityDetector::new(ActivityConfig::default());
+    detector.set_mpris(mpris);
+    detector
+}
+
+#[test]
+fn test_parse_dbus_send_output() {
+    assert_eq!(
+        parse_player_names(LIST_NAMES),
+        vec![
+            "org.mpris.MediaPlayer2.vlc",
+            "org.mpris.MediaPlayer2.firefox.instance_1_42"
+        ]
+    );
+    assert_eq!(
+        parse_string_property("method return ...\n   variant       string \"Playing\"\n"),
+        Some("Playing".to_string())
+    );
+    assert_eq!(parse_string_property("method return ...\n"), None);
+    assert_eq!(
+        PlaybackStatus::parse("Paused"),
+        Some(PlaybackStatus::Paused)
+    );
+    assert_eq!(PlaybackStatus::parse("playing"), None);
+
+    assert_eq!(
+        parse_track(METADATA),
+        Some("Blender - Big Buck Bunny".to_string())
+    );
+    let without_artist = METADATA.replace("string \"xesam:artist\"", "string \"xesam:album\"");
+    assert_eq!(
+        parse_track(&without_artist),
+        Some("Big Buck Bunny".to_string())
+    );
+    assert_eq!(
+        parse_track("method return ...\n   variant       array [\n      ]\n"),
+        None
+    );
+}
+
+#[test]
+fn test_title_keywords_match_whole_words() {
+    let mut detector = detector(None);
+
+    for title in ["Playwright docs", "Streamlit dashboard", "videogame-ci.yml"] {
+        assert_eq!(
+            detector.classify(ACTIVE, Some("Firefox"), Some(title)),
+            ActivityStatus::Active,
+            "{title}"
+        );
+    }
+    for title in ["How to play chess", "Live stream - Twitch", "正在播放电影"] {
+        assert_eq!(
+            detector.classify(ACTIVE, Some("Firefox"), Some(title)),
+            ActivityStatus::WatchingVideo,
+            "{title}"
+        );
+    }
+}
+
+#[test]
+fn test_idle_without_mpris_uses_foreground_window() {
+    let mut detector = detector(None);
+
+    assert_eq!(
+        detector.classify(IDLE, Some("Firefox"), Some("Playwright docs")),
+        ActivityStatus::Idle
+    );
+    assert_eq!(detector.current_media(), None);
+
+    assert_eq!(
+        detector.classify(IDLE, Some("VLC"), Some("movie.mkv")),
+        ActivityStatus::WatchingVideo
+    );
+    assert_eq!(
+        detector.current_media(),
+        Some(&MediaInfo {
+            player: "VLC".to_string(),
+            track: Some("movie.mkv".to_string()),
+        })
+    );
+
+    // 恢复输入后不再记录媒体
+    detector.classify(ACTIVE, Some("VLC"), Some("movie.mkv"));
+    assert_eq!(detector.current_media(), None);
+
+    // 无法访问总线时同样按前台窗口判断
+    let mut detector = self::detector(Some(MprisClient::with_address(
+        "unix:path=/nonexistent/timetracker-test-bus",
+    )));
+    assert_eq!(
+        detector.classify(IDLE, Some("VLC"), Some("movie.mkv")),
+        ActivityStatus::WatchingVideo
+    );
+}
+
+#[test]
+fn test_media_is_recorded_on_status_timeline() -> Result<()> {
+    let media = |track: &str| MediaInfo {
+        player: "VLC media player".to_string(),
+        track: Some(track.to_string()),
+    };
+
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.observe_media(Some(media("Episode 1")));
+    for now in (0..=20).step_by(5) {
+        tracker.record_status(Some(StatusKind::VideoPassive), at(now), at(now))?;
+    }
+    // 换到下一集时开始新的时间段
+    tracker.observe_media(Some(media("Episode 2")));
+    for now in (25..=40).step_by(5) {
+        tracker.record_status(Some(StatusKind::VideoPassive), at(now), at(now))?;
+    }
+
+    let timeline = tracker.status_timeline(at(0), at(100))?;
+    assert_eq!(timeline.len(), 2);
+    assert_eq!(timeline[0].media, Some(media("Episode 1")));
+    assert_eq!((timeline[0].start, timeline[0].end), (at(0), at(25)));
+    assert_eq!(timeline[1].media, Some(media("Episode 2")));
+
+    // 只有被动观看的时间段记录媒体
+    tracker.record_status(Some(StatusKind::Active), at(45), at(45))?;
+    tracker.record_status(Some(StatusKind::Active), at(50), at(50))?;
+    assert_eq!(tracker.status_timeline(at(45), at(100))?[0].media, None);
+    Ok(())
+}
+
+#[test]
+fn test_encrypted_store_omits_track_from_timeline() -> Result<()> {
+    // 时间线文件是明文，加密存储时不写入曲目
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.set_store_options(StoreOptions {
+        passphrase: Some(Passphrase::new("correct horse")),
+        ..StoreOptions::default()
+    });
+    tracker.observe_media(Some(MediaInfo {
+        player: "VLC media player".to_string(),
+        track: Some("Private Episode".to_string()),
+    }));
+    tracker.record_status(Some(StatusKind::VideoPassive), at(0), at(0))?;
+    tracker.record_status(Some(StatusKind::VideoPassive), at(5), at(5))?;
+
+    let timeline = tracker.status_timeline(at(0), at(100))?;
+    assert_eq!(
+        timeline[0].media,
+        Some(MediaInfo {
+            player: "VLC media player".to_string(),
+            track: None,
+        })
+    );
+    Ok(())
+}
+
+/// 私有 D-Bus 总线，结束时停止总线和其上的模拟播放器
+struct PrivateBus {
+    address: String,
+    children: Vec<Child>,
+}
+
+impl PrivateBus {
+    /// 启动私有总线，缺少 dbus-daemon 时返回 None
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some(Self {
+            address: address.trim().to_string(),
+            children: vec![daemon],
+        })
+    }
+
+    /// 在总线上启动模拟播放器，缺少 python3-dbus 时返回 None
+    fn spawn_player(
+        &mut self,
+        suffix: &str,
+        status: &str,
+        identity: &str,
+        title: &str,
+    ) -> Option<()> {
+        let script = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mock_mpris.py");
+        for python in ["/usr/bin/python3", "python3"] {
+            let Ok(mut child) = Command::new(python)
+                .args([script, &self.address, suffix, status, identity, title])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+            else {
+                continue;
+            };
+            let mut line = String::new();
+            let ready = child
+                .stdout
+                .take()
+                .map(|stdout| BufReader::new(stdout).read_line(&mut line))
+                .is_some_and(|read| read.is_ok() && line.trim() == "ready");
+            if ready {
+                self.children.push(child);
+                return Some(());
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        None
+    }
+
+    fn client(&self) -> MprisClient {
+        MprisClient::with_address(self.address.clone())
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        for child in self.children.iter_mut().rev() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[test]
+#[ignore = "需要 dbus-daemon、python3-dbus 和 python3-gi（tests/fixtures/mock_mpris.py），用 cargo test -- --ignored 运行"]
+fn test_mock_mpris_player() {
+    let mut bus = PrivateBus::start().expect("无法启动私有 D-Bus 总线（需要 dbus-daemon）");
+    bus.spawn_player("paused", "Paused", "Rhythmbox", "Podcast")
+        .expect("无法启动模拟 MPRIS 播放器（需要 python3-dbus 和 python3-gi）");
+    // 模拟播放器在总线上注册名称后才输出 ready
+    let client = bus.client();
+
+    // 只有暂停的播放器：闲置时即使前台是视频应用也算闲置
+    assert_eq!(client.playing().unwrap(), None);
+    let mut detector = detector(Some(client.clone()));
+    assert_eq!(
+        detector.classify(IDLE, Some("VLC"), Some("movie.mkv")),
+        ActivityStatus::Idle
+    );
+
+    bus.spawn_player("vlc", "Playing", "VLC media player", "Big Buck Bunny")
+        .expect("第二个模拟播放器应该能启动");
+    let expected = MediaInfo {
+        player: "VLC media player".to_string(),
+        track: Some("Big Buck Bunny".to_string()),
+    };
+    assert_eq!(client.playing().unwrap(), Some(expected.clone()));
+
+    // 正在播放时，即使前台窗口标题与视频无关也算在观看
+    let mut detector = self::detector(Some(client));
+    assert_eq!(
+        detector.classify(IDLE, Some("Terminal"), Some("~/src")),
+        ActivityStatus::WatchingVideo
+    );
+    assert_eq!(detector.current_media(), Some(&expected));
+    assert_eq!(detector.get_stats().media, Some(expected));
+}
@@ -0,0 +1,182 @@
+// 数据迁移测试
+// 测试数据文件结构版本的逐步升级，以及加载旧文件时的自动迁移
+
+mod common;
+
+use anyhow::Result;
+use common::temp_dir;
+use serde_json::{json, Value};
+use timetracker::{
+    core::tracker::TimeTrackerData,
+    storage::{
+        migration::{
+            backup_path, detect_schema_version, migrate_value, parse_data, CURRENT_SCHEMA_VERSION,
+            MIGRATIONS,
+        },
+        sqlite::SCHEMA_VERSION,
+        ActivityStore, JsonFileStore, SqliteStore,
+    },
+};
+
+/// 0.1 版本的记录：时长字段名为 duration，没有窗口信息和 id
+fn v0_record(app: &str, minute: u32) -> Value {
+    json!({
+        "app_name": app,
+        "window_title": format!("{app} window"),
+        "start_time": format!("2024-05-01T09:{minute:02}:00Z"),
+        "end_time": format!("2024-05-01T09:{:02}:00Z", minute + 5),
+        "duration": 300,
+        "process_id": 42
+    })
+}
+
+#[test]
+fn test_migrations_cover_every_version() {
+    // 每个版本恰好有一个升级步骤，最终到达当前版本
+    let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.from).collect();
+    let expected: Vec<u32> = (0..CURRENT_SCHEMA_VERSION).collect();
+    assert_eq!(versions, expected);
+    assert_eq!(
+        TimeTrackerData::default().schema_version,
+        CURRENT_SCHEMA_VERSION
+    );
+}
+
+#[test]
+fn test_detect_schema_version() {
+    assert_eq!(detect_schema_version(&json!([])), 0);
+    assert_eq!(detect_schema_version(&json!({ "activities": [] })), 1);
+    assert_eq!(
+        detect_schema_version(&json!({ "activities": [], "schema_version": 2 })),
+        2
+    );
+}
+
+#[test]
+fn test_migrate_legacy_array() -> Result<()> {
+    let mut value = json!([v0_record("Firefox", 0), v0_record("Code", 10)]);
+    let report = migrate_value(&mut value)?;
+
+    assert_eq!(report.from_version, 0);
+    assert_eq!(report.to_version, CURRENT_SCHEMA_VERSION);
+    assert_eq!(report.steps.len(), MIGRATIONS.len());
+    assert_eq!(report.steps[0].changes, vec!["2 条记录转换为对象格式"]);
+
+    let data: TimeTrackerData = serde_json::from_value(value)?;
+    assert_eq!(data.schema_version, CURRENT_SCHEMA_VERSION);
+    assert_eq!(data.activities.len(), 2);
+    assert_eq!(data.activities[0].duration, 300);
+    assert!(data.activities[0].app_path.is_none());
+    assert!(data.activities.iter().all(|r| !r.id.is_empty()));
+    assert_ne!(data.activities[0].id, data.activities[1].id);
+    Ok(())
+}
+
+#[test]
+fn test_migrate_v1_renames_fields_and_fills_defaults() -> Result<()> {
+    let mut current = v0_record("Terminal", 20);
+    current["end_time"] = Value::Null;
+    let mut value = json!({
+        "activities": [v0_record("Firefox", 0)],
+        "current_activity": current,
+        "last_updated": "2024-05-01T09:30:00Z",
+        "version": "0.1.2"
+    });
+
+    let report = migrate_value(&mut value)?;
+    assert_eq!(report.from_version, 1);
+    assert_eq!(report.steps.len(), 2);
+    assert_eq!(
+        report.steps[0].changes,
+        vec![
+            "2 条记录的 duration 字段更名为 duration_seconds",
+            "2 条记录补全了窗口信息字段"
+        ]
+    );
+
+    let record = &value["activities"][0];
+    assert_eq!(record["duration_seconds"], 300);
+    assert!(record.get("duration").is_none());
+    assert_eq!(record["confidence"], 0.0);
+    assert!(value["current_activity"]["id"].is_string());
+    assert_eq!(value["version"], env!("CARGO_PKG_VERSION"));
+    Ok(())
+}
+
+#[test]
+fn test_migrate_v2_keeps_existing_ids() -> Result<()> {
+    let mut with_id = v0_record("Firefox", 0);
+    with_id["id"] = json!("keep-me");
+    let mut value = json!({
+        "activities": [with_id, v0_record("Code", 10)],
+        "current_activity": null,
+        "last_updated": "2024-05-01T09:30:00Z",
+        "schema_version": 2
+    });
+
+    let report = migrate_value(&mut value)?;
+    assert_eq!(report.steps.len(), 1);
+    assert_eq!(report.steps[0].changes, vec!["为 1 条记录生成了 id"]);
+    assert_eq!(value["activities"][0]["id"], "keep-me");
+    assert_eq!(value["schema_version"], CURRENT_SCHEMA_VERSION);
+    Ok(())
+}
+
+#[test]
+fn test_current_and_future_versions() -> Result<()> {
+    let content = serde_json::to_string(&TimeTrackerData::default())?;
+    let (_, report) = parse_data(&content)?;
+    assert!(report.is_empty());
+
+    let mut future = json!({ "activities": [], "schema_version": CURRENT_SCHEMA_VERSION + 1 });
+    assert!(migrate_value(&mut future).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_json_store_migrates_on_load() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+    let original = serde_json::to_string(&json!([v0_record("Firefox", 0)]))?;
+    std::fs::write(&path, &original)?;
+
+    let first = JsonFileStore::new(&path).load()?;
+    assert_eq!(first.activities.len(), 1);
+
+    // 原文件已备份，升级后的文件写回磁盘，再次加载时 id 保持不变
+    assert_eq!(std::fs::read_to_string(backup_path(&path, 0))?, original);
+    let on_disk: Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    assert_eq!(on_disk["schema_version"], CURRENT_SCHEMA_VERSION);
+    let second = JsonFileStore::new(&path).load()?;
+    assert_eq!(first.activities[0].id, second.activities[0].id);
+
+    // 按 id 删除在其他进程中同样生效
+    let mut store = JsonFileStore::new(&path);
+    assert_eq!(store.delete(&[first.activities[0].id.clone()])?, 1);
+    assert!(JsonFileStore::new(&path).load()?.activities.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_import_migrates_old_json() -> Result<()> {
+    let dir = temp_dir();
+    let json_path = dir.join("activities.json");
+    std::fs::write(
+        &json_path,
+        serde_json::to_string(&json!([v0_record("Firefox", 0)]))?,
+    )?;
+
+    let db_path = dir.join("activities.db");
+    let mut store = SqliteStore::open(&db_path)?;
+    assert_eq!(store.import_json(&json_path)?, 1);
+    assert_eq!(store.load_activities()?[0].duration, 300);
+    drop(store);
+
+    assert_eq!(SqliteStore::read_schema_version(&db_path)?, SCHEMA_VERSION);
+    assert!(SqliteStore::pending_schema_changes(SCHEMA_VERSION).is_empty());
+    assert_eq!(
+        SqliteStore::pending_schema_changes(1).len(),
+        (SCHEMA_VERSION - 1) as usize
+    );
+    Ok(())
+}
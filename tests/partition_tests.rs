@@ -0,0 +1,351 @@
+// 按日期分区存储测试
+// 测试分区写入、按配置时区划分分区、按时间范围读取、分区开关迁移、加密以及数据保留策略
+
+mod common;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::temp_dir;
+use std::io::Read;
+use std::path::Path;
+use timetracker::{
+    config::app::{AppConfig, StorageConfig},
+    core::tracker::ActivityRecord,
+    storage::{
+        crypto, open_store_with_options,
+        partition::{self, list_partitions, partition_dir, partition_span},
+        ActivityStore, JsonFileStore, PartitionedStore, Partitioning, Passphrase, RetentionAction,
+        RetentionPolicy, StoreOptions,
+    },
+    utils::time::{DayBoundary, DayTimeZone},
+};
+
+/// 指定日期中午（UTC）开始的记录，避开时区造成的日期边界偏移
+fn record(app: &str, month: u32, day: u32) -> ActivityRecord {
+    let start = Utc.with_ymd_and_hms(2024, month, day, 12, 0, 0).unwrap();
+    ActivityRecord {
+        id: format!("{app}-{month}-{day}"),
+        ..common::record(app, start, 60)
+    }
+}
+
+fn utc(month: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, month, day, 0, 0, 0).unwrap()
+}
+
+fn options(partitioning: Partitioning) -> StoreOptions {
+    StoreOptions {
+        partitioning: Some(partitioning),
+        ..StoreOptions::default()
+    }
+}
+
+/// 分区目录中是否有文件出现了指定的明文
+fn partitions_contain(path: &Path, needle: &str) -> bool {
+    std::fs::read_dir(partition_dir(path))
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .any(|entry| {
+            String::from_utf8_lossy(&std::fs::read(entry.path()).unwrap()).contains(needle)
+        })
+}
+
+#[test]
+fn test_partition_keys_and_spans() {
+    let time = Utc.with_ymd_and_hms(2024, 5, 15, 12, 0, 0).unwrap();
+    assert_eq!(
+        Partitioning::Month.key(time, DayBoundary::default()),
+        "2024-05"
+    );
+    assert_eq!(
+        Partitioning::Day.key(time, DayBoundary::default()),
+        "2024-05-15"
+    );
+
+    let (start, end) = partition_span("2024-05", DayBoundary::default()).unwrap();
+    assert!(start <= time && time < end);
+    assert!(end - start >= Duration::days(30));
+    let (start, end) = partition_span("2024-05-15", DayBoundary::default()).unwrap();
+    assert!(start <= time && time < end);
+
+    assert!(partition_span("archive", DayBoundary::default()).is_none());
+    assert!(partition_span("2024-13", DayBoundary::default()).is_none());
+    assert_eq!(Partitioning::from_name("none"), Some(Partitioning::Off));
+    assert_eq!(Partitioning::from_name("weekly"), None);
+}
+
+#[test]
+fn test_partitions_follow_configured_timezone() -> Result<()> {
+    let shanghai = DayBoundary::new(DayTimeZone::from_name("Asia/Shanghai")?, 0);
+
+    // UTC 5 月 31 日 20:00 是上海 6 月 1 日 04:00
+    let time = Utc.with_ymd_and_hms(2024, 5, 31, 20, 0, 0).unwrap();
+    assert_eq!(Partitioning::Month.key(time, shanghai), "2024-06");
+    assert_eq!(Partitioning::Day.key(time, shanghai), "2024-06-01");
+    assert_eq!(
+        partition_span("2024-06-01", shanghai),
+        Some((
+            Utc.with_ymd_and_hms(2024, 5, 31, 16, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 6, 1, 16, 0, 0).unwrap(),
+        ))
+    );
+
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+    let options = StoreOptions {
+        day_boundary: shanghai,
+        ..options(Partitioning::Day)
+    };
+    let mut store = open_store_with_options(&path, &options)?;
+    store.append(&ActivityRecord {
+        id: "late".to_string(),
+        ..common::record("Firefox", time, 60)
+    })?;
+    assert_eq!(list_partitions(&path)?, vec!["2024-06-01"]);
+
+    // 按范围查询时同样按上海时间的分区区间判断
+    let records = store.query_range(
+        Utc.with_ymd_and_hms(2024, 5, 31, 18, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2024, 5, 31, 22, 0, 0).unwrap(),
+    )?;
+    assert_eq!(records.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_records_are_written_to_monthly_partitions() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut store =
+        PartitionedStore::open(&path, Partitioning::Month, DayBoundary::default(), None)?;
+    store.append(&record("Firefox", 5, 10))?;
+    store.append(&record("Code", 5, 20))?;
+    store.append(&record("Terminal", 6, 3))?;
+    store.set_current(Some(&record("Slack", 6, 4)))?;
+    store.flush()?;
+    drop(store);
+
+    assert_eq!(list_partitions(&path)?, vec!["2024-05", "2024-06"]);
+    assert_eq!(partition::detect_partitioning(&path), Partitioning::Month);
+
+    // 不指定分区方式时沿用已有的布局
+    let mut store = open_store_with_options(&path, &StoreOptions::default())?;
+    assert_eq!(store.name(), "partitioned-json");
+    let data = store.load()?;
+    assert_eq!(data.activities.len(), 3);
+    assert_eq!(data.current_activity.unwrap().app_name, "Slack");
+
+    let june = store.query_range(utc(6, 1), utc(7, 1))?;
+    assert_eq!(june.len(), 1);
+    assert_eq!(june[0].app_name, "Terminal");
+    Ok(())
+}
+
+#[test]
+fn test_query_range_reads_only_overlapping_partitions() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut store =
+        PartitionedStore::open(&path, Partitioning::Month, DayBoundary::default(), None)?;
+    store.append(&record("Firefox", 3, 10))?;
+    store.append(&record("Code", 5, 10))?;
+    store.flush()?;
+    drop(store);
+
+    // 损坏三月的分区：只查询五月时不应读取（也不会隔离）它
+    let march = partition_dir(&path).join("2024-03.json");
+    std::fs::write(&march, "{ not json")?;
+
+    let mut store =
+        PartitionedStore::open(&path, Partitioning::Month, DayBoundary::default(), None)?;
+    let may = store.query_range(utc(5, 1), utc(6, 1))?;
+    assert_eq!(may.len(), 1);
+    assert_eq!(may[0].app_name, "Code");
+    assert!(store.take_warnings().is_empty());
+    assert_eq!(std::fs::read_to_string(&march)?, "{ not json");
+    Ok(())
+}
+
+#[test]
+fn test_enabling_and_disabling_partitioning_migrates_data() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut plain = JsonFileStore::new(&path);
+    plain.append(&record("Firefox", 5, 10))?;
+    plain.append(&record("Code", 5, 11))?;
+    plain.compact()?;
+    drop(plain);
+
+    // 启用按天分区：已有记录移入分区，主数据文件不再保存历史记录
+    let mut store = open_store_with_options(&path, &options(Partitioning::Day))?;
+    assert_eq!(store.load()?.activities.len(), 2);
+    drop(store);
+    assert_eq!(list_partitions(&path)?, vec!["2024-05-10", "2024-05-11"]);
+    assert!(JsonFileStore::new(&path).load()?.activities.is_empty());
+
+    // 关闭分区：全部记录合并回主数据文件
+    let mut store = open_store_with_options(&path, &options(Partitioning::Off))?;
+    assert_eq!(store.name(), "json");
+    assert_eq!(store.load()?.activities.len(), 2);
+    drop(store);
+    assert!(!partition::has_partitions(&path));
+    assert!(!partition_dir(&path).exists());
+    Ok(())
+}
+
+#[test]
+fn test_update_moves_record_between_partitions() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut store =
+        PartitionedStore::open(&path, Partitioning::Month, DayBoundary::default(), None)?;
+    let mut moved = record("Firefox", 5, 10);
+    store.append(&moved)?;
+    store.append(&record("Code", 5, 11))?;
+
+    moved.start_time = Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+    assert!(store.update(&moved)?);
+    assert!(!store.update(&record("Missing", 5, 12))?);
+
+    assert_eq!(store.query_range(utc(5, 1), utc(6, 1))?.len(), 1);
+    let june = store.query_range(utc(6, 1), utc(7, 1))?;
+    assert_eq!(june.len(), 1);
+    assert_eq!(june[0].id, moved.id);
+
+    assert_eq!(store.delete(&[moved.id.clone()])?, 1);
+    assert_eq!(store.load()?.activities.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_retention_archives_or_deletes_expired_partitions() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut store =
+        PartitionedStore::open(&path, Partitioning::Month, DayBoundary::default(), None)?;
+    store.append(&record("Firefox", 4, 10))?;
+    store.append(&record("Code", 5, 10))?;
+    store.append(&record("Terminal", 6, 10))?;
+
+    let now = Utc.with_ymd_and_hms(2024, 7, 10, 0, 0, 0).unwrap();
+    let archive = RetentionPolicy {
+        keep_days: 60,
+        action: RetentionAction::Archive,
+    };
+    let report = store.apply_retention(&archive, now)?;
+    assert_eq!(report.archived.len(), 1);
+    assert!(report.deleted.is_empty());
+
+    let (key, archive_path) = &report.archived[0];
+    assert_eq!(key, "2024-04");
+    let mut contents = String::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(archive_path)?)
+        .read_to_string(&mut contents)?;
+    assert!(contents.contains("Firefox window"));
+
+    let delete = RetentionPolicy {
+        keep_days: 30,
+        action: RetentionAction::Delete,
+    };
+    let report = store.apply_retention(&delete, now)?;
+    assert_eq!(report.deleted, vec!["2024-05"]);
+
+    assert_eq!(list_partitions(&path)?, vec!["2024-06"]);
+    let data = store.load()?;
+    assert_eq!(data.activities.len(), 1);
+    assert_eq!(data.activities[0].app_name, "Terminal");
+
+    // 不分区的存储不处理保留策略
+    let mut plain = JsonFileStore::new(dir.join("plain.json"));
+    plain.append(&record("Firefox", 1, 10))?;
+    assert!(plain.apply_retention(&delete, now)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_encrypted_partitions() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+    let old = Passphrase::new("old passphrase");
+    let new = Passphrase::new("new passphrase");
+
+    let mut store = PartitionedStore::open(
+        &path,
+        Partitioning::Month,
+        DayBoundary::default(),
+        Some(&old),
+    )?;
+    store.append(&record("Firefox", 5, 10))?;
+    store.append(&record("Code", 6, 10))?;
+    store.flush()?;
+    assert!(!partitions_contain(&path, "Firefox window"));
+
+    store.rotate_key(&new)?;
+    drop(store);
+
+    assert!(
+        PartitionedStore::open(&path, Partitioning::Month, DayBoundary::default(), None).is_err()
+    );
+    assert!(PartitionedStore::open(
+        &path,
+        Partitioning::Month,
+        DayBoundary::default(),
+        Some(&old)
+    )
+    .is_err());
+    let mut store = PartitionedStore::open(
+        &path,
+        Partitioning::Month,
+        DayBoundary::default(),
+        Some(&new),
+    )?;
+    assert_eq!(store.load()?.activities.len(), 2);
+
+    store.remove_encryption()?;
+    drop(store);
+    assert!(!crypto::is_encrypted(&path));
+    assert!(partitions_contain(&path, "Firefox window"));
+    let mut store =
+        PartitionedStore::open(&path, Partitioning::Month, DayBoundary::default(), None)?;
+    assert_eq!(store.load()?.activities.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_partition_and_retention_config_validation() {
+    let mut storage = StorageConfig {
+        partition: "month".to_string(),
+        ..Default::default()
+    };
+    assert!(storage.validate().is_ok());
+
+    storage.backend = "sqlite".to_string();
+    assert!(storage.validate().is_err());
+    assert!(!storage.fix().is_empty());
+    assert_eq!(storage.partition, "none");
+
+    storage.partition = "weekly".to_string();
+    assert!(storage.validate().is_err());
+
+    // 启用数据保留时自动启用按月分区
+    let mut config = AppConfig::default();
+    assert!(config.retention.policy().is_none());
+    config.retention.enabled = true;
+    assert!(config.validate().is_err());
+    assert!(!config.fix().is_empty());
+    assert_eq!(config.storage.partition, "month");
+    assert!(config.validate().is_ok());
+    assert_eq!(
+        config.retention.policy(),
+        Some(RetentionPolicy {
+            keep_days: 365,
+            action: RetentionAction::Archive,
+        })
+    );
+}
@@ -0,0 +1,181 @@
+// 休眠和锁屏感知测试
+// 测试 logind 信号和会话 LockedHint 变化的解析、时钟跳变检测，以及休眠/锁屏时在上次采样时结束当前活动
+
+mod common;
+
+use anyhow::Result;
+use chrono::Duration;
+use common::{at, titled_heartbeat};
+use std::time::Duration as StdDuration;
+use timetracker::{
+    core::{
+        power::{is_clock_jump, AwayReason, LogindSignalParser, PowerEvent},
+        tracker::TimeTracker,
+    },
+    storage::MemoryStore,
+};
+
+fn tracker_with_activity() -> Result<TimeTracker> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.load_data()?;
+    tracker.record_heartbeat(titled_heartbeat("Firefox", "Docs", 0))?;
+    Ok(tracker)
+}
+
+#[test]
+fn test_parse_logind_signals() {
+    let output = "\
+signal time=1715760000.1 sender=:1.3 -> destination=(null destination) serial=9 path=/org/freedesktop/login1; interface=org.freedesktop.login1.Manager; member=PrepareForSleep
+   boolean true
+signal time=1715763600.2 sender=:1.3 -> destination=(null destination) serial=10 path=/org/freedesktop/login1; interface=org.freedesktop.login1.Manager; member=PrepareForSleep
+   boolean false
+signal time=1715763700.3 sender=:1.3 -> destination=(null destination) serial=11 path=/org/freedesktop/login1/session/_32; interface=org.freedesktop.login1.Session; member=Lock
+signal time=1715763800.4 sender=:1.3 -> destination=(null destination) serial=12 path=/org/freedesktop/login1/session/_32; interface=org.freedesktop.login1.Session; member=Unlock
+signal time=1715763900.5 sender=:1.3 -> destination=(null destination) serial=13 path=/org/freedesktop/login1; interface=org.freedesktop.login1.Manager; member=SessionNew
+   string \"c2\"
+";
+
+    let mut parser = LogindSignalParser::new();
+    let events: Vec<PowerEvent> = output
+        .lines()
+        .filter_map(|line| parser.parse_line(line))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            PowerEvent::Sleep,
+            PowerEvent::Resume,
+            PowerEvent::Lock,
+            PowerEvent::Unlock
+        ]
+    );
+}
+
+#[test]
+fn test_parse_locked_hint_changes() {
+    let output = "\
+signal time=1715763700.3 sender=:1.3 -> destination=(null destination) serial=11 path=/org/freedesktop/login1/session/_32; interface=org.freedesktop.DBus.Properties; member=PropertiesChanged
+   string \"org.freedesktop.login1.Session\"
+   array [
+      dict entry(
+         string \"LockedHint\"
+         variant             boolean true
+      )
+   ]
+   array [
+   ]
+signal time=1715763750.0 sender=:1.3 -> destination=(null destination) serial=12 path=/org/freedesktop/login1/session/_32; interface=org.freedesktop.DBus.Properties; member=PropertiesChanged
+   string \"org.freedesktop.login1.Session\"
+   array [
+      dict entry(
+         string \"IdleHint\"
+         variant             boolean false
+      )
+   ]
+   array [
+   ]
+signal time=1715763760.0 sender=:1.3 -> destination=(null destination) serial=13 path=/org/freedesktop/login1/user/_1000; interface=org.freedesktop.DBus.Properties; member=PropertiesChanged
+   string \"org.freedesktop.login1.User\"
+   array [
+      dict entry(
+         string \"LockedHint\"
+         variant             boolean false
+      )
+   ]
+   array [
+   ]
+signal time=1715763800.4 sender=:1.3 -> destination=(null destination) serial=14 path=/org/freedesktop/login1/session/_32; interface=org.freedesktop.DBus.Properties; member=PropertiesChanged
+   string \"org.freedesktop.login1.Session\"
+   array [
+      dict entry(
+         string \"LockedHint\"
+         variant             boolean false
+      )
+   ]
+   array [
+   ]
+";
+
+    let mut parser = LogindSignalParser::new();
+    let events: Vec<PowerEvent> = output
+        .lines()
+        .filter_map(|line| parser.parse_line(line))
+        .collect();
+    assert_eq!(events, vec![PowerEvent::Lock, PowerEvent::Unlock]);
+}
+
+#[test]
+fn test_clock_jump_detection() {
+    let now = at(0);
+    let interval = StdDuration::from_secs(1);
+    assert!(!is_clock_jump(now, now + Duration::seconds(1), interval));
+    assert!(!is_clock_jump(now, now + Duration::seconds(20), interval));
+    assert!(is_clock_jump(now, now + Duration::minutes(10), interval));
+    // 时钟被向后调整
+    assert!(is_clock_jump(now, now - Duration::minutes(10), interval));
+    // 采样间隔较长时阈值随之放大
+    let interval = StdDuration::from_secs(60);
+    assert!(!is_clock_jump(now, now + Duration::seconds(120), interval));
+    assert!(is_clock_jump(now, now + Duration::seconds(200), interval));
+}
+
+#[test]
+fn test_clock_jump_closes_activity_at_last_tick() -> Result<()> {
+    let mut tracker = tracker_with_activity()?;
+    let start = tracker.current_activity.as_ref().unwrap().start_time;
+
+    let last_tick = start + Duration::seconds(5);
+    tracker.observe_tick(last_tick)?;
+    // 休眠一小时后唤醒
+    let resumed = last_tick + Duration::hours(1);
+    tracker.observe_tick(resumed)?;
+
+    assert!(tracker.current_activity.is_none());
+    let record = &tracker.data.activities[0];
+    assert_eq!(record.end_time, Some(last_tick));
+    assert_eq!(record.duration, 5);
+
+    let away = tracker.away_periods();
+    assert_eq!(away.len(), 1);
+    assert_eq!(away[0].reason, AwayReason::ClockJump);
+    assert_eq!((away[0].start, away[0].end), (last_tick, Some(resumed)));
+    assert!(!tracker.is_away());
+    Ok(())
+}
+
+#[test]
+fn test_sleep_and_lock_signals_mark_away() -> Result<()> {
+    let mut tracker = tracker_with_activity()?;
+    let start = tracker.current_activity.as_ref().unwrap().start_time;
+
+    let last_tick = start + Duration::seconds(3);
+    tracker.observe_tick(last_tick)?;
+
+    // 先锁屏再休眠，唤醒后仍处于锁屏状态
+    tracker.handle_power_event(PowerEvent::Lock, last_tick + Duration::seconds(1))?;
+    assert!(tracker.is_away());
+    assert_eq!(tracker.data.activities[0].end_time, Some(last_tick));
+    assert_eq!(tracker.data.activities[0].duration, 3);
+
+    tracker.handle_power_event(PowerEvent::Sleep, last_tick + Duration::seconds(2))?;
+    let resumed = last_tick + Duration::hours(2);
+    tracker.handle_power_event(PowerEvent::Resume, resumed)?;
+    // 离开期间的时钟跳变不重复记录
+    tracker.observe_tick(resumed)?;
+    assert!(tracker.is_away());
+    assert!(tracker.away_periods().is_empty());
+
+    let unlocked = resumed + Duration::seconds(30);
+    tracker.handle_power_event(PowerEvent::Unlock, unlocked)?;
+    assert!(!tracker.is_away());
+    let away = tracker.away_periods();
+    assert_eq!(away.len(), 1);
+    assert_eq!(away[0].reason, AwayReason::Lock);
+    assert_eq!((away[0].start, away[0].end), (last_tick, Some(unlocked)));
+    assert_eq!(away[0].duration_at(unlocked), 2 * 3600 + 30);
+
+    // 只记录了一条活动，离开时段不计入使用时间
+    assert_eq!(tracker.data.activities.len(), 1);
+    assert_eq!(tracker.get_total_time(), 3);
+    Ok(())
+}
@@ -0,0 +1,88 @@
+// 异常退出恢复测试
+// 测试启动时按最后更新时间结束遗留的当前活动、恢复标记的持久化，以及不会重复恢复
+
+mod common;
+
+use anyhow::Result;
+use common::at_minute;
+use timetracker::{
+    core::tracker::{ActivityRecord, RecordSource, TimeTracker, TimeTrackerData},
+    storage::{ActivityStore, MemoryStore, SqliteStore},
+};
+
+fn stale_current() -> ActivityRecord {
+    ActivityRecord {
+        id: "stale".to_string(),
+        window_title: "main.rs".to_string(),
+        end_time: None,
+        ..common::record("Code", at_minute(0), 0)
+    }
+}
+
+/// 模拟上次异常退出：存储中留有当前活动，最后更新于开始后 25 分钟
+fn crashed_store(current: ActivityRecord) -> MemoryStore {
+    MemoryStore::with_data(TimeTrackerData {
+        current_activity: Some(current),
+        last_updated: at_minute(25),
+        ..TimeTrackerData::default()
+    })
+}
+
+#[test]
+fn test_recover_closes_at_last_updated() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(crashed_store(stale_current())), 1);
+    tracker.load_data()?;
+
+    let recovered = tracker.recover_stale_activity()?.unwrap();
+    assert_eq!(recovered.end_time, Some(at_minute(25)));
+    assert_eq!(recovered.duration, 25 * 60);
+    assert_eq!(recovered.source, RecordSource::Recovered);
+
+    assert_eq!(tracker.data.activities, vec![recovered]);
+    assert!(tracker.data.current_activity.is_none());
+
+    // 再次启动时没有需要恢复的活动
+    assert!(tracker.recover_stale_activity()?.is_none());
+    assert_eq!(tracker.data.activities.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_recover_skips_already_committed_record() -> Result<()> {
+    let mut committed = stale_current();
+    committed.finish_at(at_minute(10));
+    let mut store = crashed_store(stale_current());
+    store.append(&committed)?;
+
+    let mut tracker = TimeTracker::with_store(Box::new(store), 1);
+    tracker.load_data()?;
+
+    assert!(tracker.recover_stale_activity()?.is_none());
+    assert_eq!(tracker.data.activities.len(), 1);
+    assert_eq!(tracker.data.activities[0].source, RecordSource::Tracked);
+    assert!(tracker.data.current_activity.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_recovered_source_persists() -> Result<()> {
+    let mut record = stale_current();
+    record.finish_at(at_minute(25));
+    record.source = RecordSource::Recovered;
+
+    let mut store = SqliteStore::open_in_memory()?;
+    store.append(&record)?;
+    assert_eq!(store.load()?.activities, vec![record.clone()]);
+
+    // 自动记录在 JSON 中不写出来源字段，旧数据缺少该字段时按自动记录读取
+    let mut tracked = record.clone();
+    tracked.source = RecordSource::Tracked;
+    let json = serde_json::to_string(&tracked)?;
+    assert!(!json.contains("source"));
+    let parsed: ActivityRecord = serde_json::from_str(&json)?;
+    assert_eq!(parsed.source, RecordSource::Tracked);
+
+    let json = serde_json::to_string(&record)?;
+    assert!(json.contains("\"source\":\"recovered\""));
+    Ok(())
+}
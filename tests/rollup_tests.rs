@@ -0,0 +1,285 @@
+// 每日汇总测试
+// 测试旧记录按配置的逻辑日合并为每日汇总、各存储后端的汇总读写，以及统计中汇总与原始记录的合并
+
+mod common;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use common::temp_dir;
+use timetracker::{
+    config::app::{AppConfig, RollupConfig},
+    core::tracker::{ActivityRecord, DailyRollup, TimeTracker},
+    storage::{
+        rollup::{build_rollups, merge_rollups, preview_rollup, rollup_cutoff},
+        sqlite::SCHEMA_VERSION,
+        ActivityStore, JsonFileStore, JsonlStore, MemoryStore, PartitionedStore, Partitioning,
+        SqliteStore,
+    },
+    utils::time::{DayBoundary, DayTimeZone},
+};
+
+/// 指定日期中午（UTC）开始、持续 `duration` 秒的记录
+fn record(app: &str, day: u32, minute_offset: i64, duration: u64) -> ActivityRecord {
+    let start =
+        Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap() + Duration::minutes(minute_offset);
+    ActivityRecord {
+        id: format!("{app}-{day}-{minute_offset}"),
+        ..common::record(app, start, duration)
+    }
+}
+
+fn utc(day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, day, 0, 0, 0).unwrap()
+}
+
+/// 五月 1 日两条 Firefox、一条 Code，五月 20 日一条 Firefox
+fn sample() -> Vec<ActivityRecord> {
+    vec![
+        record("Firefox", 1, 0, 60),
+        record("Firefox", 1, 30, 120),
+        record("Code", 1, 10, 300),
+        record("Firefox", 20, 0, 45),
+    ]
+}
+
+/// 对存储执行汇总并检查结果（汇总五月 10 日之前的记录）
+fn check_store_rollup(store: &mut dyn ActivityStore) -> Result<()> {
+    for record in sample() {
+        store.append(&record)?;
+    }
+
+    let report = store.rollup(utc(10), DayBoundary::default())?;
+    assert_eq!(report.records, 3);
+    assert_eq!(report.rollups, 2);
+
+    // 再次汇总没有新的旧记录
+    assert!(store.rollup(utc(10), DayBoundary::default())?.is_empty());
+
+    let data = store.load()?;
+    assert_eq!(data.activities.len(), 1);
+    assert_eq!(data.rollups.len(), 2);
+    let firefox = data
+        .rollups
+        .iter()
+        .find(|r| r.app_name == "Firefox")
+        .unwrap();
+    assert_eq!(firefox.total_duration, 180);
+    assert_eq!(firefox.record_count, 2);
+
+    assert_eq!(store.query_rollups(utc(1), utc(2))?.len(), 2);
+    assert!(store.query_rollups(utc(2), utc(31))?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_build_and_merge_rollups() {
+    let records = sample();
+    let rollups = build_rollups(&records[..3], DayBoundary::default());
+    assert_eq!(rollups.len(), 2);
+
+    let firefox = rollups.iter().find(|r| r.app_name == "Firefox").unwrap();
+    assert_eq!(firefox.total_duration, 180);
+    assert_eq!(firefox.record_count, 2);
+    assert_eq!(firefox.first_seen, records[0].start_time);
+    assert_eq!(firefox.last_seen, records[1].end_time.unwrap());
+
+    // 同一天同一应用窗口的汇总相加，其他的追加
+    let mut existing = rollups.clone();
+    let touched = merge_rollups(
+        &mut existing,
+        vec![
+            DailyRollup::from_record(&records[0], DayBoundary::default()),
+            DailyRollup::from_record(&records[3], DayBoundary::default()),
+        ],
+    );
+    assert_eq!(touched, 2);
+    assert_eq!(existing.len(), 3);
+    let firefox = existing
+        .iter()
+        .find(|r| r.app_name == "Firefox" && r.record_count > 1)
+        .unwrap();
+    assert_eq!(firefox.total_duration, 240);
+    assert_eq!(firefox.record_count, 3);
+    assert!(existing.windows(2).all(|w| w[0].date <= w[1].date));
+}
+
+#[test]
+fn test_rollup_cutoff_is_local_midnight() {
+    let now = Utc.with_ymd_and_hms(2024, 5, 31, 12, 0, 0).unwrap();
+    let cutoff = rollup_cutoff(now, 30, DayBoundary::default());
+    assert!(cutoff <= now - Duration::days(29));
+    assert!(cutoff > now - Duration::days(32));
+    assert!(rollup_cutoff(now, 1, DayBoundary::default()) > cutoff);
+}
+
+#[test]
+fn test_rollup_uses_day_boundary() -> Result<()> {
+    // 上海时区，凌晨 4 点分割
+    let boundary = DayBoundary::new(DayTimeZone::from_name("Asia/Shanghai")?, 4);
+
+    // UTC 5 月 1 日 17:00 是上海 5 月 2 日 01:00，仍属于 5 月 1 日；
+    // UTC 5 月 1 日 21:00 是上海 5 月 2 日 05:00，属于 5 月 2 日
+    let late = record("Firefox", 1, 5 * 60, 60);
+    let morning = record("Firefox", 1, 9 * 60, 60);
+    let rollups = build_rollups(&[late.clone(), morning.clone()], boundary);
+    assert_eq!(
+        rollups.iter().map(|r| r.date).collect::<Vec<_>>(),
+        vec![
+            NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+        ]
+    );
+
+    // 截止时间是逻辑日的开始：上海 5 月 1 日 04:00 即 UTC 4 月 30 日 20:00
+    let now = Utc.with_ymd_and_hms(2024, 5, 31, 12, 0, 0).unwrap();
+    assert_eq!(
+        rollup_cutoff(now, 30, boundary),
+        Utc.with_ymd_and_hms(2024, 4, 30, 20, 0, 0).unwrap()
+    );
+
+    // 存储汇总时同样按逻辑日分组
+    let mut store = MemoryStore::new();
+    store.append(&late)?;
+    store.append(&morning)?;
+    let report = store.rollup(utc(10), boundary)?;
+    assert_eq!((report.records, report.rollups), (2, 2));
+    Ok(())
+}
+
+#[test]
+fn test_memory_store_rollup() -> Result<()> {
+    check_store_rollup(&mut MemoryStore::new())
+}
+
+#[test]
+fn test_json_store_rollup_persists() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut store = JsonFileStore::new(&path);
+    check_store_rollup(&mut store)?;
+    drop(store);
+
+    let data = JsonFileStore::new(&path).load()?;
+    assert_eq!(data.activities.len(), 1);
+    assert_eq!(data.rollups.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_store_rollup_persists() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.jsonl");
+
+    let mut store = JsonlStore::new(&path);
+    check_store_rollup(&mut store)?;
+
+    // 编辑记录时保留汇总行
+    let mut recent = store.load()?.activities[0].clone();
+    recent.duration = 50;
+    assert!(store.update(&recent)?);
+    drop(store);
+
+    let mut store = JsonlStore::new(&path);
+    let data = store.load()?;
+    assert_eq!(data.activities[0].duration, 50);
+    assert_eq!(data.rollups.len(), 2);
+    assert!(store.take_warnings().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_store_rollup() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.db");
+
+    let mut store = SqliteStore::open(&path)?;
+    check_store_rollup(&mut store)?;
+
+    // 同一天的记录再次汇总时累加到已有汇总
+    store.append(&record("Firefox", 1, 60, 20))?;
+    let report = store.rollup(utc(10), DayBoundary::default())?;
+    assert_eq!((report.records, report.rollups), (1, 1));
+    drop(store);
+
+    assert_eq!(SqliteStore::read_schema_version(&path)?, SCHEMA_VERSION);
+    let data = SqliteStore::open(&path)?.load()?;
+    let firefox = data
+        .rollups
+        .iter()
+        .find(|r| r.app_name == "Firefox")
+        .unwrap();
+    assert_eq!(firefox.total_duration, 200);
+    assert_eq!(firefox.record_count, 3);
+    Ok(())
+}
+
+#[test]
+fn test_partitioned_store_rollup() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut store = PartitionedStore::open(&path, Partitioning::Day, DayBoundary::default(), None)?;
+    check_store_rollup(&mut store)?;
+    drop(store);
+
+    // 关闭分区时汇总一并合并回主数据文件
+    let store = PartitionedStore::open(&path, Partitioning::Day, DayBoundary::default(), None)?;
+    assert_eq!(store.consolidate()?, 1);
+    let data = JsonFileStore::new(&path).load()?;
+    assert_eq!(data.activities.len(), 1);
+    assert_eq!(data.rollups.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_preview_rollup_does_not_modify_store() -> Result<()> {
+    let mut store = MemoryStore::new();
+    for record in sample() {
+        store.append(&record)?;
+    }
+
+    let report = preview_rollup(&mut store, utc(10), DayBoundary::default())?;
+    assert_eq!((report.records, report.rollups), (3, 2));
+    let data = store.load()?;
+    assert_eq!(data.activities.len(), 4);
+    assert!(data.rollups.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_statistics_combine_rollups_and_records() -> Result<()> {
+    let mut store = MemoryStore::new();
+    for record in sample() {
+        store.append(&record)?;
+    }
+    store.rollup(utc(10), DayBoundary::default())?;
+
+    let mut tracker = TimeTracker::with_store(Box::new(store), 1);
+    tracker.load_data()?;
+    assert_eq!(tracker.get_total_time(), 60 + 120 + 300 + 45);
+    let stats = tracker.get_statistics();
+    assert_eq!(stats["Firefox - Firefox window"], 60 + 120 + 45);
+    assert_eq!(stats["Code - Code window"], 300);
+
+    // 按时间范围加载时只包含范围内的汇总
+    tracker.load_range(utc(15), utc(31))?;
+    assert!(tracker.data.rollups.is_empty());
+    assert_eq!(tracker.get_total_time(), 45);
+    Ok(())
+}
+
+#[test]
+fn test_rollup_config_validation() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.rollup.after_days(), None);
+
+    config.rollup = RollupConfig {
+        enabled: true,
+        after_days: 0,
+    };
+    assert!(config.validate().is_err());
+    assert!(!config.fix().is_empty());
+    assert!(config.validate().is_ok());
+    assert_eq!(config.rollup.after_days(), Some(90));
+}
@@ -0,0 +1,250 @@
+// 状态时间线测试
+// 测试活跃、闲置、锁屏状态的切换与回溯、休眠造成的时钟跳变、时间线文件持久化和保留期清理、统计和导出
+
+mod common;
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use common::{at, temp_dir};
+use std::io::Write;
+use timetracker::{
+    core::{
+        media::MediaInfo,
+        status::{daily_summaries, StatusInterval, StatusKind, StatusSummary},
+        tracker::TimeTracker,
+    },
+    storage::{
+        status::{status_log_path, StatusLog},
+        MemoryStore,
+    },
+    utils::time::{DayBoundary, DayTimeZone},
+};
+
+fn interval(status: StatusKind, start: i64, end: i64) -> StatusInterval {
+    StatusInterval::new(status, at(start), at(end))
+}
+
+/// 从 `from` 到 `to` 每 5 秒采样一次相同的状态
+fn sample(
+    tracker: &mut TimeTracker,
+    status: StatusKind,
+    since: i64,
+    from: i64,
+    to: i64,
+) -> Result<()> {
+    for now in (from..=to).step_by(5) {
+        tracker.record_status(Some(status), at(since), at(now))?;
+    }
+    Ok(())
+}
+
+fn timeline(tracker: &mut TimeTracker) -> Result<Vec<StatusInterval>> {
+    tracker.status_timeline(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+}
+
+#[test]
+fn test_idle_and_lock_are_backdated() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+
+    sample(&mut tracker, StatusKind::Active, 0, 0, 60)?;
+    // 60 秒时检测到闲置，最后一次输入在 32 秒：活跃时间段截止到 32 秒
+    sample(&mut tracker, StatusKind::Idle, 32, 60, 120)?;
+    // 120 秒时锁屏，300 秒时解锁
+    sample(&mut tracker, StatusKind::Locked, 120, 125, 300)?;
+    sample(&mut tracker, StatusKind::Active, 300, 305, 330)?;
+
+    assert_eq!(
+        timeline(&mut tracker)?,
+        vec![
+            interval(StatusKind::Active, 0, 32),
+            interval(StatusKind::Idle, 32, 120),
+            interval(StatusKind::Locked, 120, 300),
+            interval(StatusKind::Active, 300, 330),
+        ]
+    );
+
+    // 停止监控后进行中的时间段写入时间线
+    tracker.stop_monitoring()?;
+    assert_eq!(timeline(&mut tracker)?.len(), 4);
+    Ok(())
+}
+
+#[test]
+fn test_sleep_gap_is_not_counted() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+
+    sample(&mut tracker, StatusKind::Active, 0, 0, 60)?;
+    // 休眠一小时后唤醒：活跃时间段在最后一次采样时结束，休眠时间不计入任何状态
+    tracker.record_status(None, at(60), at(3660))?;
+    sample(&mut tracker, StatusKind::Active, 3665, 3665, 3700)?;
+
+    let intervals = timeline(&mut tracker)?;
+    assert_eq!(
+        intervals,
+        vec![
+            interval(StatusKind::Active, 0, 60),
+            interval(StatusKind::Active, 3665, 3700),
+        ]
+    );
+    let summary = StatusSummary::from_intervals(&intervals, at(0), at(3700));
+    assert_eq!(summary.computer_on(), 95);
+    assert_eq!(summary.active, 95);
+
+    // 没有休眠信号时，两次采样之间的时钟跳变同样不计入，新的时间段不会回溯到跳变之前
+    sample(&mut tracker, StatusKind::Active, 0, 7300, 7310)?;
+    let intervals = timeline(&mut tracker)?;
+    assert_eq!(intervals.len(), 3);
+    assert_eq!(intervals[1], interval(StatusKind::Active, 3665, 3700));
+    assert_eq!(intervals[2], interval(StatusKind::Active, 7300, 7310));
+    Ok(())
+}
+
+#[test]
+fn test_status_log_file_is_shared() -> Result<()> {
+    let dir = temp_dir();
+    let data_file = dir.join("activities.db");
+    let path = status_log_path(&data_file);
+    assert_eq!(path, dir.join("activities.status.jsonl"));
+
+    // 守护进程写入，另一个进程读取已结束和进行中的时间段
+    let mut daemon = StatusLog::for_data_file(&data_file.to_string_lossy());
+    daemon.append(&interval(StatusKind::Active, 0, 100))?;
+    daemon.append(&interval(StatusKind::Idle, 100, 400))?;
+    daemon.set_current(Some(&interval(StatusKind::Active, 400, 500)))?;
+
+    // 无法解析的行被跳过
+    writeln!(
+        std::fs::OpenOptions::new().append(true).open(&path)?,
+        "not json"
+    )?;
+
+    let mut reader = StatusLog::open(&path);
+    assert_eq!(
+        reader.query(at(50), at(450))?,
+        vec![
+            interval(StatusKind::Active, 50, 100),
+            interval(StatusKind::Idle, 100, 400),
+            interval(StatusKind::Active, 400, 450),
+        ]
+    );
+
+    // 异常退出后重启：遗留的进行中时间段写入时间线
+    let mut restarted = StatusLog::open(&path);
+    assert_eq!(
+        restarted.recover_current()?,
+        Some(interval(StatusKind::Active, 400, 500))
+    );
+    assert_eq!(restarted.current()?, None);
+    assert_eq!(reader.query(at(0), at(1000))?.len(), 3);
+    assert_eq!(restarted.recover_current()?, None);
+    Ok(())
+}
+
+#[test]
+fn test_status_log_retention() -> Result<()> {
+    let dir = temp_dir();
+    let data_file = dir.join("activities.db");
+    let mut log = StatusLog::for_data_file(&data_file.to_string_lossy());
+    log.append(&interval(StatusKind::Active, 0, 100))?;
+    log.append(&interval(StatusKind::Idle, 100, 400))?;
+    log.append(&interval(StatusKind::Active, 400, 500))?;
+    log.set_current(Some(&interval(StatusKind::Active, 500, 600)))?;
+
+    // 跨越截止时间的时间段保留
+    assert_eq!(log.purge_before(at(200))?, 1);
+    assert_eq!(
+        log.query(at(0), at(1000))?,
+        vec![
+            interval(StatusKind::Idle, 100, 400),
+            interval(StatusKind::Active, 400, 500),
+            interval(StatusKind::Active, 500, 600),
+        ]
+    );
+    assert_eq!(log.purge_before(at(200))?, 0);
+
+    // 进行中的时间段不受影响
+    assert_eq!(log.purge_before(at(1000))?, 2);
+    assert_eq!(
+        log.query(at(0), at(1000))?,
+        vec![interval(StatusKind::Active, 500, 600)]
+    );
+
+    let mut memory = StatusLog::in_memory();
+    memory.append(&interval(StatusKind::Active, 0, 100))?;
+    assert_eq!(memory.purge_before(at(100))?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_status_summary() {
+    let intervals = vec![
+        interval(StatusKind::Active, 0, 600),
+        interval(StatusKind::VideoPassive, 600, 900),
+        interval(StatusKind::Idle, 900, 1200),
+        interval(StatusKind::Locked, 1200, 1800),
+    ];
+
+    let summary = StatusSummary::from_intervals(&intervals, at(300), at(1500));
+    assert_eq!(summary.active, 300);
+    assert_eq!(summary.video_passive, 300);
+    assert_eq!(summary.idle, 300);
+    assert_eq!(summary.locked, 300);
+    assert_eq!(summary.computer_on(), 1200);
+    assert!((summary.active_ratio() - 0.25).abs() < f64::EPSILON);
+
+    let summary = StatusSummary::from_intervals(&intervals, at(0), at(1800));
+    assert_eq!(summary.dominant(), Some(StatusKind::Active));
+    assert_eq!(StatusSummary::default().dominant(), None);
+    assert_eq!(StatusSummary::default().active_ratio(), 0.0);
+
+    // 上海时间 23:00 到次日 01:00 按逻辑日拆分
+    let boundary = DayBoundary::new(DayTimeZone::from_name("Asia/Shanghai").unwrap(), 0);
+    let night = StatusInterval::new(
+        StatusKind::Idle,
+        Utc.with_ymd_and_hms(2024, 5, 15, 15, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2024, 5, 15, 17, 0, 0).unwrap(),
+    );
+    let days = daily_summaries(&[night], boundary);
+    assert_eq!(days.len(), 2);
+    assert_eq!(
+        days[&NaiveDate::from_ymd_opt(2024, 5, 15).unwrap()].idle,
+        3600
+    );
+    assert_eq!(
+        days[&NaiveDate::from_ymd_opt(2024, 5, 16).unwrap()].idle,
+        3600
+    );
+}
+
+#[test]
+fn test_export_status_csv() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.load_data()?;
+    assert!(tracker.data.status_timeline.is_empty());
+
+    tracker.data.status_timeline = vec![
+        interval(StatusKind::Active, 0, 90),
+        StatusInterval {
+            media: Some(MediaInfo {
+                player: "VLC media player".to_string(),
+                track: Some("Big Buck Bunny".to_string()),
+            }),
+            ..interval(StatusKind::VideoPassive, 90, 150)
+        },
+    ];
+    let csv = tracker.export_status_csv()?;
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "status,start_time,end_time,duration,player,track",
+            "active,2024-05-15 12:00:00,2024-05-15 12:01:30,90,,",
+            "video_passive,2024-05-15 12:01:30,2024-05-15 12:02:30,60,VLC media player,Big Buck Bunny",
+        ]
+    );
+
+    // 状态时间线随 JSON 导出
+    let json: serde_json::Value = serde_json::from_str(&tracker.export_json()?)?;
+    assert_eq!(json["status_timeline"][1]["status"], "video_passive");
+    Ok(())
+}
@@ -0,0 +1,535 @@
+// 存储后端测试
+// 测试存储接口的各个实现、后端选择以及 JSON 数据迁移
+
+mod common;
+
+use anyhow::Result;
+use chrono::{Duration, TimeZone, Utc};
+use common::temp_dir;
+use std::path::PathBuf;
+use timetracker::{
+    config::app::StorageConfig,
+    core::tracker::{ActivityRecord, TimeTracker, TimeTrackerData},
+    storage::{
+        atomic_write,
+        json::journal_path,
+        open_store,
+        repair::{merge_records, salvage_records},
+        resolve_data_file, ActivityStore, JsonFileStore, JsonlStore, MemoryStore, SqliteStore,
+        StorageBackend, StoreLock,
+    },
+};
+
+fn record(app: &str, title: &str, start_offset_minutes: i64, duration: u64) -> ActivityRecord {
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap()
+        + Duration::minutes(start_offset_minutes);
+    ActivityRecord {
+        id: format!("{app}-{start_offset_minutes}"),
+        window_title: title.to_string(),
+        app_path: Some("/usr/bin/app".to_string()),
+        ..common::record(app, start, duration)
+    }
+}
+
+#[test]
+fn test_backend_detection() {
+    assert_eq!(
+        StorageBackend::from_path(&PathBuf::from("activities.json")),
+        StorageBackend::Json
+    );
+    assert_eq!(
+        StorageBackend::from_path(&PathBuf::from("activities.db")),
+        StorageBackend::Sqlite
+    );
+    assert_eq!(
+        StorageBackend::from_path(&PathBuf::from("data.SQLITE")),
+        StorageBackend::Sqlite
+    );
+
+    let mut config = StorageConfig::default();
+    assert_eq!(
+        resolve_data_file(&PathBuf::from("/data"), &config),
+        PathBuf::from("/data/activities.json")
+    );
+    config.backend = "sqlite".to_string();
+    assert_eq!(
+        resolve_data_file(&PathBuf::from("/data"), &config),
+        PathBuf::from("/data/activities.db")
+    );
+}
+
+#[test]
+fn test_storage_config_validation() {
+    let mut config = StorageConfig::default();
+    assert!(config.validate().is_ok());
+
+    config.backend = "mongodb".to_string();
+    assert!(config.validate().is_err());
+
+    let fixes = config.fix();
+    assert!(!fixes.is_empty());
+    assert_eq!(config.backend, "json");
+}
+
+#[test]
+fn test_sqlite_insert_and_query_range() -> Result<()> {
+    let mut store = SqliteStore::open_in_memory()?;
+    store.append(&record("Code", "main.rs", 30, 600))?;
+    store.append(&record("Firefox", "Docs", 0, 300))?;
+    store.append(&record("Terminal", "bash", 120, 60))?;
+
+    assert_eq!(store.count()?, 3);
+
+    // 按开始时间排序
+    let all = store.load_activities()?;
+    assert_eq!(all[0].app_name, "Firefox");
+    assert_eq!(all[1], record("Code", "main.rs", 30, 600));
+
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 15, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+    let in_range = store.query_range(start, end)?;
+    assert_eq!(in_range.len(), 1);
+    assert_eq!(in_range[0].app_name, "Code");
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_current_activity_roundtrip() -> Result<()> {
+    let mut store = SqliteStore::open_in_memory()?;
+    let mut current = record("Code", "lib.rs", 0, 0);
+    current.end_time = None;
+
+    store.set_current(Some(&current))?;
+    assert_eq!(store.load()?.current_activity, Some(current));
+
+    store.set_current(None)?;
+    assert!(store.load()?.current_activity.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_import_json_formats() -> Result<()> {
+    let dir = temp_dir();
+
+    // 当前格式，包含未结束的当前活动
+    let mut current = record("Code", "lib.rs", 60, 0);
+    current.end_time = None;
+    let data = TimeTrackerData {
+        activities: vec![record("Firefox", "Docs", 0, 300)],
+        current_activity: Some(current),
+        last_updated: Utc.with_ymd_and_hms(2024, 5, 1, 10, 10, 0).unwrap(),
+        version: "0.2.2".to_string(),
+        ..TimeTrackerData::default()
+    };
+    let json_path = dir.join("activities.json");
+    std::fs::write(&json_path, serde_json::to_string(&data)?)?;
+
+    let mut store = SqliteStore::open(dir.join("activities.db"))?;
+    assert_eq!(store.import_json(&json_path)?, 2);
+    let imported = store.load_activities()?;
+    assert_eq!(imported[1].app_name, "Code");
+    assert_eq!(imported[1].duration, 600);
+
+    // 旧的纯数组格式
+    let legacy_path = dir.join("legacy.json");
+    std::fs::write(
+        &legacy_path,
+        serde_json::to_string(&vec![record("Terminal", "bash", 0, 60)])?,
+    )?;
+    let mut legacy_store = SqliteStore::open_in_memory()?;
+    assert_eq!(legacy_store.import_json(&legacy_path)?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_tracker_sqlite_backend_migrates_and_persists() -> Result<()> {
+    let dir = temp_dir();
+    let data = TimeTrackerData {
+        activities: vec![
+            record("Firefox", "Docs", 0, 300),
+            record("Code", "a.rs", 10, 60),
+        ],
+        ..TimeTrackerData::default()
+    };
+    std::fs::write(dir.join("activities.json"), serde_json::to_string(&data)?)?;
+
+    let db_file = dir.join("activities.db").to_string_lossy().to_string();
+
+    // 首次加载时自动导入同目录下的 JSON 数据
+    let mut tracker = TimeTracker::new(db_file.clone(), 1);
+    tracker.load_data()?;
+    assert_eq!(tracker.storage_name(), "sqlite");
+    assert_eq!(tracker.data.activities.len(), 2);
+
+    // 删除后重新打开应保留修改且不会重复导入
+    assert_eq!(tracker.delete_records(&["Firefox-0".to_string()])?, 1);
+
+    let mut reopened = TimeTracker::new(db_file, 1);
+    reopened.load_data()?;
+    assert_eq!(reopened.data.activities.len(), 1);
+    assert_eq!(reopened.data.activities[0].app_name, "Code");
+    Ok(())
+}
+
+/// 对任意存储实现执行相同的读写检查
+fn exercise_store(store: &mut dyn ActivityStore) -> Result<()> {
+    store.append(&record("Firefox", "Docs", 0, 300))?;
+    store.append(&record("Code", "main.rs", 30, 600))?;
+    store.append(&record("Terminal", "bash", 120, 60))?;
+    store.flush()?;
+
+    let mut edited = record("Code", "main.rs", 30, 600);
+    edited.window_title = "lib.rs".to_string();
+    assert!(store.update(&edited)?);
+    assert!(!store.update(&record("Missing", "none", 0, 1))?);
+
+    assert_eq!(store.delete(&["Terminal-120".to_string()])?, 1);
+    assert_eq!(store.delete(&["Terminal-120".to_string()])?, 0);
+
+    let mut current = record("Slack", "general", 200, 0);
+    current.end_time = None;
+    store.set_current(Some(&current))?;
+    store.flush()?;
+
+    let data = store.load()?;
+    assert_eq!(data.activities.len(), 2);
+    assert_eq!(data.activities[1].window_title, "lib.rs");
+    assert_eq!(
+        data.current_activity.map(|c| c.app_name),
+        Some("Slack".to_string())
+    );
+
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 15, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let in_range = store.query_range(start, end)?;
+    assert_eq!(in_range.len(), 1);
+    assert_eq!(in_range[0].id, "Code-30");
+
+    store.replace_all(&[record("Vim", "notes", 0, 10)])?;
+    store.flush()?;
+    assert_eq!(store.load()?.activities.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_all_stores_share_behaviour() -> Result<()> {
+    let dir = temp_dir();
+
+    exercise_store(&mut MemoryStore::new())?;
+    exercise_store(&mut JsonFileStore::new(dir.join("activities.json")))?;
+    exercise_store(&mut JsonlStore::new(dir.join("activities.jsonl")))?;
+    exercise_store(&mut SqliteStore::open(dir.join("activities.db"))?)?;
+
+    // 重新打开文件存储，数据应已持久化
+    for file in ["activities.json", "activities.jsonl", "activities.db"] {
+        let mut reopened = open_store(dir.join(file))?;
+        let data = reopened.load()?;
+        assert_eq!(data.activities.len(), 1, "{file}");
+        assert_eq!(data.activities[0].app_name, "Vim", "{file}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_skips_damaged_lines() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.jsonl");
+    let mut store = JsonlStore::new(&path);
+    store.append(&record("Firefox", "Docs", 0, 300))?;
+
+    // 模拟写到一半的最后一行
+    let mut content = std::fs::read_to_string(&path)?;
+    content.push_str("{\"app_name\": \"Cod");
+    std::fs::write(&path, content)?;
+
+    assert_eq!(store.load()?.activities.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_tracker_with_custom_store() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.load_data()?;
+    assert_eq!(tracker.storage_name(), "memory");
+
+    tracker.add_record(record("Firefox", "Docs", 0, 300))?;
+    tracker.add_record(record("Code", "main.rs", 30, 600))?;
+
+    let mut edited = tracker.data.activities[0].clone();
+    edited.duration = 120;
+    assert!(tracker.update_record(edited)?);
+    assert_eq!(tracker.data.activities[0].duration, 120);
+
+    assert_eq!(tracker.delete_records(&["Code-30".to_string()])?, 1);
+    assert_eq!(tracker.get_total_time(), 120);
+
+    // 重新加载后与存储保持一致
+    tracker.load_data()?;
+    assert_eq!(tracker.data.activities.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_sqlite_upgrades_v1_schema() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("old.db");
+    {
+        let conn = rusqlite::Connection::open(&path)?;
+        conn.execute_batch(
+            "CREATE TABLE activities (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 app_name TEXT NOT NULL, window_title TEXT NOT NULL,
+                 start_time TEXT NOT NULL, end_time TEXT,
+                 duration_seconds INTEGER NOT NULL DEFAULT 0,
+                 process_id INTEGER NOT NULL DEFAULT 0,
+                 app_path TEXT, bundle_id TEXT, window_geometry TEXT,
+                 confidence REAL NOT NULL DEFAULT 0);
+             CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+             INSERT INTO activities (app_name, window_title, start_time, duration_seconds)
+                 VALUES ('Code', 'main.rs', '2024-05-01 09:00:00+00:00', 60);
+             PRAGMA user_version = 1;",
+        )?;
+    }
+
+    let mut store = SqliteStore::open(&path)?;
+    assert_eq!(
+        store.schema_version()?,
+        timetracker::storage::sqlite::SCHEMA_VERSION
+    );
+    let data = store.load()?;
+    assert_eq!(data.activities.len(), 1);
+    assert!(!data.activities[0].id.is_empty());
+
+    // 升级后生成的 id 可用于删除
+    let id = data.activities[0].id.clone();
+    assert_eq!(store.delete(&[id])?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_json_journal_recovers_after_crash() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    {
+        let mut store = JsonFileStore::new(&path);
+        store.load()?;
+        store.append(&record("Firefox", "Docs", 0, 300))?;
+        let mut current = record("Code", "main.rs", 5, 0);
+        current.end_time = None;
+        store.set_current(Some(&current))?;
+        store.flush()?;
+        // 未压缩就"崩溃"：快照尚未写入，数据只在日志中
+    }
+    assert!(!path.exists());
+    assert!(journal_path(&path).exists());
+
+    // 模拟断电时写到一半的最后一行
+    let mut journal = std::fs::read_to_string(journal_path(&path))?;
+    journal.push_str("{\"event\":\"finish\",\"rec");
+    std::fs::write(journal_path(&path), journal)?;
+
+    let mut tracker = TimeTracker::new(path.to_string_lossy().to_string(), 1);
+    tracker.load_data()?;
+    assert_eq!(tracker.data.activities.len(), 1);
+    assert_eq!(
+        tracker
+            .data
+            .current_activity
+            .as_ref()
+            .map(|c| c.app_name.as_str()),
+        Some("Code")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_json_background_compaction() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut store = JsonFileStore::new(&path);
+    store.set_compact_threshold(3);
+    store.load()?;
+    store.append(&record("Firefox", "Docs", 0, 300))?;
+    store.append(&record("Code", "main.rs", 30, 600))?;
+    store.flush()?;
+    assert!(!path.exists());
+
+    store.append(&record("Terminal", "bash", 120, 60))?;
+    store.flush()?;
+    store.wait_for_compaction()?;
+
+    // 压缩后快照包含全部记录，日志已清空
+    assert!(path.exists());
+    assert!(!journal_path(&path).exists());
+    let snapshot: TimeTrackerData = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    assert_eq!(snapshot.activities.len(), 3);
+
+    // 压缩后的新事件写入新日志，重新加载时与快照合并
+    store.delete(&["Firefox-0".to_string()])?;
+    let mut reopened = JsonFileStore::new(&path);
+    assert_eq!(reopened.load()?.activities.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_json_concurrent_writers_merge() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    // 模拟守护进程和 TUI 各自打开同一个数据文件
+    let mut daemon = JsonFileStore::new(&path);
+    let mut tui = JsonFileStore::new(&path);
+    daemon.set_compact_threshold(2);
+    daemon.load()?;
+    daemon.append(&record("Firefox", "Docs", 0, 300))?;
+    daemon.append(&record("Code", "main.rs", 30, 600))?;
+
+    // TUI 加载后删除一条记录，期间守护进程继续追加并触发压缩
+    assert_eq!(tui.load()?.activities.len(), 2);
+    daemon.append(&record("Terminal", "bash", 60, 60))?;
+    daemon.flush()?;
+    assert_eq!(tui.delete(&["Firefox-0".to_string()])?, 1);
+
+    let mut edited = record("Terminal", "bash", 60, 60);
+    edited.window_title = "zsh".to_string();
+    assert!(tui.update(&edited)?);
+    daemon.append(&record("Slack", "general", 90, 120))?;
+    daemon.wait_for_compaction()?;
+
+    let data = JsonFileStore::new(&path).load()?;
+    let ids: Vec<_> = data.activities.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["Code-30", "Terminal-60", "Slack-90"]);
+    assert_eq!(data.activities[1].window_title, "zsh");
+    Ok(())
+}
+
+#[test]
+fn test_json_replace_all_reloaded_by_other_writer() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let mut daemon = JsonFileStore::new(&path);
+    daemon.append(&record("Firefox", "Docs", 0, 300))?;
+    daemon.append(&record("Code", "main.rs", 30, 600))?;
+
+    // 另一个进程整体替换后，守护进程的下一次写入基于新快照，不会恢复被替换的记录
+    let mut repair = JsonFileStore::new(&path);
+    repair.replace_all(&[record("Code", "main.rs", 30, 600)])?;
+    daemon.append(&record("Terminal", "bash", 60, 60))?;
+    daemon.compact()?;
+
+    let data = JsonFileStore::new(&path).load()?;
+    let ids: Vec<_> = data.activities.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["Code-30", "Terminal-60"]);
+    Ok(())
+}
+
+#[test]
+fn test_store_lock_is_exclusive() -> Result<()> {
+    let dir = temp_dir();
+    let lock = StoreLock::new(dir.join("activities.json"));
+
+    let mut guard = lock.acquire()?;
+    assert_eq!(guard.generation()?, 0);
+    assert_eq!(guard.bump_generation()?, 1);
+
+    let other = lock.clone();
+    let waiter = std::thread::spawn(move || -> Result<u64> { other.acquire()?.generation() });
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert!(!waiter.is_finished());
+
+    drop(guard);
+    assert_eq!(waiter.join().unwrap()?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_atomic_write_replaces_file() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("data.json");
+
+    atomic_write(&path, b"first")?;
+    atomic_write(&path, b"second")?;
+    assert_eq!(std::fs::read_to_string(&path)?, "second");
+
+    // 不应留下临时文件
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_corrupt_json_is_quarantined() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+    std::fs::write(&path, "{\"activities\": [{\"app_name\": \"Co")?;
+
+    let mut tracker = TimeTracker::new(path.to_string_lossy().to_string(), 1);
+    tracker.load_data()?;
+    assert!(tracker.data.activities.is_empty());
+
+    // 原文件被移动到隔离副本，并产生一条警告
+    assert!(!path.exists());
+    let quarantined: Vec<_> = std::fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("activities.json.corrupt-"))
+        .collect();
+    assert_eq!(quarantined.len(), 1);
+
+    let warnings = tracker.take_load_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains(&quarantined[0]));
+    assert!(tracker.take_load_warnings().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_salvage_truncated_files() {
+    let records = vec![
+        record("Firefox", "Docs", 0, 300),
+        record("Code", "main.rs", 30, 600),
+        record("Terminal", "bash", 120, 60),
+    ];
+
+    // 当前格式，截断在最后一条记录中间
+    let data = TimeTrackerData {
+        activities: records.clone(),
+        ..TimeTrackerData::default()
+    };
+    let json = serde_json::to_string_pretty(&data).unwrap();
+    let cut = json.find("Terminal").unwrap();
+    let report = salvage_records(&json[..cut]);
+    assert!(!report.intact);
+    assert_eq!(report.records, records[..2].to_vec());
+
+    // 旧的纯数组格式，记录缺少 id
+    let legacy = r#"[
+        {"app_name": "Firefox", "window_title": "Docs", "start_time": "2024-05-01T09:00:00Z",
+         "end_time": "2024-05-01T09:05:00Z", "duration_seconds": 300, "process_id": 1},
+        {"app_name": "Code", "window_title": "main.rs", "start_time": "2024-05-01T09:30:00Z",
+         "end_time": null, "duration_seconds": 60, "pro"#;
+    let report = salvage_records(legacy);
+    assert_eq!(report.records.len(), 1);
+    assert_eq!(report.records[0].app_name, "Firefox");
+    assert!(!report.records[0].id.is_empty());
+
+    // 完好的文件无需修复
+    assert!(salvage_records(&json).intact);
+}
+
+#[test]
+fn test_merge_salvaged_records_skips_duplicates() {
+    let mut existing = vec![record("Firefox", "Docs", 0, 300)];
+
+    let mut same_key = record("Firefox", "Docs", 0, 300);
+    same_key.id = "other-id".to_string();
+    let salvaged = vec![same_key, record("Code", "main.rs", 30, 600)];
+
+    assert_eq!(merge_records(&mut existing, salvaged), 1);
+    assert_eq!(existing.len(), 2);
+}
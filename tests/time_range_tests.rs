@@ -0,0 +1,401 @@
+// 时间范围测试
+// 测试时区和日分割点下的时间区间计算，自定义/滚动时间范围的解析，跨零点记录的拆分和按时间范围导出时的截取，以及 TUI 数据的时间过滤
+
+mod common;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc};
+use common::temp_dir;
+use timetracker::{
+    config::app::AppConfig,
+    core::tracker::{ActivityRecord, TimeTracker},
+    storage::{ActivityStore, JsonFileStore, MemoryStore, StoreOptions},
+    ui::{components::TimeRangeFilter, data::DataManager},
+    utils::time::{month_start, today_start, week_start, DayBoundary, DayTimeZone, TimeRange},
+};
+
+fn date(month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+}
+
+/// 2024 年指定本地时间
+fn local(month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+    Local
+        .with_ymd_and_hms(2024, month, day, hour, 0, 0)
+        .earliest()
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+/// 系统本地时区、指定日分割点
+fn local_boundary(split_hour: u8) -> DayBoundary {
+    DayBoundary::new(DayTimeZone::Local, split_hour)
+}
+
+/// 上海时区（UTC+8）、指定日分割点
+fn shanghai(split_hour: u8) -> DayBoundary {
+    DayBoundary::new(DayTimeZone::from_name("Asia/Shanghai").unwrap(), split_hour)
+}
+
+fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, month, day, hour, minute, 0)
+        .unwrap()
+}
+
+fn record(app: &str, start: DateTime<Utc>) -> ActivityRecord {
+    common::record(app, start, 60)
+}
+
+#[test]
+fn test_logical_date_respects_day_split_hour() {
+    let early = local(5, 15, 3);
+    assert_eq!(local_boundary(0).logical_date(early), date(5, 15));
+    assert_eq!(local_boundary(4).logical_date(early), date(5, 14));
+    assert_eq!(local_boundary(4).logical_date(local(5, 15, 4)), date(5, 15));
+
+    assert_eq!(local_boundary(0).day_start(date(5, 15)), local(5, 15, 0));
+    assert_eq!(local_boundary(4).day_start(date(5, 15)), local(5, 15, 4));
+}
+
+#[test]
+fn test_time_range_bounds() {
+    // 2024-05-15 是周三
+    let now = local(5, 15, 10);
+    let start = |month, day| local_boundary(0).day_start(date(month, day));
+
+    assert_eq!(
+        TimeRange::Today.bounds_at(now, local_boundary(0)),
+        (start(5, 15), start(5, 16))
+    );
+    assert_eq!(
+        TimeRange::Yesterday.bounds_at(now, local_boundary(0)),
+        (start(5, 14), start(5, 15))
+    );
+    assert_eq!(
+        TimeRange::ThisWeek.bounds_at(now, local_boundary(0)),
+        (start(5, 13), start(5, 20))
+    );
+    assert_eq!(
+        TimeRange::LastWeek.bounds_at(now, local_boundary(0)),
+        (start(5, 6), start(5, 13))
+    );
+    assert_eq!(
+        TimeRange::ThisMonth.bounds_at(now, local_boundary(0)),
+        (start(5, 1), start(6, 1))
+    );
+    assert_eq!(
+        TimeRange::LastMonth.bounds_at(now, local_boundary(0)),
+        (start(4, 1), start(5, 1))
+    );
+    assert_eq!(
+        TimeRange::LastDays(7).bounds_at(now, local_boundary(0)),
+        (start(5, 9), start(5, 16))
+    );
+}
+
+#[test]
+fn test_today_before_day_split_hour_is_previous_day() {
+    // 日分割点为 4 点时，凌晨 3 点仍属于 5 月 14 日
+    let now = local(5, 15, 3);
+    let (start, end) = TimeRange::Today.bounds_at(now, local_boundary(4));
+    assert_eq!(start, local(5, 14, 4));
+    assert_eq!(end, local(5, 15, 4));
+    assert!(start <= now && now < end);
+}
+
+#[test]
+fn test_parse_time_range_filter() -> Result<()> {
+    assert_eq!(TimeRangeFilter::parse("today")?, TimeRangeFilter::Today);
+    assert_eq!(TimeRangeFilter::parse("7d")?, TimeRangeFilter::Last7Days);
+    assert_eq!(
+        TimeRangeFilter::parse("last-30-days")?,
+        TimeRangeFilter::Last30Days
+    );
+    assert_eq!(
+        TimeRangeFilter::parse("2024-05-01..2024-05-03")?,
+        TimeRangeFilter::Custom(date(5, 1), date(5, 3))
+    );
+    assert_eq!(
+        TimeRangeFilter::parse("2024-05-01")?,
+        TimeRangeFilter::Custom(date(5, 1), date(5, 1))
+    );
+
+    assert!(TimeRangeFilter::parse("2024-05-03..2024-05-01").is_err());
+    assert!(TimeRangeFilter::parse("fortnight").is_err());
+    assert!(TimeRangeFilter::parse("2024-13-01").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_custom_and_rolling_filter_bounds() {
+    let now = local(5, 15, 10);
+    let custom = TimeRangeFilter::Custom(date(5, 1), date(5, 3));
+    assert_eq!(
+        custom.bounds_at(now, local_boundary(0)),
+        Some((
+            local_boundary(0).day_start(date(5, 1)),
+            local_boundary(0).day_start(date(5, 4))
+        ))
+    );
+    assert_eq!(
+        custom.bounds_at(now, local_boundary(6)),
+        Some((
+            local_boundary(6).day_start(date(5, 1)),
+            local_boundary(6).day_start(date(5, 4))
+        ))
+    );
+    assert_eq!(
+        TimeRangeFilter::Last30Days.bounds_at(now, local_boundary(0)),
+        Some((
+            local_boundary(0).day_start(date(4, 16)),
+            local_boundary(0).day_start(date(5, 16))
+        ))
+    );
+    assert_eq!(TimeRangeFilter::All.bounds_at(now, local_boundary(0)), None);
+    assert_eq!(custom.title(), "2024-05-01 ~ 2024-05-03");
+}
+
+#[test]
+fn test_data_manager_filters_by_time_range() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    let now = Utc::now();
+    let old = now - Duration::days(10);
+    let mut store = JsonFileStore::new(&path);
+    store.append(&record("Recent", now))?;
+    store.append(&record("Old", old))?;
+    store.flush()?;
+    drop(store);
+
+    let mut manager =
+        DataManager::new(path.to_string_lossy().to_string(), StoreOptions::default())?;
+    manager.initialize_data()?;
+
+    let apps = |filter: TimeRangeFilter| -> Vec<String> {
+        let mut apps: Vec<String> = manager
+            .get_unified_activities(filter)
+            .into_iter()
+            .map(|item| item.app_name)
+            .collect();
+        apps.sort();
+        apps
+    };
+
+    assert_eq!(apps(TimeRangeFilter::Today), vec!["Recent"]);
+    assert_eq!(apps(TimeRangeFilter::Last30Days), vec!["Old", "Recent"]);
+    assert_eq!(apps(TimeRangeFilter::All), vec!["Old", "Recent"]);
+
+    let old_date = local_boundary(0).logical_date(old);
+    assert_eq!(
+        apps(TimeRangeFilter::Custom(old_date, old_date)),
+        vec!["Old"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_current_bounds_use_given_boundary() {
+    // 不带当前时间的便捷函数同样按给定的时区和日分割点划分
+    let boundary = shanghai(4);
+    let (start, end) = TimeRange::Today.bounds(boundary);
+    assert_eq!(end - start, Duration::days(1));
+    assert_eq!(
+        boundary.logical_date(start),
+        boundary.logical_date(end - Duration::seconds(1))
+    );
+    assert_eq!(start, boundary.day_start(boundary.logical_date(start)));
+    assert_eq!(today_start(boundary), start);
+    assert!(TimeRange::Today.contains(start, boundary));
+    assert!(!TimeRange::Today.contains(end, boundary));
+    assert!(week_start(boundary) <= start && month_start(boundary) <= start);
+}
+
+#[test]
+fn test_named_timezone_day_boundaries() -> Result<()> {
+    // 上海时间 2024-05-15 01:30 对应 UTC 5 月 14 日 17:30
+    let now = utc(5, 14, 17, 30);
+    let boundary = shanghai(0);
+    assert_eq!(boundary.logical_date(now), date(5, 15));
+    assert_eq!(
+        TimeRange::Today.bounds_at(now, boundary),
+        (utc(5, 14, 16, 0), utc(5, 15, 16, 0))
+    );
+    // 周一 5 月 13 日上海零点
+    assert_eq!(
+        TimeRange::ThisWeek.bounds_at(now, boundary).0,
+        utc(5, 12, 16, 0)
+    );
+
+    // 日分割点为 4 时，上海凌晨 1:30 仍属于 5 月 14 日
+    let boundary = shanghai(4);
+    assert_eq!(boundary.logical_date(now), date(5, 14));
+    assert_eq!(
+        TimeRange::Today.bounds_at(now, boundary),
+        (utc(5, 13, 20, 0), utc(5, 14, 20, 0))
+    );
+    assert_eq!(boundary.hour_of_day(now), 21);
+
+    assert_eq!(DayTimeZone::from_name("local")?, DayTimeZone::Local);
+    assert_eq!(DayTimeZone::from_name("")?, DayTimeZone::Local);
+    assert_eq!(
+        DayTimeZone::from_name("Asia/Shanghai")?.name(),
+        "Asia/Shanghai"
+    );
+    assert!(DayTimeZone::from_name("Mars/Olympus").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_record_crossing_midnight_is_split() {
+    let boundary = shanghai(0);
+    // 上海时间 5 月 14 日 23:00 到 5 月 15 日 01:00
+    let late = common::record("Code", utc(5, 14, 15, 0), 7200);
+
+    let days = boundary.split_days(late.start_time, late.effective_end());
+    assert_eq!(
+        days,
+        vec![
+            (date(5, 14), utc(5, 14, 15, 0), utc(5, 14, 16, 0)),
+            (date(5, 15), utc(5, 14, 16, 0), utc(5, 14, 17, 0)),
+        ]
+    );
+
+    let (start, end) = TimeRange::Today.bounds_at(utc(5, 14, 20, 0), boundary);
+    let today = late.clipped(start, end).unwrap();
+    assert_eq!(today.start_time, start);
+    assert_eq!(today.duration, 3600);
+    let (start, end) = TimeRange::Yesterday.bounds_at(utc(5, 14, 20, 0), boundary);
+    let yesterday = late.clipped(start, end).unwrap();
+    assert_eq!(yesterday.end_time, Some(start + Duration::days(1)));
+    assert_eq!(yesterday.duration, 3600);
+    assert!(late.clipped(utc(5, 20, 0, 0), utc(5, 21, 0, 0)).is_none());
+
+    // 上海时间 23:00、00:00 两个小时各一小时
+    let hours: Vec<usize> = boundary
+        .split_hours(late.start_time, late.effective_end())
+        .into_iter()
+        .map(|(hour, _, _)| hour)
+        .collect();
+    assert_eq!(hours, vec![23, 0]);
+}
+
+#[test]
+fn test_statistics_in_range_split_records() -> Result<()> {
+    let mut store = MemoryStore::new();
+    store.append(&common::record("Code", utc(5, 14, 15, 0), 7200))?;
+    store.append(&common::record("Firefox", utc(5, 14, 18, 0), 600))?;
+
+    let mut tracker = TimeTracker::with_store(Box::new(store), 1);
+    tracker.load_data()?;
+
+    let (start, end) = TimeRange::Today.bounds_at(utc(5, 14, 20, 0), shanghai(0));
+    assert_eq!(tracker.get_total_time_in(start, end), 3600 + 600);
+    let stats = tracker.get_statistics_in(start, end);
+    assert_eq!(stats["Code - Code window"], 3600);
+    assert_eq!(stats["Firefox - Firefox window"], 600);
+    Ok(())
+}
+
+#[test]
+fn test_range_export_clips_records() -> Result<()> {
+    let mut store = MemoryStore::new();
+    // 上海时间 5 月 14 日 23:00 到 5 月 15 日 01:00，以及 5 月 15 日 02:00 的记录
+    store.append(&common::record("Code", utc(5, 14, 15, 0), 7200))?;
+    store.append(&common::record("Firefox", utc(5, 14, 18, 0), 600))?;
+    store.append(&common::record("Mail", utc(5, 15, 17, 0), 600))?;
+    store.set_current(Some(&common::record("Slack", utc(5, 15, 15, 30), 3600)))?;
+
+    let mut tracker = TimeTracker::with_store(Box::new(store), 1);
+    let (start, end) = TimeRange::Today.bounds_at(utc(5, 14, 20, 0), shanghai(0));
+    tracker.load_range_clipped(start, end)?;
+
+    let spans: Vec<_> = tracker
+        .data
+        .activities
+        .iter()
+        .map(|r| {
+            (
+                r.app_name.as_str(),
+                r.start_time,
+                r.effective_end(),
+                r.duration,
+            )
+        })
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            ("Code", utc(5, 14, 16, 0), utc(5, 14, 17, 0), 3600),
+            ("Firefox", utc(5, 14, 18, 0), utc(5, 14, 18, 10), 600),
+        ]
+    );
+    let current = tracker.data.current_activity.clone().unwrap();
+    assert_eq!(
+        (current.start_time, current.duration),
+        (utc(5, 15, 15, 30), 1800)
+    );
+
+    // 导出的合计时长与统计一致
+    assert_eq!(tracker.get_total_time(), 3600 + 600);
+    let csv = tracker.export_csv()?;
+    assert!(csv.contains("Code,Code window,2024-05-14 16:00:00,2024-05-14 17:00:00,3600,"));
+    Ok(())
+}
+
+#[test]
+fn test_data_manager_chart_statistics() -> Result<()> {
+    let dir = temp_dir();
+    let path = dir.join("activities.json");
+
+    // 今天的 0 点开始前后各半小时的记录（跨越今天的开始）
+    let boundary = local_boundary(0);
+    let (today_start, _) = TimeRange::Today.bounds_at(Utc::now(), boundary);
+    let mut store = JsonFileStore::new(&path);
+    store.append(&common::record(
+        "Code",
+        today_start - Duration::minutes(30),
+        3600,
+    ))?;
+    store.flush()?;
+    drop(store);
+
+    let mut manager =
+        DataManager::new(path.to_string_lossy().to_string(), StoreOptions::default())?;
+    manager.set_day_boundary(boundary);
+    manager.initialize_data()?;
+
+    let chart = manager.get_chart_statistics();
+    assert_eq!(chart.today, boundary.logical_date(Utc::now()));
+    assert_eq!(chart.today_by_app, vec![("Code".to_string(), 1800)]);
+    assert_eq!(chart.hours[0], 1800);
+    assert_eq!(chart.hours.iter().sum::<u64>(), 1800);
+
+    // 今天只统计 0 点之后的部分
+    let today = manager.get_unified_activities(TimeRangeFilter::Today);
+    assert_eq!(today[0].total_duration, 1800);
+
+    // 本周统计包含今天的半小时，周一时前一天的部分不在本周
+    let weekday = chart.today.weekday().num_days_from_monday() as usize;
+    assert_eq!(chart.week[weekday], 1800);
+    let expected_week = if weekday == 0 { 1800 } else { 3600 };
+    assert_eq!(chart.week.iter().sum::<u64>(), expected_week);
+    Ok(())
+}
+
+#[test]
+fn test_timezone_config_validation() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.ui.day_boundary(), DayBoundary::default());
+
+    config.ui.timezone = "Asia/Shanghai".to_string();
+    config.ui.day_split_hour = 4;
+    assert!(config.validate().is_ok());
+    assert_eq!(config.ui.day_boundary(), shanghai(4));
+
+    config.ui.timezone = "Not/AZone".to_string();
+    assert!(config.validate().is_err());
+    assert!(!config.fix().is_empty());
+    assert_eq!(config.ui.timezone, "local");
+    assert!(config.validate().is_ok());
+}
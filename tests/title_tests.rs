@@ -0,0 +1,154 @@
+// 窗口标题规范化测试
+// 测试内置规则、自定义替换和删除规则、按应用限定，以及追踪器按规范化后的标题合并记录并保留原始标题
+
+mod common;
+
+use anyhow::Result;
+use common::titled_heartbeat;
+use timetracker::{
+    config::app::AppConfig,
+    core::{
+        title::{TitleNormalizer, TitleRule},
+        tracker::TimeTracker,
+    },
+    storage::{ActivityStore, MemoryStore, SqliteStore},
+};
+
+#[test]
+fn test_builtin_rules() {
+    let normalizer = TitleNormalizer::builtin();
+    let cases = [
+        ("Slack", "(3) Slack | general", "Slack | general"),
+        ("Google Chrome", "(12+) Inbox - Gmail", "Inbox - Gmail"),
+        (
+            "Code",
+            "● main.rs - timetracker - Visual Studio Code",
+            "main.rs - timetracker - Visual Studio Code",
+        ),
+        ("gedit", "*notes.txt - gedit", "notes.txt - gedit"),
+        ("mpv", "Lecture 3.mkv - 12:34 / 45:00", "Lecture 3.mkv"),
+        ("vlc", "01:02:03 / 02:00:00 - movie.mp4", "movie.mp4"),
+        (
+            "Google Chrome",
+            "Music - Audio playing - Google Chrome",
+            "Music - Google Chrome",
+        ),
+        ("Terminal", "cargo build [42%]", "cargo build"),
+        // 规则不影响普通标题
+        (
+            "Firefox",
+            "Rust Book - Mozilla Firefox",
+            "Rust Book - Mozilla Firefox",
+        ),
+    ];
+    for (app, raw, expected) in cases {
+        assert_eq!(normalizer.normalize(app, raw), expected, "{raw}");
+    }
+
+    // 清空后保留原标题
+    assert_eq!(normalizer.normalize("App", "(3)"), "(3)");
+}
+
+#[test]
+fn test_custom_rules() -> Result<()> {
+    let rules = vec![
+        TitleRule::replace(r"^Ticket #\d+: (.*)$", "Ticket: $1"),
+        TitleRule::strip(r" \(Private Browsing\)").for_app("firefox"),
+    ];
+    let normalizer = TitleNormalizer::new(false, &rules)?;
+
+    assert_eq!(
+        normalizer.normalize("Browser", "Ticket #1234: Fix login"),
+        "Ticket: Fix login"
+    );
+    // 应用名匹配不区分大小写
+    assert_eq!(
+        normalizer.normalize("Firefox", "Docs (Private Browsing)"),
+        "Docs"
+    );
+    assert_eq!(
+        normalizer.normalize("Chrome", "Docs (Private Browsing)"),
+        "Docs (Private Browsing)"
+    );
+    // 未启用内置规则
+    assert_eq!(normalizer.normalize("Slack", "(3) general"), "(3) general");
+
+    assert!(TitleNormalizer::new(false, &[TitleRule::strip("(")]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_tracker_merges_normalized_titles() -> Result<()> {
+    let mut tracker = TimeTracker::with_store(Box::new(MemoryStore::new()), 1);
+    tracker.load_data()?;
+
+    // 播放进度每秒变化，仍是同一条记录
+    for seconds in 0..5 {
+        let title = format!("Lecture.mkv - 00:0{seconds} / 45:00");
+        tracker.record_heartbeat(titled_heartbeat("mpv", &title, seconds))?;
+    }
+    tracker.record_heartbeat(titled_heartbeat("Slack", "(3) Slack | general", 5))?;
+    tracker.record_heartbeat(titled_heartbeat("Slack", "(4) Slack | general", 6))?;
+
+    assert_eq!(tracker.data.activities.len(), 1);
+    let video = &tracker.data.activities[0];
+    assert_eq!(video.window_title, "Lecture.mkv");
+    assert_eq!(
+        video.raw_title.as_deref(),
+        Some("Lecture.mkv - 00:00 / 45:00")
+    );
+    assert_eq!(video.duration, 5);
+
+    let current = tracker.current_activity.as_ref().unwrap();
+    assert_eq!(current.window_title, "Slack | general");
+    assert_eq!(current.duration, 1);
+
+    let stats = tracker.get_statistics();
+    assert_eq!(stats.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_raw_title_persists() -> Result<()> {
+    let mut record = titled_heartbeat("Slack", "Slack | general", 0).into_record();
+    record.raw_title = Some("(3) Slack | general".to_string());
+
+    let mut store = SqliteStore::open_in_memory()?;
+    store.append(&record)?;
+    assert_eq!(store.load()?.activities, vec![record.clone()]);
+
+    // 标题未变化时不写出原始标题
+    record.raw_title = None;
+    assert!(!serde_json::to_string(&record)?.contains("raw_title"));
+    Ok(())
+}
+
+#[test]
+fn test_title_rules_config() -> Result<()> {
+    let mut config = AppConfig {
+        tracking: toml::from_str(
+            r#"
+        builtin_title_rules = false
+
+        [[title_rules]]
+        app = "code"
+        pattern = " - Visual Studio Code$"
+
+        [[title_rules]]
+        pattern = "("
+        "#,
+        )?,
+        ..AppConfig::default()
+    };
+    assert!(config.validate().is_err());
+    assert_eq!(config.fix().len(), 1);
+    assert!(config.validate().is_ok());
+    assert_eq!(config.tracking.title_rules.len(), 1);
+
+    let normalizer = config.tracking.title_normalizer();
+    assert_eq!(
+        normalizer.normalize("Code", "● main.rs - Visual Studio Code"),
+        "● main.rs"
+    );
+    Ok(())
+}